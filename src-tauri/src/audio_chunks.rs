@@ -0,0 +1,212 @@
+use crate::ffmpeg_utils;
+use crate::models::SubtitleError;
+use log::{error, info};
+use std::path::{Path, PathBuf};
+
+/// Longest chunk sent to a transcription backend in one request (seconds)
+///
+/// Ten minutes of 64 kbit/s mono MP3 is under 5 MB, well inside upload limits.
+pub const MAX_CHUNK_SECONDS: f64 = 600.0;
+
+/// Chunks are cut at the last silence in the second half of the allowed length
+const MIN_CHUNK_FRACTION: f64 = 0.5;
+
+/// Level below which audio counts as silence (dB)
+const SILENCE_NOISE_DB: i32 = -35;
+
+/// Shortest pause that can be used as a cut point (seconds)
+const SILENCE_MIN_SECONDS: f64 = 0.4;
+
+/// A span of an audio file transcribed in one request
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioChunk {
+    pub index: usize,
+    pub start: f64,  // seconds
+    pub end: f64,  // seconds
+}
+
+impl AudioChunk {
+    pub fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+/// FFmpeg codec arguments for extracted transcription audio
+pub fn audio_codec_args(extension: &str) -> [&'static str; 2] {
+    if extension == "wav" {
+        ["-c:a", "pcm_s16le"]
+    } else {
+        ["-b:a", "64k"]  // Low bitrate for smaller upload
+    }
+}
+
+/// Split an audio file into chunks of at most `MAX_CHUNK_SECONDS`, cutting in pauses
+pub async fn plan_audio_chunks(audio_path: &Path) -> Result<Vec<AudioChunk>, SubtitleError> {
+    let output = tokio::process::Command::new(ffmpeg_path()?)
+        .arg("-hide_banner")
+        .arg("-i")
+        .arg(audio_path)
+        .arg("-af")
+        .arg(format!("silencedetect=noise={}dB:d={}", SILENCE_NOISE_DB, SILENCE_MIN_SECONDS))
+        .arg("-f")
+        .arg("null")
+        .arg("-")
+        .output()
+        .await?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        error!("FFmpeg silence detection failed: {}", stderr);
+        return Err(SubtitleError::FFmpegError(format!("Silence detection failed: {}", stderr)));
+    }
+
+    let (duration, silences) = parse_silencedetect(&stderr);
+    let duration = duration.ok_or_else(|| SubtitleError::FFmpegError(
+        format!("Could not read the audio duration: {}", stderr)
+    ))?;
+    let chunks = plan_chunks(duration, &silences, MAX_CHUNK_SECONDS);
+
+    info!("Planned {} transcription chunks for {:.1}s of audio", chunks.len(), duration);
+    Ok(chunks)
+}
+
+/// Parse the input duration and `(start, end)` silences from FFmpeg's stderr
+pub fn parse_silencedetect(stderr: &str) -> (Option<f64>, Vec<(f64, f64)>) {
+    let mut duration = None;
+    let mut silences = Vec::new();
+    let mut silence_start: Option<f64> = None;
+
+    for line in stderr.lines() {
+        if let Some(rest) = line.trim().strip_prefix("Duration: ") {
+            duration = rest.split(',').next().and_then(parse_clock);
+        } else if let Some(pos) = line.find("silence_start: ") {
+            silence_start = line[pos + 15..].trim().parse().ok();
+        } else if let Some(pos) = line.find("silence_end: ") {
+            let end = line[pos + 13..].split('|').next().and_then(|v| v.trim().parse::<f64>().ok());
+            if let (Some(start), Some(end)) = (silence_start.take(), end) {
+                silences.push((start.max(0.0), end));
+            }
+        }
+    }
+
+    // Silence running to the end of the file has no end marker
+    if let (Some(start), Some(end)) = (silence_start, duration) {
+        silences.push((start, end));
+    }
+
+    (duration, silences)
+}
+
+/// Parse "HH:MM:SS.ss"
+fn parse_clock(clock: &str) -> Option<f64> {
+    let parts: Vec<f64> = clock.trim().split(':').map(|p| p.parse().ok()).collect::<Option<_>>()?;
+    match parts.as_slice() {
+        [h, m, s] => Some(h * 3600.0 + m * 60.0 + s),
+        _ => None,
+    }
+}
+
+/// Split `duration` seconds into chunks no longer than `max_chunk`
+///
+/// Each chunk ends in the middle of the last pause in the second half of its
+/// allowed length, or exactly at the limit when there is no pause there.
+pub fn plan_chunks(duration: f64, silences: &[(f64, f64)], max_chunk: f64) -> Vec<AudioChunk> {
+    let mut chunks = Vec::new();
+    let mut start = 0.0;
+
+    while duration - start > max_chunk {
+        let limit = start + max_chunk;
+        let earliest = start + max_chunk * MIN_CHUNK_FRACTION;
+        let end = silences.iter()
+            .map(|(s, e)| (s + e) / 2.0)
+            .rfind(|mid| *mid > earliest && *mid <= limit)
+            .unwrap_or(limit);
+
+        chunks.push(AudioChunk { index: chunks.len(), start, end });
+        start = end;
+    }
+
+    chunks.push(AudioChunk { index: chunks.len(), start, end: duration });
+    chunks
+}
+
+/// Cut a chunk out of an audio file into a temporary file
+pub async fn extract_chunk(
+    audio_path: &Path,
+    chunk: &AudioChunk,
+    extension: &str,
+) -> Result<PathBuf, SubtitleError> {
+    let chunk_path = std::env::temp_dir().join(format!(
+        "clipforge_chunk_{}_{}.{}", uuid::Uuid::new_v4(), chunk.index, extension
+    ));
+
+    let output = tokio::process::Command::new(ffmpeg_path()?)
+        .arg("-y")
+        .arg("-ss")
+        .arg(format!("{}", chunk.start))
+        .arg("-t")
+        .arg(format!("{}", chunk.duration()))
+        .arg("-i")
+        .arg(audio_path)
+        .arg("-ar")
+        .arg("16000")
+        .arg("-ac")
+        .arg("1")
+        .args(audio_codec_args(extension))
+        .arg(&chunk_path)
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        error!("FFmpeg chunk extraction failed: {}", stderr);
+        return Err(SubtitleError::FFmpegError(format!("Failed to extract chunk {}: {}", chunk.index, stderr)));
+    }
+
+    Ok(chunk_path)
+}
+
+/// The system FFmpeg, or the bundled one when none is installed
+pub fn ffmpeg_path() -> Result<PathBuf, SubtitleError> {
+    ffmpeg_utils::find_ffmpeg_path()
+        .map_err(|e| SubtitleError::FFmpegError(format!("FFmpeg not found: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_silencedetect() {
+        let stderr = "\
+Input #0, mp3, from 'audio.mp3':
+  Duration: 00:20:00.50, start: 0.025057, bitrate: 64 kb/s
+[silencedetect @ 0x1] silence_start: 295.2
+[silencedetect @ 0x1] silence_end: 296.4 | silence_duration: 1.2
+[silencedetect @ 0x1] silence_start: 1195.5
+";
+        let (duration, silences) = parse_silencedetect(stderr);
+        assert_eq!(duration, Some(1200.5));
+        assert_eq!(silences, vec![(295.2, 296.4), (1195.5, 1200.5)]);
+    }
+
+    #[test]
+    fn test_short_audio_is_one_chunk() {
+        let chunks = plan_chunks(120.0, &[], MAX_CHUNK_SECONDS);
+        assert_eq!(chunks, vec![AudioChunk { index: 0, start: 0.0, end: 120.0 }]);
+    }
+
+    #[test]
+    fn test_chunks_cut_in_pauses() {
+        let silences = [(100.0, 101.0), (400.0, 402.0), (550.0, 551.0), (700.0, 800.0)];
+        let chunks = plan_chunks(1500.0, &silences, 600.0);
+
+        // Last pause in the second half of the first 600s, then no pause
+        // between 1100s and 1150s so the second chunk is cut at the limit
+        assert_eq!(chunks.len(), 3);
+        assert_eq!((chunks[0].start, chunks[0].end), (0.0, 550.5));
+        assert_eq!((chunks[1].start, chunks[1].end), (550.5, 1150.5));
+        assert_eq!((chunks[2].start, chunks[2].end), (1150.5, 1500.0));
+        assert!(chunks.iter().all(|c| c.duration() <= 600.0));
+    }
+}
@@ -2,21 +2,60 @@ use std::path::PathBuf;
 use tauri::State;
 use crate::file_service::FileService;
 use crate::metadata::extract_metadata;
-use crate::models::{MediaFile, FileMetadata};
+use crate::models::{MediaFile, MediaMarker, MediaType, FileMetadata};
+use crate::scene_detection::SceneDetectionOptions;
 use crate::error_handler::{handle_command_error, handle_command_error_with_context};
 
 /// Import a media file into the library
+///
+/// With `scene_detection` set, videos are also analyzed for shot boundaries.
+/// A failed analysis is logged and does not fail the import.
 #[tauri::command]
 pub async fn import_media_file(
     path: String,
+    scene_detection: Option<SceneDetectionOptions>,
     file_service: State<'_, FileService>
 ) -> Result<MediaFile, String> {
     log::info!("Command: import_media_file({})", path);
     let path_buf = PathBuf::from(&path);
-    file_service
+    let media_file = file_service
         .import_file(path_buf)
         .await
-        .map_err(|e| handle_command_error_with_context(e, "Failed to import media file", &path))
+        .map_err(|e| handle_command_error_with_context(e, "Failed to import media file", &path))?;
+
+    if let (Some(options), MediaType::Video) = (scene_detection, &media_file.media_type) {
+        if let Err(e) = file_service.detect_scenes(&media_file.id, &options).await {
+            log::warn!("Scene detection failed for {}: {}", path, e);
+        }
+    }
+
+    Ok(media_file)
+}
+
+/// Detect shot boundaries in a video, replacing previously detected ones
+#[tauri::command]
+pub async fn detect_scene_cuts(
+    id: String,
+    options: Option<SceneDetectionOptions>,
+    file_service: State<'_, FileService>
+) -> Result<Vec<MediaMarker>, String> {
+    log::info!("Command: detect_scene_cuts({})", id);
+    file_service
+        .detect_scenes(&id, &options.unwrap_or_default())
+        .await
+        .map_err(|e| handle_command_error_with_context(e, "Failed to detect scene cuts", &id))
+}
+
+/// Get the markers stored for a media file, such as scene cuts
+#[tauri::command]
+pub async fn get_media_markers(
+    id: String,
+    file_service: State<'_, FileService>
+) -> Result<Vec<MediaMarker>, String> {
+    log::info!("Command: get_media_markers({})", id);
+    file_service
+        .get_markers(&id)
+        .map_err(|e| handle_command_error_with_context(e, "Failed to get media markers", &id))
 }
 
 /// Get all media files in the library
@@ -69,6 +108,29 @@ pub async fn get_file_metadata(
         .map_err(|e| handle_command_error_with_context(e, "Failed to extract metadata", &path))
 }
 
+/// Get the clip length given to imported still images
+#[tauri::command]
+pub async fn get_default_image_duration(
+    file_service: State<'_, FileService>
+) -> Result<f64, String> {
+    log::info!("Command: get_default_image_duration()");
+    file_service
+        .default_image_duration()
+        .map_err(|e| handle_command_error(e, "Failed to get default image duration"))
+}
+
+/// Set the clip length given to imported still images
+#[tauri::command]
+pub async fn set_default_image_duration(
+    seconds: f64,
+    file_service: State<'_, FileService>
+) -> Result<(), String> {
+    log::info!("Command: set_default_image_duration({})", seconds);
+    file_service
+        .set_default_image_duration(seconds)
+        .map_err(|e| handle_command_error(e, "Failed to set default image duration"))
+}
+
 /// Generate a thumbnail for a video file at a specific timestamp
 #[tauri::command]
 pub async fn generate_thumbnail(
@@ -1,22 +1,45 @@
 // Tauri commands for screen recording
 
+use crate::ffmpeg_utils;
+use crate::file_service::FileService;
+use crate::models::{MediaFile, Timeline};
+use crate::recording::integration::{assemble_timeline, auto_import_recording};
+use crate::recording::journal::{self, RecordingJournal, UnfinishedRecording};
+use crate::recording::limits;
+use crate::recording::meter::{self, AudioMeter, MeterEvent};
+use crate::recording::segments::SegmentedOutput;
 use crate::recording::{
-    PlatformRecorder, RecordingConfig, RecordingSource, RecordingState, ScreenRecorder, SourceTypeFilter,
+    AudioDevice, PlatformRecorder, RecordingConfig, RecordingError, RecordingOutput, RecordingSource, RecordingState,
+    ScreenRecorder, SourceTypeFilter,
 };
+use crate::timeline_commands::TimelineServiceState;
+use chrono::Utc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tauri::{AppHandle, Emitter, State};
+use tauri::{AppHandle, Emitter, Manager, State};
 use tokio::sync::Mutex;
-use log::{info, error};
+use tokio::task::JoinHandle;
+use log::{info, warn, error};
 
 /// Global recording service state
 pub struct RecordingService {
     recorder: Arc<Mutex<PlatformRecorder>>,
+    /// Task waiting to start a scheduled or counted-down recording
+    scheduled: Mutex<Option<JoinHandle<()>>>,
+    /// Level meter on the audio input being previewed or recorded
+    meter: std::sync::Mutex<Option<AudioMeter>>,
+    /// Changes whenever a recording starts or stops, so the task watching a
+    /// recording can tell when it is over
+    recording_id: AtomicU64,
 }
 
 impl RecordingService {
     pub fn new() -> Self {
         Self {
             recorder: Arc::new(Mutex::new(PlatformRecorder::new())),
+            scheduled: Mutex::new(None),
+            meter: std::sync::Mutex::new(None),
+            recording_id: AtomicU64::new(0),
         }
     }
 
@@ -27,6 +50,21 @@ impl RecordingService {
     pub fn get_recorder_arc(&self) -> Arc<Mutex<PlatformRecorder>> {
         Arc::clone(&self.recorder)
     }
+
+    /// Move on to the next recording ID; call with the recorder locked
+    fn next_recording_id(&self) -> u64 {
+        self.recording_id.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    fn is_current_recording(&self, id: u64) -> bool {
+        self.recording_id.load(Ordering::SeqCst) == id
+    }
+
+    /// Replace the running level meter, if any
+    fn set_meter(&self, meter: Option<AudioMeter>) {
+        // The old meter's process is stopped as it is dropped
+        *self.meter.lock().unwrap_or_else(|e| e.into_inner()) = meter;
+    }
 }
 
 /// List available recording sources filtered by type
@@ -85,7 +123,77 @@ pub async fn request_recording_permissions(
         })
 }
 
+/// List audio input devices a recording can capture
+#[tauri::command]
+pub async fn list_audio_devices(
+    service: State<'_, RecordingService>,
+) -> Result<Vec<AudioDevice>, String> {
+    info!("Command: list_audio_devices");
+
+    let recorder = service.get_recorder().await;
+
+    recorder
+        .list_audio_devices()
+        .await
+        .map_err(|e| {
+            error!("Failed to list audio devices: {}", e);
+            e.to_string()
+        })
+}
+
+/// Start metering an audio input, e.g. to preview it before recording
+///
+/// Levels are emitted as `recording:audio_level` ten times a second, and
+/// `recording:audio_warning` when the input has been silent or clipping for
+/// `warning_seconds` and again when that ends. Replaces any meter already
+/// running; recording starts its own meter on the device it records.
+#[tauri::command]
+pub async fn start_audio_meter(
+    app: AppHandle,
+    service: State<'_, RecordingService>,
+    device_id: Option<String>,
+    warning_seconds: Option<f64>,
+) -> Result<(), String> {
+    let device = device_id.unwrap_or_else(|| meter::DEFAULT_DEVICE.to_string());
+    info!("Command: start_audio_meter ({})", device);
+
+    let warn_after = warning_seconds.unwrap_or(RecordingConfig::default().audio_warning_seconds);
+    service.set_meter(Some(spawn_meter(&app, &device, warn_after)?));
+    Ok(())
+}
+
+/// Stop metering audio input
+#[tauri::command]
+pub async fn stop_audio_meter(
+    service: State<'_, RecordingService>,
+) -> Result<(), String> {
+    info!("Command: stop_audio_meter");
+
+    service.set_meter(None);
+    Ok(())
+}
+
+fn spawn_meter(app: &AppHandle, device: &str, warn_after: f64) -> Result<AudioMeter, String> {
+    let ffmpeg_path = ffmpeg_utils::find_ffmpeg_path()?;
+    let app = app.clone();
+    AudioMeter::spawn(&ffmpeg_path, device, warn_after, move |event| {
+        let _ = match event {
+            MeterEvent::Levels(levels) => app.emit("recording:audio_level", &levels),
+            MeterEvent::Warning(warning) => app.emit("recording:audio_warning", &warning),
+        };
+    })
+    .map_err(|e| {
+        error!("Failed to start audio meter: {}", e);
+        e.to_string()
+    })
+}
+
 /// Start screen recording
+///
+/// With a countdown or a scheduled start, the recording is started later by
+/// a background task: `recording:scheduled` is emitted now, then
+/// `recording:countdown` every second of the countdown, and
+/// `recording:error` if starting fails.
 #[tauri::command]
 pub async fn start_recording(
     app: AppHandle,
@@ -95,48 +203,227 @@ pub async fn start_recording(
 ) -> Result<(), String> {
     info!("Command: start_recording for source: {}", source.name());
 
-    let mut recorder = service.get_recorder().await;
+    let now = Utc::now();
+    limits::validate(&config, now).map_err(|e| e.to_string())?;
+
+    let mut scheduled = service.scheduled.lock().await;
+    if scheduled.as_ref().is_some_and(|task| !task.is_finished()) {
+        return Err("A recording is already scheduled".to_string());
+    }
+
+    let recorder_arc = service.get_recorder_arc();
+    let start_at = limits::start_time(&config, now);
+    if start_at <= now {
+        return begin_recording(app, recorder_arc, source, config).await;
+    }
+
+    if recorder_arc.lock().await.get_state().is_active() {
+        return Err(RecordingError::AlreadyRecording.to_string());
+    }
+
+    info!("Recording scheduled for {}", start_at);
+    let _ = app.emit("recording:scheduled", serde_json::json!({
+        "start_at": start_at
+    }));
+
+    *scheduled = Some(tokio::spawn(async move {
+        loop {
+            let now = Utc::now();
+            if now >= start_at {
+                break;
+            }
+            if let Some(remaining) = limits::countdown_remaining(&config, start_at, now) {
+                let _ = app.emit("recording:countdown", serde_json::json!({
+                    "remaining": remaining
+                }));
+            }
+
+            // Tick on whole seconds before the start so the countdown lands on it
+            let until_start = (start_at - now).num_milliseconds();
+            let tick = match until_start % 1000 {
+                0 => 1000,
+                part => part,
+            };
+            tokio::time::sleep(tokio::time::Duration::from_millis(tick as u64)).await;
+        }
+
+        if let Err(e) = begin_recording(app.clone(), recorder_arc, source, config).await {
+            let _ = app.emit("recording:error", serde_json::json!({
+                "message": e
+            }));
+        }
+    }));
+
+    Ok(())
+}
+
+/// Cancel a recording waiting for its scheduled start or countdown
+///
+/// Returns whether there was one to cancel.
+#[tauri::command]
+pub async fn cancel_scheduled_recording(
+    app: AppHandle,
+    service: State<'_, RecordingService>,
+) -> Result<bool, String> {
+    info!("Command: cancel_scheduled_recording");
+
+    let mut scheduled = service.scheduled.lock().await;
+    match scheduled.take() {
+        Some(task) if !task.is_finished() => {
+            task.abort();
+            let _ = app.emit("recording:cancelled", ());
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Start the recorder, then watch the recording in the background
+async fn begin_recording(
+    app: AppHandle,
+    recorder_arc: Arc<Mutex<PlatformRecorder>>,
+    source: RecordingSource,
+    config: RecordingConfig,
+) -> Result<(), String> {
+    let mut recorder = recorder_arc.lock().await;
 
     recorder
-        .start_recording(&source, config)
+        .start_recording(&source, config.clone())
         .await
         .map_err(|e| {
             error!("Failed to start recording: {}", e);
             e.to_string()
         })?;
+    let service = app.state::<RecordingService>();
+    let recording_id = service.next_recording_id();
+    drop(recorder);
 
     // Emit event that recording started
     let _ = app.emit("recording:started", ());
 
-    // Start duration update task
-    let recorder_arc = service.get_recorder_arc();
-    let app_clone = app.clone();
+    // Meter what is being recorded; a failing meter doesn't stop the recording
+    match meter::recording_device(&config) {
+        Some(device) => match spawn_meter(&app, &device, config.audio_warning_seconds) {
+            Ok(meter) => service.set_meter(Some(meter)),
+            Err(e) => {
+                warn!("Recording without an audio meter: {}", e);
+                service.set_meter(None);
+            }
+        },
+        None => service.set_meter(None),
+    }
 
-    tokio::spawn(async move {
-        loop {
-            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+    tokio::spawn(monitor_recording(app, recorder_arc, recording_id, config));
+    Ok(())
+}
 
-            let recorder = recorder_arc.lock().await;
-            let state = recorder.get_state();
+/// Report a recording's progress and stop it when one of its limits is reached
+///
+/// Emits `recording:duration` twice a second with the duration, the bytes
+/// written and the average bitrate, and `recording:autostop` with the reason
+/// before stopping the recording itself. Ends with the recording
+/// `recording_id` names, even if another one starts right after it.
+async fn monitor_recording(
+    app: AppHandle,
+    recorder_arc: Arc<Mutex<PlatformRecorder>>,
+    recording_id: u64,
+    config: RecordingConfig,
+) {
+    loop {
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
 
-            if !state.is_recording() {
-                break;
-            }
+        let recorder = recorder_arc.lock().await;
+        if !app.state::<RecordingService>().is_current_recording(recording_id) {
+            break;
+        }
 
-            let duration = recorder.get_duration();
-            drop(recorder); // Release lock before emitting
+        // Keep going while paused; the duration just doesn't move
+        if !recorder.get_state().is_active() {
+            break;
+        }
 
-            // Emit duration update
-            let _ = app_clone.emit("recording:duration", serde_json::json!({
-                "duration": duration
+        let duration = recorder.get_duration();
+        let file_size = recorder.get_output_size();
+        drop(recorder); // Release lock before emitting
+
+        // Emit duration update
+        let _ = app.emit("recording:duration", serde_json::json!({
+            "duration": duration,
+            "file_size": file_size,
+            "bitrate": limits::bitrate(file_size, duration),
+        }));
+
+        if let Some(reason) = limits::stop_reason(&config, duration, file_size, Utc::now()) {
+            info!("Stopping recording automatically: {:?}", reason);
+            let _ = app.emit("recording:autostop", serde_json::json!({
+                "reason": reason,
+                "duration": duration,
+                "file_size": file_size,
             }));
+
+            if let Err(e) = finish_recording(&app, &recorder_arc, Some(recording_id)).await {
+                let _ = app.emit("recording:error", serde_json::json!({
+                    "message": e
+                }));
+            }
+            break;
         }
-    });
+    }
+}
+
+/// Pause screen recording
+#[tauri::command]
+pub async fn pause_recording(
+    app: AppHandle,
+    service: State<'_, RecordingService>,
+) -> Result<(), String> {
+    info!("Command: pause_recording");
+
+    let mut recorder = service.get_recorder().await;
+
+    recorder
+        .pause_recording()
+        .await
+        .map_err(|e| {
+            error!("Failed to pause recording: {}", e);
+            e.to_string()
+        })?;
 
+    let _ = app.emit("recording:paused", serde_json::json!({
+        "duration": recorder.get_duration()
+    }));
+    Ok(())
+}
+
+/// Resume a paused screen recording
+#[tauri::command]
+pub async fn resume_recording(
+    app: AppHandle,
+    service: State<'_, RecordingService>,
+) -> Result<(), String> {
+    info!("Command: resume_recording");
+
+    let mut recorder = service.get_recorder().await;
+
+    recorder
+        .resume_recording()
+        .await
+        .map_err(|e| {
+            error!("Failed to resume recording: {}", e);
+            e.to_string()
+        })?;
+
+    let _ = app.emit("recording:resumed", serde_json::json!({
+        "duration": recorder.get_duration()
+    }));
     Ok(())
 }
 
 /// Stop screen recording
+///
+/// A recording of several files, screen and webcam or system audio apart
+/// from the microphone, is imported and added to the end of the current
+/// timeline, announced with `recording:assembled`.
 #[tauri::command]
 pub async fn stop_recording(
     app: AppHandle,
@@ -144,27 +431,82 @@ pub async fn stop_recording(
 ) -> Result<String, String> {
     info!("Command: stop_recording");
 
-    let mut recorder = service.get_recorder().await;
+    finish_recording(&app, &service.get_recorder_arc(), None).await
+}
 
-    let output_path = recorder
+/// Stop the recorder and announce the finished recording
+///
+/// With `recording_id`, only stops that recording, not one started since.
+async fn finish_recording(
+    app: &AppHandle,
+    recorder_arc: &Arc<Mutex<PlatformRecorder>>,
+    recording_id: Option<u64>,
+) -> Result<String, String> {
+    let mut recorder = recorder_arc.lock().await;
+    let service = app.state::<RecordingService>();
+    if recording_id.is_some_and(|id| !service.is_current_recording(id)) {
+        return Err(RecordingError::NotRecording.to_string());
+    }
+
+    let output = recorder
         .stop_recording()
         .await
         .map_err(|e| {
             error!("Failed to stop recording: {}", e);
             e.to_string()
         })?;
+    service.next_recording_id();
+    drop(recorder); // New recordings can start while this one is imported
+    service.set_meter(None);
 
-    let output_path_str = output_path.to_string_lossy().to_string();
+    let output_path_str = output.path.to_string_lossy().to_string();
 
     // Emit event that recording stopped with file_path in payload
     let _ = app.emit("recording:stopped", serde_json::json!({
-        "file_path": output_path_str.clone()
+        "file_path": output_path_str.clone(),
+        "webcam_path": output.webcam.as_ref().map(|webcam| webcam.path.to_string_lossy().to_string()),
+        "system_audio_path": output.system_audio.as_ref().map(|path| path.to_string_lossy().to_string()),
     }));
 
+    if output.webcam.is_some() || output.system_audio.is_some() {
+        let file_service = app.state::<FileService>();
+        let timeline_state = app.state::<TimelineServiceState>();
+        match assemble_recording(&file_service, &timeline_state, &output).await {
+            Ok(timeline) => {
+                let _ = app.emit("recording:assembled", &timeline);
+            }
+            Err(e) => error!("Failed to assemble recording into a timeline: {}", e),
+        }
+    }
+
     info!("Recording saved to: {}", output_path_str);
     Ok(output_path_str)
 }
 
+/// Import a multi-file recording and add it to the current timeline
+async fn assemble_recording(
+    file_service: &FileService,
+    timeline_state: &TimelineServiceState,
+    output: &RecordingOutput,
+) -> Result<Timeline, String> {
+    let screen = auto_import_recording(file_service, &output.path).await?;
+    let webcam = match &output.webcam {
+        Some(webcam) => Some(auto_import_recording(file_service, &webcam.path).await?),
+        None => None,
+    };
+    let system_audio = match &output.system_audio {
+        Some(path) => Some(auto_import_recording(file_service, path).await?),
+        None => None,
+    };
+
+    let name = output.path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "Recording".to_string());
+    let mut timeline_service = timeline_state.service.lock().await;
+    assemble_timeline(&mut timeline_service, name, output, &screen, webcam.as_ref(), system_audio.as_ref())
+        .map_err(|e| e.to_string())
+}
+
 /// Get current recording state
 #[tauri::command]
 pub async fn get_recording_state(
@@ -183,6 +525,59 @@ pub async fn get_recording_duration(
     Ok(recorder.get_duration())
 }
 
+/// List recordings an earlier run of the app never finished, e.g. because it crashed
+#[tauri::command]
+pub async fn list_unfinished_recordings() -> Result<Vec<UnfinishedRecording>, String> {
+    info!("Command: list_unfinished_recordings");
+
+    let Some(dir) = journal::journal_dir() else {
+        return Ok(Vec::new());
+    };
+    Ok(journal::find_orphaned(&dir).into_iter().map(UnfinishedRecording::from).collect())
+}
+
+/// Remux an unfinished recording's segments into its file and import it
+#[tauri::command]
+pub async fn recover_recording(
+    file_service: State<'_, FileService>,
+    id: String,
+) -> Result<MediaFile, String> {
+    info!("Command: recover_recording ({})", id);
+
+    let (dir, journal) = load_unfinished_recording(&id)?;
+    let ffmpeg_path = ffmpeg_utils::find_ffmpeg_path()?;
+    let path = SegmentedOutput::from_journal(dir, journal)
+        .recover(&ffmpeg_path)
+        .map_err(|e| {
+            error!("Failed to recover recording {}: {}", id, e);
+            e.to_string()
+        })?;
+
+    auto_import_recording(&file_service, &path).await
+}
+
+/// Delete an unfinished recording's segments instead of recovering it
+#[tauri::command]
+pub async fn discard_unfinished_recording(id: String) -> Result<(), String> {
+    info!("Command: discard_unfinished_recording ({})", id);
+
+    let (dir, journal) = load_unfinished_recording(&id)?;
+    SegmentedOutput::from_journal(dir, journal).discard();
+    Ok(())
+}
+
+fn load_unfinished_recording(id: &str) -> Result<(std::path::PathBuf, RecordingJournal), String> {
+    let dir = journal::journal_dir()
+        .ok_or_else(|| "No data directory for recording journals".to_string())?;
+    let journal = RecordingJournal::load(&dir, id).map_err(|e| e.to_string())?;
+
+    // The journal of a recording still in progress is not up for recovery
+    if !journal.is_orphaned() {
+        return Err("Recording is still in progress".to_string());
+    }
+    Ok((dir, journal))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,144 @@
+use crate::credentials::{self, ProviderCredential, ProviderCredentialInfo, SecretStore, OPENAI_PROVIDER};
+use crate::subtitle::SubtitleService;
+use crate::subtitle_commands::SubtitleServiceState;
+use crate::transcription::{TranscriptionConfig, OPENAI_BASE_URL};
+use log::{error, info};
+use std::sync::Arc;
+use tauri::State;
+use tokio::sync::Mutex;
+
+/// Shared credential store state
+pub struct CredentialState {
+    pub store: Arc<Mutex<Box<dyn SecretStore>>>,
+}
+
+/// Transcription service for the saved OpenAI key, if there is one
+pub fn restore_transcription_service(store: &dyn SecretStore) -> Option<SubtitleService> {
+    let credential = match store.get(OPENAI_PROVIDER) {
+        Ok(credential) => credential?,
+        Err(e) => {
+            error!("Failed to read saved OpenAI credentials: {}", e);
+            return None;
+        }
+    };
+
+    let provider = transcription_config(&credential).build().ok()?;
+    match SubtitleService::new(provider) {
+        Ok(service) => {
+            info!("Restored transcription provider from saved credentials");
+            Some(service)
+        }
+        Err(e) => {
+            error!("Failed to initialize subtitle service: {}", e);
+            None
+        }
+    }
+}
+
+fn transcription_config(credential: &ProviderCredential) -> TranscriptionConfig {
+    let mut config = TranscriptionConfig::openai(credential.api_key.clone());
+    if let (TranscriptionConfig::OpenAiCompatible { base_url, .. }, Some(custom)) = (&mut config, &credential.base_url) {
+        *base_url = custom.clone();
+    }
+    config
+}
+
+/// Save a provider's API key; the OpenAI key also becomes the transcription provider
+#[tauri::command]
+pub async fn set_provider_credential(
+    provider: String,
+    api_key: String,
+    base_url: Option<String>,
+    state: State<'_, CredentialState>,
+    subtitle_state: State<'_, SubtitleServiceState>,
+) -> Result<(), String> {
+    save_provider_credential(&state, &subtitle_state, &provider, api_key, base_url).await
+}
+
+/// Save a provider's trimmed key, switching transcription to it only once saved
+pub async fn save_provider_credential(
+    state: &CredentialState,
+    subtitle_state: &SubtitleServiceState,
+    provider: &str,
+    api_key: String,
+    base_url: Option<String>,
+) -> Result<(), String> {
+    // The service below must use exactly what was saved
+    let credential = ProviderCredential {
+        api_key: api_key.trim().to_string(),
+        base_url: base_url.map(|url| url.trim().to_string()).filter(|url| !url.is_empty()),
+    };
+
+    state.store.lock().await
+        .set(provider, credential.clone())
+        .map_err(|e| format!("Failed to save credentials: {}", e))?;
+
+    if provider == OPENAI_PROVIDER {
+        let service = transcription_config(&credential).build()
+            .and_then(SubtitleService::new)
+            .map_err(|e| format!("Failed to initialize subtitle service: {}", e))?;
+        *subtitle_state.service.lock().await = Some(service);
+    }
+
+    Ok(())
+}
+
+/// Check a saved key against its provider's endpoint
+#[tauri::command]
+pub async fn test_provider_credential(
+    provider: String,
+    state: State<'_, CredentialState>,
+) -> Result<(), String> {
+    let credential = state.store.lock().await
+        .get(&provider)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("No credentials saved for {}", provider))?;
+
+    // Only the OpenAI key may go to OpenAI's endpoint
+    if provider != OPENAI_PROVIDER && credential.base_url.is_none() {
+        return Err(format!("No endpoint saved for {}; save its key with a base URL to test it", provider));
+    }
+
+    info!("Testing credentials for provider: {}", provider);
+    credentials::test_credential(&credential, OPENAI_BASE_URL).await
+}
+
+/// Delete a provider's saved key, returning whether one was saved
+#[tauri::command]
+pub async fn clear_provider_credential(
+    provider: String,
+    state: State<'_, CredentialState>,
+    subtitle_state: State<'_, SubtitleServiceState>,
+) -> Result<bool, String> {
+    let mut store = state.store.lock().await;
+
+    // Name of the transcription provider that relies on the key, to stop using it
+    let dependent_provider = match store.get(&provider) {
+        Ok(Some(credential)) if provider == OPENAI_PROVIDER => {
+            transcription_config(&credential).build().ok().map(|p| p.name())
+        }
+        _ => None,
+    };
+
+    let removed = store.remove(&provider)
+        .map_err(|e| format!("Failed to clear credentials: {}", e))?;
+
+    if let Some(name) = dependent_provider {
+        let mut service = subtitle_state.service.lock().await;
+        if service.as_ref().map(|s| s.provider_name()) == Some(name) {
+            *service = None;
+        }
+    }
+
+    Ok(removed)
+}
+
+/// Providers with saved credentials (never the keys themselves)
+#[tauri::command]
+pub async fn list_provider_credentials(
+    state: State<'_, CredentialState>,
+) -> Result<Vec<ProviderCredentialInfo>, String> {
+    state.store.lock().await
+        .list()
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,367 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{engine::general_purpose, Engine as _};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Provider id under which the hosted OpenAI key is stored
+pub const OPENAI_PROVIDER: &str = "openai";
+
+/// Credential storage errors
+#[derive(Debug, thiserror::Error)]
+pub enum CredentialError {
+    #[error("Invalid provider id: {0}")]
+    InvalidProvider(String),
+
+    #[error("API key cannot be empty")]
+    EmptyKey,
+
+    #[error("Credential store is corrupt: {0}")]
+    Corrupt(String),
+
+    #[error("Failed to decrypt credentials for {0}; the key file may have been replaced")]
+    DecryptionFailed(String),
+
+    #[error("Data directory not found")]
+    NoDataDir,
+
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+/// API key and endpoint for a provider
+#[derive(Clone)]
+pub struct ProviderCredential {
+    pub api_key: String,
+    pub base_url: Option<String>,  // Endpoint the key belongs to, the provider default when unset
+}
+
+/// Keep keys out of logs and error messages
+impl std::fmt::Debug for ProviderCredential {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProviderCredential")
+            .field("api_key", &"<redacted>")
+            .field("base_url", &self.base_url)
+            .finish()
+    }
+}
+
+/// What the frontend may see about a stored credential
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderCredentialInfo {
+    pub provider: String,
+    pub base_url: Option<String>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Storage for provider API keys
+///
+/// Implemented by the encrypted file store used by the app and by an
+/// in-memory store for tests; an OS keychain backend can be added behind
+/// the same trait.
+pub trait SecretStore: Send + Sync {
+    fn get(&self, provider: &str) -> Result<Option<ProviderCredential>, CredentialError>;
+
+    fn set(&mut self, provider: &str, credential: ProviderCredential) -> Result<(), CredentialError>;
+
+    /// Remove a credential, returning whether one was stored
+    fn remove(&mut self, provider: &str) -> Result<bool, CredentialError>;
+
+    fn list(&self) -> Result<Vec<ProviderCredentialInfo>, CredentialError>;
+}
+
+/// Check a provider id and a credential before storing them
+fn validate(provider: &str, credential: &ProviderCredential) -> Result<(), CredentialError> {
+    let valid_id = !provider.is_empty()
+        && provider.len() <= 64
+        && provider.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid_id {
+        return Err(CredentialError::InvalidProvider(provider.to_string()));
+    }
+    if credential.api_key.trim().is_empty() {
+        return Err(CredentialError::EmptyKey);
+    }
+    Ok(())
+}
+
+/// Credentials kept in memory only
+#[derive(Default)]
+pub struct MemorySecretStore {
+    entries: BTreeMap<String, (ProviderCredential, chrono::DateTime<chrono::Utc>)>,
+}
+
+impl SecretStore for MemorySecretStore {
+    fn get(&self, provider: &str) -> Result<Option<ProviderCredential>, CredentialError> {
+        Ok(self.entries.get(provider).map(|(credential, _)| credential.clone()))
+    }
+
+    fn set(&mut self, provider: &str, credential: ProviderCredential) -> Result<(), CredentialError> {
+        validate(provider, &credential)?;
+        let credential = ProviderCredential { api_key: credential.api_key.trim().to_string(), ..credential };
+        self.entries.insert(provider.to_string(), (credential, chrono::Utc::now()));
+        Ok(())
+    }
+
+    fn remove(&mut self, provider: &str) -> Result<bool, CredentialError> {
+        Ok(self.entries.remove(provider).is_some())
+    }
+
+    fn list(&self) -> Result<Vec<ProviderCredentialInfo>, CredentialError> {
+        Ok(self.entries.iter()
+            .map(|(provider, (credential, updated_at))| ProviderCredentialInfo {
+                provider: provider.clone(),
+                base_url: credential.base_url.clone(),
+                updated_at: *updated_at,
+            })
+            .collect())
+    }
+}
+
+/// Encrypted entry as written to disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EncryptedEntry {
+    base_url: Option<String>,
+    updated_at: chrono::DateTime<chrono::Utc>,
+    nonce: String,       // Base64, 96 bits
+    ciphertext: String,  // Base64 AES-256-GCM of the API key, authenticated with the provider id
+}
+
+/// Credentials encrypted with AES-256-GCM in the app data directory
+///
+/// The data key lives in a separate owner-only file, so the credentials
+/// file on its own (in a backup or synced folder) does not reveal keys.
+pub struct EncryptedFileStore {
+    path: PathBuf,
+    cipher: Aes256Gcm,
+    entries: BTreeMap<String, EncryptedEntry>,
+}
+
+impl EncryptedFileStore {
+    /// Open the store in `~/.local/share/clipforge` (or the platform equivalent)
+    pub fn open_default() -> Result<Self, CredentialError> {
+        let dir = dirs::data_local_dir()
+            .ok_or(CredentialError::NoDataDir)?
+            .join("clipforge");
+        Self::open(&dir.join("credentials.json"), &dir.join("credentials.key"))
+    }
+
+    /// Open a store, creating its key file on first use
+    pub fn open(path: &Path, key_path: &Path) -> Result<Self, CredentialError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let key = match fs::read(key_path) {
+            Ok(bytes) if bytes.len() == 32 => *Key::<Aes256Gcm>::from_slice(&bytes),
+            Ok(_) => return Err(CredentialError::Corrupt("Key file has the wrong length".to_string())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let key = Aes256Gcm::generate_key(OsRng);
+                write_private(key_path, key.as_slice())?;
+                info!("Created credential key file");
+                key
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let entries = match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            cipher: Aes256Gcm::new(&key),
+            entries,
+        })
+    }
+
+    fn save(&self) -> Result<(), CredentialError> {
+        let json = serde_json::to_string_pretty(&self.entries)?;
+        write_private(&self.path, json.as_bytes())
+    }
+}
+
+impl SecretStore for EncryptedFileStore {
+    fn get(&self, provider: &str) -> Result<Option<ProviderCredential>, CredentialError> {
+        let entry = match self.entries.get(provider) {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        let nonce = general_purpose::STANDARD.decode(&entry.nonce)
+            .map_err(|e| CredentialError::Corrupt(e.to_string()))?;
+        let ciphertext = general_purpose::STANDARD.decode(&entry.ciphertext)
+            .map_err(|e| CredentialError::Corrupt(e.to_string()))?;
+        if nonce.len() != 12 {
+            return Err(CredentialError::Corrupt(format!("Bad nonce for {}", provider)));
+        }
+
+        let plaintext = self.cipher
+            .decrypt(Nonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: provider.as_bytes() })
+            .map_err(|_| CredentialError::DecryptionFailed(provider.to_string()))?;
+        let api_key = String::from_utf8(plaintext)
+            .map_err(|_| CredentialError::DecryptionFailed(provider.to_string()))?;
+
+        Ok(Some(ProviderCredential {
+            api_key,
+            base_url: entry.base_url.clone(),
+        }))
+    }
+
+    fn set(&mut self, provider: &str, credential: ProviderCredential) -> Result<(), CredentialError> {
+        validate(provider, &credential)?;
+
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self.cipher
+            .encrypt(&nonce, Payload { msg: credential.api_key.trim().as_bytes(), aad: provider.as_bytes() })
+            .map_err(|_| CredentialError::Corrupt("Encryption failed".to_string()))?;
+
+        self.entries.insert(provider.to_string(), EncryptedEntry {
+            base_url: credential.base_url,
+            updated_at: chrono::Utc::now(),
+            nonce: general_purpose::STANDARD.encode(nonce),
+            ciphertext: general_purpose::STANDARD.encode(ciphertext),
+        });
+        self.save()?;
+
+        info!("Stored credentials for provider: {}", provider);
+        Ok(())
+    }
+
+    fn remove(&mut self, provider: &str) -> Result<bool, CredentialError> {
+        if self.entries.remove(provider).is_none() {
+            return Ok(false);
+        }
+        self.save()?;
+
+        info!("Removed credentials for provider: {}", provider);
+        Ok(true)
+    }
+
+    fn list(&self) -> Result<Vec<ProviderCredentialInfo>, CredentialError> {
+        Ok(self.entries.iter()
+            .map(|(provider, entry)| ProviderCredentialInfo {
+                provider: provider.clone(),
+                base_url: entry.base_url.clone(),
+                updated_at: entry.updated_at,
+            })
+            .collect())
+    }
+}
+
+/// Write a file readable only by the current user
+fn write_private(path: &Path, contents: &[u8]) -> Result<(), CredentialError> {
+    // Write beside the target and rename, so a crash never leaves half a file
+    let temp_path = path.with_extension("tmp");
+
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        use std::os::unix::fs::OpenOptionsExt;
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+    }
+    #[cfg(not(unix))]
+    fs::write(&temp_path, contents)?;
+
+    fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+/// Check a key against an OpenAI-compatible endpoint by listing its models
+pub async fn test_credential(credential: &ProviderCredential, default_base_url: &str) -> Result<(), String> {
+    let base_url = credential.base_url.as_deref().unwrap_or(default_base_url).trim_end_matches('/');
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(15))
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let response = client.get(format!("{}/models", base_url))
+        .bearer_auth(&credential.api_key)
+        .send()
+        .await
+        .map_err(|e| format!("Could not reach {}: {}", base_url, e.without_url()))?;
+
+    match response.status() {
+        status if status.is_success() => Ok(()),
+        reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN => {
+            Err("The API key was rejected".to_string())
+        }
+        status => {
+            warn!("Credential test against {} returned {}", base_url, status);
+            Err(format!("{} returned {}", base_url, status))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn credential(api_key: &str) -> ProviderCredential {
+        ProviderCredential { api_key: api_key.to_string(), base_url: None }
+    }
+
+    #[test]
+    fn test_encrypted_store_round_trip() {
+        let dir = std::env::temp_dir().join(format!("clipforge_credentials_{}", uuid::Uuid::new_v4()));
+        let (path, key_path) = (dir.join("credentials.json"), dir.join("credentials.key"));
+
+        let mut store = EncryptedFileStore::open(&path, &key_path).unwrap();
+        store.set(OPENAI_PROVIDER, credential("sk-secret-value")).unwrap();
+        store.set("local-llm", ProviderCredential {
+            api_key: "token".to_string(),
+            base_url: Some("http://localhost:11434/v1".to_string()),
+        }).unwrap();
+
+        // The key never reaches the disk in plain text
+        let on_disk = fs::read_to_string(&path).unwrap();
+        assert!(!on_disk.contains("sk-secret-value"));
+
+        let reopened = EncryptedFileStore::open(&path, &key_path).unwrap();
+        assert_eq!(reopened.get(OPENAI_PROVIDER).unwrap().unwrap().api_key, "sk-secret-value");
+        let providers: Vec<String> = reopened.list().unwrap().into_iter().map(|i| i.provider).collect();
+        assert_eq!(providers, vec!["local-llm", "openai"]);
+
+        // A replaced key file cannot decrypt the old entries
+        fs::remove_file(&key_path).unwrap();
+        let rekeyed = EncryptedFileStore::open(&path, &key_path).unwrap();
+        assert!(matches!(rekeyed.get(OPENAI_PROVIDER), Err(CredentialError::DecryptionFailed(_))));
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_store_validation_and_removal() {
+        let mut store = MemorySecretStore::default();
+
+        assert!(matches!(store.set("open ai", credential("key")), Err(CredentialError::InvalidProvider(_))));
+        assert!(matches!(store.set(OPENAI_PROVIDER, credential("  ")), Err(CredentialError::EmptyKey)));
+
+        // Stored trimmed, like the encrypted store
+        store.set(OPENAI_PROVIDER, credential(" key\n")).unwrap();
+        assert_eq!(store.get(OPENAI_PROVIDER).unwrap().unwrap().api_key, "key");
+        assert!(store.remove(OPENAI_PROVIDER).unwrap());
+        assert!(!store.remove(OPENAI_PROVIDER).unwrap());
+        assert!(store.get(OPENAI_PROVIDER).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_debug_output_hides_key() {
+        let debug = format!("{:?}", credential("sk-secret-value"));
+        assert!(!debug.contains("sk-secret-value"));
+    }
+}
@@ -0,0 +1,254 @@
+use crate::audio_chunks;
+use crate::models::{Clip, MediaFile, SubtitleTrack};
+use crate::transcript_edit::TimeRange;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Words flagged as filler when no list is given
+const DEFAULT_FILLER_WORDS: &[&str] = &["um", "umm", "uh", "uhm", "erm", "er", "ah", "hmm", "mm"];
+
+#[derive(Debug, Error)]
+pub enum CutAnalysisError {
+    #[error("Invalid analysis options: {0}")]
+    InvalidOptions(String),
+
+    #[error("Clip has no source media: {0}")]
+    NoSource(String),
+
+    #[error("Silence detection failed: {0}")]
+    DetectionFailed(String),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+/// Settings for finding parts of a clip worth cutting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CutAnalysisOptions {
+    pub noise_db: f64,  // Audio below this level counts as silence
+    pub min_silence: f64,  // Shortest silence suggested for cutting (seconds)
+    pub padding: f64,  // Silence kept on each side of speech (seconds)
+    pub detect_fillers: bool,  // Flag filler words using the transcript's word timing
+    pub filler_words: Vec<String>,
+}
+
+impl Default for CutAnalysisOptions {
+    fn default() -> Self {
+        Self {
+            noise_db: -35.0,
+            min_silence: 0.75,
+            padding: 0.15,
+            detect_fillers: true,
+            filler_words: DEFAULT_FILLER_WORDS.iter().map(|w| w.to_string()).collect(),
+        }
+    }
+}
+
+impl CutAnalysisOptions {
+    pub fn validate(&self) -> Result<(), CutAnalysisError> {
+        if !(-100.0..=0.0).contains(&self.noise_db) {
+            return Err(CutAnalysisError::InvalidOptions("noise threshold must be between -100 and 0 dB".to_string()));
+        }
+        if self.min_silence <= 0.0 {
+            return Err(CutAnalysisError::InvalidOptions("minimum silence must be positive".to_string()));
+        }
+        if self.padding < 0.0 || self.padding * 2.0 >= self.min_silence {
+            return Err(CutAnalysisError::InvalidOptions("padding must leave part of the minimum silence to cut".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Why a range was suggested for cutting
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CutReason {
+    Silence,
+    FillerWord { word: String },
+}
+
+/// A range of timeline time proposed for removal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CutSuggestion {
+    pub range: TimeRange,
+    pub reason: CutReason,
+}
+
+/// Suggest cuts for a clip: silences in its audio and, given a transcript, filler words
+///
+/// Ranges are in timeline time, sorted and limited to the clip.
+pub async fn analyze_clip(
+    clip: &Clip,
+    media_file: &MediaFile,
+    transcript: Option<&SubtitleTrack>,
+    options: &CutAnalysisOptions,
+) -> Result<Vec<CutSuggestion>, CutAnalysisError> {
+    options.validate()?;
+    if clip.is_generated() || clip.media_file_id != media_file.id {
+        return Err(CutAnalysisError::NoSource(clip.id.clone()));
+    }
+
+    let source_length = clip.duration * clip.speed as f64;
+    let output = tokio::process::Command::new("ffmpeg")
+        .arg("-hide_banner")
+        .arg("-ss")
+        .arg(clip.trim_start.to_string())
+        .arg("-t")
+        .arg(source_length.to_string())
+        .arg("-i")
+        .arg(&media_file.path)
+        .arg("-vn")
+        .arg("-af")
+        .arg(format!("silencedetect=noise={}dB:d={}", options.noise_db, options.min_silence))
+        .arg("-f")
+        .arg("null")
+        .arg("-")
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        error!("FFmpeg silence detection failed: {}", stderr);
+        return Err(CutAnalysisError::DetectionFailed(stderr.lines().last().unwrap_or_default().to_string()));
+    }
+
+    let (_, silences) = audio_chunks::parse_silencedetect(&String::from_utf8_lossy(&output.stderr));
+    let mut suggestions = silence_suggestions(clip, &silences, options);
+    if let (true, Some(track)) = (options.detect_fillers, transcript) {
+        suggestions.extend(filler_suggestions(clip, track, &options.filler_words));
+    }
+    suggestions.sort_by(|a, b| a.range.start.total_cmp(&b.range.start));
+
+    info!("Suggested {} cuts for clip {}", suggestions.len(), clip.id);
+    Ok(suggestions)
+}
+
+/// Map silences, in seconds from the clip's first source frame, to padded timeline ranges
+pub fn silence_suggestions(
+    clip: &Clip,
+    silences: &[(f64, f64)],
+    options: &CutAnalysisOptions,
+) -> Vec<CutSuggestion> {
+    let speed = clip.speed as f64;
+    let clip_end = clip.track_position + clip.duration;
+
+    silences.iter()
+        .filter_map(|&(start, end)| {
+            let start = (clip.track_position + start / speed).max(clip.track_position);
+            let end = (clip.track_position + end / speed).min(clip_end);
+
+            // Silence at a clip edge needs no padding on that side
+            let start = if start > clip.track_position { start + options.padding } else { start };
+            let end = if end < clip_end { end - options.padding } else { end };
+            (end > start).then_some(CutSuggestion {
+                range: TimeRange { start, end },
+                reason: CutReason::Silence,
+            })
+        })
+        .collect()
+}
+
+/// Filler words spoken within the clip, from the transcript's word timing
+pub fn filler_suggestions(clip: &Clip, track: &SubtitleTrack, filler_words: &[String]) -> Vec<CutSuggestion> {
+    let clip_end = clip.track_position + clip.duration;
+
+    track.segments.iter()
+        .flat_map(|segment| &segment.words)
+        .filter(|word| word.start_time >= clip.track_position && word.end_time <= clip_end)
+        .filter_map(|word| {
+            let normalized = normalize_word(&word.text);
+            filler_words.iter().any(|f| f.eq_ignore_ascii_case(&normalized)).then_some(CutSuggestion {
+                range: TimeRange { start: word.start_time, end: word.end_time },
+                reason: CutReason::FillerWord { word: normalized },
+            })
+        })
+        .collect()
+}
+
+fn normalize_word(text: &str) -> String {
+    text.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{SubtitleSegment, SubtitleSource, SubtitleWord};
+    use crate::transcript_edit;
+
+    fn clip(track_position: f64, duration: f64, speed: f32) -> Clip {
+        Clip {
+            id: "clip".to_string(),
+            media_file_id: "talk".to_string(),
+            name: None,
+            track_position,
+            duration,
+            trim_start: 5.0,
+            trim_end: 5.0 + duration * speed as f64,
+            effects: Vec::new(),
+            volume: 1.0,
+            speed,
+            generator: None,
+            ken_burns: None,
+            animation_span: None,
+        }
+    }
+
+    #[test]
+    fn test_silence_suggestions_map_to_timeline() {
+        let options = CutAnalysisOptions { padding: 0.25, ..Default::default() };
+
+        // Double speed: source seconds are half as long on the timeline
+        let clip = clip(10.0, 10.0, 2.0);
+        let silences = [(0.0, 3.0), (6.0, 7.0), (8.0, 10.0), (18.0, 20.0)];
+        let ranges: Vec<TimeRange> = silence_suggestions(&clip, &silences, &options)
+            .into_iter()
+            .map(|s| s.range)
+            .collect();
+
+        assert_eq!(ranges, vec![
+            TimeRange { start: 10.0, end: 11.25 },
+            TimeRange { start: 14.25, end: 14.75 },
+            TimeRange { start: 19.25, end: 20.0 },
+        ]);
+        assert!(options.validate().is_ok());
+        assert!(CutAnalysisOptions { padding: 0.5, ..Default::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_filler_suggestions_from_transcript() {
+        let word = |text: &str, start_time: f64, end_time: f64| SubtitleWord {
+            text: text.to_string(),
+            start_time,
+            end_time,
+        };
+        let track = SubtitleTrack {
+            segments: vec![SubtitleSegment {
+                id: 1,
+                start_time: 0.0,
+                end_time: 4.0,
+                text: "Um, so uh umbrella".to_string(),
+                words: vec![word("Um,", 0.5, 0.9), word("so", 1.0, 1.2), word("uh", 1.3, 1.6), word("umbrella", 2.0, 2.8)],
+                style: None,
+                cue_settings: None,
+                style_override: None,
+            }],
+            language: "en".to_string(),
+            source: SubtitleSource::Manual,
+            ass_header: None,
+        };
+
+        let fillers = CutAnalysisOptions::default().filler_words;
+        let suggestions = filler_suggestions(&clip(1.0, 5.0, 1.0), &track, &fillers);
+        assert_eq!(suggestions, vec![CutSuggestion {
+            range: TimeRange { start: 1.3, end: 1.6 },
+            reason: CutReason::FillerWord { word: "uh".to_string() },
+        }]);
+
+        let mut all = filler_suggestions(&clip(0.0, 5.0, 1.0), &track, &fillers);
+        all.push(CutSuggestion { range: TimeRange { start: 0.8, end: 1.35 }, reason: CutReason::Silence });
+        let ranges = all.iter().map(|s| s.range).collect();
+        assert_eq!(transcript_edit::merge_ranges(ranges), vec![TimeRange { start: 0.5, end: 1.6 }]);
+    }
+}
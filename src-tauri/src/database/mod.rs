@@ -1,7 +1,7 @@
 use rusqlite::{Connection, params};
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use crate::models::{MediaFile, MediaType, Resolution, MediaCodec, ProxyStatus};
+use crate::models::{MediaFile, MediaMarker, MediaMarkerKind, MediaType, Resolution, MediaCodec, ProxyStatus};
 
 /// Database wrapper for media library storage
 pub struct Database {
@@ -210,9 +210,13 @@ impl Database {
         Ok(files)
     }
 
-    /// Delete media file by ID
+    /// Delete media file by ID, along with its markers
     pub fn delete_media_file(&self, id: &str) -> Result<(), rusqlite::Error> {
         let conn = self.lock_conn()?;
+        conn.execute(
+            "DELETE FROM media_markers WHERE media_file_id = ?1",
+            params![id],
+        )?;
         conn.execute(
             "DELETE FROM media_files WHERE id = ?1",
             params![id],
@@ -220,6 +224,85 @@ impl Database {
         Ok(())
     }
 
+    /// Replace a media file's markers of one kind (using transaction so readers never see a partial set)
+    pub fn replace_markers(
+        &self,
+        media_file_id: &str,
+        kind: MediaMarkerKind,
+        markers: &[MediaMarker],
+    ) -> Result<(), rusqlite::Error> {
+        let mut conn = self.lock_conn()?;
+        let tx = conn.transaction()?;
+        let kind_str = Self::marker_kind_str(kind);
+
+        tx.execute(
+            "DELETE FROM media_markers WHERE media_file_id = ?1 AND kind = ?2",
+            params![media_file_id, kind_str],
+        )?;
+
+        for marker in markers {
+            let thumbnail_str = marker.thumbnail_path.as_ref()
+                .map(|p| p.to_str()
+                    .ok_or_else(|| rusqlite::Error::ToSqlConversionFailure(
+                        Box::new(std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            "Thumbnail path contains invalid UTF-8 characters"
+                        ))
+                    ))
+                )
+                .transpose()?;
+
+            tx.execute(
+                "INSERT INTO media_markers (media_file_id, kind, time, score, thumbnail_path)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![media_file_id, kind_str, marker.time, marker.score, thumbnail_str],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Get a media file's markers in time order
+    pub fn get_markers(&self, media_file_id: &str) -> Result<Vec<MediaMarker>, rusqlite::Error> {
+        let conn = self.lock_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT media_file_id, kind, time, score, thumbnail_path
+             FROM media_markers WHERE media_file_id = ?1 ORDER BY time"
+        )?;
+
+        let rows = stmt.query_map(params![media_file_id], |row| {
+            let kind = match row.get::<_, String>(1)?.as_str() {
+                "scene_cut" => MediaMarkerKind::SceneCut,
+                other => return Err(rusqlite::Error::FromSqlConversionFailure(
+                    1,
+                    rusqlite::types::Type::Text,
+                    format!("Unknown marker kind: {}", other).into()
+                )),
+            };
+            Ok(MediaMarker {
+                media_file_id: row.get(0)?,
+                kind,
+                time: row.get(2)?,
+                score: row.get(3)?,
+                thumbnail_path: row.get::<_, Option<String>>(4)?.map(PathBuf::from),
+            })
+        })?;
+
+        let mut markers = Vec::new();
+        for marker in rows {
+            markers.push(marker?);
+        }
+
+        Ok(markers)
+    }
+
+    fn marker_kind_str(kind: MediaMarkerKind) -> &'static str {
+        match kind {
+            MediaMarkerKind::SceneCut => "scene_cut",
+        }
+    }
+
     /// Find media files by codec
     #[allow(dead_code)]
     pub fn find_by_codec(&self, codec: &str) -> Result<Vec<MediaFile>, rusqlite::Error> {
@@ -367,6 +450,29 @@ impl Database {
         Ok(())
     }
 
+    /// Get an application setting
+    pub fn get_setting(&self, key: &str) -> Result<Option<String>, rusqlite::Error> {
+        let conn = self.lock_conn()?;
+        let mut stmt = conn.prepare("SELECT value FROM settings WHERE key = ?1")?;
+        let mut rows = stmt.query(params![key])?;
+
+        match rows.next()? {
+            Some(row) => Ok(Some(row.get(0)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Store an application setting, replacing any previous value
+    pub fn set_setting(&self, key: &str, value: &str) -> Result<(), rusqlite::Error> {
+        let conn = self.lock_conn()?;
+        conn.execute(
+            "INSERT INTO settings (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
     /// Update proxy status and path for a media file
     pub fn update_proxy_status(
         &self,
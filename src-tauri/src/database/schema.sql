@@ -20,10 +20,27 @@ CREATE TABLE IF NOT EXISTS media_files (
     proxy_status TEXT DEFAULT 'none' -- none | generating | ready | failed
 );
 
+-- Application settings stored as key/value pairs
+CREATE TABLE IF NOT EXISTS settings (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+
+-- Analysis results within media files, such as detected scene cuts
+CREATE TABLE IF NOT EXISTS media_markers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    media_file_id TEXT NOT NULL,
+    kind TEXT NOT NULL,             -- scene_cut
+    time REAL NOT NULL,             -- Source time in seconds
+    score REAL,                     -- Detection confidence, NULL if not scored
+    thumbnail_path TEXT
+);
+
 -- Indexes for fast lookups
 CREATE INDEX IF NOT EXISTS idx_hash ON media_files(hash);           -- O(log n) duplicate detection
 CREATE INDEX IF NOT EXISTS idx_imported_at ON media_files(imported_at DESC);  -- Recent files first
 CREATE INDEX IF NOT EXISTS idx_path ON media_files(path);           -- Find by filesystem path
+CREATE INDEX IF NOT EXISTS idx_markers_media ON media_markers(media_file_id, time);  -- Markers of a file in order
 
 -- Optional: Full-text search on filename (can be added later if needed)
 -- CREATE VIRTUAL TABLE IF NOT EXISTS media_files_fts USING fts5(filename, content='media_files', content_rowid='rowid');
@@ -1,9 +1,13 @@
 use crate::models::{
-    Timeline, Effect, EffectType, TrackType,
+    Timeline, Clip, Effect, EffectType, TrackType,
     ExportSettings, ExportProgress, ExportError, MediaFile,
-    SubtitleTrack,
+    SubtitleTrack, MediaType, KenBurns, SubtitleExportMode,
 };
-use crate::ffmpeg_utils;
+use crate::ffmpeg_utils::{self, TempFile};
+use crate::generator;
+use crate::markers;
+use crate::subtitle_format::{self, SubtitleFormat};
+use crate::subtitle_style;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::collections::HashMap;
@@ -14,6 +18,51 @@ use log::info;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 
+/// Sample rate used while pitch shifting audio
+const PITCH_SAMPLE_RATE: u32 = 48000;
+
+/// Escape a file path for use as a filter option value
+pub(crate) fn escape_filter_path(path: &Path) -> String {
+    path.to_string_lossy()
+        .replace('\\', "\\\\")
+        .replace(':', "\\:")
+        .replace('\'', "\\'")
+}
+
+/// Parse a "#RRGGBB" (or "RRGGBB") color into uppercase hex digits
+fn parse_hex_color(color: &str) -> Option<String> {
+    let hex = color.trim().trim_start_matches('#');
+    if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        Some(hex.to_ascii_uppercase())
+    } else {
+        None
+    }
+}
+
+/// Stream type a clip filter chain is built for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamKind {
+    Video,
+    Audio,
+}
+
+impl StreamKind {
+    /// Stream exported for clips on the given track type
+    fn for_track(track_type: TrackType) -> Self {
+        match track_type {
+            TrackType::Video | TrackType::Overlay => StreamKind::Video,
+            TrackType::Audio => StreamKind::Audio,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            StreamKind::Video => "video",
+            StreamKind::Audio => "audio",
+        }
+    }
+}
+
 /// Export service for rendering timelines to video files
 pub struct ExportService {
     ffmpeg_path: String,
@@ -54,12 +103,15 @@ impl ExportService {
         // Step 1: Validate timeline
         self.validate_timeline(timeline, media_files)?;
 
-        // Step 2: Build FFmpeg command
+        // Step 2: Build FFmpeg command; the files it reads are removed when
+        // `temp_files` goes out of scope, however the export ends
+        let mut temp_files = Vec::new();
         let ffmpeg_args = self.build_ffmpeg_command(
             timeline,
             settings,
             &output_path,
             media_files,
+            &mut temp_files,
         )?;
 
         // Step 3: Execute FFmpeg with progress tracking
@@ -90,27 +142,72 @@ impl ExportService {
             ));
         }
 
-        // Validate all clips reference existing media files
+        // Validate all clips reference existing media files or generate their content
         for track in &timeline.tracks {
             for clip in &track.clips {
-                if !media_files.contains_key(&clip.media_file_id) {
+                if clip.is_generated() {
+                    if track.track_type == TrackType::Audio {
+                        return Err(ExportError::ValidationError(
+                            format!("Generated clip {} cannot be placed on an audio track", clip.id)
+                        ));
+                    }
+                } else if let Some(media_file) = media_files.get(&clip.media_file_id) {
+                    if media_file.media_type == MediaType::Image && track.track_type == TrackType::Audio {
+                        return Err(ExportError::ValidationError(
+                            format!("Still image clip {} cannot be placed on an audio track", clip.id)
+                        ));
+                    }
+                } else {
                     return Err(ExportError::ValidationError(
                         format!("Clip references missing media file: {}", clip.media_file_id)
                     ));
                 }
 
+                if let Some(ken_burns) = &clip.ken_burns {
+                    Self::validate_ken_burns(ken_burns).map_err(|e| match e {
+                        ExportError::ValidationError(msg) => {
+                            ExportError::ValidationError(format!("Clip {}: {}", clip.id, msg))
+                        }
+                        other => other,
+                    })?;
+                }
+
                 // Validate clip duration
                 if clip.duration <= 0.0 {
                     return Err(ExportError::ValidationError(
                         format!("Clip has invalid duration: {}", clip.duration)
                     ));
                 }
+
+                Self::validate_clip_effects(track.track_type, clip)?;
             }
         }
 
         Ok(())
     }
 
+    /// Check that every enabled effect on a clip can be rendered on the
+    /// stream exported for its track
+    pub fn validate_clip_effects(track_type: TrackType, clip: &Clip) -> Result<(), ExportError> {
+        let stream = StreamKind::for_track(track_type);
+        Self::build_effects_filter(&clip.effects, stream, Self::rendered_duration(clip))
+            .map(|_| ())
+            .map_err(|e| match e {
+                ExportError::ValidationError(msg) => {
+                    ExportError::ValidationError(format!("Clip {}: {}", clip.id, msg))
+                }
+                other => other,
+            })
+    }
+
+    /// Duration of a clip after its filter chain has been applied
+    ///
+    /// A clip's duration is already in timeline seconds, with its speed
+    /// applied, so every stream renders to exactly that length.
+    pub fn rendered_duration(clip: &Clip) -> f64 {
+        clip.duration
+    }
+
     /// Build FFmpeg command with filter_complex
     fn build_ffmpeg_command(
         &self,
@@ -118,12 +215,28 @@ impl ExportService {
         settings: &ExportSettings,
         output_path: &Path,
         media_files: &HashMap<String, MediaFile>,
+        temp_files: &mut Vec<TempFile>,
     ) -> Result<Vec<String>, ExportError> {
         let mut args = Vec::new();
 
         // Overwrite output file
         args.push("-y".to_string());
 
+        // Still images are looped, so find how much of each one the clips use
+        let mut image_spans: HashMap<&str, f64> = HashMap::new();
+        for track in timeline.tracks.iter().filter(|t| !t.muted) {
+            for clip in &track.clips {
+                let is_image = media_files.get(&clip.media_file_id)
+                    .map(|m| m.media_type == MediaType::Image)
+                    .unwrap_or(false);
+                if is_image {
+                    let end = clip.trim_start + clip.duration;
+                    let span = image_spans.entry(clip.media_file_id.as_str()).or_insert(0.0);
+                    *span = span.max(end);
+                }
+            }
+        }
+
         // Add all input files
         let mut input_map: HashMap<String, usize> = HashMap::new();
         let mut input_index = 0;
@@ -133,13 +246,22 @@ impl ExportService {
                 continue;
             }
 
-            for clip in &track.clips {
+            // Generated clips are rendered by source filters and need no input
+            for clip in track.clips.iter().filter(|c| !c.is_generated()) {
                 let media_file = media_files.get(&clip.media_file_id)
                     .ok_or_else(|| ExportError::ValidationError(
                         format!("Media file not found: {}", clip.media_file_id)
                     ))?;
 
                 if !input_map.contains_key(&clip.media_file_id) {
+                    if let Some(span) = image_spans.get(clip.media_file_id.as_str()) {
+                        args.push("-loop".to_string());
+                        args.push("1".to_string());
+                        args.push("-framerate".to_string());
+                        args.push(format!("{}", timeline.framerate));
+                        args.push("-t".to_string());
+                        args.push(format!("{}", span));
+                    }
                     args.push("-i".to_string());
                     args.push(media_file.path.to_string_lossy().to_string());
                     input_map.insert(clip.media_file_id.clone(), input_index);
@@ -148,8 +270,52 @@ impl ExportService {
             }
         }
 
+        // Soft subtitles are extra inputs, one per language track
+        let soft_subtitles = match settings.subtitle_mode {
+            SubtitleExportMode::Soft => timeline.export_subtitle_tracks(),
+            SubtitleExportMode::BurnIn => Vec::new(),
+        };
+        let container = output_path.extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or(&settings.format)
+            .to_lowercase();
+        let mut subtitle_streams: Vec<(usize, &'static str, String)> = Vec::new();
+
+        for track in soft_subtitles {
+            let (format, codec) = Self::soft_subtitle_codec(&container, track)?;
+            let subtitle_file = TempFile::write(
+                "clipforge_subtitles", format.extension(), subtitle_format::generate(track, format),
+            ).map_err(|e| ExportError::OutputError(format!("Failed to write subtitles: {}", e)))?;
+
+            args.push("-i".to_string());
+            args.push(subtitle_file.path().to_string_lossy().to_string());
+            temp_files.push(subtitle_file);
+            subtitle_streams.push((input_index, codec, subtitle_format::iso639_2(&track.language)));
+            input_index += 1;
+        }
+
+        // Chapters come from an FFmpeg metadata file given as one more input
+        let chapters = if settings.chapters { markers::chapters(timeline) } else { Vec::new() };
+        let chapter_input = if chapters.is_empty() {
+            None
+        } else {
+            if !matches!(container.as_str(), "mp4" | "m4v" | "mov" | "mkv") {
+                return Err(ExportError::ValidationError(
+                    format!("Chapters are not supported in {} files", container)
+                ));
+            }
+            let chapter_file = TempFile::write("clipforge_chapters", "txt", markers::ffmetadata(&chapters))
+                .map_err(|e| ExportError::OutputError(format!("Failed to write chapters: {}", e)))?;
+
+            args.push("-i".to_string());
+            args.push(chapter_file.path().to_string_lossy().to_string());
+            temp_files.push(chapter_file);
+            Some(input_index)
+        };
+
         // Build filter_complex for timeline
-        let filter_complex = self.build_filter_complex(timeline, &input_map, media_files)?;
+        let burn_subtitles = settings.subtitle_mode == SubtitleExportMode::BurnIn;
+        let filter_complex = self.build_filter_complex(timeline, &input_map, media_files, burn_subtitles, temp_files)?;
 
         info!("Generated filter_complex ({} bytes): {}", filter_complex.len(),
             if filter_complex.len() > 500 {
@@ -169,6 +335,21 @@ impl ExportService {
             args.push("[outa]".to_string());
         }
 
+        for (stream, (input, codec, language)) in subtitle_streams.iter().enumerate() {
+            args.push("-map".to_string());
+            args.push(format!("{}:s:0", input));
+            args.push(format!("-c:s:{}", stream));
+            args.push(codec.to_string());
+            args.push(format!("-metadata:s:s:{}", stream));
+            args.push(format!("language={}", language));
+        }
+
+        if let Some(input) = chapter_input {
+            args.push("-map_chapters".to_string());
+            args.push(input.to_string());
+            info!("Writing {} chapters", chapters.len());
+        }
+
         // Performance and stability flags
         args.push("-threads".to_string());
         args.push("0".to_string()); // Auto-detect optimal thread count
@@ -214,10 +395,15 @@ impl ExportService {
         timeline: &Timeline,
         input_map: &HashMap<String, usize>,
         _media_files: &HashMap<String, MediaFile>,
+        burn_subtitles: bool,
+        temp_files: &mut Vec<TempFile>,
     ) -> Result<String, ExportError> {
         let mut filters = Vec::new();
         let mut video_inputs = Vec::new();
         let mut audio_inputs = Vec::new();
+        // Overlay track layers as (label, start, end) in timeline seconds
+        let mut overlay_layers: Vec<(String, f64, f64)> = Vec::new();
+        let (width, height) = (timeline.resolution.width, timeline.resolution.height);
 
         // Count how many times each input is used for video and audio
         let mut video_usage_count: HashMap<usize, usize> = HashMap::new();
@@ -300,45 +486,84 @@ impl ExportService {
 
             match track.track_type {
                 TrackType::Video | TrackType::Overlay => {
+                    let is_overlay = track.track_type == TrackType::Overlay;
+
                     // Process video clips
                     for (clip_idx, clip) in track.clips.iter().enumerate() {
-                        let input_idx = input_map.get(&clip.media_file_id)
-                            .ok_or_else(|| ExportError::ValidationError(
-                                format!("Input mapping not found for: {}", clip.media_file_id)
-                            ))?;
-
                         let label = format!("v{}_{}", track_idx, clip_idx);
 
-                        // Determine source stream (either split output or direct input)
-                        let source_stream = if let Some(split_outputs) = video_split_map.get(input_idx) {
-                            let counter = video_split_counters.entry(*input_idx).or_insert(0);
-                            let stream = split_outputs[*counter].clone();
-                            *counter += 1;
-                            format!("[{}]", stream)
+                        let mut clip_filter = if clip.is_generated() {
+                            generator::build_clip_source(clip, timeline.framerate, width, height, 1.0, temp_files)?
                         } else {
-                            format!("[{}:v]", input_idx)
+                            let input_idx = input_map.get(&clip.media_file_id)
+                                .ok_or_else(|| ExportError::ValidationError(
+                                    format!("Input mapping not found for: {}", clip.media_file_id)
+                                ))?;
+
+                            // Determine source stream (either split output or direct input)
+                            let source_stream = if let Some(split_outputs) = video_split_map.get(input_idx) {
+                                let counter = video_split_counters.entry(*input_idx).or_insert(0);
+                                let stream = split_outputs[*counter].clone();
+                                *counter += 1;
+                                format!("[{}]", stream)
+                            } else {
+                                format!("[{}:v]", input_idx)
+                            };
+
+                            // Trim the source the clip plays, which speed stretches to its duration
+                            let mut media_filter = format!(
+                                "{}trim=start={}:duration={},setpts=PTS-STARTPTS",
+                                source_stream, clip.trim_start, clip.duration * clip.speed as f64
+                            );
+
+                            // Apply speed
+                            if (clip.speed - 1.0).abs() > 0.01 {
+                                media_filter.push_str(&format!(",setpts={}*PTS", 1.0 / clip.speed));
+                            }
+                            media_filter
                         };
 
-                        // Trim and scale clip
-                        let mut clip_filter = format!(
-                            "{}trim=start={}:duration={},setpts=PTS-STARTPTS",
-                            source_stream, clip.trim_start, clip.duration
-                        );
-
-                        // Apply effects
-                        if !clip.effects.is_empty() {
-                            let effects_str = self.build_effects_filter(&clip.effects)?;
+                        // Apply effects after speed so fades line up with the rendered clip
+                        let rendered_duration = Self::rendered_duration(clip);
+                        let effects_str = Self::build_effects_filter(
+                            &clip.effects,
+                            StreamKind::Video,
+                            rendered_duration,
+                        )?;
+                        if !effects_str.is_empty() {
                             clip_filter.push_str(&format!(",{}", effects_str));
                         }
 
-                        // Apply speed
-                        if (clip.speed - 1.0).abs() > 0.01 {
-                            clip_filter.push_str(&format!(",setpts={}*PTS", 1.0 / clip.speed));
+                        if let Some(ken_burns) = &clip.ken_burns {
+                            clip_filter.push_str(&format!(",{}", Self::build_ken_burns_filter(
+                                ken_burns, width, height, timeline.framerate, clip,
+                            )?));
+                        }
+
+                        if is_overlay {
+                            // Keep transparency and move the layer to its place on the timeline
+                            clip_filter.push_str(&format!(
+                                ",{},setpts=PTS+{}/TB",
+                                generator::fit_layer_filter(width, height),
+                                clip.track_position
+                            ));
+                            overlay_layers.push((
+                                label.clone(),
+                                clip.track_position,
+                                clip.track_position + rendered_duration,
+                            ));
+                        } else {
+                            // Fit to timeline resolution so concat accepts cropped or rotated clips
+                            clip_filter.push_str(&format!(
+                                ",scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2,setsar=1",
+                                w = width,
+                                h = height
+                            ));
+                            video_inputs.push(label.clone());
                         }
 
                         clip_filter.push_str(&format!("[{}]", label));
                         filters.push(clip_filter);
-                        video_inputs.push(label);
                     }
                 }
                 TrackType::Audio => {
@@ -371,6 +596,16 @@ impl ExportService {
                             clip_filter.push_str(&format!(",volume={}", clip.volume));
                         }
 
+                        // Apply audio effects
+                        let effects_str = Self::build_effects_filter(
+                            &clip.effects,
+                            StreamKind::Audio,
+                            Self::rendered_duration(clip),
+                        )?;
+                        if !effects_str.is_empty() {
+                            clip_filter.push_str(&format!(",{}", effects_str));
+                        }
+
                         clip_filter.push_str(&format!("[{}]", label));
                         filters.push(clip_filter);
                         audio_inputs.push(label);
@@ -379,48 +614,47 @@ impl ExportService {
             }
         }
 
-        // Concatenate all video inputs
+        // Concatenate all video inputs, then composite overlays and subtitles on top
         if !video_inputs.is_empty() {
-            let video_label = if timeline.subtitle_enabled && timeline.subtitle_track.is_some() {
-                "[vconcat]" // Intermediate label for subtitle burning
-            } else {
-                "[outv]" // Final output if no subtitles
-            };
+            let mut post_filters: Vec<String> = overlay_layers.iter()
+                .map(|(label, start, end)| format!(
+                    "[{}]overlay=format=auto:eof_action=pass:enable='between(t,{},{})'",
+                    label, start, end
+                ))
+                .collect();
 
-            let concat_filter = format!(
-                "{}concat=n={}:v=1:a=0{}",
+            // Add subtitle burning if enabled
+            if burn_subtitles {
+                if let Some(subtitle_track) = timeline.burn_in_subtitle_track() {
+                    let script = subtitle_style::build_burn_in_script(
+                        subtitle_track, &timeline.subtitle_style, &timeline.resolution,
+                    )?;
+                    let script_file = TempFile::write("clipforge_subtitles", "ass", script)
+                        .map_err(|e| ExportError::OutputError(format!("Failed to write subtitles: {}", e)))?;
+
+                    post_filters.push(subtitle_style::burn_in_filter(script_file.path(), &timeline.subtitle_style));
+                    temp_files.push(script_file);
+
+                    info!("Added subtitle burning filter with {} {} segments",
+                        subtitle_track.segments.len(), subtitle_track.language);
+                }
+            }
+
+            let concat_label = if post_filters.is_empty() { "outv" } else { "vconcat" };
+            filters.push(format!(
+                "{}concat=n={}:v=1:a=0[{}]",
                 video_inputs.iter().map(|l| format!("[{}]", l)).collect::<Vec<_>>().join(""),
                 video_inputs.len(),
-                video_label
-            );
-            filters.push(concat_filter);
+                concat_label
+            ));
 
-            // Add subtitle burning if enabled
-            if timeline.subtitle_enabled {
-                if let Some(ref subtitle_track) = timeline.subtitle_track {
-                    // Generate SRT content
-                    let srt_content = self.generate_srt_content(subtitle_track)?;
-
-                    // Create temporary SRT file path
-                    let temp_srt = std::env::temp_dir().join(format!("clipforge_subtitles_{}.srt",
-                        std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap()
-                            .as_secs()));
-
-                    // Write SRT file
-                    std::fs::write(&temp_srt, srt_content)
-                        .map_err(|e| ExportError::OutputError(format!("Failed to write SRT: {}", e)))?;
-
-                    // Add subtitles filter
-                    let subtitle_filter = format!(
-                        "[vconcat]subtitles={}:force_style='FontName=Arial,FontSize=24,PrimaryColour=&H00FFFFFF,OutlineColour=&H00000000,BorderStyle=3,Outline=2,Shadow=1,MarginV=20'[outv]",
-                        temp_srt.to_string_lossy().replace("\\", "\\\\").replace(":", "\\:")
-                    );
-                    filters.push(subtitle_filter);
-
-                    info!("Added subtitle burning filter with {} segments", subtitle_track.segments.len());
-                }
+            // Chain the post-processing stages, the last one producing [outv]
+            let mut current = concat_label.to_string();
+            let stage_count = post_filters.len();
+            for (i, stage) in post_filters.into_iter().enumerate() {
+                let output = if i + 1 == stage_count { "outv".to_string() } else { format!("vpost{}", i) };
+                filters.push(format!("[{}]{}[{}]", current, stage, output));
+                current = output;
             }
         }
 
@@ -437,8 +671,15 @@ impl ExportService {
         Ok(filters.join(";"))
     }
 
-    /// Build effects filter string
-    fn build_effects_filter(&self, effects: &[Effect]) -> Result<String, ExportError> {
+    /// Build the effects filter chain for one stream of a clip
+    ///
+    /// `clip_duration` is the duration of the clip as rendered, used to place
+    /// fade-outs. Effects that cannot be applied to `stream` are rejected.
+    pub fn build_effects_filter(
+        effects: &[Effect],
+        stream: StreamKind,
+        clip_duration: f64,
+    ) -> Result<String, ExportError> {
         let mut filters = Vec::new();
 
         for effect in effects {
@@ -446,6 +687,19 @@ impl ExportService {
                 continue;
             }
 
+            let target = effect.effect_type.target();
+            let supported = match stream {
+                StreamKind::Video => target.applies_to_video(),
+                StreamKind::Audio => target.applies_to_audio(),
+            };
+            if !supported {
+                return Err(ExportError::ValidationError(format!(
+                    "{} effect cannot be applied to a {} stream",
+                    effect.effect_type.name(),
+                    stream.label()
+                )));
+            }
+
             let filter = match &effect.effect_type {
                 EffectType::Brightness { value } => {
                     format!("eq=brightness={}", value)
@@ -462,14 +716,167 @@ impl ExportService {
                 EffectType::Sharpen { amount } => {
                     format!("unsharp=5:5:{}", amount * 2.0)
                 }
+                EffectType::ColorTemperature { kelvin } => {
+                    if !(1000.0..=40000.0).contains(kelvin) {
+                        return Err(ExportError::ValidationError(
+                            format!("Color temperature {}K is outside 1000K to 40000K", kelvin)
+                        ));
+                    }
+                    format!("colortemperature=temperature={}", kelvin)
+                }
+                EffectType::Tint { value } => {
+                    format!("colorbalance=gs={v}:gm={v}:gh={v}", v = value.clamp(-1.0, 1.0))
+                }
+                EffectType::Curves { master, red, green, blue } => {
+                    let mut curves = Vec::new();
+                    for (key, points) in [("master", master), ("r", red), ("g", green), ("b", blue)] {
+                        if let Some(points) = points {
+                            Self::validate_curve_points(points)?;
+                            curves.push(format!("{}='{}'", key, points.trim()));
+                        }
+                    }
+                    if curves.is_empty() {
+                        return Err(ExportError::ValidationError(
+                            "Curves effect needs at least one curve".to_string()
+                        ));
+                    }
+                    format!("curves={}", curves.join(":"))
+                }
+                EffectType::Lut { path } => {
+                    let is_cube = path.extension()
+                        .map(|ext| ext.eq_ignore_ascii_case("cube"))
+                        .unwrap_or(false);
+                    if !is_cube {
+                        return Err(ExportError::ValidationError(
+                            format!("LUT must be a .cube file: {}", path.display())
+                        ));
+                    }
+                    if !path.exists() {
+                        return Err(ExportError::ValidationError(
+                            format!("LUT file not found: {}", path.display())
+                        ));
+                    }
+                    format!("lut3d=file={}", escape_filter_path(path))
+                }
+                EffectType::HueShift { degrees } => {
+                    format!("hue=h={}", degrees)
+                }
+                EffectType::Vignette { intensity } => {
+                    format!("vignette=angle={}", intensity.clamp(0.0, 1.0) * std::f32::consts::FRAC_PI_2)
+                }
+                EffectType::ChromaKey { color, similarity, blend } => {
+                    let hex = parse_hex_color(color).ok_or_else(|| ExportError::ValidationError(
+                        format!("Invalid chroma key color: {}", color)
+                    ))?;
+                    format!(
+                        "chromakey=color=0x{}:similarity={}:blend={}",
+                        hex, similarity.clamp(0.01, 1.0), blend.clamp(0.0, 1.0)
+                    )
+                }
+                EffectType::Crop { x, y, width, height } => {
+                    if *width == 0 || *height == 0 {
+                        return Err(ExportError::ValidationError(
+                            "Crop width and height must be greater than zero".to_string()
+                        ));
+                    }
+                    format!("crop={}:{}:{}:{}", width, height, x, y)
+                }
+                EffectType::Flip { horizontal, vertical } => {
+                    match (horizontal, vertical) {
+                        (true, true) => "hflip,vflip".to_string(),
+                        (true, false) => "hflip".to_string(),
+                        (false, true) => "vflip".to_string(),
+                        (false, false) => continue,
+                    }
+                }
+                EffectType::Rotate { degrees } => {
+                    let normalized = degrees.rem_euclid(360.0);
+                    if normalized.abs() < f32::EPSILON {
+                        continue;
+                    } else if (normalized - 90.0).abs() < f32::EPSILON {
+                        "transpose=clock".to_string()
+                    } else if (normalized - 180.0).abs() < f32::EPSILON {
+                        "hflip,vflip".to_string()
+                    } else if (normalized - 270.0).abs() < f32::EPSILON {
+                        "transpose=cclock".to_string()
+                    } else {
+                        let radians = normalized.to_radians();
+                        format!("rotate={r}:ow=rotw({r}):oh=roth({r}):c=black", r = radians)
+                    }
+                }
+                EffectType::Stabilize { strength } => {
+                    let range = 16 + (strength.clamp(0.0, 1.0) * 48.0).round() as u32;
+                    format!("deshake=rx={}:ry={}", range, range)
+                }
+                EffectType::Denoise { strength } => {
+                    format!("hqdn3d={}", strength.clamp(0.0, 1.0) * 10.0)
+                }
                 EffectType::Normalize => {
                     "loudnorm".to_string()
                 }
-                EffectType::FadeIn { duration } => {
-                    format!("fade=t=in:st=0:d={}", duration)
+                EffectType::Equalizer { bands } => {
+                    if bands.is_empty() {
+                        return Err(ExportError::ValidationError(
+                            "Equalizer effect needs at least one band".to_string()
+                        ));
+                    }
+                    bands.iter()
+                        .map(|band| format!(
+                            "equalizer=f={}:t=q:w={}:g={}",
+                            band.frequency, band.q, band.gain_db
+                        ))
+                        .collect::<Vec<_>>()
+                        .join(",")
+                }
+                EffectType::Compressor { threshold_db, ratio, attack_ms, release_ms, makeup_db } => {
+                    format!(
+                        "acompressor=threshold={}dB:ratio={}:attack={}:release={}:makeup={}dB",
+                        threshold_db, ratio.clamp(1.0, 20.0), attack_ms, release_ms,
+                        makeup_db.clamp(0.0, 36.0)
+                    )
                 }
-                EffectType::FadeOut { duration } => {
-                    format!("fade=t=out:st=0:d={}", duration)
+                EffectType::NoiseGate { threshold_db, ratio, attack_ms, release_ms } => {
+                    format!(
+                        "agate=threshold={}dB:ratio={}:attack={}:release={}",
+                        threshold_db, ratio.clamp(1.0, 9000.0), attack_ms, release_ms
+                    )
+                }
+                EffectType::HighPass { frequency } => {
+                    format!("highpass=f={}", frequency)
+                }
+                EffectType::NoiseReduction { amount_db } => {
+                    format!("afftdn=nr={}", amount_db.clamp(0.01, 97.0))
+                }
+                EffectType::Pitch { semitones } => {
+                    if !(-12.0..=12.0).contains(semitones) {
+                        return Err(ExportError::ValidationError(
+                            format!("Pitch shift of {} semitones is outside -12 to 12", semitones)
+                        ));
+                    }
+                    // Resample to shift pitch, then restore the original tempo
+                    let ratio = 2f64.powf(*semitones as f64 / 12.0);
+                    format!(
+                        "aresample={sr},asetrate={sr}*{ratio},aresample={sr},atempo={tempo}",
+                        sr = PITCH_SAMPLE_RATE, ratio = ratio, tempo = 1.0 / ratio
+                    )
+                }
+                EffectType::FadeIn { duration } | EffectType::FadeOut { duration } => {
+                    if !duration.is_finite() || *duration <= 0.0 || *duration > clip_duration {
+                        return Err(ExportError::ValidationError(format!(
+                            "{} duration {}s must be between 0 and the clip duration ({}s)",
+                            effect.effect_type.name(), duration, clip_duration
+                        )));
+                    }
+
+                    let fade = match stream {
+                        StreamKind::Video => "fade",
+                        StreamKind::Audio => "afade",
+                    };
+                    if matches!(effect.effect_type, EffectType::FadeIn { .. }) {
+                        format!("{}=t=in:st=0:d={}", fade, duration)
+                    } else {
+                        format!("{}=t=out:st={}:d={}", fade, clip_duration - duration, duration)
+                    }
                 }
             };
             filters.push(filter);
@@ -478,34 +885,101 @@ impl ExportService {
         Ok(filters.join(","))
     }
 
-    /// Generate SRT content from subtitle track
-    fn generate_srt_content(&self, track: &SubtitleTrack) -> Result<String, ExportError> {
-        let mut srt = String::new();
-
-        for (idx, segment) in track.segments.iter().enumerate() {
-            // Index (1-based)
-            srt.push_str(&format!("{}\n", idx + 1));
+    /// Check Ken Burns zoom levels and centre points
+    fn validate_ken_burns(ken_burns: &KenBurns) -> Result<(), ExportError> {
+        for zoom in [ken_burns.start_zoom, ken_burns.end_zoom] {
+            if !(1.0..=10.0).contains(&zoom) {
+                return Err(ExportError::ValidationError(
+                    format!("Ken Burns zoom {} is outside 1.0 to 10.0", zoom)
+                ));
+            }
+        }
+        for position in [ken_burns.start_x, ken_burns.start_y, ken_burns.end_x, ken_burns.end_y] {
+            if !(0.0..=1.0).contains(&position) {
+                return Err(ExportError::ValidationError(
+                    format!("Ken Burns position {} is outside 0.0 to 1.0", position)
+                ));
+            }
+        }
+        Ok(())
+    }
 
-            // Timecode format: HH:MM:SS,mmm --> HH:MM:SS,mmm
-            let start = self.format_srt_timestamp(segment.start_time);
-            let end = self.format_srt_timestamp(segment.end_time);
-            srt.push_str(&format!("{} --> {}\n", start, end));
+    /// Build a pan and zoom filter that fills a `width`x`height` frame
+    ///
+    /// Progress follows the input timestamps, so the chain must start at zero
+    /// at the start of the clip. The move runs across the clip's animation
+    /// range, so the halves of a split clip continue it rather than each
+    /// repeating it.
+    pub fn build_ken_burns_filter(
+        ken_burns: &KenBurns,
+        width: u32,
+        height: u32,
+        framerate: f64,
+        clip: &Clip,
+    ) -> Result<String, ExportError> {
+        Self::validate_ken_burns(ken_burns)?;
+
+        let (span_start, span_end) = clip.animation_range();
+        let progress = format!(
+            "clip(({}+it*{})/{},0,1)",
+            clip.trim_start - span_start, clip.speed, (span_end - span_start).max(0.001)
+        );
+        let zoom = format!(
+            "{}+({})*{}",
+            ken_burns.start_zoom, ken_burns.end_zoom - ken_burns.start_zoom, progress
+        );
+        // Keep the view centred on the moving point without leaving the frame
+        let x = format!(
+            "clip(({}+({})*{})*iw-iw/zoom/2,0,iw-iw/zoom)",
+            ken_burns.start_x, ken_burns.end_x - ken_burns.start_x, progress
+        );
+        let y = format!(
+            "clip(({}+({})*{})*ih-ih/zoom/2,0,ih-ih/zoom)",
+            ken_burns.start_y, ken_burns.end_y - ken_burns.start_y, progress
+        );
+
+        Ok(format!(
+            "scale={w}:{h}:force_original_aspect_ratio=increase,crop={w}:{h},zoompan=z='{z}':x='{x}':y='{y}':d=1:s={w}x{h}:fps={fps}",
+            w = width, h = height, z = zoom, x = x, y = y, fps = framerate
+        ))
+    }
 
-            // Text
-            srt.push_str(&format!("{}\n\n", segment.text));
+    /// Validate curve control points ("x/y x/y ...", values 0.0 to 1.0)
+    fn validate_curve_points(points: &str) -> Result<(), ExportError> {
+        let invalid = || ExportError::ValidationError(format!("Invalid curve points: {}", points));
+
+        let mut count = 0;
+        for point in points.split_whitespace() {
+            let (x, y) = point.split_once('/').ok_or_else(invalid)?;
+            for value in [x, y] {
+                let value: f64 = value.parse().map_err(|_| invalid())?;
+                if !(0.0..=1.0).contains(&value) {
+                    return Err(invalid());
+                }
+            }
+            count += 1;
         }
 
-        Ok(srt)
+        if count < 2 {
+            return Err(invalid());
+        }
+        Ok(())
     }
 
-    /// Format timestamp for SRT format
-    fn format_srt_timestamp(&self, seconds: f64) -> String {
-        let hours = (seconds / 3600.0) as u32;
-        let minutes = ((seconds % 3600.0) / 60.0) as u32;
-        let secs = (seconds % 60.0) as u32;
-        let millis = ((seconds % 1.0) * 1000.0) as u32;
-
-        format!("{:02}:{:02}:{:02},{:03}", hours, minutes, secs, millis)
+    /// Subtitle file format and stream codec for soft subtitles in a container
+    fn soft_subtitle_codec(
+        container: &str,
+        track: &SubtitleTrack,
+    ) -> Result<(SubtitleFormat, &'static str), ExportError> {
+        match container {
+            "mp4" | "m4v" | "mov" => Ok((SubtitleFormat::Srt, "mov_text")),
+            "mkv" if track.ass_header.is_some() => Ok((SubtitleFormat::Ass, "ass")),
+            "mkv" => Ok((SubtitleFormat::Srt, "srt")),
+            "webm" => Ok((SubtitleFormat::Vtt, "webvtt")),
+            other => Err(ExportError::ValidationError(
+                format!("Soft subtitles are not supported in {} files", other)
+            )),
+        }
     }
 
     /// Execute FFmpeg command with progress tracking
@@ -670,4 +1144,441 @@ mod tests {
         assert_eq!(presets.len(), 3);
         assert_eq!(presets[0].0, "YouTube 1080p");
     }
+
+    fn create_test_clip(duration: f64, effects: Vec<EffectType>) -> Clip {
+        Clip {
+            id: "clip1".to_string(),
+            media_file_id: "media1".to_string(),
+            name: None,
+            track_position: 0.0,
+            duration,
+            trim_start: 0.0,
+            trim_end: duration,
+            effects: effects.into_iter().enumerate().map(|(i, effect_type)| Effect {
+                id: format!("effect{}", i),
+                effect_type,
+                enabled: true,
+            }).collect(),
+            volume: 1.0,
+            speed: 1.0,
+            generator: None,
+            ken_burns: None,
+            animation_span: None,
+        }
+    }
+
+    #[test]
+    fn test_effects_routed_by_stream() {
+        let clip = create_test_clip(10.0, vec![
+            EffectType::FadeIn { duration: 1.0 },
+            EffectType::FadeOut { duration: 2.0 },
+        ]);
+
+        let video = ExportService::build_effects_filter(&clip.effects, StreamKind::Video, 10.0).unwrap();
+        assert_eq!(video, "fade=t=in:st=0:d=1,fade=t=out:st=8:d=2");
+
+        let audio = ExportService::build_effects_filter(&clip.effects, StreamKind::Audio, 10.0).unwrap();
+        assert_eq!(audio, "afade=t=in:st=0:d=1,afade=t=out:st=8:d=2");
+    }
+
+    #[test]
+    fn test_fade_out_uses_rendered_duration() {
+        use crate::models::{Resolution, Track};
+
+        // 10 timeline seconds at 2x play 20 seconds of source
+        let mut clip = create_test_clip(10.0, vec![EffectType::FadeOut { duration: 1.0 }]);
+        clip.speed = 2.0;
+        clip.trim_end = 20.0;
+
+        assert_eq!(ExportService::rendered_duration(&clip), 10.0);
+
+        let timeline = Timeline {
+            id: "timeline".to_string(),
+            name: "Test".to_string(),
+            framerate: 30.0,
+            resolution: Resolution { width: 1280, height: 720 },
+            tracks: vec![Track {
+                id: "video".to_string(),
+                track_type: TrackType::Video,
+                clips: vec![clip],
+                muted: false,
+                locked: false,
+            }],
+            duration: 10.0,
+            subtitle_tracks: Vec::new(),
+            burn_in_language: None,
+            subtitle_enabled: false,
+            subtitle_style: Default::default(),
+            markers: Vec::new(),
+        };
+        let service = ExportService {
+            ffmpeg_path: "ffmpeg".to_string(),
+            cancel_flag: Arc::new(AtomicBool::new(false)),
+        };
+        let input_map = HashMap::from([("media1".to_string(), 0)]);
+        let filter = service.build_filter_complex(&timeline, &input_map, &HashMap::new(), true, &mut Vec::new()).unwrap();
+
+        assert!(filter.contains("[0:v]trim=start=0:duration=20,setpts=PTS-STARTPTS,setpts=0.5*PTS,fade=t=out:st=9:d=1"));
+    }
+
+    #[test]
+    fn test_mismatched_effects_rejected() {
+        let normalize = create_test_clip(5.0, vec![EffectType::Normalize]);
+        assert!(ExportService::validate_clip_effects(TrackType::Audio, &normalize).is_ok());
+        assert!(matches!(
+            ExportService::validate_clip_effects(TrackType::Video, &normalize),
+            Err(ExportError::ValidationError(_))
+        ));
+
+        let brightness = create_test_clip(5.0, vec![EffectType::Brightness { value: 0.2 }]);
+        assert!(ExportService::validate_clip_effects(TrackType::Overlay, &brightness).is_ok());
+        assert!(matches!(
+            ExportService::validate_clip_effects(TrackType::Audio, &brightness),
+            Err(ExportError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_expanded_effect_filters() {
+        let clip = create_test_clip(5.0, vec![
+            EffectType::Curves {
+                master: Some("0/0 0.5/0.6 1/1".to_string()),
+                red: None,
+                green: None,
+                blue: None,
+            },
+            EffectType::ChromaKey { color: "#00ff00".to_string(), similarity: 0.2, blend: 0.1 },
+            EffectType::Rotate { degrees: -270.0 },
+            EffectType::Flip { horizontal: false, vertical: false },
+        ]);
+        let video = ExportService::build_effects_filter(&clip.effects, StreamKind::Video, 5.0).unwrap();
+        assert_eq!(
+            video,
+            "curves=master='0/0 0.5/0.6 1/1',chromakey=color=0x00FF00:similarity=0.2:blend=0.1,transpose=clock"
+        );
+
+        let clip = create_test_clip(5.0, vec![
+            EffectType::HighPass { frequency: 80.0 },
+            EffectType::Equalizer {
+                bands: vec![
+                    crate::models::EqBand { frequency: 100.0, gain_db: -3.0, q: 1.0 },
+                    crate::models::EqBand { frequency: 3000.0, gain_db: 2.0, q: 0.7 },
+                ],
+            },
+            EffectType::Pitch { semitones: 12.0 },
+        ]);
+        let audio = ExportService::build_effects_filter(&clip.effects, StreamKind::Audio, 5.0).unwrap();
+        assert_eq!(
+            audio,
+            "highpass=f=80,equalizer=f=100:t=q:w=1:g=-3,equalizer=f=3000:t=q:w=0.7:g=2,\
+             aresample=48000,asetrate=48000*2,aresample=48000,atempo=0.5"
+        );
+    }
+
+    #[test]
+    fn test_invalid_effect_parameters_rejected() {
+        let bad_curves = create_test_clip(5.0, vec![EffectType::Curves {
+            master: Some("0/0 1/1;drawtext".to_string()),
+            red: None,
+            green: None,
+            blue: None,
+        }]);
+        assert!(ExportService::validate_clip_effects(TrackType::Video, &bad_curves).is_err());
+
+        let bad_lut = create_test_clip(5.0, vec![EffectType::Lut { path: PathBuf::from("/tmp/look.png") }]);
+        assert!(ExportService::validate_clip_effects(TrackType::Video, &bad_lut).is_err());
+
+        let bad_color = create_test_clip(5.0, vec![EffectType::ChromaKey {
+            color: "green".to_string(),
+            similarity: 0.1,
+            blend: 0.0,
+        }]);
+        assert!(ExportService::validate_clip_effects(TrackType::Video, &bad_color).is_err());
+    }
+
+    #[test]
+    fn test_fade_longer_than_clip_rejected() {
+        let clip = create_test_clip(2.0, vec![EffectType::FadeOut { duration: 3.0 }]);
+        assert!(ExportService::validate_clip_effects(TrackType::Video, &clip).is_err());
+    }
+
+    #[test]
+    fn test_overlay_tracks_composited_over_base() {
+        use crate::models::{ClipGenerator, Resolution, Track};
+
+        let mut title = create_test_clip(2.0, vec![EffectType::FadeIn { duration: 0.5 }]);
+        title.id = "title".to_string();
+        title.media_file_id = String::new();
+        title.track_position = 1.0;
+        title.generator = Some(ClipGenerator::SolidColor { color: "#FF000080".to_string() });
+
+        let timeline = Timeline {
+            id: "timeline".to_string(),
+            name: "Test".to_string(),
+            framerate: 30.0,
+            resolution: Resolution { width: 1280, height: 720 },
+            tracks: vec![
+                Track {
+                    id: "video".to_string(),
+                    track_type: TrackType::Video,
+                    clips: vec![create_test_clip(5.0, Vec::new())],
+                    muted: false,
+                    locked: false,
+                },
+                Track {
+                    id: "overlay".to_string(),
+                    track_type: TrackType::Overlay,
+                    clips: vec![title],
+                    muted: false,
+                    locked: false,
+                },
+            ],
+            duration: 5.0,
+            subtitle_tracks: Vec::new(),
+            burn_in_language: None,
+            subtitle_enabled: false,
+            subtitle_style: Default::default(),
+            markers: Vec::new(),
+        };
+
+        let service = ExportService {
+            ffmpeg_path: "ffmpeg".to_string(),
+            cancel_flag: Arc::new(AtomicBool::new(false)),
+        };
+        let input_map = HashMap::from([("media1".to_string(), 0)]);
+        let filter = service.build_filter_complex(&timeline, &input_map, &HashMap::new(), true, &mut Vec::new()).unwrap();
+
+        // Base track is concatenated, the overlay is generated and placed at its position
+        assert!(filter.contains("[v0_0]concat=n=1:v=1:a=0[vconcat]"));
+        assert!(filter.contains("color=c=0xFF0000@0.502:s=1280x720:r=30:d=2"));
+        assert!(filter.contains("fade=t=in:st=0:d=0.5,format=rgba,scale=1280:720"));
+        assert!(filter.contains("setpts=PTS+1/TB[v1_0]"));
+        assert!(filter.contains(
+            "[vconcat][v1_0]overlay=format=auto:eof_action=pass:enable='between(t,1,3)'[outv]"
+        ));
+    }
+
+    #[test]
+    fn test_still_images_looped_to_clip_length() {
+        use crate::models::{MediaCodec, ProxyStatus, Resolution, Track};
+
+        let image = MediaFile {
+            id: "media1".to_string(),
+            path: PathBuf::from("/photos/still.png"),
+            filename: "still.png".to_string(),
+            media_type: MediaType::Image,
+            duration: 5.0,
+            resolution: Some(Resolution { width: 4000, height: 3000 }),
+            codec: MediaCodec { video: Some("png".to_string()), audio: None },
+            file_size: 1024,
+            thumbnail_path: None,
+            hash: "hash".to_string(),
+            imported_at: chrono::Utc::now(),
+            proxy_path: None,
+            has_proxy: false,
+            proxy_status: ProxyStatus::None,
+        };
+        let mut clip = create_test_clip(8.0, Vec::new());
+        clip.ken_burns = Some(KenBurns {
+            start_zoom: 1.0,
+            end_zoom: 1.5,
+            start_x: 0.5,
+            start_y: 0.5,
+            end_x: 0.25,
+            end_y: 0.5,
+        });
+
+        let timeline = Timeline {
+            id: "timeline".to_string(),
+            name: "Test".to_string(),
+            framerate: 30.0,
+            resolution: Resolution { width: 1920, height: 1080 },
+            tracks: vec![Track {
+                id: "video".to_string(),
+                track_type: TrackType::Video,
+                clips: vec![clip],
+                muted: false,
+                locked: false,
+            }],
+            duration: 8.0,
+            subtitle_tracks: Vec::new(),
+            burn_in_language: None,
+            subtitle_enabled: false,
+            subtitle_style: Default::default(),
+            markers: Vec::new(),
+        };
+
+        let service = ExportService {
+            ffmpeg_path: "ffmpeg".to_string(),
+            cancel_flag: Arc::new(AtomicBool::new(false)),
+        };
+        let media_files = HashMap::from([("media1".to_string(), image)]);
+        let args = service.build_ffmpeg_command(
+            &timeline,
+            &ExportService::get_presets()[0].1,
+            Path::new("/tmp/out.mp4"),
+            &media_files,
+            &mut Vec::new(),
+        ).unwrap();
+
+        let input = args.iter().position(|a| a == "/photos/still.png").unwrap();
+        assert_eq!(&args[input - 7..input], ["-loop", "1", "-framerate", "30", "-t", "8", "-i"]);
+
+        let filter = &args[args.iter().position(|a| a == "-filter_complex").unwrap() + 1];
+        assert!(filter.contains(
+            "scale=1920:1080:force_original_aspect_ratio=increase,crop=1920:1080,\
+             zoompan=z='1+(0.5)*clip((0+it*1)/8,0,1)':\
+             x='clip((0.5+(-0.25)*clip((0+it*1)/8,0,1))*iw-iw/zoom/2,0,iw-iw/zoom)':\
+             y='clip((0.5+(0)*clip((0+it*1)/8,0,1))*ih-ih/zoom/2,0,ih-ih/zoom)':\
+             d=1:s=1920x1080:fps=30"
+        ));
+    }
+
+    #[test]
+    fn test_soft_subtitles_muxed_with_language() {
+        use crate::models::{
+            MediaCodec, ProxyStatus, Resolution, SubtitleSegment, SubtitleSource, Track,
+        };
+
+        let video = MediaFile {
+            id: "media1".to_string(),
+            path: PathBuf::from("/videos/talk.mp4"),
+            filename: "talk.mp4".to_string(),
+            media_type: MediaType::Video,
+            duration: 5.0,
+            resolution: Some(Resolution { width: 1920, height: 1080 }),
+            codec: MediaCodec { video: Some("h264".to_string()), audio: Some("aac".to_string()) },
+            file_size: 1024,
+            thumbnail_path: None,
+            hash: "hash".to_string(),
+            imported_at: chrono::Utc::now(),
+            proxy_path: None,
+            has_proxy: false,
+            proxy_status: ProxyStatus::None,
+        };
+        let subtitles = SubtitleTrack {
+            segments: vec![SubtitleSegment {
+                id: 1,
+                start_time: 0.5,
+                end_time: 2.0,
+                text: "Hallo".to_string(),
+                words: Vec::new(),
+                style: None,
+                cue_settings: None,
+                style_override: None,
+            }],
+            language: "de".to_string(),
+            source: SubtitleSource::Manual,
+            ass_header: None,
+        };
+        let timeline = Timeline {
+            id: "timeline".to_string(),
+            name: "Test".to_string(),
+            framerate: 30.0,
+            resolution: Resolution { width: 1920, height: 1080 },
+            tracks: vec![Track {
+                id: "video".to_string(),
+                track_type: TrackType::Video,
+                clips: vec![create_test_clip(5.0, Vec::new())],
+                muted: false,
+                locked: false,
+            }],
+            duration: 5.0,
+            subtitle_tracks: vec![subtitles],
+            burn_in_language: None,
+            subtitle_enabled: true,
+            subtitle_style: Default::default(),
+            markers: Vec::new(),
+        };
+
+        let service = ExportService {
+            ffmpeg_path: "ffmpeg".to_string(),
+            cancel_flag: Arc::new(AtomicBool::new(false)),
+        };
+        let media_files = HashMap::from([("media1".to_string(), video)]);
+        let mut settings = ExportService::get_presets()[0].1.clone();
+        settings.subtitle_mode = SubtitleExportMode::Soft;
+
+        let args = service.build_ffmpeg_command(
+            &timeline, &settings, Path::new("/tmp/out.mp4"), &media_files, &mut Vec::new(),
+        ).unwrap();
+
+        let subtitle_input = args.iter().position(|a| a.ends_with(".srt")).unwrap();
+        assert_eq!(args[subtitle_input - 1], "-i");
+        let map = args.iter().position(|a| a == "1:s:0").unwrap();
+        assert_eq!(&args[map + 1..map + 5], ["-c:s:0", "mov_text", "-metadata:s:s:0", "language=ger"]);
+
+        // Not burned in as well
+        let filter = &args[args.iter().position(|a| a == "-filter_complex").unwrap() + 1];
+        assert!(!filter.contains("subtitles="));
+
+        // WebM gets WebVTT, and containers without subtitle support are rejected
+        let webm = service.build_ffmpeg_command(
+            &timeline, &settings, Path::new("/tmp/out.webm"), &media_files, &mut Vec::new(),
+        ).unwrap();
+        assert!(webm.iter().any(|a| a == "webvtt"));
+        assert!(service.build_ffmpeg_command(
+            &timeline, &settings, Path::new("/tmp/out.avi"), &media_files, &mut Vec::new(),
+        ).is_err());
+
+        // Chapter markers become one more input mapped as chapters
+        let mut timeline = timeline;
+        timeline.markers.push(crate::models::TimelineMarker {
+            id: "chapter".to_string(),
+            name: "Opening".to_string(),
+            time: 0.0,
+            end_time: None,
+            kind: crate::models::MarkerKind::Chapter,
+            color: "#F6AD55".to_string(),
+            notes: String::new(),
+        });
+        settings.chapters = true;
+        let args = service.build_ffmpeg_command(
+            &timeline, &settings, Path::new("/tmp/out.mkv"), &media_files, &mut Vec::new(),
+        ).unwrap();
+        let map = args.iter().position(|a| a == "-map_chapters").unwrap();
+        assert_eq!(args[map + 1], "2");
+        assert!(args.iter().any(|a| a.contains("clipforge_chapters_")));
+        assert!(service.build_ffmpeg_command(
+            &timeline, &settings, Path::new("/tmp/out.webm"), &media_files, &mut Vec::new(),
+        ).is_err());
+    }
+
+    #[test]
+    fn test_invalid_ken_burns_rejected() {
+        let ken_burns = KenBurns {
+            start_zoom: 0.5,
+            end_zoom: 1.0,
+            start_x: 0.5,
+            start_y: 0.5,
+            end_x: 0.5,
+            end_y: 0.5,
+        };
+        let clip = create_test_clip(5.0, Vec::new());
+        assert!(ExportService::build_ken_burns_filter(&ken_burns, 1920, 1080, 30.0, &clip).is_err());
+    }
+
+    #[test]
+    fn test_ken_burns_continues_across_split() {
+        let ken_burns = KenBurns {
+            start_zoom: 1.0,
+            end_zoom: 2.0,
+            start_x: 0.5,
+            start_y: 0.5,
+            end_x: 0.5,
+            end_y: 0.5,
+        };
+
+        let whole = create_test_clip(8.0, Vec::new());
+        let filter = ExportService::build_ken_burns_filter(&ken_burns, 1920, 1080, 30.0, &whole).unwrap();
+        assert!(filter.contains("z='1+(1)*clip((0+it*1)/8,0,1)'"));
+
+        // The second half of a split at 6s picks up the move three quarters in
+        let mut second = create_test_clip(2.0, Vec::new());
+        second.trim_start = 6.0;
+        second.trim_end = 8.0;
+        second.animation_span = Some((0.0, 8.0));
+        let filter = ExportService::build_ken_burns_filter(&ken_burns, 1920, 1080, 30.0, &second).unwrap();
+        assert!(filter.contains("z='1+(1)*clip((6+it*1)/8,0,1)'"));
+    }
 }
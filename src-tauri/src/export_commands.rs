@@ -70,9 +70,12 @@ pub async fn validate_timeline_for_export(
     // Check all clips have valid media files
     for track in &timeline.tracks {
         for clip in &track.clips {
-            if !media_files_map.contains_key(&clip.media_file_id) {
+            if !clip.is_generated() && !media_files_map.contains_key(&clip.media_file_id) {
                 return Err(format!("Clip references missing media file: {}", clip.media_file_id));
             }
+
+            ExportService::validate_clip_effects(track.track_type, clip)
+                .map_err(|e| e.to_string())?;
         }
     }
 
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Get the FFmpeg executable path
 ///
@@ -162,6 +162,37 @@ fn get_bundled_binary_path(binary_name: &str) -> Result<PathBuf, String> {
     Ok(binary_path)
 }
 
+/// Temporary input file for an FFmpeg run, deleted when dropped
+///
+/// Keep it alive until FFmpeg has exited, whether the run succeeded or not.
+#[derive(Debug)]
+pub struct TempFile {
+    path: PathBuf,
+}
+
+impl TempFile {
+    /// Write `contents` to a new file named `{prefix}_{uuid}.{extension}` in the temp directory
+    pub fn write(prefix: &str, extension: &str, contents: impl AsRef<[u8]>) -> std::io::Result<Self> {
+        let path = std::env::temp_dir().join(format!("{}_{}.{}", prefix, uuid::Uuid::new_v4(), extension));
+        std::fs::write(&path, contents)?;
+        Ok(Self { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                log::warn!("Failed to remove temporary file {:?}: {}", self.path, e);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -177,6 +208,16 @@ mod tests {
         assert!(path_str.contains("ffmpeg-aarch64-apple-darwin"));
     }
 
+    #[test]
+    fn test_temp_file_removed_on_drop() {
+        let file = TempFile::write("clipforge_test", "txt", "contents").unwrap();
+        let path = file.path().to_path_buf();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "contents");
+
+        drop(file);
+        assert!(!path.exists());
+    }
+
     #[test]
     fn test_ffmpeg_path() {
         // This test will only pass in a bundled build
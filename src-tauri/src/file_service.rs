@@ -11,7 +11,11 @@ use chrono::Utc;
 use crate::database::Database;
 use crate::thumbnail::ThumbnailGenerator;
 use crate::metadata::extract_metadata;
-use crate::models::{MediaFile, MediaType, FileError, ProxyStatus};
+use crate::models::{MediaFile, MediaMarker, MediaMarkerKind, MediaType, FileError, ProxyStatus, DEFAULT_IMAGE_DURATION};
+use crate::scene_detection::{self, SceneDetectionOptions};
+
+/// Settings key for the clip length given to imported still images
+const DEFAULT_IMAGE_DURATION_KEY: &str = "default_image_duration";
 
 /// Service for managing media file imports and library
 pub struct FileService {
@@ -76,16 +80,24 @@ impl FileService {
                        metadata.duration, metadata.media_type);
         }
 
-        // 5. Generate thumbnail (only for video files, skip for audio)
-        let thumbnail_path = if metadata.media_type == MediaType::Video {
-            let thumb_timestamp = if metadata.duration > 5.0 { 5.0 } else { 0.0 };
-            Some(self.thumbnail_generator
-                .generate(&path, thumb_timestamp)
-                .await
-                .map_err(|_| FileError::ThumbnailError)?)
-        } else {
+        // 5. Generate thumbnail (video and image files, skip for audio)
+        let thumbnail_path = match metadata.media_type {
+            MediaType::Video | MediaType::Image => {
+                let thumb_timestamp = if metadata.duration > 5.0 { 5.0 } else { 0.0 };
+                Some(self.thumbnail_generator
+                    .generate(&path, thumb_timestamp)
+                    .await
+                    .map_err(|_| FileError::ThumbnailError)?)
+            }
             // Audio files: no thumbnail
-            None
+            MediaType::Audio => None,
+        };
+
+        // Stills have no length of their own, so they start at the configured duration
+        let duration = if metadata.media_type == MediaType::Image {
+            self.default_image_duration()?
+        } else {
+            metadata.duration
         };
 
         // 6. Create MediaFile object
@@ -97,7 +109,7 @@ impl FileService {
                 .to_string_lossy()
                 .to_string(),
             media_type: metadata.media_type,
-            duration: metadata.duration,
+            duration,
             resolution: metadata.resolution,
             codec: metadata.codec,
             file_size,  // Use previously calculated file_size
@@ -166,6 +178,78 @@ impl FileService {
         Ok(())
     }
 
+    /// Detect shot boundaries in a video, replacing its stored scene cut markers
+    ///
+    /// Each marker gets a thumbnail from the middle of the shot it starts.
+    pub async fn detect_scenes(
+        &self,
+        id: &str,
+        options: &SceneDetectionOptions,
+    ) -> Result<Vec<MediaMarker>, FileError> {
+        let file = self.get_by_id(id).await?
+            .ok_or_else(|| FileError::FileNotFound(PathBuf::from(id)))?;
+
+        if file.media_type != MediaType::Video {
+            return Err(FileError::SceneDetectionError(
+                format!("{} is not a video", file.filename)
+            ));
+        }
+
+        let cuts = scene_detection::detect_scene_cuts(&file.path, file.duration, options).await?;
+        let thumbnail_times = scene_detection::shot_thumbnail_times(&cuts, file.duration);
+
+        let mut markers = Vec::with_capacity(cuts.len());
+        for (cut, thumbnail_time) in cuts.iter().zip(thumbnail_times) {
+            // A missing shot thumbnail shouldn't lose the cut itself
+            let thumbnail_path = match self.thumbnail_generator.generate(&file.path, thumbnail_time).await {
+                Ok(path) => Some(path),
+                Err(e) => {
+                    log::warn!("Failed to generate shot thumbnail at {:.2}s: {}", thumbnail_time, e);
+                    None
+                }
+            };
+
+            markers.push(MediaMarker {
+                media_file_id: file.id.clone(),
+                kind: MediaMarkerKind::SceneCut,
+                time: cut.time,
+                score: Some(cut.score),
+                thumbnail_path,
+            });
+        }
+
+        self.db.replace_markers(&file.id, MediaMarkerKind::SceneCut, &markers)?;
+        log::info!("Stored {} scene cuts for {}", markers.len(), file.filename);
+
+        Ok(markers)
+    }
+
+    /// Get a media file's markers in time order
+    pub fn get_markers(&self, id: &str) -> Result<Vec<MediaMarker>, FileError> {
+        Ok(self.db.get_markers(id)?)
+    }
+
+    /// Clip length in seconds given to newly imported still images
+    pub fn default_image_duration(&self) -> Result<f64, FileError> {
+        let stored = self.db.get_setting(DEFAULT_IMAGE_DURATION_KEY)?;
+        Ok(stored
+            .and_then(|value| value.parse::<f64>().ok())
+            .filter(|seconds| seconds.is_finite() && *seconds > 0.0)
+            .unwrap_or(DEFAULT_IMAGE_DURATION))
+    }
+
+    /// Change the clip length given to newly imported still images
+    pub fn set_default_image_duration(&self, seconds: f64) -> Result<(), FileError> {
+        if !seconds.is_finite() || seconds <= 0.0 {
+            return Err(FileError::InvalidSetting(
+                format!("Image duration must be greater than zero: {}", seconds)
+            ));
+        }
+        self.db.set_setting(DEFAULT_IMAGE_DURATION_KEY, &seconds.to_string())?;
+        log::info!("Default image duration set to {}s", seconds);
+        Ok(())
+    }
+
     /// Calculate SHA-256 hash of a file
     fn calculate_hash(&self, path: &Path) -> Result<String, FileError> {
         let mut file = File::open(path)?;
@@ -0,0 +1,414 @@
+use crate::export::escape_filter_path;
+use crate::ffmpeg_utils::TempFile;
+use crate::models::{Clip, ClipGenerator, ExportError, TextAlignment, TextAnimation, TextOverlay};
+
+/// Distance text travels during slide animations, as a fraction of the frame
+const SLIDE_DISTANCE: f64 = 0.05;
+
+/// Parse a "#RRGGBB" or "#RRGGBBAA" color into an FFmpeg color ("0xRRGGBB@alpha")
+pub fn ffmpeg_color(color: &str) -> Option<String> {
+    let hex = color.trim().trim_start_matches('#');
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    match hex.len() {
+        6 => Some(format!("0x{}", hex.to_ascii_uppercase())),
+        8 => {
+            let alpha = u8::from_str_radix(&hex[6..], 16).ok()? as f64 / 255.0;
+            Some(format!("0x{}@{:.3}", hex[..6].to_ascii_uppercase(), alpha))
+        }
+        _ => None,
+    }
+}
+
+/// Parse a "#RRGGBB" or "#RRGGBBAA" color into RGBA components
+pub(crate) fn rgba_components(color: &str) -> Option<[u8; 4]> {
+    let hex = color.trim().trim_start_matches('#');
+    if !matches!(hex.len(), 6 | 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    let alpha = if hex.len() == 8 { channel(6)? } else { 255 };
+    Some([channel(0)?, channel(2)?, channel(4)?, alpha])
+}
+
+fn parse_color(color: &str, what: &str) -> Result<String, ExportError> {
+    ffmpeg_color(color).ok_or_else(|| ExportError::ValidationError(
+        format!("Invalid {} color: {}", what, color)
+    ))
+}
+
+/// Filter that fits a layer into a `width`x`height` frame, keeping transparency
+/// around it so lower layers show through
+pub fn fit_layer_filter(width: u32, height: u32) -> String {
+    format!(
+        "format=rgba,scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2:color=black@0,setsar=1",
+        w = width,
+        h = height
+    )
+}
+
+/// Build the source filter chain for a generated clip
+///
+/// The chain starts with a source filter, so it needs no input, and produces
+/// an RGBA `width`x`height` stream lasting `clip.duration` with timestamps
+/// starting at zero. Sizes in the generator are in timeline pixels and are
+/// multiplied by `scale` so lower resolution previews match the export.
+/// Files the chain reads are added to `temp_files`, which must outlive the
+/// FFmpeg run.
+pub fn build_clip_source(
+    clip: &Clip,
+    framerate: f64,
+    width: u32,
+    height: u32,
+    scale: f64,
+    temp_files: &mut Vec<TempFile>,
+) -> Result<String, ExportError> {
+    let generator = clip.generator.as_ref().ok_or_else(|| ExportError::ValidationError(
+        format!("Clip {} is not a generated clip", clip.id)
+    ))?;
+
+    if width == 0 || height == 0 || framerate <= 0.0 {
+        return Err(ExportError::ValidationError(
+            "Generated clips need a valid resolution and framerate".to_string()
+        ));
+    }
+    if !(clip.speed.is_finite() && clip.speed > 0.0) {
+        return Err(ExportError::ValidationError(format!("Invalid clip speed: {}", clip.speed)));
+    }
+
+    // Generate the whole title a split clip came from, so animations stay
+    // where they were, then cut out the visible part at the clip's speed
+    let speed = clip.speed as f64;
+    let (span_start, span_end) = clip.animation_range();
+    let visible = clip.duration * speed;
+    let offset = (clip.trim_start - span_start).max(0.0);
+    let source_duration = (span_end - span_start).max(offset + visible);
+    let size = format!("{}x{}", width, height);
+
+    let source = match generator {
+        ClipGenerator::SolidColor { color } => {
+            format!(
+                "color=c={}:s={}:r={}:d={},format=rgba",
+                parse_color(color, "fill")?, size, framerate, source_duration
+            )
+        }
+        ClipGenerator::Gradient { start_color, end_color, angle } => {
+            let gradient = build_gradient_filter(start_color, end_color, *angle, width, height)?;
+            // Render the gradient once and repeat the frame
+            format!(
+                "color=c=black:s={}:r={}:d=1,trim=end_frame=1,{},loop=loop=-1:size=1,trim=duration={}",
+                size, framerate, gradient, source_duration
+            )
+        }
+        ClipGenerator::Text(text) => {
+            format!(
+                "color=c=black@0:s={}:r={}:d={},format=rgba,{}",
+                size, framerate, source_duration, build_text_filter(text, source_duration, scale, temp_files)?
+            )
+        }
+    };
+
+    let mut filter = format!(
+        "{},trim=start={}:duration={},setpts=PTS-STARTPTS",
+        source, offset, visible
+    );
+    if (speed - 1.0).abs() > 0.01 {
+        filter.push_str(&format!(",setpts={}*PTS,fps={}", 1.0 / speed, framerate));
+    }
+    Ok(filter)
+}
+
+/// Build a `geq` filter drawing a linear gradient across the frame
+fn build_gradient_filter(
+    start_color: &str,
+    end_color: &str,
+    angle: f32,
+    width: u32,
+    height: u32,
+) -> Result<String, ExportError> {
+    let start = rgba_components(start_color).ok_or_else(|| ExportError::ValidationError(
+        format!("Invalid gradient start color: {}", start_color)
+    ))?;
+    let end = rgba_components(end_color).ok_or_else(|| ExportError::ValidationError(
+        format!("Invalid gradient end color: {}", end_color)
+    ))?;
+
+    // Project each pixel onto the gradient direction, giving 0.0 at the
+    // first corner reached and 1.0 at the opposite one
+    let radians = (angle as f64).to_radians();
+    let (cos, sin) = (radians.cos(), radians.sin());
+    let extent = (width as f64 * cos.abs() + height as f64 * sin.abs()).max(1.0);
+    let position = format!(
+        "(0.5+((X-{cx})*{cos:.6}+(Y-{cy})*{sin:.6})/{extent:.3})",
+        cx = width as f64 / 2.0,
+        cy = height as f64 / 2.0,
+        cos = cos,
+        sin = sin,
+        extent = extent
+    );
+
+    let channels: Vec<String> = ["r", "g", "b", "a"].iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let from = start[i] as i32;
+            let delta = end[i] as i32 - from;
+            format!("{}='{}+{}*{}'", name, from, delta, position)
+        })
+        .collect();
+
+    Ok(format!("format=rgba,geq={}", channels.join(":")))
+}
+
+/// Build the drawtext chain for a text overlay, including animations
+fn build_text_filter(
+    text: &TextOverlay,
+    duration: f64,
+    scale: f64,
+    temp_files: &mut Vec<TempFile>,
+) -> Result<String, ExportError> {
+    if text.text.trim().is_empty() {
+        return Err(ExportError::ValidationError("Text overlay has no text".to_string()));
+    }
+    if !(text.font_size.is_finite() && text.font_size > 0.0) {
+        return Err(ExportError::ValidationError(
+            format!("Invalid font size: {}", text.font_size)
+        ));
+    }
+    if !(text.x.is_finite() && text.y.is_finite()) {
+        return Err(ExportError::ValidationError("Invalid text position".to_string()));
+    }
+    if !(text.animation_duration.is_finite() && text.animation_duration >= 0.0) {
+        return Err(ExportError::ValidationError(
+            format!("Invalid animation duration: {}", text.animation_duration)
+        ));
+    }
+
+    let mut options = Vec::new();
+
+    if let Some(font_file) = &text.font_file {
+        if !font_file.exists() {
+            return Err(ExportError::ValidationError(
+                format!("Font file not found: {}", font_file.display())
+            ));
+        }
+        options.push(format!("fontfile={}", escape_filter_path(font_file)));
+    } else if let Some(family) = &text.font_family {
+        let valid = !family.trim().is_empty()
+            && family.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, ' ' | '-' | '_'));
+        if !valid {
+            return Err(ExportError::ValidationError(format!("Invalid font family: {}", family)));
+        }
+        options.push(format!("font='{}'", family.trim()));
+    }
+
+    // Text goes through a file so it needs no filter escaping
+    let text_file = TempFile::write("clipforge_text", "txt", &text.text)
+        .map_err(|e| ExportError::OutputError(format!("Failed to write overlay text: {}", e)))?;
+    options.push(format!("textfile={}", escape_filter_path(text_file.path())));
+    temp_files.push(text_file);
+    options.push("expansion=none".to_string());
+    options.push(format!("fontsize={:.2}", text.font_size as f64 * scale));
+    options.push(format!("fontcolor={}", parse_color(&text.color, "text")?));
+
+    if text.outline_width > 0.0 {
+        let outline = text.outline_color.as_deref().unwrap_or("#000000");
+        options.push(format!("borderw={:.2}", text.outline_width as f64 * scale));
+        options.push(format!("bordercolor={}", parse_color(outline, "outline")?));
+    }
+
+    if let Some(background) = &text.background {
+        options.push("box=1".to_string());
+        options.push(format!("boxcolor={}", parse_color(&background.color, "background")?));
+        options.push(format!("boxborderw={}", (background.padding.max(0.0) as f64 * scale).round() as u32));
+    }
+
+    // Fade and slide timings, shortened when the clip is too short for both
+    let animation = text.animation_duration.min(duration / 2.0);
+    let out_start = duration - animation;
+    let slide_in = format!("(1-clip(t/{d:.3},0,1))", d = animation);
+    let slide_out = format!("clip((t-{s:.3})/{d:.3},0,1)", s = out_start, d = animation);
+
+    let anchor = match text.alignment {
+        TextAlignment::Left => "",
+        TextAlignment::Center => "-text_w/2",
+        TextAlignment::Right => "-text_w",
+    };
+    let mut x = format!("{}*w{}", text.x, anchor);
+    let mut y = format!("{}*h-text_h/2", text.y);
+
+    if animation > 0.0 {
+        // Slide in towards the anchor and keep moving in the same direction on the way out
+        match text.animate_in {
+            TextAnimation::SlideUp => y.push_str(&format!("+{}*h*{}", SLIDE_DISTANCE, slide_in)),
+            TextAnimation::SlideLeft => x.push_str(&format!("+{}*w*{}", SLIDE_DISTANCE, slide_in)),
+            TextAnimation::None | TextAnimation::Fade => {}
+        }
+        match text.animate_out {
+            TextAnimation::SlideUp => y.push_str(&format!("-{}*h*{}", SLIDE_DISTANCE, slide_out)),
+            TextAnimation::SlideLeft => x.push_str(&format!("-{}*w*{}", SLIDE_DISTANCE, slide_out)),
+            TextAnimation::None | TextAnimation::Fade => {}
+        }
+    }
+
+    options.push(format!("x='{}'", x));
+    options.push(format!("y='{}'", y));
+
+    let mut filter = format!("drawtext={}", options.join(":"));
+
+    if animation > 0.0 {
+        if text.animate_in != TextAnimation::None {
+            filter.push_str(&format!(",fade=t=in:st=0:d={:.3}:alpha=1", animation));
+        }
+        if text.animate_out != TextAnimation::None {
+            filter.push_str(&format!(",fade=t=out:st={:.3}:d={:.3}:alpha=1", out_start, animation));
+        }
+    }
+
+    Ok(filter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TextBackground;
+
+    fn generated_clip(generator: ClipGenerator, duration: f64) -> Clip {
+        Clip {
+            id: "generated".to_string(),
+            media_file_id: String::new(),
+            name: None,
+            track_position: 0.0,
+            duration,
+            trim_start: 0.0,
+            trim_end: duration,
+            effects: Vec::new(),
+            volume: 1.0,
+            speed: 1.0,
+            generator: Some(generator),
+            ken_burns: None,
+            animation_span: None,
+        }
+    }
+
+    fn title(text: &str) -> TextOverlay {
+        TextOverlay {
+            text: text.to_string(),
+            font_family: None,
+            font_file: None,
+            font_size: 64.0,
+            color: "#FFFFFF".to_string(),
+            outline_color: Some("#000000".to_string()),
+            outline_width: 2.0,
+            background: Some(TextBackground { color: "#00000080".to_string(), padding: 10.0 }),
+            alignment: TextAlignment::Center,
+            x: 0.5,
+            y: 0.8,
+            animate_in: TextAnimation::Fade,
+            animate_out: TextAnimation::SlideUp,
+            animation_duration: 0.5,
+        }
+    }
+
+    #[test]
+    fn test_ffmpeg_color() {
+        assert_eq!(ffmpeg_color("#ff0000"), Some("0xFF0000".to_string()));
+        assert_eq!(ffmpeg_color("#00000080"), Some("0x000000@0.502".to_string()));
+        assert_eq!(ffmpeg_color("red"), None);
+        assert_eq!(ffmpeg_color("#12345"), None);
+    }
+
+    #[test]
+    fn test_solid_color_source() {
+        let clip = generated_clip(ClipGenerator::SolidColor { color: "#102030".to_string() }, 4.0);
+        let filter = build_clip_source(&clip, 30.0, 1920, 1080, 1.0, &mut Vec::new()).unwrap();
+
+        assert!(filter.starts_with("color=c=0x102030:s=1920x1080:r=30:d=4"));
+        assert!(filter.ends_with("trim=start=0:duration=4,setpts=PTS-STARTPTS"));
+    }
+
+    #[test]
+    fn test_gradient_source() {
+        let clip = generated_clip(ClipGenerator::Gradient {
+            start_color: "#000000".to_string(),
+            end_color: "#FFFFFF".to_string(),
+            angle: 0.0,
+        }, 2.0);
+        let filter = build_clip_source(&clip, 25.0, 100, 50, 1.0, &mut Vec::new()).unwrap();
+
+        assert!(filter.contains("geq=r='0+255*(0.5+((X-50)*1.000000+(Y-25)*0.000000)/100.000)'"));
+        assert!(filter.contains("loop=loop=-1:size=1,trim=duration=2"));
+    }
+
+    #[test]
+    fn test_text_source_scales_and_animates() {
+        let clip = generated_clip(ClipGenerator::Text(title("Hello: 'world'")), 3.0);
+        let mut temp_files = Vec::new();
+        let filter = build_clip_source(&clip, 30.0, 960, 540, 0.5, &mut temp_files).unwrap();
+
+        assert!(filter.contains("drawtext="));
+        assert!(filter.contains("expansion=none"));
+        assert!(filter.contains("fontsize=32.00"));
+        assert!(filter.contains("borderw=1.00"));
+        assert!(filter.contains("boxcolor=0x000000@0.502"));
+        assert!(filter.contains("boxborderw=5"));
+        assert!(filter.contains("x='0.5*w-text_w/2'"));
+        assert!(filter.contains("y='0.8*h-text_h/2-0.05*h*clip((t-2.500)/0.500,0,1)'"));
+        assert!(filter.contains("fade=t=in:st=0:d=0.500:alpha=1"));
+        assert!(filter.contains("fade=t=out:st=2.500:d=0.500:alpha=1"));
+        // Text is passed through a file rather than inline, removed with its guard
+        assert!(!filter.contains("world"));
+        let text_file = temp_files[0].path().to_path_buf();
+        assert_eq!(std::fs::read_to_string(&text_file).unwrap(), "Hello: 'world'");
+        drop(temp_files);
+        assert!(!text_file.exists());
+    }
+
+    #[test]
+    fn test_split_text_clip_keeps_animation_timing() {
+        let mut first = generated_clip(ClipGenerator::Text(title("Title")), 2.0);
+        first.animation_span = Some((0.0, 4.0));
+        let mut second = first.clone();
+        second.trim_start = 2.0;
+        second.trim_end = 4.0;
+
+        // Both halves render the whole original title, so the fade-out ends
+        // it rather than the first half
+        let filter = build_clip_source(&first, 30.0, 1920, 1080, 1.0, &mut Vec::new()).unwrap();
+        assert!(filter.contains(":d=4,format=rgba"));
+        assert!(filter.contains("fade=t=out:st=3.500"));
+        assert!(filter.ends_with("trim=start=0:duration=2,setpts=PTS-STARTPTS"));
+
+        let filter = build_clip_source(&second, 30.0, 1920, 1080, 1.0, &mut Vec::new()).unwrap();
+        assert!(filter.contains(":d=4,format=rgba"));
+        assert!(filter.ends_with("trim=start=2:duration=2,setpts=PTS-STARTPTS"));
+    }
+
+    #[test]
+    fn test_text_clip_speed() {
+        // A 4s title played at double speed lasts 2s on the timeline
+        let mut clip = generated_clip(ClipGenerator::Text(title("Title")), 2.0);
+        clip.speed = 2.0;
+        clip.trim_end = 4.0;
+        let filter = build_clip_source(&clip, 30.0, 1920, 1080, 1.0, &mut Vec::new()).unwrap();
+
+        assert!(filter.contains(":d=4,format=rgba"));
+        assert!(filter.contains("fade=t=out:st=3.500"));
+        assert!(filter.ends_with("trim=start=0:duration=4,setpts=PTS-STARTPTS,setpts=0.5*PTS,fps=30"));
+    }
+
+    #[test]
+    fn test_invalid_generators_rejected() {
+        let clip = generated_clip(ClipGenerator::SolidColor { color: "blue".to_string() }, 1.0);
+        assert!(build_clip_source(&clip, 30.0, 1920, 1080, 1.0, &mut Vec::new()).is_err());
+
+        let mut text = title("   ");
+        let clip = generated_clip(ClipGenerator::Text(text.clone()), 1.0);
+        assert!(build_clip_source(&clip, 30.0, 1920, 1080, 1.0, &mut Vec::new()).is_err());
+
+        text.text = "Title".to_string();
+        text.font_family = Some("Sans'; rm".to_string());
+        let clip = generated_clip(ClipGenerator::Text(text), 1.0);
+        assert!(build_clip_source(&clip, 30.0, 1920, 1080, 1.0, &mut Vec::new()).is_err());
+    }
+}
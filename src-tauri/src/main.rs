@@ -20,6 +20,7 @@ mod metadata;
 mod thumbnail;
 mod screen_preview;
 mod file_service;
+mod scene_detection;
 mod error_handler;
 
 // Module 3: FFmpeg Integration
@@ -32,9 +33,13 @@ mod recording;
 // Module 5: Timeline Engine
 mod timeline;
 mod timeline_commands;
+mod markers;
+mod transcript_edit;
+mod cut_suggestions;
 
 // Module 6: Export & Rendering
 mod export;
+mod generator;
 mod export_commands;
 
 // Module 8: Video Preview
@@ -43,8 +48,19 @@ mod preview_service;
 
 // AI Subtitle Generation
 mod subtitle;
+mod subtitle_segmentation;
+mod subtitle_edit;
+mod subtitle_format;
+mod subtitle_style;
+mod transcription;
+mod audio_chunks;
+mod translation;
 mod subtitle_commands;
 
+// Settings: provider credentials
+mod credentials;
+mod credential_commands;
+
 use database::Database;
 use thumbnail::ThumbnailGenerator;
 use file_service::FileService;
@@ -114,9 +130,22 @@ fn main() {
             // Module 8: Initialize Preview service
             let preview_service = Arc::new(Mutex::new(PreviewService::new()));
 
-            // AI Subtitle Generation: Initialize subtitle service state (API key set later)
+            // Settings: Open the encrypted credential store, in memory if the data dir is unusable
+            let credential_store: Box<dyn credentials::SecretStore> =
+                match credentials::EncryptedFileStore::open_default() {
+                    Ok(store) => Box::new(store),
+                    Err(e) => {
+                        log::error!("Failed to open credential store, keys will not be saved: {}", e);
+                        Box::new(credentials::MemorySecretStore::default())
+                    }
+                };
+
+            // AI Subtitle Generation: Initialize subtitle service state from saved credentials
             let subtitle_state = subtitle_commands::SubtitleServiceState {
-                service: Arc::new(Mutex::new(None)),
+                service: Arc::new(Mutex::new(
+                    credential_commands::restore_transcription_service(credential_store.as_ref())
+                )),
+                translator: Arc::new(Mutex::new(None)),
             };
 
             app.manage(file_service);
@@ -126,6 +155,9 @@ fn main() {
             app.manage(export_state);
             app.manage(preview_service);
             app.manage(subtitle_state);
+            app.manage(credential_commands::CredentialState {
+                store: Arc::new(Mutex::new(credential_store)),
+            });
 
             log::info!("ClipForge initialized successfully");
 
@@ -145,9 +177,13 @@ fn main() {
             commands::get_media_library,
             commands::get_media_file,
             commands::delete_media_file,
+            commands::detect_scene_cuts,
+            commands::get_media_markers,
             commands::get_file_metadata,
             commands::generate_thumbnail,
             commands::generate_thumbnail_sequence,
+            commands::get_default_image_duration,
+            commands::set_default_image_duration,
             // Module 3 commands
             commands::trim_video_clip,
             commands::concatenate_clips,
@@ -157,10 +193,19 @@ fn main() {
             commands::list_recording_sources,
             commands::check_recording_permissions,
             commands::request_recording_permissions,
+            commands::list_audio_devices,
+            commands::start_audio_meter,
+            commands::stop_audio_meter,
             commands::start_recording,
+            commands::pause_recording,
+            commands::resume_recording,
+            commands::cancel_scheduled_recording,
             commands::stop_recording,
             commands::get_recording_state,
             commands::get_recording_duration,
+            commands::list_unfinished_recordings,
+            commands::recover_recording,
+            commands::discard_unfinished_recording,
             // Module 5 commands
             timeline_commands::create_timeline,
             timeline_commands::get_current_timeline,
@@ -171,9 +216,23 @@ fn main() {
             timeline_commands::move_clip_on_timeline,
             timeline_commands::trim_clip_on_timeline,
             timeline_commands::split_clip_at_time,
+            timeline_commands::split_clip_at_scene_cuts,
             timeline_commands::get_clips_at_playhead,
             timeline_commands::save_timeline_project,
             timeline_commands::load_timeline_project,
+            timeline_commands::add_timeline_marker,
+            timeline_commands::update_timeline_marker,
+            timeline_commands::remove_timeline_marker,
+            timeline_commands::get_next_marker,
+            timeline_commands::get_previous_marker,
+            timeline_commands::export_youtube_chapters,
+            timeline_commands::set_subtitle_style,
+            timeline_commands::set_subtitle_track,
+            timeline_commands::remove_subtitle_track,
+            timeline_commands::set_burn_in_subtitles,
+            timeline_commands::cut_transcript,
+            timeline_commands::apply_cuts,
+            timeline_commands::detect_cut_suggestions,
             // Module 6 commands
             export_commands::export_timeline,
             export_commands::cancel_export,
@@ -185,12 +244,26 @@ fn main() {
             commands::get_cache_stats,
             // AI Subtitle commands
             subtitle_commands::set_openai_api_key,
+            subtitle_commands::set_transcription_provider,
             subtitle_commands::check_subtitle_available,
             subtitle_commands::transcribe_timeline_audio,
+            subtitle_commands::resegment_subtitles,
+            subtitle_commands::set_translation_provider,
+            subtitle_commands::translate_subtitles,
             subtitle_commands::update_subtitle_segment,
+            subtitle_commands::split_subtitle_segment,
+            subtitle_commands::merge_subtitle_segments,
+            subtitle_commands::insert_subtitle_segment,
+            subtitle_commands::delete_subtitle_segment,
+            subtitle_commands::shift_subtitles,
             subtitle_commands::toggle_subtitles,
-            subtitle_commands::export_subtitles_srt,
-            subtitle_commands::import_subtitles_srt,
+            subtitle_commands::export_subtitles,
+            subtitle_commands::import_subtitles,
+            // Settings commands
+            credential_commands::set_provider_credential,
+            credential_commands::test_provider_credential,
+            credential_commands::clear_provider_credential,
+            credential_commands::list_provider_credentials,
         ])
         // Handle window events
         .on_window_event(|window, event| if let tauri::WindowEvent::CloseRequested { .. } = event {
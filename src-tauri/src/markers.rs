@@ -0,0 +1,253 @@
+use crate::models::{MarkerKind, Timeline, TimelineMarker};
+use crate::timeline::TimelineError;
+use serde::Serialize;
+use uuid::Uuid;
+
+/// Markers closer than this to a time are treated as at it (seconds)
+const MARKER_EPSILON: f64 = 0.001;
+
+fn invalid(message: String) -> TimelineError {
+    TimelineError::InvalidOperation(message)
+}
+
+/// Check a marker's name, times and color
+pub fn validate_marker(marker: &TimelineMarker) -> Result<(), TimelineError> {
+    if marker.name.trim().is_empty() {
+        return Err(invalid("Marker has no name".to_string()));
+    }
+    if !marker.time.is_finite() || marker.time < 0.0 {
+        return Err(invalid(format!("Marker {} starts before the timeline ({}s)", marker.name, marker.time)));
+    }
+    if let Some(end_time) = marker.end_time {
+        if !end_time.is_finite() || end_time <= marker.time {
+            return Err(invalid(format!(
+                "Region {} must end after it starts ({}s - {}s)", marker.name, marker.time, end_time
+            )));
+        }
+    }
+
+    let hex = marker.color.strip_prefix('#').unwrap_or("");
+    if !matches!(hex.len(), 3 | 6) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid(format!("Invalid marker color: {}", marker.color)));
+    }
+    Ok(())
+}
+
+fn sort(markers: &mut [TimelineMarker]) {
+    markers.sort_by(|a, b| a.time.total_cmp(&b.time));
+}
+
+/// Add a marker, giving it an id when it has none, and return the id
+pub fn add_marker(markers: &mut Vec<TimelineMarker>, mut marker: TimelineMarker) -> Result<String, TimelineError> {
+    validate_marker(&marker)?;
+    if marker.id.is_empty() {
+        marker.id = Uuid::new_v4().to_string();
+    } else if markers.iter().any(|m| m.id == marker.id) {
+        return Err(invalid(format!("Marker already exists: {}", marker.id)));
+    }
+
+    let id = marker.id.clone();
+    markers.push(marker);
+    sort(markers);
+    Ok(id)
+}
+
+/// Replace the marker with the same id
+pub fn update_marker(markers: &mut [TimelineMarker], marker: TimelineMarker) -> Result<(), TimelineError> {
+    validate_marker(&marker)?;
+    let existing = markers.iter_mut()
+        .find(|m| m.id == marker.id)
+        .ok_or_else(|| invalid(format!("Marker not found: {}", marker.id)))?;
+
+    *existing = marker;
+    sort(markers);
+    Ok(())
+}
+
+/// Remove a marker by id
+pub fn remove_marker(markers: &mut Vec<TimelineMarker>, id: &str) -> Result<(), TimelineError> {
+    let before = markers.len();
+    markers.retain(|m| m.id != id);
+    if markers.len() == before {
+        return Err(invalid(format!("Marker not found: {}", id)));
+    }
+    Ok(())
+}
+
+/// First marker after `time`
+pub fn next_marker(markers: &[TimelineMarker], time: f64) -> Option<&TimelineMarker> {
+    markers.iter().find(|m| m.time > time + MARKER_EPSILON)
+}
+
+/// Last marker before `time`
+pub fn previous_marker(markers: &[TimelineMarker], time: f64) -> Option<&TimelineMarker> {
+    markers.iter().rev().find(|m| m.time < time - MARKER_EPSILON)
+}
+
+/// Close the gap left by removing `start..end` from the timeline
+///
+/// Markers inside the range move to its start, so a chapter whose opening
+/// is cut still begins where its remaining material does. Regions shrink,
+/// and are dropped when nothing of them remains.
+pub fn cut_range(markers: &mut Vec<TimelineMarker>, start: f64, end: f64) {
+    let removed = end - start;
+    let remap = |time: f64| {
+        if time <= start {
+            time
+        } else if time >= end {
+            time - removed
+        } else {
+            start
+        }
+    };
+
+    markers.retain_mut(|marker| {
+        marker.time = remap(marker.time);
+        match marker.end_time.map(remap) {
+            Some(end_time) if end_time - marker.time < MARKER_EPSILON => false,
+            end_time => {
+                marker.end_time = end_time;
+                true
+            }
+        }
+    });
+    sort(markers);
+}
+
+/// A chapter of an exported video
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Chapter {
+    pub title: String,
+    pub start: f64,  // seconds
+    pub end: f64,  // seconds
+}
+
+/// Chapters from the timeline's chapter markers
+///
+/// A chapter runs to its region end, or else to the next chapter or the end
+/// of the timeline.
+pub fn chapters(timeline: &Timeline) -> Vec<Chapter> {
+    let starts: Vec<&TimelineMarker> = timeline.markers.iter()
+        .filter(|m| m.kind == MarkerKind::Chapter && m.time < timeline.duration)
+        .collect();
+
+    starts.iter()
+        .enumerate()
+        .map(|(i, marker)| {
+            let next = starts.get(i + 1).map(|m| m.time).unwrap_or(timeline.duration);
+            Chapter {
+                title: marker.name.clone(),
+                start: marker.time,
+                end: marker.end_time.unwrap_or(next).min(next),
+            }
+        })
+        .collect()
+}
+
+/// FFmpeg metadata file declaring the chapters, for `-map_chapters`
+pub fn ffmetadata(chapters: &[Chapter]) -> String {
+    let mut out = String::from(";FFMETADATA1\n");
+    for chapter in chapters {
+        out.push_str("\n[CHAPTER]\nTIMEBASE=1/1000\n");
+        out.push_str(&format!("START={}\n", (chapter.start * 1000.0).round() as u64));
+        out.push_str(&format!("END={}\n", (chapter.end * 1000.0).round() as u64));
+        out.push_str(&format!("title={}\n", escape_ffmetadata(&chapter.title)));
+    }
+    out
+}
+
+fn escape_ffmetadata(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Chapter list in the form YouTube reads from video descriptions
+///
+/// YouTube needs the first chapter at 0:00, so one is added when the first
+/// chapter marker starts later.
+pub fn youtube_chapters(chapters: &[Chapter]) -> String {
+    let long = chapters.last().is_some_and(|c| c.start >= 3600.0);
+    let mut lines = Vec::with_capacity(chapters.len() + 1);
+    if chapters.first().is_some_and(|c| c.start >= 1.0) {
+        lines.push(format!("{} Intro", youtube_timestamp(0.0, long)));
+    }
+
+    for chapter in chapters {
+        lines.push(format!("{} {}", youtube_timestamp(chapter.start, long), chapter.title.trim()));
+    }
+    lines.join("\n")
+}
+
+fn youtube_timestamp(seconds: f64, with_hours: bool) -> String {
+    let total = seconds.max(0.0).floor() as u64;
+    let (h, m, s) = (total / 3600, total % 3600 / 60, total % 60);
+    if with_hours {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{}:{:02}", m, s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marker(name: &str, time: f64, end_time: Option<f64>, kind: MarkerKind) -> TimelineMarker {
+        TimelineMarker {
+            id: String::new(),
+            name: name.to_string(),
+            time,
+            end_time,
+            kind,
+            color: "#F6AD55".to_string(),
+            notes: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_markers_sorted_navigated_and_rippled() {
+        let mut markers = Vec::new();
+        let late = add_marker(&mut markers, marker("Outro", 20.0, None, MarkerKind::Chapter)).unwrap();
+        add_marker(&mut markers, marker("Retake", 6.0, Some(9.0), MarkerKind::Marker)).unwrap();
+        add_marker(&mut markers, marker("Demo", 4.0, None, MarkerKind::Chapter)).unwrap();
+        assert!(add_marker(&mut markers, marker("Bad", 5.0, Some(5.0), MarkerKind::Marker)).is_err());
+
+        let names: Vec<&str> = markers.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["Demo", "Retake", "Outro"]);
+        assert_eq!(next_marker(&markers, 4.0).unwrap().name, "Retake");
+        assert_eq!(previous_marker(&markers, 4.0).map(|m| m.name.as_str()), None);
+        assert_eq!(previous_marker(&markers, 30.0).unwrap().id, late);
+
+        // The region loses its first half; the chapter inside the cut moves to its start
+        cut_range(&mut markers, 3.0, 7.5);
+        let spans: Vec<(f64, Option<f64>)> = markers.iter().map(|m| (m.time, m.end_time)).collect();
+        assert_eq!(spans, vec![(3.0, None), (3.0, Some(4.5)), (15.5, None)]);
+
+        cut_range(&mut markers, 2.0, 5.0);
+        assert_eq!(markers.len(), 2);
+    }
+
+    #[test]
+    fn test_chapter_exports() {
+        let chapters = vec![
+            Chapter { title: "Setup; part=1".to_string(), start: 12.4, end: 75.0 },
+            Chapter { title: "Results".to_string(), start: 75.0, end: 3700.0 },
+            Chapter { title: "Q&A".to_string(), start: 3700.0, end: 3900.0 },
+        ];
+
+        assert_eq!(
+            youtube_chapters(&chapters),
+            "0:00:00 Intro\n0:00:12 Setup; part=1\n0:01:15 Results\n1:01:40 Q&A"
+        );
+        assert_eq!(youtube_chapters(&chapters[..2]), "0:00 Intro\n0:12 Setup; part=1\n1:15 Results");
+
+        let metadata = ffmetadata(&chapters[..1]);
+        assert_eq!(metadata, ";FFMETADATA1\n\n[CHAPTER]\nTIMEBASE=1/1000\nSTART=12400\nEND=75000\ntitle=Setup\\; part\\=1\n");
+    }
+}
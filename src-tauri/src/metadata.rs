@@ -59,7 +59,17 @@ pub async fn extract_metadata(path: &Path) -> Result<FileMetadata, MetadataError
     let has_video = video_stream.is_some();
     let has_audio = audio_stream.is_some();
 
-    let media_type = if has_video && has_audio {
+    // Still images are a single video frame read by an image demuxer
+    let is_still = video_stream
+        .map(|vs| is_still_image_stream(
+            format["format_name"].as_str().unwrap_or(""),
+            vs["codec_name"].as_str().unwrap_or(""),
+        ))
+        .unwrap_or(false);
+
+    let media_type = if is_still {
+        MediaType::Image
+    } else if has_video && has_audio {
         MediaType::Video
     } else if has_audio {
         MediaType::Audio
@@ -69,11 +79,15 @@ pub async fn extract_metadata(path: &Path) -> Result<FileMetadata, MetadataError
         return Err(MetadataError::FFprobeError);  // No valid streams
     };
 
-    // Parse duration
-    let duration = format["duration"]
-        .as_str()
-        .and_then(|s| s.parse::<f64>().ok())
-        .unwrap_or(0.0);
+    // Parse duration (stills have none, their clip length is chosen on import)
+    let duration = if is_still {
+        0.0
+    } else {
+        format["duration"]
+            .as_str()
+            .and_then(|s| s.parse::<f64>().ok())
+            .unwrap_or(0.0)
+    };
 
     // Parse resolution (only for video files)
     let resolution = video_stream.map(|vs| {
@@ -99,6 +113,7 @@ pub async fn extract_metadata(path: &Path) -> Result<FileMetadata, MetadataError
 
     // Parse framerate (only for video files)
     let framerate = video_stream
+        .filter(|_| !is_still)
         .and_then(|vs| vs["r_frame_rate"].as_str())
         .map(parse_framerate);
 
@@ -117,6 +132,12 @@ pub async fn extract_metadata(path: &Path) -> Result<FileMetadata, MetadataError
     })
 }
 
+/// Whether FFprobe's container and codec names describe a JPEG, PNG or WebP still
+fn is_still_image_stream(format_name: &str, codec_name: &str) -> bool {
+    let image_demuxer = format_name == "image2" || format_name.ends_with("_pipe");
+    image_demuxer && matches!(codec_name, "mjpeg" | "png" | "webp")
+}
+
 /// Parse framerate string like "30/1" or "24000/1001"
 fn parse_framerate(fps_str: &str) -> f64 {
     let parts: Vec<&str> = fps_str.split('/').collect();
@@ -141,4 +162,14 @@ mod tests {
         // 23.976 fps (common for film)
         assert!((parse_framerate("24000/1001") - 23.976).abs() < 0.001);
     }
+
+    #[test]
+    fn test_is_still_image_stream() {
+        assert!(is_still_image_stream("image2", "mjpeg"));
+        assert!(is_still_image_stream("png_pipe", "png"));
+        assert!(is_still_image_stream("webp_pipe", "webp"));
+        // Motion JPEG inside a video container is still a video
+        assert!(!is_still_image_stream("mov,mp4,m4a,3gp,3g2,mj2", "mjpeg"));
+        assert!(!is_still_image_stream("image2", "h264"));
+    }
 }
@@ -8,7 +8,21 @@ use chrono::{DateTime, Utc};
 pub enum MediaType {
     Video,  // Video file (with or without audio)
     Audio,  // Audio-only file (.mp3, .wav, .aac, etc.)
-    Image,  // Image file (.jpg, .png, etc.) - for future use
+    Image,  // Still image file (.jpg, .png, .webp)
+}
+
+/// Extensions of still images that can be imported
+pub const STILL_IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp"];
+
+/// Duration given to imported still images unless configured otherwise (seconds)
+pub const DEFAULT_IMAGE_DURATION: f64 = 5.0;
+
+/// Whether a path has a still image extension
+pub fn is_still_image_path(path: &std::path::Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| STILL_IMAGE_EXTENSIONS.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+        .unwrap_or(false)
 }
 
 /// Represents a media file in the library
@@ -40,6 +54,23 @@ pub enum ProxyStatus {
     Failed,     // Proxy generation failed
 }
 
+/// Kind of point marked in a media file
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaMarkerKind {
+    SceneCut,  // First frame of a new shot
+}
+
+/// A point of interest in a media file, found by analysis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaMarker {
+    pub media_file_id: String,
+    pub kind: MediaMarkerKind,
+    pub time: f64,                        // Source time in seconds
+    pub score: Option<f64>,               // Detection confidence (scene score 0-1)
+    pub thumbnail_path: Option<PathBuf>,  // Thumbnail of the shot starting here
+}
+
 /// Video resolution
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Resolution {
@@ -90,6 +121,12 @@ pub enum FileError {
 
     #[error("Invalid file format")]
     InvalidFormat,
+
+    #[error("Invalid setting: {0}")]
+    InvalidSetting(String),
+
+    #[error("Scene detection failed: {0}")]
+    SceneDetectionError(String),
 }
 
 /// Custom error types for metadata extraction
@@ -131,10 +168,98 @@ pub struct Timeline {
     pub resolution: Resolution,
     pub tracks: Vec<Track>,
     pub duration: f64,  // Total duration in seconds
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub subtitle_track: Option<SubtitleTrack>,  // AI-generated or imported subtitles
+    #[serde(
+        default,
+        alias = "subtitle_track",
+        deserialize_with = "deserialize_subtitle_tracks",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub subtitle_tracks: Vec<SubtitleTrack>,  // One per language
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burn_in_language: Option<String>,  // Track drawn into the video, the first one when unset
     #[serde(default)]
     pub subtitle_enabled: bool,  // Global toggle for preview and export
+    #[serde(default)]
+    pub subtitle_style: SubtitleStyle,  // Look of burned-in subtitles
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub markers: Vec<TimelineMarker>,  // Sorted by time
+}
+
+impl Timeline {
+    /// Subtitle track for a language
+    pub fn subtitle_track(&self, language: &str) -> Option<&SubtitleTrack> {
+        self.subtitle_tracks.iter().find(|t| t.language == language)
+    }
+
+    /// Subtitle track for a language, for editing
+    pub fn subtitle_track_mut(&mut self, language: &str) -> Option<&mut SubtitleTrack> {
+        self.subtitle_tracks.iter_mut().find(|t| t.language == language)
+    }
+
+    /// Subtitle track drawn into preview frames and burn-in exports
+    pub fn burn_in_subtitle_track(&self) -> Option<&SubtitleTrack> {
+        if !self.subtitle_enabled {
+            return None;
+        }
+        match &self.burn_in_language {
+            Some(language) => self.subtitle_track(language),
+            None => self.subtitle_tracks.first(),
+        }
+    }
+
+    /// Subtitle tracks written into exports, empty when subtitles are off
+    pub fn export_subtitle_tracks(&self) -> Vec<&SubtitleTrack> {
+        if !self.subtitle_enabled {
+            return Vec::new();
+        }
+        self.subtitle_tracks.iter().collect()
+    }
+}
+
+/// Accept the single `subtitle_track` of older projects as well as a list
+fn deserialize_subtitle_tracks<'de, D>(deserializer: D) -> Result<Vec<SubtitleTrack>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Tracks {
+        List(Vec<SubtitleTrack>),
+        Single(Option<SubtitleTrack>),
+    }
+
+    Ok(match Tracks::deserialize(deserializer)? {
+        Tracks::List(tracks) => tracks,
+        Tracks::Single(track) => track.into_iter().collect(),
+    })
+}
+
+/// What a timeline marker is used for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MarkerKind {
+    #[default]
+    Marker,   // Editing note
+    Chapter,  // Start of a chapter in exports and chapter lists
+}
+
+/// A named point, or range when it has an end, on the timeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimelineMarker {
+    pub id: String,
+    pub name: String,
+    pub time: f64,  // Position on timeline in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<f64>,  // End of a region, None for a point marker
+    #[serde(default)]
+    pub kind: MarkerKind,
+    #[serde(default = "default_marker_color")]
+    pub color: String,  // Hex color, e.g. "#F6AD55"
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+}
+
+fn default_marker_color() -> String {
+    "#F6AD55".to_string()
 }
 
 /// A track in the timeline (Video, Audio, or Overlay)
@@ -159,7 +284,8 @@ pub enum TrackType {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Clip {
     pub id: String,
-    pub media_file_id: String,
+    #[serde(default)]
+    pub media_file_id: String,  // Empty for generated clips
     pub name: Option<String>,  // Display name (typically filename)
     pub track_position: f64,  // Position on timeline in seconds
     pub duration: f64,  // Duration in seconds (can differ from source if trimmed)
@@ -168,6 +294,115 @@ pub struct Clip {
     pub effects: Vec<Effect>,
     pub volume: f32,  // 0.0 to 1.0 (or higher for amplification)
     pub speed: f32,  // Playback speed multiplier (0.5 = half speed, 2.0 = double speed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generator: Option<ClipGenerator>,  // Generated content instead of a media file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ken_burns: Option<KenBurns>,  // Pan and zoom, typically for still images
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub animation_span: Option<(f64, f64)>,  // Source range of the clip this was split from
+}
+
+/// Pan and zoom animated across the clip
+///
+/// The frame is filled with the source (cropping to the timeline aspect
+/// ratio), then zoomed around a centre point that moves from start to end.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KenBurns {
+    pub start_zoom: f32,  // 1.0 = whole frame
+    pub end_zoom: f32,
+    pub start_x: f32,  // Centre of the view, 0.0 to 1.0 of the frame
+    pub start_y: f32,
+    pub end_x: f32,
+    pub end_y: f32,
+}
+
+impl Clip {
+    /// Whether the clip renders generated content rather than a media file
+    pub fn is_generated(&self) -> bool {
+        self.generator.is_some()
+    }
+
+    /// Source range that animations (text, Ken Burns) run across
+    ///
+    /// For a split clip this is the range of the clip it was split from, so
+    /// animations carry on across the cut instead of restarting.
+    pub fn animation_range(&self) -> (f64, f64) {
+        self.animation_span.unwrap_or_else(|| {
+            let end = self.trim_start + self.duration * self.speed as f64;
+            (self.trim_start, end)
+        })
+    }
+}
+
+/// Clip content generated at render time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ClipGenerator {
+    Text(TextOverlay),
+    SolidColor { color: String },  // "#RRGGBB" or "#RRGGBBAA"
+    Gradient {
+        start_color: String,
+        end_color: String,
+        #[serde(default)]
+        angle: f32,  // Degrees, 0 = left to right, 90 = top to bottom
+    },
+}
+
+/// Text title or lower-third rendered on a transparent canvas
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextOverlay {
+    pub text: String,
+    #[serde(default)]
+    pub font_family: Option<String>,  // Fontconfig family name
+    #[serde(default)]
+    pub font_file: Option<PathBuf>,  // Takes precedence over font_family
+    pub font_size: f32,  // Pixels at timeline resolution
+    pub color: String,  // "#RRGGBB" or "#RRGGBBAA"
+    #[serde(default)]
+    pub outline_color: Option<String>,
+    #[serde(default)]
+    pub outline_width: f32,  // Pixels at timeline resolution
+    #[serde(default)]
+    pub background: Option<TextBackground>,
+    #[serde(default)]
+    pub alignment: TextAlignment,
+    pub x: f32,  // Anchor position, 0.0 (left) to 1.0 (right)
+    pub y: f32,  // Anchor position, 0.0 (top) to 1.0 (bottom)
+    #[serde(default)]
+    pub animate_in: TextAnimation,
+    #[serde(default)]
+    pub animate_out: TextAnimation,
+    #[serde(default = "default_text_animation_duration")]
+    pub animation_duration: f64,  // seconds
+}
+
+fn default_text_animation_duration() -> f64 { 0.5 }
+
+/// Box drawn behind text
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextBackground {
+    pub color: String,  // "#RRGGBB" or "#RRGGBBAA"
+    #[serde(default)]
+    pub padding: f32,  // Pixels at timeline resolution
+}
+
+/// Horizontal alignment of text relative to its anchor position
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextAlignment {
+    Left,
+    #[default]
+    Center,
+    Right,
+}
+
+/// Animation used when text enters or leaves
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextAnimation {
+    #[default]
+    None,
+    Fade,
+    SlideUp,  // Fade while moving vertically
+    SlideLeft,  // Fade while moving horizontally
 }
 
 /// Video/audio effect applied to a clip
@@ -189,10 +424,146 @@ pub enum EffectType {
     Blur { radius: f32 },  // 0.0 to 100.0
     Sharpen { amount: f32 },  // 0.0 to 1.0
 
+    // Color effects
+    ColorTemperature { kelvin: f32 },  // 1000.0 to 40000.0 (6500.0 = neutral)
+    Tint { value: f32 },  // -1.0 (magenta) to 1.0 (green)
+    Curves {
+        // Control points "x/y x/y ...", each value 0.0 to 1.0
+        #[serde(default)]
+        master: Option<String>,
+        #[serde(default)]
+        red: Option<String>,
+        #[serde(default)]
+        green: Option<String>,
+        #[serde(default)]
+        blue: Option<String>,
+    },
+    Lut { path: PathBuf },  // 3D LUT in .cube format
+    HueShift { degrees: f32 },  // -180.0 to 180.0
+    Vignette { intensity: f32 },  // 0.0 to 1.0
+    ChromaKey { color: String, similarity: f32, blend: f32 },  // "#RRGGBB", 0.01 to 1.0, 0.0 to 1.0
+
+    // Geometry effects
+    Crop { x: u32, y: u32, width: u32, height: u32 },  // pixels in the source frame
+    Flip { horizontal: bool, vertical: bool },
+    Rotate { degrees: f32 },  // clockwise
+
+    // Cleanup effects
+    Stabilize { strength: f32 },  // 0.0 to 1.0
+    Denoise { strength: f32 },  // 0.0 to 1.0
+
     // Audio effects
     Normalize,
     FadeIn { duration: f64 },  // seconds
     FadeOut { duration: f64 },  // seconds
+    Equalizer { bands: Vec<EqBand> },
+    Compressor {
+        threshold_db: f32,  // -60.0 to 0.0
+        ratio: f32,  // 1.0 to 20.0
+        attack_ms: f32,
+        release_ms: f32,
+        makeup_db: f32,  // 0.0 to 36.0
+    },
+    NoiseGate {
+        threshold_db: f32,  // -80.0 to 0.0
+        ratio: f32,  // 1.0 to 9000.0
+        attack_ms: f32,
+        release_ms: f32,
+    },
+    HighPass { frequency: f32 },  // Hz
+    NoiseReduction { amount_db: f32 },  // 0.01 to 97.0
+    Pitch { semitones: f32 },  // -12.0 to 12.0, tempo unchanged
+}
+
+/// A single peaking band of an equalizer effect
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EqBand {
+    pub frequency: f32,  // Center frequency in Hz
+    pub gain_db: f32,  // -30.0 to 30.0
+    pub q: f32,  // Band width as Q factor
+}
+
+/// Stream(s) an effect can be applied to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EffectTarget {
+    Video,
+    Audio,
+    Both,  // Applied to the video and the audio stream of a clip
+}
+
+impl EffectTarget {
+    pub fn applies_to_video(self) -> bool {
+        matches!(self, EffectTarget::Video | EffectTarget::Both)
+    }
+
+    pub fn applies_to_audio(self) -> bool {
+        matches!(self, EffectTarget::Audio | EffectTarget::Both)
+    }
+}
+
+impl EffectType {
+    /// Classify the effect by the stream type it operates on
+    pub fn target(&self) -> EffectTarget {
+        match self {
+            EffectType::Brightness { .. }
+            | EffectType::Contrast { .. }
+            | EffectType::Saturation { .. }
+            | EffectType::Blur { .. }
+            | EffectType::Sharpen { .. }
+            | EffectType::ColorTemperature { .. }
+            | EffectType::Tint { .. }
+            | EffectType::Curves { .. }
+            | EffectType::Lut { .. }
+            | EffectType::HueShift { .. }
+            | EffectType::Vignette { .. }
+            | EffectType::ChromaKey { .. }
+            | EffectType::Crop { .. }
+            | EffectType::Flip { .. }
+            | EffectType::Rotate { .. }
+            | EffectType::Stabilize { .. }
+            | EffectType::Denoise { .. } => EffectTarget::Video,
+            EffectType::Normalize
+            | EffectType::Equalizer { .. }
+            | EffectType::Compressor { .. }
+            | EffectType::NoiseGate { .. }
+            | EffectType::HighPass { .. }
+            | EffectType::NoiseReduction { .. }
+            | EffectType::Pitch { .. } => EffectTarget::Audio,
+            EffectType::FadeIn { .. } | EffectType::FadeOut { .. } => EffectTarget::Both,
+        }
+    }
+
+    /// Human-readable effect name for error messages
+    pub fn name(&self) -> &'static str {
+        match self {
+            EffectType::Brightness { .. } => "Brightness",
+            EffectType::Contrast { .. } => "Contrast",
+            EffectType::Saturation { .. } => "Saturation",
+            EffectType::Blur { .. } => "Blur",
+            EffectType::Sharpen { .. } => "Sharpen",
+            EffectType::ColorTemperature { .. } => "ColorTemperature",
+            EffectType::Tint { .. } => "Tint",
+            EffectType::Curves { .. } => "Curves",
+            EffectType::Lut { .. } => "Lut",
+            EffectType::HueShift { .. } => "HueShift",
+            EffectType::Vignette { .. } => "Vignette",
+            EffectType::ChromaKey { .. } => "ChromaKey",
+            EffectType::Crop { .. } => "Crop",
+            EffectType::Flip { .. } => "Flip",
+            EffectType::Rotate { .. } => "Rotate",
+            EffectType::Stabilize { .. } => "Stabilize",
+            EffectType::Denoise { .. } => "Denoise",
+            EffectType::Normalize => "Normalize",
+            EffectType::FadeIn { .. } => "FadeIn",
+            EffectType::FadeOut { .. } => "FadeOut",
+            EffectType::Equalizer { .. } => "Equalizer",
+            EffectType::Compressor { .. } => "Compressor",
+            EffectType::NoiseGate { .. } => "NoiseGate",
+            EffectType::HighPass { .. } => "HighPass",
+            EffectType::NoiseReduction { .. } => "NoiseReduction",
+            EffectType::Pitch { .. } => "Pitch",
+        }
+    }
 }
 
 /// Custom error types for timeline operations
@@ -243,6 +614,18 @@ pub struct ExportSettings {
     pub framerate: f64,           // frames per second
     pub resolution: Resolution,   // output resolution
     pub format: String,           // e.g., "mp4", "mov", "webm"
+    #[serde(default)]
+    pub subtitle_mode: SubtitleExportMode,  // How enabled subtitles are exported
+    #[serde(default)]
+    pub chapters: bool,  // Write chapter markers as container chapters (MP4/MOV/MKV)
+}
+
+/// How subtitles are written into an export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SubtitleExportMode {
+    #[default]
+    BurnIn,  // Rendered into the video frames
+    Soft,    // Muxed as selectable subtitle streams (mov_text, SRT/ASS or WebVTT)
 }
 
 impl ExportSettings {
@@ -256,6 +639,8 @@ impl ExportSettings {
             framerate: 30.0,
             resolution: Resolution { width: 1920, height: 1080 },
             format: "mp4".to_string(),
+            subtitle_mode: SubtitleExportMode::BurnIn,
+            chapters: false,
         }
     }
 
@@ -269,6 +654,8 @@ impl ExportSettings {
             framerate: 30.0,
             resolution: Resolution { width: 1080, height: 1080 },
             format: "mp4".to_string(),
+            subtitle_mode: SubtitleExportMode::BurnIn,
+            chapters: false,
         }
     }
 
@@ -282,6 +669,8 @@ impl ExportSettings {
             framerate: 30.0,
             resolution: Resolution { width: 1280, height: 720 },
             format: "mp4".to_string(),
+            subtitle_mode: SubtitleExportMode::BurnIn,
+            chapters: false,
         }
     }
 
@@ -302,6 +691,8 @@ impl ExportSettings {
             framerate,
             resolution,
             format: "mp4".to_string(),
+            subtitle_mode: SubtitleExportMode::BurnIn,
+            chapters: false,
         }
     }
 }
@@ -345,6 +736,135 @@ pub struct SubtitleSegment {
     pub start_time: f64,      // seconds from timeline start
     pub end_time: f64,        // seconds from timeline start
     pub text: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<SubtitleWord>,  // Word timing, when the backend provides it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub style: Option<String>,  // ASS/SSA style name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cue_settings: Option<String>,  // WebVTT cue settings, e.g. "align:start line:10%"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub style_override: Option<SubtitleStyleOverride>,  // Changes to the timeline style for this segment
+}
+
+/// A single word of a subtitle segment, timed on the same clock as the segment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubtitleWord {
+    pub text: String,
+    pub start_time: f64,
+    pub end_time: f64,
+}
+
+impl SubtitleWord {
+    /// Copy shifted by `offset` seconds and scaled by `1 / speed` around `origin`
+    pub fn remapped(&self, origin: f64, offset: f64, speed: f64) -> Self {
+        Self {
+            text: self.text.clone(),
+            start_time: offset + (self.start_time - origin) / speed,
+            end_time: offset + (self.end_time - origin) / speed,
+        }
+    }
+}
+
+/// Look of burned-in subtitles
+///
+/// Sizes are fractions of the output height (margins of the matching
+/// dimension), so subtitles keep their proportions at any export resolution.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubtitleStyle {
+    pub font_family: String,
+    pub font_file: Option<PathBuf>,  // Loaded from the file's directory when set
+    pub font_size: f64,
+    pub bold: bool,
+    pub italic: bool,
+    pub primary_color: String,  // "#RRGGBB" or "#RRGGBBAA"
+    pub outline_color: String,
+    pub outline_width: f64,
+    pub shadow_color: String,
+    pub shadow_offset: f64,
+    pub background: Option<String>,  // Box colour; the outline width becomes the box padding
+    pub position: SubtitlePosition,
+    pub alignment: TextAlignment,
+    pub margin_horizontal: f64,
+    pub margin_vertical: f64,
+}
+
+impl Default for SubtitleStyle {
+    /// White Arial on a black box near the bottom of the frame
+    fn default() -> Self {
+        Self {
+            font_family: "Arial".to_string(),
+            font_file: None,
+            font_size: 0.083,
+            bold: false,
+            italic: false,
+            primary_color: "#FFFFFF".to_string(),
+            outline_color: "#000000".to_string(),
+            outline_width: 0.007,
+            shadow_color: "#00000080".to_string(),
+            shadow_offset: 0.0035,
+            background: Some("#000000".to_string()),
+            position: SubtitlePosition::Bottom,
+            alignment: TextAlignment::Center,
+            margin_horizontal: 0.026,
+            margin_vertical: 0.07,
+        }
+    }
+}
+
+impl SubtitleStyle {
+    /// This style with a segment's overrides applied
+    pub fn with_override(&self, style_override: &SubtitleStyleOverride) -> Self {
+        let o = style_override.clone();
+        Self {
+            font_family: o.font_family.unwrap_or_else(|| self.font_family.clone()),
+            font_size: o.font_size.unwrap_or(self.font_size),
+            bold: o.bold.unwrap_or(self.bold),
+            italic: o.italic.unwrap_or(self.italic),
+            primary_color: o.primary_color.unwrap_or_else(|| self.primary_color.clone()),
+            outline_color: o.outline_color.unwrap_or_else(|| self.outline_color.clone()),
+            background: o.background.unwrap_or_else(|| self.background.clone()),
+            position: o.position.unwrap_or(self.position),
+            alignment: o.alignment.unwrap_or(self.alignment),
+            margin_vertical: o.margin_vertical.unwrap_or(self.margin_vertical),
+            ..self.clone()
+        }
+    }
+}
+
+/// Vertical placement of subtitles
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SubtitlePosition {
+    Top,
+    Middle,
+    #[default]
+    Bottom,
+}
+
+/// Per-segment changes to the timeline subtitle style; unset fields are inherited
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubtitleStyleOverride {
+    pub font_family: Option<String>,
+    pub font_size: Option<f64>,
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub primary_color: Option<String>,
+    pub outline_color: Option<String>,
+    #[serde(deserialize_with = "deserialize_some", skip_serializing_if = "Option::is_none")]
+    pub background: Option<Option<String>>,  // Some(None) removes the box
+    pub position: Option<SubtitlePosition>,
+    pub alignment: Option<TextAlignment>,
+    pub margin_vertical: Option<f64>,
+}
+
+/// Read a present field as `Some`, so an explicit null becomes `Some(None)`
+fn deserialize_some<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
 }
 
 /// Source of subtitle generation
@@ -355,9 +875,17 @@ pub enum SubtitleSource {
         media_file_id: String,
         provider: String,  // "openai-whisper"
     },
+    TranscribedTimeline {
+        timeline_id: String,  // Segments are in timeline time, remapped per clip
+        provider: String,
+    },
     Imported {
         file_path: PathBuf
     },
+    Translated {
+        from_language: String,
+        provider: String,
+    },
     Manual,
 }
 
@@ -367,6 +895,8 @@ pub struct SubtitleTrack {
     pub segments: Vec<SubtitleSegment>,
     pub language: String,      // ISO 639-1 code (e.g., "en", "es")
     pub source: SubtitleSource,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ass_header: Option<String>,  // Imported ASS/SSA script info and styles, written back on export
 }
 
 /// Custom error types for subtitle operations
@@ -378,9 +908,21 @@ pub enum SubtitleError {
     #[error("No audio track found in media file")]
     NoAudioTrack,
 
+    #[error("Media file not found: {0}")]
+    MediaNotFound(String),
+
+    #[error("Transcription provider error: {0}")]
+    ProviderError(String),
+
+    #[error("FFmpeg error: {0}")]
+    FFmpegError(String),
+
     #[error("Invalid SRT format: {0}")]
     InvalidSRT(String),
 
+    #[error("Invalid subtitle file: {0}")]
+    InvalidFormat(String),
+
     #[error("Transcription cache error: {0}")]
     CacheError(String),
 
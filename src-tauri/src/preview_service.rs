@@ -1,5 +1,8 @@
 use crate::preview_cache::PreviewCache;
-use crate::models::{Clip, Timeline, TrackType};
+use crate::export::{ExportService, StreamKind};
+use crate::generator;
+use crate::models::{is_still_image_path, Clip, Effect, SubtitleTrack, Timeline, TrackType};
+use crate::subtitle_style;
 use anyhow::{anyhow, Context, Result};
 use base64::{engine::general_purpose, Engine as _};
 use log::{debug, info, warn};
@@ -55,33 +58,64 @@ impl PreviewService {
         // Find the active clip(s) at this time
         let active_clips = self.find_active_clips(timeline, time);
 
-        if active_clips.is_empty() {
-            let frame_data = self.render_blank_frame(&timeline.resolution).await?;
-            return Ok(general_purpose::STANDARD.encode(&frame_data));
-        }
+        // Subtitles are burned in with the same script and filter as export
+        let subtitles = Self::write_subtitle_script(timeline, time).await?;
+        let subtitle_filter = subtitles.as_ref().map(|path| format!(
+            "setpts=PTS-STARTPTS+{}/TB,{}",
+            time,
+            subtitle_style::burn_in_filter(path, &timeline.subtitle_style)
+        ));
+        let subtitle_filter = subtitle_filter.as_deref();
+
+        let frame_data = if active_clips.is_empty() {
+            self.render_blank_frame(&timeline.resolution, subtitle_filter).await
+        } else if active_clips.len() == 1 && !active_clips[0].is_generated() {
+            // For a single media clip, render directly
+            self.render_single_clip_frame(&active_clips[0], time, timeline, media_files, subtitle_filter)
+                .await
+        } else {
+            // For multiple or generated clips, composite them
+            self.render_composite_frame(&active_clips, time, timeline, media_files, subtitle_filter)
+                .await
+        };
 
-        // For single clip, render directly
-        if active_clips.len() == 1 {
-            let clip = &active_clips[0];
-            let frame_data = self
-                .render_single_clip_frame(clip, time, &timeline.resolution, media_files)
-                .await?;
+        if let Some(path) = subtitles {
+            let _ = fs::remove_file(path).await;
+        }
+        let frame_data = frame_data?;
 
-            // Cache the frame
+        // Cache the frame (blank frames are cheap to regenerate)
+        if !active_clips.is_empty() {
             self.cache.put(time, frame_data.clone()).await;
-
-            return Ok(general_purpose::STANDARD.encode(&frame_data));
         }
 
-        // For multiple clips, composite them
-        let frame_data = self
-            .render_composite_frame(&active_clips, time, &timeline.resolution, media_files)
-            .await?;
+        Ok(general_purpose::STANDARD.encode(&frame_data))
+    }
 
-        // Cache the frame
-        self.cache.put(time, frame_data.clone()).await;
+    /// Write a burn-in script for the subtitles showing at `time`, if any
+    async fn write_subtitle_script(timeline: &Timeline, time: f64) -> Result<Option<PathBuf>> {
+        let track = match timeline.burn_in_subtitle_track() {
+            Some(track) => track,
+            None => return Ok(None),
+        };
 
-        Ok(general_purpose::STANDARD.encode(&frame_data))
+        let segments: Vec<_> = track.segments.iter()
+            .filter(|s| time >= s.start_time && time < s.end_time)
+            .cloned()
+            .collect();
+        if segments.is_empty() {
+            return Ok(None);
+        }
+
+        let script = subtitle_style::build_burn_in_script(
+            &SubtitleTrack { segments, ..track.clone() },
+            &timeline.subtitle_style,
+            &timeline.resolution,
+        )?;
+        let path = std::env::temp_dir().join(format!("clipforge_preview_subtitles_{}.ass", uuid::Uuid::new_v4()));
+        fs::write(&path, script).await.context("Failed to write subtitle script")?;
+
+        Ok(Some(path))
     }
 
     /// Find all clips that are active at the given time
@@ -116,50 +150,215 @@ impl PreviewService {
         &self,
         clip: &Clip,
         timeline_time: f64,
-        resolution: &crate::models::Resolution,
+        timeline: &Timeline,
         media_files: &HashMap<String, PathBuf>,
+        subtitle_filter: Option<&str>,
     ) -> Result<Vec<u8>> {
         let media_path = media_files
             .get(&clip.media_file_id)
             .ok_or_else(|| anyhow!("Media file not found: {}", clip.media_file_id))?;
 
         // Calculate time within the clip
-        let clip_time = (timeline_time - clip.track_position) / clip.speed as f64 + clip.trim_start;
+        let clip_time = Self::source_time(clip, media_path, timeline_time);
 
         debug!(
             "Rendering clip '{}' at {}s (clip time: {}s)",
             clip.id, timeline_time, clip_time
         );
 
-        self.extract_frame(media_path, clip_time, resolution).await
+        let (width, height) = Self::preview_size(&timeline.resolution);
+        let effects_filter = Self::build_clip_effects_filter(
+            clip, timeline_time, timeline.framerate, width, height,
+        )?;
+
+        self.extract_frame(media_path, clip_time, &timeline.resolution, effects_filter.as_deref(), subtitle_filter)
+            .await
+    }
+
+    /// Position in the source file shown at `timeline_time`
+    ///
+    /// Still images only have one frame, so they are always read from the start.
+    fn source_time(clip: &Clip, media_path: &Path, timeline_time: f64) -> f64 {
+        if is_still_image_path(media_path) {
+            0.0
+        } else {
+            (timeline_time - clip.track_position) * clip.speed as f64 + clip.trim_start
+        }
+    }
+
+    /// Build the video effects chain for a clip, matching the export filters
+    ///
+    /// Timestamps are shifted so time-based effects such as fades and Ken
+    /// Burns moves see the frame's position within the clip. Audio-only
+    /// effects are skipped.
+    fn build_clip_effects_filter(
+        clip: &Clip,
+        timeline_time: f64,
+        framerate: f64,
+        width: u32,
+        height: u32,
+    ) -> Result<Option<String>> {
+        let mut stages = Vec::new();
+        if let Some(effects) = Self::build_video_effects(clip)? {
+            stages.push(effects);
+        }
+        if let Some(ken_burns) = &clip.ken_burns {
+            stages.push(ExportService::build_ken_burns_filter(
+                ken_burns,
+                width,
+                height,
+                framerate,
+                clip,
+            )?);
+        }
+
+        if stages.is_empty() {
+            return Ok(None);
+        }
+
+        let offset = (timeline_time - clip.track_position).max(0.0);
+        Ok(Some(format!("setpts=PTS-STARTPTS+{}/TB,{}", offset, stages.join(","))))
+    }
+
+    /// Build the export filters for a clip's enabled video effects
+    fn build_video_effects(clip: &Clip) -> Result<Option<String>> {
+        let video_effects: Vec<Effect> = clip.effects.iter()
+            .filter(|e| e.enabled && e.effect_type.target().applies_to_video())
+            .cloned()
+            .collect();
+
+        if video_effects.is_empty() {
+            return Ok(None);
+        }
+
+        // Timeline seconds, like the offset the frame is shifted by
+        let effects = ExportService::build_effects_filter(
+            &video_effects,
+            StreamKind::Video,
+            ExportService::rendered_duration(clip),
+        )?;
+
+        Ok(Some(effects))
     }
 
     /// Render a composite frame from multiple clips
+    ///
+    /// Clips are stacked in track order over a black background, each fitted
+    /// to the frame the same way export fits overlay layers.
     async fn render_composite_frame(
         &self,
         clips: &[Clip],
         time: f64,
-        resolution: &crate::models::Resolution,
+        timeline: &Timeline,
         media_files: &HashMap<String, PathBuf>,
+        subtitle_filter: Option<&str>,
     ) -> Result<Vec<u8>> {
         info!("Compositing {} clips at {}s", clips.len(), time);
 
-        // For MVP, just render the topmost clip
-        // TODO: Implement proper layer compositing with FFmpeg filter_complex
-        if let Some(clip) = clips.last() {
-            self.render_single_clip_frame(clip, time, resolution, media_files)
-                .await
-        } else {
-            self.render_blank_frame(resolution).await
+        let (width, height) = Self::preview_size(&timeline.resolution);
+        let scale = height as f64 / timeline.resolution.height.max(1) as f64;
+
+        let mut args: Vec<String> = Vec::new();
+        // Files generated clips read, removed once this frame is rendered
+        let mut temp_files = Vec::new();
+        let mut filters = vec![format!("color=c=black:s={}x{}:d=1,format=rgba[base]", width, height)];
+        let mut input_index = 0;
+
+        for (i, clip) in clips.iter().enumerate() {
+            let layer = if clip.is_generated() {
+                // Generated sources start at the clip start, so effects see clip time directly
+                let mut chain = generator::build_clip_source(clip, timeline.framerate, width, height, scale, &mut temp_files)?;
+                if let Some(effects) = Self::build_video_effects(clip)? {
+                    chain.push_str(&format!(",{}", effects));
+                }
+                if let Some(ken_burns) = &clip.ken_burns {
+                    chain.push_str(&format!(",{}", ExportService::build_ken_burns_filter(
+                        ken_burns, width, height, timeline.framerate, clip,
+                    )?));
+                }
+                let offset = (time - clip.track_position).max(0.0);
+                format!("{},trim=start={},setpts=PTS-STARTPTS", chain, offset)
+            } else {
+                let media_path = media_files
+                    .get(&clip.media_file_id)
+                    .ok_or_else(|| anyhow!("Media file not found: {}", clip.media_file_id))?;
+                let clip_time = Self::source_time(clip, media_path, time);
+
+                args.extend(["-ss".to_string(), format!("{}", clip_time), "-i".to_string()]);
+                args.push(media_path.to_string_lossy().to_string());
+
+                let effects = Self::build_clip_effects_filter(clip, time, timeline.framerate, width, height)?;
+                let chain = match effects {
+                    Some(effects) => format!("[{}:v]{},setpts=PTS-STARTPTS", input_index, effects),
+                    None => format!("[{}:v]setpts=PTS-STARTPTS", input_index),
+                };
+                input_index += 1;
+                chain
+            };
+
+            filters.push(format!("{},{}[l{}]", layer, generator::fit_layer_filter(width, height), i));
+
+            let below = if i == 0 { "base".to_string() } else { format!("c{}", i - 1) };
+            let output = if i + 1 == clips.len() && subtitle_filter.is_none() {
+                "outv".to_string()
+            } else {
+                format!("c{}", i)
+            };
+            filters.push(format!("[{}][l{}]overlay=format=auto[{}]", below, i, output));
+        }
+        if let Some(subtitles) = subtitle_filter {
+            filters.push(format!("[c{}]{}[outv]", clips.len() - 1, subtitles));
         }
+
+        let temp_file = std::env::temp_dir().join(format!("clipforge_frame_{}.jpg", uuid::Uuid::new_v4()));
+
+        let output = Command::new(&self.ffmpeg_path)
+            .args(&args)
+            .arg("-filter_complex")
+            .arg(filters.join(";"))
+            .arg("-map")
+            .arg("[outv]")
+            .arg("-frames:v")
+            .arg("1")
+            .arg("-q:v")
+            .arg("5")
+            .arg("-f")
+            .arg("image2")
+            .arg(&temp_file)
+            .arg("-y")
+            .output()
+            .context("Failed to spawn ffmpeg process")?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            warn!("FFmpeg error: {}", stderr);
+            return Err(anyhow!("FFmpeg failed to composite frame: {}", stderr));
+        }
+
+        let frame_data = fs::read(&temp_file)
+            .await
+            .context("Failed to read composited frame")?;
+
+        let _ = fs::remove_file(&temp_file).await;
+
+        Ok(frame_data)
     }
 
-    /// Extract a single frame from a video file
+    /// Preview frame size, limited to 1280px width for faster processing
+    fn preview_size(resolution: &crate::models::Resolution) -> (u32, u32) {
+        let preview_width = resolution.width.min(1280);
+        let preview_height = (preview_width as f64 / resolution.width as f64 * resolution.height as f64) as u32;
+        (preview_width, preview_height)
+    }
+
+    /// Extract a single frame from a video file, optionally applying a filter chain
     async fn extract_frame(
         &self,
         video_path: &Path,
         time: f64,
         resolution: &crate::models::Resolution,
+        effects_filter: Option<&str>,
+        subtitle_filter: Option<&str>,
     ) -> Result<Vec<u8>> {
         let temp_dir = std::env::temp_dir();
         let temp_file = temp_dir.join(format!("clipforge_frame_{}.jpg", uuid::Uuid::new_v4()));
@@ -170,9 +369,19 @@ impl PreviewService {
         );
 
         // Build FFmpeg command (optimized for preview performance)
-        // Limit preview resolution to 1280px width for faster processing
-        let preview_width = resolution.width.min(1280);
-        let preview_height = (preview_width as f64 / resolution.width as f64 * resolution.height as f64) as u32;
+        let (preview_width, preview_height) = Self::preview_size(resolution);
+
+        // Fit into the preview frame the same way export fits clips to the timeline
+        let scale_filter = format!(
+            "scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2",
+            w = preview_width,
+            h = preview_height
+        );
+        let video_filter = [effects_filter, Some(scale_filter.as_str()), subtitle_filter]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(",");
 
         let output = Command::new(&self.ffmpeg_path)
             .arg("-hwaccel")
@@ -184,7 +393,7 @@ impl PreviewService {
             .arg("-vframes")
             .arg("1") // Extract 1 frame
             .arg("-vf")
-            .arg(format!("scale={}:{}", preview_width, preview_height))
+            .arg(video_filter)
             .arg("-q:v")
             .arg("5") // Balanced quality/speed (was 2 - highest quality but slower)
             .arg("-f")
@@ -217,6 +426,7 @@ impl PreviewService {
     async fn render_blank_frame(
         &self,
         resolution: &crate::models::Resolution,
+        subtitle_filter: Option<&str>,
     ) -> Result<Vec<u8>> {
         debug!(
             "Rendering blank frame at {}x{}",
@@ -227,14 +437,20 @@ impl PreviewService {
         let temp_file = temp_dir.join(format!("clipforge_blank_{}.jpg", uuid::Uuid::new_v4()));
 
         // Generate black frame using FFmpeg
-        let output = Command::new(&self.ffmpeg_path)
+        let mut command = Command::new(&self.ffmpeg_path);
+        command
             .arg("-f")
             .arg("lavfi")
             .arg("-i")
             .arg(format!(
                 "color=black:s={}x{}:d=0.1",
                 resolution.width, resolution.height
-            ))
+            ));
+        if let Some(subtitles) = subtitle_filter {
+            command.arg("-vf").arg(subtitles);
+        }
+
+        let output = command
             .arg("-frames:v")
             .arg("1")
             .arg("-q:v")
@@ -310,6 +526,9 @@ mod tests {
                         effects: vec![],
                         volume: 1.0,
                         speed: 1.0,
+                        generator: None,
+                        ken_burns: None,
+                        animation_span: None,
                     },
                     Clip {
                         id: "clip2".to_string(),
@@ -322,9 +541,17 @@ mod tests {
                         effects: vec![],
                         volume: 1.0,
                         speed: 1.0,
+                        generator: None,
+                        ken_burns: None,
+                        animation_span: None,
                     },
                 ],
             }],
+            subtitle_tracks: Vec::new(),
+            burn_in_language: None,
+            subtitle_enabled: false,
+            subtitle_style: Default::default(),
+            markers: Vec::new(),
         };
 
         // Test at 5.0 seconds (should find clip1)
@@ -341,4 +568,69 @@ mod tests {
         let active = service.find_active_clips(&timeline, 25.0);
         assert_eq!(active.len(), 0);
     }
+
+    #[test]
+    fn test_clip_effects_filter_matches_export() {
+        use crate::models::EffectType;
+
+        let effect = |effect_type| Effect { id: "fx".to_string(), effect_type, enabled: true };
+        let clip = Clip {
+            id: "clip1".to_string(),
+            media_file_id: "media1".to_string(),
+            name: None,
+            track_position: 10.0,
+            duration: 5.0,
+            trim_start: 0.0,
+            trim_end: 5.0,
+            effects: vec![
+                effect(EffectType::Normalize),
+                effect(EffectType::HueShift { degrees: 30.0 }),
+                effect(EffectType::FadeOut { duration: 1.0 }),
+            ],
+            volume: 1.0,
+            speed: 1.0,
+            generator: None,
+            ken_burns: None,
+            animation_span: None,
+        };
+
+        let filter = PreviewService::build_clip_effects_filter(&clip, 12.5, 30.0, 1280, 720).unwrap();
+        assert_eq!(
+            filter.as_deref(),
+            Some("setpts=PTS-STARTPTS+2.5/TB,hue=h=30,fade=t=out:st=4:d=1")
+        );
+    }
+
+    #[test]
+    fn test_sped_up_clip_previews_like_export() {
+        use crate::models::EffectType;
+
+        // 5 timeline seconds at 2x, playing 10 seconds of source
+        let clip = Clip {
+            id: "clip1".to_string(),
+            media_file_id: "media1".to_string(),
+            name: None,
+            track_position: 10.0,
+            duration: 5.0,
+            trim_start: 1.0,
+            trim_end: 11.0,
+            effects: vec![Effect {
+                id: "fx".to_string(),
+                effect_type: EffectType::FadeOut { duration: 1.0 },
+                enabled: true,
+            }],
+            volume: 1.0,
+            speed: 2.0,
+            generator: None,
+            ken_burns: None,
+            animation_span: None,
+        };
+
+        let source = PreviewService::source_time(&clip, Path::new("/videos/clip.mp4"), 12.5);
+        assert_eq!(source, 6.0);
+
+        // The fade ends with the clip's span on the timeline
+        let filter = PreviewService::build_clip_effects_filter(&clip, 12.5, 30.0, 1280, 720).unwrap();
+        assert_eq!(filter.as_deref(), Some("setpts=PTS-STARTPTS+2.5/TB,fade=t=out:st=4:d=1"));
+    }
 }
@@ -0,0 +1,508 @@
+// Recording with one capture process per segment
+//
+// The macOS, X11 and Wayland backends all record the same way: each stretch
+// between pauses is captured by its own processes into a segment, pausing
+// stops them, resuming starts new ones and stopping joins the segments. How
+// a segment's processes are started is up to each backend; the rest lives
+// here.
+
+use super::segments::SegmentedOutput;
+use super::state::{RecordingClock, RecordingTransition};
+use super::sync::{self, SegmentStarts};
+use super::{AudioInputType, RecordingConfig, RecordingError, RecordingOutput, RecordingState};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use log::{info, warn, error};
+
+/// How long a new segment's processes are watched for quitting at once
+const STARTUP_CHECK: Duration = Duration::from_millis(500);
+
+/// A running recording process whose stderr is collected as it is written
+///
+/// Reading stderr only once the process is stopped would let a long
+/// recording fill the pipe and stall the process.
+pub(super) struct RecordingProcess {
+    child: Child,
+    stderr: Option<JoinHandle<String>>,
+}
+
+impl RecordingProcess {
+    pub(super) fn spawn(command: &mut Command) -> std::io::Result<Self> {
+        let mut child = command.stderr(Stdio::piped()).spawn()?;
+        let stderr = child.stderr.take().map(|mut stderr| {
+            std::thread::spawn(move || {
+                let mut output = Vec::new();
+                let _ = stderr.read_to_end(&mut output);
+                String::from_utf8_lossy(&output).into_owned()
+            })
+        });
+        Ok(Self { child, stderr })
+    }
+
+    fn has_exited(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(Some(_)))
+    }
+
+    /// What a process that has exited wrote to stderr
+    async fn exit_output(mut self) -> String {
+        let Some(reader) = self.stderr.take() else { return String::new() };
+        tokio::task::spawn_blocking(move || reader.join().unwrap_or_default())
+            .await
+            .unwrap_or_default()
+    }
+}
+
+/// Ask a recording process to finish writing its file and wait for it to exit
+///
+/// FFmpeg and `gst-launch-1.0 -e` both finalize their output on SIGINT.
+/// Returns what the process wrote to stderr. Blocks until the process exits.
+fn finish_process(mut process: RecordingProcess, label: &str) -> String {
+    info!("Sending SIGINT to {} recording process {}", label, process.child.id());
+    unsafe {
+        libc::kill(process.child.id() as i32, libc::SIGINT);
+    }
+
+    let status = process.child.wait();
+    let stderr_output = process.stderr.take()
+        .and_then(|reader| reader.join().ok())
+        .unwrap_or_default();
+
+    match status {
+        Ok(status) => {
+            info!("{} recording stopped, exit status: {}", label, status);
+            if !stderr_output.is_empty() {
+                info!("{} recording stderr: {}", label, stderr_output.trim());
+            }
+        }
+        Err(e) => {
+            warn!("Error waiting for {} recording process: {}", label, e);
+            if !stderr_output.is_empty() {
+                warn!("{} recording stderr: {}", label, stderr_output.trim());
+            }
+        }
+    }
+    stderr_output
+}
+
+/// [`finish_process`] on a blocking thread, for stopping a process from async code
+pub(super) async fn stop_process(process: RecordingProcess, label: &'static str) -> String {
+    tokio::task::spawn_blocking(move || finish_process(process, label))
+        .await
+        .unwrap_or_else(|e| {
+            error!("Failed to stop {} recording process: {}", label, e);
+            String::new()
+        })
+}
+
+fn exited_at_start(what: &str, stderr: &str) -> RecordingError {
+    let stderr = stderr.trim();
+    if stderr.is_empty() {
+        RecordingError::RecordingFailed(format!("{} stopped right after starting", what))
+    } else {
+        RecordingError::RecordingFailed(format!("{} stopped right after starting: {}", what, stderr))
+    }
+}
+
+/// A recording in progress
+///
+/// `source` is whatever else the backend needs to start the next segment,
+/// e.g. the screen area to grab.
+pub(super) struct Capture<S> {
+    pub config: RecordingConfig,
+    pub source: S,
+    pub webcam_device: Option<String>,
+    pub process: Option<RecordingProcess>,         // Screen (or webcam-only) process, None while paused
+    pub webcam_process: Option<RecordingProcess>,  // Webcam process (for dual mode)
+    pub output: SegmentedOutput,
+    pub webcam_output: Option<SegmentedOutput>,    // Webcam file (for dual mode)
+    pub system_audio_output: Option<SegmentedOutput>, // System audio file, when recorded apart
+    pub starts: Vec<SegmentStarts>,                // When each segment's processes started
+    pub clock: RecordingClock,
+}
+
+impl<S> Capture<S> {
+    /// A capture that has not recorded anything yet, journaled to `config.output_path`
+    pub fn new(config: RecordingConfig, source: S, webcam_device: Option<String>, webcam_output: Option<PathBuf>) -> Self {
+        Self {
+            output: SegmentedOutput::journaled(config.output_path.clone()),
+            webcam_output: webcam_output.map(SegmentedOutput::journaled),
+            system_audio_output: None,
+            config,
+            source,
+            webcam_device,
+            process: None,
+            webcam_process: None,
+            starts: Vec::new(),
+            clock: RecordingClock::default(),
+        }
+    }
+
+    /// Record system audio to a file of its own when recording it with the microphone
+    ///
+    /// The file goes next to the output, so the two can be edited apart
+    /// rather than mixed into one track.
+    pub fn with_separate_system_audio(mut self) -> Self {
+        if self.config.audio_input == AudioInputType::Both {
+            let path = system_audio_path(&self.config.output_path);
+            self.system_audio_output = Some(SegmentedOutput::journaled(path));
+        }
+        self
+    }
+
+    /// Bytes written to all segments so far
+    pub fn written_bytes(&self) -> u64 {
+        self.output.written_bytes()
+            + self.webcam_output.as_ref().map_or(0, |w| w.written_bytes())
+            + self.system_audio_output.as_ref().map_or(0, |s| s.written_bytes())
+    }
+
+    /// Remove what a capture that failed to start had set up
+    pub fn discard(self) {
+        self.output.discard();
+        for output in self.webcam_output.into_iter().chain(self.system_audio_output) {
+            output.discard();
+        }
+    }
+
+    /// Record a new segment's processes once they are seen to keep running
+    ///
+    /// A missing device or a rejected PipeWire descriptor makes a process quit
+    /// at once. The segment then fails with what it wrote to stderr, rather
+    /// than the recording seeming to run until it is stopped.
+    pub async fn segment_started(
+        &mut self,
+        mut process: RecordingProcess,
+        mut webcam_process: Option<RecordingProcess>,
+        starts: SegmentStarts,
+    ) -> Result<(), RecordingError> {
+        let deadline = tokio::time::Instant::now() + STARTUP_CHECK;
+        loop {
+            if process.has_exited() {
+                if let Some(webcam_process) = webcam_process {
+                    stop_process(webcam_process, "Webcam").await;
+                }
+                return Err(exited_at_start("Recording", &process.exit_output().await));
+            }
+            if let Some(webcam_process) = webcam_process.take_if(|w| w.has_exited()) {
+                stop_process(process, "Screen").await;
+                return Err(exited_at_start("Webcam recording", &webcam_process.exit_output().await));
+            }
+            if tokio::time::Instant::now() >= deadline {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+
+        self.process = Some(process);
+        self.webcam_process = webcam_process;
+        self.starts.push(starts);
+        Ok(())
+    }
+
+    /// Take out the processes recording the current segment, to stop them
+    fn take_processes(&mut self) -> SegmentProcesses {
+        SegmentProcesses { process: self.process.take(), webcam_process: self.webcam_process.take() }
+    }
+
+    /// Note when the stopped processes of the last segment reported starting
+    fn report_starts(&mut self, (stderr, webcam_stderr): (Option<String>, Option<String>)) {
+        let Some(starts) = self.starts.last_mut() else { return };
+        if let Some(stderr) = stderr {
+            starts.main.report(&stderr);
+        }
+        if let (Some(webcam), Some(stderr)) = (starts.webcam.as_mut(), webcam_stderr) {
+            webcam.report(&stderr);
+        }
+    }
+
+    /// Join a stopped capture's segments into its files and check the main one
+    ///
+    /// Runs FFmpeg on a blocking thread.
+    async fn finish(self, ffmpeg_path: PathBuf) -> Result<RecordingOutput, RecordingError> {
+        let Self { output, webcam_output, system_audio_output, starts, config, .. } = self;
+        tokio::task::spawn_blocking(move || {
+            join_outputs(&ffmpeg_path, output, webcam_output, system_audio_output, &starts, config.fps)
+        })
+            .await
+            .map_err(|e| RecordingError::SystemError(format!("Failed to finish recording: {}", e)))?
+    }
+}
+
+/// `{name}.system-audio.m4a` next to a recording's output
+fn system_audio_path(output_path: &Path) -> PathBuf {
+    let stem = output_path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    output_path.with_file_name(format!("{}.system-audio.m4a", stem))
+}
+
+/// Processes recording one segment
+struct SegmentProcesses {
+    process: Option<RecordingProcess>,
+    webcam_process: Option<RecordingProcess>,
+}
+
+impl SegmentProcesses {
+    /// Stop the processes on a blocking thread, returning what they wrote to stderr
+    async fn finish(self) -> (Option<String>, Option<String>) {
+        let Self { process, webcam_process } = self;
+        if process.is_none() && webcam_process.is_none() {
+            return (None, None);
+        }
+
+        tokio::task::spawn_blocking(move || (
+            process.map(|process| finish_process(process, "Screen")),
+            webcam_process.map(|process| finish_process(process, "Webcam")),
+        )).await.unwrap_or_else(|e| {
+            error!("Failed to stop recording processes: {}", e);
+            (None, None)
+        })
+    }
+}
+
+/// Join the segments of a stopped recording
+///
+/// The webcam of a dual recording is aligned to the main file first, while
+/// the segment boundaries are still known.
+fn join_outputs(
+    ffmpeg_path: &Path,
+    output: SegmentedOutput,
+    webcam_output: Option<SegmentedOutput>,
+    system_audio_output: Option<SegmentedOutput>,
+    starts: &[SegmentStarts],
+    fps: u32,
+) -> Result<RecordingOutput, RecordingError> {
+    let webcam = webcam_output.and_then(|webcam_output| {
+        let aligned = sync::align_stream(webcam_output.final_path().to_path_buf(), output.segments(), webcam_output.segments(), starts);
+        match webcam_output.finish(ffmpeg_path).and_then(|path| verify_output(&path)) {
+            Ok(()) => Some(aligned),
+            Err(e) => {
+                warn!("Webcam recording was not saved: {}", e);
+                None
+            }
+        }
+    });
+
+    let system_audio = system_audio_output.and_then(|system_audio_output| {
+        match system_audio_output.finish(ffmpeg_path).and_then(|path| verify_output(&path).map(|()| path)) {
+            Ok(path) => Some(path),
+            Err(e) => {
+                warn!("System audio recording was not saved: {}", e);
+                None
+            }
+        }
+    });
+
+    let path = output.finish(ffmpeg_path)?;
+    verify_output(&path)?;
+    Ok(RecordingOutput { path, webcam, system_audio, fps })
+}
+
+/// Check that the recording process left a non-empty file behind
+fn verify_output(path: &Path) -> Result<(), RecordingError> {
+    // The file can take a moment to appear after FFmpeg exits
+    for attempt in 0..5 {
+        if path.exists() {
+            break;
+        }
+        if attempt < 4 {
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+    }
+
+    match std::fs::metadata(path) {
+        Ok(metadata) if metadata.len() > 0 => {
+            info!("Recording saved successfully: {} ({} bytes)", path.display(), metadata.len());
+            Ok(())
+        }
+        Ok(_) => {
+            error!("Recording file is empty (0 bytes): {}", path.display());
+            Err(RecordingError::RecordingFailed(
+                "Recording file is empty. Recording may have been too short or the encoder failed.".to_string()
+            ))
+        }
+        Err(e) => {
+            error!("Recording file was not created: {} ({})", path.display(), e);
+            Err(RecordingError::RecordingFailed(format!(
+                "Recording file not created: {}. The encoder may have failed during finalization.",
+                path.display()
+            )))
+        }
+    }
+}
+
+pub(super) fn create_parent_dir(path: &Path) -> Result<(), RecordingError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| {
+            RecordingError::SystemError(format!("Failed to create output directory: {}", e))
+        })?;
+    }
+    Ok(())
+}
+
+struct Inner<S> {
+    state: RecordingState,
+    capture: Option<Capture<S>>,
+}
+
+/// Recording state of a backend and its capture in progress
+///
+/// Processes are never stopped while the lock is held.
+pub(super) struct CaptureState<S> {
+    inner: Mutex<Inner<S>>,
+}
+
+impl<S: Send + 'static> CaptureState<S> {
+    pub fn new() -> Self {
+        Self {
+            inner: Mutex::new(Inner { state: RecordingState::Idle, capture: None }),
+        }
+    }
+
+    pub fn check_can_start(&self) -> Result<(), RecordingError> {
+        if !self.inner.lock().unwrap().state.can_start() {
+            return Err(RecordingError::AlreadyRecording);
+        }
+        Ok(())
+    }
+
+    /// Make a capture whose first segment is recording the current one
+    pub fn started(&self, mut capture: Capture<S>) {
+        capture.clock = RecordingClock::started();
+        let mut inner = self.inner.lock().unwrap();
+        inner.capture = Some(capture);
+        if let Some(recording) = inner.state.transition(RecordingTransition::Start) {
+            inner.state = recording;
+        }
+    }
+
+    /// End the current segment
+    pub async fn pause(&self) -> Result<(), RecordingError> {
+        // Taken out so they are stopped without holding the lock
+        let processes = {
+            let mut inner = self.inner.lock().unwrap();
+            let paused = inner.state.transition(RecordingTransition::Pause)
+                .ok_or(RecordingError::NotRecording)?;
+            let capture = inner.capture.as_mut()
+                .ok_or_else(|| RecordingError::RecordingFailed("No recording process found".to_string()))?;
+
+            info!("Pausing recording");
+            capture.clock.pause();
+            let processes = capture.take_processes();
+            inner.state = paused;
+            processes
+        };
+
+        let stderr = processes.finish().await;
+        if let Some(capture) = self.inner.lock().unwrap().capture.as_mut() {
+            capture.report_starts(stderr);
+        }
+        Ok(())
+    }
+
+    /// Take out a paused capture to start its next segment
+    ///
+    /// The state stays paused until [`Self::resumed`] puts it back.
+    pub fn take_paused(&self) -> Result<Capture<S>, RecordingError> {
+        let mut inner = self.inner.lock().unwrap();
+        if !inner.state.can_resume() {
+            return Err(RecordingError::NotPaused);
+        }
+        info!("Resuming recording");
+        inner.capture.take()
+            .ok_or_else(|| RecordingError::RecordingFailed("No recording process found".to_string()))
+    }
+
+    /// Put back a capture from [`Self::take_paused`], recording again if its segment started
+    pub fn resumed(&self, mut capture: Capture<S>, result: Result<(), RecordingError>) -> Result<(), RecordingError> {
+        let mut inner = self.inner.lock().unwrap();
+        if let (Ok(()), Some(recording)) = (&result, inner.state.transition(RecordingTransition::Resume)) {
+            capture.clock.resume();
+            inner.state = recording;
+        }
+        inner.capture = Some(capture);
+        result
+    }
+
+    /// Stop the capture, returning it for [`Self::finalize`] to join its segments
+    pub async fn stop(&self) -> Result<Capture<S>, RecordingError> {
+        let mut capture = {
+            let mut inner = self.inner.lock().unwrap();
+            let finalizing = inner.state.transition(RecordingTransition::Stop)
+                .ok_or(RecordingError::NotRecording)?;
+
+            info!("Stopping recording");
+            let capture = inner.capture.take().ok_or_else(|| {
+                RecordingError::RecordingFailed("No recording process found".to_string())
+            })?;
+            inner.state = finalizing;
+            capture
+        };
+
+        let stderr = capture.take_processes().finish().await;
+        capture.report_starts(stderr);
+        Ok(capture)
+    }
+
+    /// Join a stopped capture's segments, finishing or failing the recording
+    ///
+    /// A new recording can start either way.
+    pub async fn finalize(&self, capture: Capture<S>, ffmpeg_path: PathBuf) -> Result<RecordingOutput, RecordingError> {
+        let result = capture.finish(ffmpeg_path).await;
+
+        let transition = match result {
+            Ok(_) => RecordingTransition::Finish,
+            Err(_) => RecordingTransition::Fail,
+        };
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(state) = inner.state.transition(transition) {
+            inner.state = state;
+        }
+        result
+    }
+
+    pub fn state(&self) -> RecordingState {
+        self.inner.lock().unwrap().state
+    }
+
+    pub fn duration(&self) -> f64 {
+        let inner = self.inner.lock().unwrap();
+        inner.capture.as_ref().map(|c| c.clock.elapsed()).unwrap_or(0.0)
+    }
+
+    pub fn output_size(&self) -> u64 {
+        let inner = self.inner.lock().unwrap();
+        inner.capture.as_ref().map(|c| c.written_bytes()).unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_system_audio_path() {
+        let path = system_audio_path(Path::new("/videos/recording_2024.mp4"));
+        assert_eq!(path, PathBuf::from("/videos/recording_2024.system-audio.m4a"));
+    }
+
+    #[test]
+    fn test_stderr_drained_while_recording() {
+        // More than a pipe holds, written before the process goes on recording
+        let marker = std::env::temp_dir().join(format!("clipforge-capture-{}", uuid::Uuid::new_v4()));
+        let script = format!("head -c 200000 /dev/zero >&2; touch '{}'; exec sleep 10", marker.display());
+        let process = RecordingProcess::spawn(Command::new("sh").args(["-c", &script])).unwrap();
+
+        let mut attempts = 0;
+        while !marker.exists() && attempts < 50 {
+            std::thread::sleep(std::time::Duration::from_millis(100));
+            attempts += 1;
+        }
+        assert!(marker.exists(), "process blocked writing stderr");
+
+        assert_eq!(finish_process(process, "Test").len(), 200000);
+        std::fs::remove_file(&marker).unwrap();
+    }
+}
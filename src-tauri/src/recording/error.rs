@@ -16,6 +16,9 @@ pub enum RecordingError {
     #[error("No recording in progress")]
     NotRecording,
 
+    #[error("Recording is not paused")]
+    NotPaused,
+
     #[error("Invalid configuration: {0}")]
     InvalidConfig(String),
 
@@ -1,7 +1,11 @@
 // Integration helpers for recording (e.g., auto-import to media library)
 
+use super::RecordingOutput;
 use crate::file_service::FileService;
+use crate::models::{Clip, MediaFile, Resolution, Timeline, TrackType};
+use crate::timeline::{TimelineError, TimelineService};
 use std::path::Path;
+use uuid::Uuid;
 use log::{info, error};
 
 /// Auto-import a recorded video file to the media library
@@ -11,13 +15,13 @@ use log::{info, error};
 pub async fn auto_import_recording(
     file_service: &FileService,
     recording_path: &Path,
-) -> Result<String, String> {
+) -> Result<MediaFile, String> {
     info!("Auto-importing recording: {}", recording_path.display());
 
     match file_service.import_file(recording_path.to_path_buf()).await {
         Ok(media_file) => {
             info!("Successfully imported recording with ID: {}", media_file.id);
-            Ok(media_file.id)
+            Ok(media_file)
         }
         Err(e) => {
             error!("Failed to auto-import recording: {}", e);
@@ -25,3 +29,230 @@ pub async fn auto_import_recording(
         }
     }
 }
+
+/// Add an imported multi-file recording to the end of the current timeline
+///
+/// The screen goes on a video track with its audio lined up underneath on an
+/// audio track, system audio recorded apart on a second audio track, and the
+/// webcam on an overlay track, placed where it was measured to start against
+/// the screen. The first unlocked tracks of each type are used, with tracks
+/// added where there are not enough. Without a current timeline, a new one
+/// named `name` is created for it.
+pub fn assemble_timeline(
+    timeline_service: &mut TimelineService,
+    name: String,
+    output: &RecordingOutput,
+    screen: &MediaFile,
+    webcam: Option<&MediaFile>,
+    system_audio: Option<&MediaFile>,
+) -> Result<Timeline, TimelineError> {
+    // After everything already on the timeline, so none of it is touched
+    let start = match timeline_service.get_timeline() {
+        Ok(timeline) => timeline.duration,
+        Err(_) => {
+            let resolution = screen.resolution.clone()
+                .unwrap_or(Resolution { width: 1920, height: 1080 });
+            timeline_service.create_timeline(name, output.fps as f64, resolution)?;
+            0.0
+        }
+    };
+
+    let video_track_id = recording_track(timeline_service, TrackType::Video, 0)?;
+    timeline_service.add_clip(&video_track_id, recording_clip(screen, start, 0.0, screen.duration))?;
+    if screen.codec.audio.is_some() {
+        let audio_track_id = recording_track(timeline_service, TrackType::Audio, 0)?;
+        timeline_service.add_clip(&audio_track_id, recording_clip(screen, start, 0.0, screen.duration))?;
+    }
+
+    if let Some(system_audio) = system_audio {
+        // Recorded by the same process as the screen, so it starts with it
+        let system_audio_track_id = recording_track(timeline_service, TrackType::Audio, 1)?;
+        let clip = recording_clip(system_audio, start, 0.0, system_audio.duration);
+        timeline_service.add_clip(&system_audio_track_id, clip)?;
+    }
+
+    if let (Some(webcam), Some(aligned)) = (webcam, &output.webcam) {
+        let overlay_track_id = recording_track(timeline_service, TrackType::Overlay, 0)?;
+
+        if aligned.spans.is_empty() {
+            // Segment lengths unknown: line the whole file up by its first offset
+            let early = (-aligned.offset).max(0.0);
+            let duration = webcam.duration - early;
+            if duration > 0.0 {
+                let clip = recording_clip(webcam, start + aligned.offset.max(0.0), early, duration);
+                timeline_service.add_clip(&overlay_track_id, clip)?;
+            }
+        } else {
+            for span in &aligned.spans {
+                let duration = span.duration.min(webcam.duration - span.source_start);
+                if duration > 0.0 {
+                    let clip = recording_clip(webcam, start + span.position, span.source_start, duration);
+                    timeline_service.add_clip(&overlay_track_id, clip)?;
+                }
+            }
+        }
+    }
+
+    info!("Added recording {} to the timeline at {:.2}s", output.path.display(), start);
+    Ok(timeline_service.get_timeline()?.clone())
+}
+
+/// The `nth` unlocked track of a type, adding tracks until there is one
+fn recording_track(
+    timeline_service: &mut TimelineService,
+    track_type: TrackType,
+    nth: usize,
+) -> Result<String, TimelineError> {
+    let existing = timeline_service.get_timeline()?.tracks.iter()
+        .filter(|t| t.track_type == track_type && !t.locked)
+        .map(|t| t.id.clone())
+        .collect::<Vec<_>>();
+    if let Some(track_id) = existing.get(nth) {
+        return Ok(track_id.clone());
+    }
+
+    let mut track_id = timeline_service.add_track(track_type)?;
+    for _ in existing.len()..nth {
+        track_id = timeline_service.add_track(track_type)?;
+    }
+    Ok(track_id)
+}
+
+/// Clip playing `duration` seconds of a media file from `source_start`
+fn recording_clip(media_file: &MediaFile, position: f64, source_start: f64, duration: f64) -> Clip {
+    Clip {
+        id: Uuid::new_v4().to_string(),
+        media_file_id: media_file.id.clone(),
+        name: Some(media_file.filename.clone()),
+        track_position: position,
+        duration,
+        trim_start: source_start,
+        trim_end: source_start + duration,
+        effects: Vec::new(),
+        volume: 1.0,
+        speed: 1.0,
+        generator: None,
+        ken_burns: None,
+        animation_span: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{MediaCodec, MediaType, ProxyStatus};
+    use crate::recording::sync::{AlignedStream, StreamSpan};
+    use std::path::PathBuf;
+
+    fn media_file(id: &str, duration: f64, audio: Option<&str>) -> MediaFile {
+        MediaFile {
+            id: id.to_string(),
+            path: PathBuf::from(format!("/videos/{}.mp4", id)),
+            filename: format!("{}.mp4", id),
+            media_type: MediaType::Video,
+            duration,
+            resolution: Some(Resolution { width: 2560, height: 1440 }),
+            codec: MediaCodec { video: Some("h264".to_string()), audio: audio.map(|a| a.to_string()) },
+            file_size: 1,
+            thumbnail_path: None,
+            hash: id.to_string(),
+            imported_at: chrono::Utc::now(),
+            proxy_path: None,
+            has_proxy: false,
+            proxy_status: ProxyStatus::None,
+        }
+    }
+
+    #[test]
+    fn test_assemble_dual_recording() {
+        let screen = media_file("screen", 15.0, Some("aac"));
+        let webcam = media_file("webcam", 15.75, None);
+        let output = RecordingOutput {
+            path: screen.path.clone(),
+            webcam: Some(AlignedStream {
+                path: webcam.path.clone(),
+                spans: vec![
+                    StreamSpan { position: 0.25, source_start: 0.0, duration: 9.75 },
+                    StreamSpan { position: 10.0, source_start: 10.25, duration: 5.0 },
+                ],
+                offset: 0.25,
+            }),
+            system_audio: None,
+            fps: 60,
+        };
+
+        let mut service = TimelineService::new();
+        let timeline = assemble_timeline(&mut service, "Recording".to_string(), &output, &screen, Some(&webcam), None).unwrap();
+
+        assert_eq!(timeline.framerate, 60.0);
+        assert_eq!(timeline.resolution.width, 2560);
+        assert_eq!(timeline.duration, 15.0);
+
+        let (video, audio, overlay) = (&timeline.tracks[0], &timeline.tracks[1], &timeline.tracks[2]);
+        assert_eq!(video.clips[0].media_file_id, "screen");
+        assert_eq!(audio.clips[0].track_position, video.clips[0].track_position);
+        assert_eq!(audio.clips[0].duration, 15.0);
+
+        assert_eq!(overlay.track_type, TrackType::Overlay);
+        assert_eq!(overlay.clips.len(), 2);
+        assert_eq!(overlay.clips[0].track_position, 0.25);
+        assert_eq!(overlay.clips[1].trim_start, 10.25);
+        assert_eq!(overlay.clips[1].trim_end, 15.25);
+    }
+
+    #[test]
+    fn test_assemble_by_first_offset_without_spans() {
+        let screen = media_file("screen", 10.0, None);
+        let webcam = media_file("webcam", 10.5, None);
+        let output = RecordingOutput {
+            path: screen.path.clone(),
+            webcam: Some(AlignedStream { path: webcam.path.clone(), spans: Vec::new(), offset: -0.5 }),
+            system_audio: None,
+            fps: 30,
+        };
+
+        let mut service = TimelineService::new();
+        let timeline = assemble_timeline(&mut service, "Recording".to_string(), &output, &screen, Some(&webcam), None).unwrap();
+
+        // No audio in the recording, so the audio track stays empty
+        assert!(timeline.tracks[1].clips.is_empty());
+
+        // The webcam started early: its first half second is trimmed
+        let clip = &timeline.tracks[2].clips[0];
+        assert_eq!((clip.track_position, clip.trim_start, clip.duration), (0.0, 0.5, 10.0));
+    }
+
+    #[test]
+    fn test_assemble_after_current_timeline() {
+        let screen = media_file("screen", 10.0, Some("aac"));
+        let system_audio = media_file("system-audio", 10.0, Some("aac"));
+        let output = RecordingOutput {
+            path: screen.path.clone(),
+            webcam: None,
+            system_audio: Some(system_audio.path.clone()),
+            fps: 30,
+        };
+
+        let mut service = TimelineService::new();
+        let existing = service.create_timeline("Edit".to_string(), 24.0, Resolution { width: 1280, height: 720 }).unwrap();
+        let clip = recording_clip(&media_file("intro", 4.0, None), 0.0, 0.0, 4.0);
+        service.add_clip(&existing.tracks[0].id, clip.clone()).unwrap();
+
+        let timeline = assemble_timeline(&mut service, "Recording".to_string(), &output, &screen, None, Some(&system_audio)).unwrap();
+
+        // Still the user's timeline, with their clip where it was
+        assert_eq!((timeline.id.as_str(), timeline.framerate), (existing.id.as_str(), 24.0));
+        assert_eq!(timeline.tracks[0].clips[0].id, clip.id);
+        assert_eq!(timeline.duration, 14.0);
+
+        let video = &timeline.tracks[0].clips[1];
+        assert_eq!((video.media_file_id.as_str(), video.track_position), ("screen", 4.0));
+        assert_eq!(timeline.tracks[1].clips[0].track_position, 4.0);
+
+        // System audio on an audio track of its own
+        assert_eq!(timeline.tracks.len(), 3);
+        assert_eq!(timeline.tracks[2].track_type, TrackType::Audio);
+        assert_eq!(timeline.tracks[2].clips[0].media_file_id, "system-audio");
+        assert_eq!(timeline.tracks[2].clips[0].track_position, 4.0);
+    }
+}
@@ -0,0 +1,231 @@
+// Recording journal: what a recording in progress has written so far
+//
+// Every recording keeps a journal in the app data directory listing its
+// segments, rewritten as each segment starts and removed once the recording
+// is finished. A journal left behind by an earlier run of the app belongs to
+// a recording that never finished, and its segments can still be recovered,
+// unless the process that wrote it is still running (a second instance of
+// the app recording at the same time).
+
+use super::segments;
+use super::RecordingError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use log::{info, warn};
+
+/// Identifies this run of the app, to tell its journals from orphaned ones
+fn app_session() -> &'static str {
+    static SESSION: OnceLock<String> = OnceLock::new();
+    SESSION.get_or_init(|| uuid::Uuid::new_v4().to_string())
+}
+
+/// Where recording journals are kept
+pub fn journal_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("clipforge").join("recordings"))
+}
+
+/// On-disk record of one recording file in progress
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordingJournal {
+    pub id: String,
+    /// Run of the app that wrote the journal
+    pub app_session: String,
+    /// Process that wrote the journal; 0 in journals from before it was recorded
+    #[serde(default)]
+    pub pid: u32,
+    /// File the segments are joined into
+    pub output_path: PathBuf,
+    pub segments: Vec<PathBuf>,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl RecordingJournal {
+    pub fn new(output_path: PathBuf) -> Self {
+        let now = Utc::now();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            app_session: app_session().to_string(),
+            pid: std::process::id(),
+            output_path,
+            segments: Vec::new(),
+            started_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Read a journal by ID
+    ///
+    /// IDs come from the frontend, so anything but a UUID is refused rather
+    /// than turned into a path.
+    pub fn load(dir: &Path, id: &str) -> Result<Self, RecordingError> {
+        let id = uuid::Uuid::parse_str(id)
+            .map_err(|_| RecordingError::InvalidConfig(format!("Invalid recording journal ID: {}", id)))?
+            .to_string();
+        let path = journal_path(dir, &id);
+        let json = std::fs::read_to_string(&path)
+            .map_err(|_| RecordingError::SourceNotFound(format!("Recording journal {}", id)))?;
+        serde_json::from_str(&json)
+            .map_err(|e| RecordingError::RecordingFailed(format!("Corrupt recording journal {}: {}", id, e)))
+    }
+
+    /// Write the journal, replacing the previous version in one step
+    pub fn save(&mut self, dir: &Path) -> Result<(), RecordingError> {
+        self.updated_at = Utc::now();
+        std::fs::create_dir_all(dir)?;
+
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| RecordingError::SystemError(format!("Failed to serialize recording journal: {}", e)))?;
+        let path = journal_path(dir, &self.id);
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, json)?;
+        std::fs::rename(&temp_path, &path)?;
+        Ok(())
+    }
+
+    pub fn remove(&self, dir: &Path) {
+        let path = journal_path(dir, &self.id);
+        if let Err(e) = std::fs::remove_file(&path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to remove recording journal {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    /// Whether the journal was left behind by an earlier run of the app
+    ///
+    /// Journals of another instance that is still running are not.
+    pub fn is_orphaned(&self) -> bool {
+        self.app_session != app_session() && !process_alive(self.pid)
+    }
+
+    /// Bytes written to the segments that still exist
+    pub fn recorded_bytes(&self) -> u64 {
+        segments::total_size(&self.segments)
+    }
+}
+
+/// A recording that never finished, as offered for recovery
+#[derive(Debug, Clone, Serialize)]
+pub struct UnfinishedRecording {
+    #[serde(flatten)]
+    pub journal: RecordingJournal,
+    pub recorded_bytes: u64,
+}
+
+impl From<RecordingJournal> for UnfinishedRecording {
+    fn from(journal: RecordingJournal) -> Self {
+        let recorded_bytes = journal.recorded_bytes();
+        Self { journal, recorded_bytes }
+    }
+}
+
+/// Whether a process with this ID exists
+///
+/// A reused ID only keeps a journal hidden until that process exits.
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    // Signal 0 only checks the process; EPERM means it exists under another user
+    pid != 0 && (unsafe { libc::kill(pid as libc::pid_t, 0) } == 0
+        || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM))
+}
+
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    false
+}
+
+fn journal_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{}.json", id))
+}
+
+/// Journals of recordings that never finished, oldest first
+///
+/// Journals whose segments are all gone have nothing left to recover and
+/// are removed.
+pub fn find_orphaned(dir: &Path) -> Vec<RecordingJournal> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut orphaned: Vec<RecordingJournal> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| {
+            let journal = std::fs::read_to_string(&path).ok()
+                .and_then(|json| serde_json::from_str::<RecordingJournal>(&json).ok());
+            if journal.is_none() {
+                warn!("Skipping unreadable recording journal {}", path.display());
+            }
+            journal
+        })
+        .filter(|journal| journal.is_orphaned())
+        .filter(|journal| {
+            if journal.recorded_bytes() > 0 {
+                return true;
+            }
+            info!("Removing recording journal {} with nothing to recover", journal.id);
+            journal.remove(dir);
+            false
+        })
+        .collect();
+
+    orphaned.sort_by_key(|journal| journal.started_at);
+    orphaned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_orphaned_journals() {
+        let dir = std::env::temp_dir().join(format!("clipforge-journal-{}", uuid::Uuid::new_v4()));
+        let segment = dir.join("screen.part1.mp4");
+
+        // A recording by this run is still in progress, not orphaned
+        let mut current = RecordingJournal::new(dir.join("screen.mp4"));
+        current.segments.push(segment.clone());
+        current.save(&dir).unwrap();
+        std::fs::write(&segment, b"video").unwrap();
+        assert!(find_orphaned(&dir).is_empty());
+
+        // A recording by another instance that is still running is not either
+        let mut other_instance = RecordingJournal::new(dir.join("other.mp4"));
+        other_instance.app_session = "other".to_string();
+        other_instance.segments.push(segment.clone());
+        other_instance.save(&dir).unwrap();
+        assert!(find_orphaned(&dir).is_empty());
+        other_instance.remove(&dir);
+
+        // The same journal written by a run that crashed
+        let mut crashed = RecordingJournal { app_session: "earlier".to_string(), pid: 0, ..current.clone() };
+        crashed.save(&dir).unwrap();
+        let mut empty = RecordingJournal::new(dir.join("empty.mp4"));
+        empty.app_session = "earlier".to_string();
+        empty.pid = 0;
+        empty.segments.push(dir.join("empty.part1.mp4"));
+        empty.save(&dir).unwrap();
+
+        let orphaned = find_orphaned(&dir);
+        assert_eq!(orphaned.len(), 1);
+        assert_eq!(orphaned[0].segments, vec![segment]);
+        assert_eq!(orphaned[0].recorded_bytes(), 5);
+        assert_eq!(RecordingJournal::load(&dir, &crashed.id).unwrap(), orphaned[0]);
+
+        // Nothing to recover from the empty one, so its journal is gone
+        assert!(RecordingJournal::load(&dir, &empty.id).is_err());
+
+        // IDs that are not UUIDs never reach the file system
+        assert!(matches!(
+            RecordingJournal::load(&dir, "../escape"),
+            Err(RecordingError::InvalidConfig(_))
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
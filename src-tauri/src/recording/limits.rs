@@ -0,0 +1,134 @@
+// Scheduling and automatic stops for a recording
+//
+// `RecordingConfig` can delay the start of a recording and set conditions that
+// stop it. The recording service waits for the start and checks the
+// conditions while the recording runs.
+
+use super::{RecordingConfig, RecordingError};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+/// Why a recording was stopped automatically
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    MaxDuration,
+    MaxFileSize,
+    StopTime,
+}
+
+/// Check the limits and schedule of a recording about to be started at `now`
+pub fn validate(config: &RecordingConfig, now: DateTime<Utc>) -> Result<(), RecordingError> {
+    if config.max_duration.is_some_and(|max| !max.is_finite() || max <= 0.0) {
+        return Err(RecordingError::InvalidConfig("Maximum duration must be a positive number of seconds".to_string()));
+    }
+    if config.max_file_size == Some(0) {
+        return Err(RecordingError::InvalidConfig("Maximum file size must be positive".to_string()));
+    }
+    if let Some(stop_at) = config.stop_at {
+        if stop_at <= start_time(config, now) {
+            return Err(RecordingError::InvalidConfig("Stop time is before the recording starts".to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// When capture should begin for a recording requested at `now`
+///
+/// A scheduled start in the past starts right away, after the countdown.
+pub fn start_time(config: &RecordingConfig, now: DateTime<Utc>) -> DateTime<Utc> {
+    let after_countdown = now + Duration::seconds(config.countdown as i64);
+    match config.start_at {
+        Some(start_at) if start_at > now => start_at,
+        _ => after_countdown,
+    }
+}
+
+/// Whole seconds left in the countdown to `start`, if it has begun
+pub fn countdown_remaining(config: &RecordingConfig, start: DateTime<Utc>, now: DateTime<Utc>) -> Option<u32> {
+    let remaining = (start - now).num_milliseconds();
+    if remaining <= 0 || remaining > config.countdown as i64 * 1000 {
+        return None;
+    }
+    Some(((remaining + 999) / 1000) as u32)
+}
+
+/// The first limit a running recording has reached, if any
+pub fn stop_reason(config: &RecordingConfig, duration: f64, file_size: u64, now: DateTime<Utc>) -> Option<StopReason> {
+    if config.max_duration.is_some_and(|max| duration >= max) {
+        Some(StopReason::MaxDuration)
+    } else if config.max_file_size.is_some_and(|max| file_size >= max) {
+        Some(StopReason::MaxFileSize)
+    } else if config.stop_at.is_some_and(|stop_at| now >= stop_at) {
+        Some(StopReason::StopTime)
+    } else {
+        None
+    }
+}
+
+/// Average bits per second written over a recording's duration
+pub fn bitrate(file_size: u64, duration: f64) -> f64 {
+    if duration > 0.0 {
+        file_size as f64 * 8.0 / duration
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedule_and_countdown() {
+        let now = Utc::now();
+        let config = RecordingConfig { countdown: 3, ..Default::default() };
+        assert_eq!(start_time(&config, now), now + Duration::seconds(3));
+
+        // A scheduled start keeps its time; the countdown runs up to it
+        let start_at = now + Duration::minutes(10);
+        let scheduled = RecordingConfig { start_at: Some(start_at), ..config.clone() };
+        assert_eq!(start_time(&scheduled, now), start_at);
+        assert_eq!(countdown_remaining(&scheduled, start_at, now), None);
+        assert_eq!(countdown_remaining(&scheduled, start_at, start_at - Duration::milliseconds(2500)), Some(3));
+        assert_eq!(countdown_remaining(&scheduled, start_at, start_at), None);
+
+        // Scheduled in the past: start now, after the countdown
+        let late = RecordingConfig { start_at: Some(now - Duration::minutes(1)), ..config };
+        assert_eq!(start_time(&late, now), now + Duration::seconds(3));
+    }
+
+    #[test]
+    fn test_validate_limits() {
+        let now = Utc::now();
+        assert!(validate(&RecordingConfig::default(), now).is_ok());
+        assert!(validate(&RecordingConfig { max_duration: Some(0.0), ..Default::default() }, now).is_err());
+        assert!(validate(&RecordingConfig { max_duration: Some(f64::NAN), ..Default::default() }, now).is_err());
+        assert!(validate(&RecordingConfig { max_duration: Some(f64::INFINITY), ..Default::default() }, now).is_err());
+        assert!(validate(&RecordingConfig { max_file_size: Some(0), ..Default::default() }, now).is_err());
+
+        let stop_at = Some(now + Duration::seconds(2));
+        assert!(validate(&RecordingConfig { stop_at, ..Default::default() }, now).is_ok());
+        assert!(validate(&RecordingConfig { stop_at, countdown: 5, ..Default::default() }, now).is_err());
+    }
+
+    #[test]
+    fn test_stop_reason() {
+        let now = Utc::now();
+        let config = RecordingConfig {
+            max_duration: Some(60.0),
+            max_file_size: Some(1_000_000),
+            stop_at: Some(now + Duration::minutes(5)),
+            ..Default::default()
+        };
+
+        assert_eq!(stop_reason(&config, 30.0, 500_000, now), None);
+        assert_eq!(stop_reason(&config, 60.0, 500_000, now), Some(StopReason::MaxDuration));
+        assert_eq!(stop_reason(&config, 30.0, 1_000_000, now), Some(StopReason::MaxFileSize));
+        assert_eq!(stop_reason(&config, 30.0, 0, now + Duration::minutes(5)), Some(StopReason::StopTime));
+        assert_eq!(stop_reason(&RecordingConfig::default(), 1e9, u64::MAX, now), None);
+
+        assert_eq!(bitrate(1_000_000, 8.0), 1_000_000.0);
+        assert_eq!(bitrate(1_000, 0.0), 0.0);
+    }
+}
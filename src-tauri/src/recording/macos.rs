@@ -1,11 +1,12 @@
 // macOS screen recording implementation using FFmpeg with screen capture
 
-use super::{AudioInputType, RecordingConfig, RecordingError, RecordingMode, RecordingSource, RecordingState, ScreenRecorder, SourceTypeFilter};
+use super::capture::{create_parent_dir, stop_process, Capture, CaptureState, RecordingProcess};
+use super::segments::FRAGMENTED_MP4_FLAGS;
+use super::sync::{SegmentStarts, StreamStart};
+use super::{AudioDevice, AudioInputType, RecordingConfig, RecordingError, RecordingMode, RecordingOutput, RecordingSource, RecordingState, ScreenRecorder, SourceTypeFilter};
 use crate::ffmpeg_utils;
-use std::path::PathBuf;
-use std::process::{Child, Command, Stdio};
-use std::sync::{Arc, Mutex};
-use std::time::Instant;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 use log::{info, warn, error};
 
 /// macOS screen recorder using FFmpeg's avfoundation device
@@ -14,19 +15,10 @@ use log::{info, warn, error};
 /// the screen, which is simpler than direct AVFoundation bindings and provides
 /// better cross-platform consistency.
 pub struct MacOSRecorder {
-    state: Arc<Mutex<RecorderState>>,
+    captures: CaptureState<String>,     // Captures know the screen being recorded
     ffmpeg_path: PathBuf,
 }
 
-struct RecorderState {
-    state: RecordingState,
-    process: Option<Child>,             // Screen recording process
-    webcam_process: Option<Child>,      // Webcam process (for dual mode)
-    output_path: Option<PathBuf>,
-    webcam_output_path: Option<PathBuf>, // Webcam output path
-    start_time: Option<Instant>,
-}
-
 impl MacOSRecorder {
     pub fn new() -> Self {
         // Find FFmpeg path during initialization
@@ -39,14 +31,7 @@ impl MacOSRecorder {
         info!("MacOSRecorder initialized with FFmpeg at: {:?}", ffmpeg_path);
 
         Self {
-            state: Arc::new(Mutex::new(RecorderState {
-                state: RecordingState::Idle,
-                process: None,
-                webcam_process: None,         // NEW
-                output_path: None,
-                webcam_output_path: None,     // NEW
-                start_time: None,
-            })),
+            captures: CaptureState::new(),
             ffmpeg_path,
         }
     }
@@ -170,12 +155,33 @@ impl MacOSRecorder {
         Ok(devices)
     }
 
+    /// Get list of available audio input devices using FFmpeg
+    fn get_audio_devices(ffmpeg_path: &PathBuf) -> Result<Vec<AudioDevice>, RecordingError> {
+        info!("Enumerating audio devices via FFmpeg");
+
+        let output = Command::new(ffmpeg_path)
+            .arg("-f")
+            .arg("avfoundation")
+            .arg("-list_devices")
+            .arg("true")
+            .arg("-i")
+            .arg("")
+            .stderr(Stdio::piped())
+            .output()
+            .map_err(|e| RecordingError::SystemError(format!("Failed to list devices: {}", e)))?;
+
+        let devices = parse_audio_devices(&String::from_utf8_lossy(&output.stderr));
+        info!("Found {} audio device(s)", devices.len());
+        Ok(devices)
+    }
+
     /// Spawn FFmpeg process for webcam recording
     fn spawn_webcam_recording(
         &self,
         webcam_id: &str,
         config: &RecordingConfig,
-    ) -> Result<Child, RecordingError> {
+        output_path: &Path,
+    ) -> Result<RecordingProcess, RecordingError> {
         info!("Spawning webcam recording process for device {}", webcam_id);
 
         let mut cmd = Command::new(&self.ffmpeg_path);
@@ -202,22 +208,201 @@ impl MacOSRecorder {
         // Pixel format for compatibility
         cmd.arg("-pix_fmt").arg("yuv420p");
 
+        // Fragmented so a crash leaves a readable file
+        cmd.arg("-movflags").arg(FRAGMENTED_MP4_FLAGS);
+
         // Overwrite output file
         cmd.arg("-y");
 
         // Output path
-        cmd.arg(config.output_path.to_str().unwrap());
+        cmd.arg(output_path);
 
-        // Redirect stderr to pipe (for error capture)
-        cmd.stderr(Stdio::piped());
         cmd.stdout(Stdio::null());
 
-        // Spawn process
-        cmd.spawn()
+        // Spawn process, collecting stderr for error capture
+        RecordingProcess::spawn(&mut cmd)
             .map_err(|e| RecordingError::RecordingFailed(format!("Failed to start webcam: {}", e)))
     }
+
+    /// Start the FFmpeg processes recording the next segment
+    async fn spawn_segment(&self, capture: &mut Capture<String>) -> Result<(), RecordingError> {
+        let config = &capture.config;
+        let source_id = &capture.source;
+        let output = &mut capture.output;
+
+        // Determine recording mode
+        let (process, webcam_process, starts) = match config.recording_mode {
+            RecordingMode::ScreenOnly => {
+                info!("Starting screen-only recording");
+
+                // Existing screen recording logic
+                let mut cmd = Command::new(&self.ffmpeg_path);
+                cmd.arg("-f").arg("avfoundation");
+
+                if config.show_cursor {
+                    cmd.arg("-capture_cursor").arg("1");
+                }
+
+                cmd.arg("-framerate").arg(config.fps.to_string());
+
+                let screen_name = format!("Capture screen {}", source_id);
+                let device_input = match config.audio_input {
+                    AudioInputType::None => format!("{}:none", screen_name),
+                    AudioInputType::Microphone => {
+                        let audio_id = config.audio_device_id.as_deref().unwrap_or("0");
+                        format!("{}:{}", screen_name, audio_id)
+                    }
+                    AudioInputType::SystemAudio => {
+                        warn!("System audio requires BlackHole setup");
+                        format!("{}:0", screen_name)
+                    }
+                    AudioInputType::Both => {
+                        warn!("Both audio sources require mixing setup");
+                        format!("{}:0", screen_name)
+                    }
+                };
+
+                cmd.arg("-i").arg(&device_input);
+                cmd.arg("-c:v").arg("libx264");
+                cmd.arg("-preset").arg("ultrafast");
+
+                let crf = 51 - (config.quality * 5);
+                cmd.arg("-crf").arg(crf.to_string());
+
+                if let Some(crop) = &config.crop_region {
+                    let crop_filter = format!("crop={}:{}:{}:{}", crop.width, crop.height, crop.x, crop.y);
+                    cmd.arg("-vf").arg(crop_filter);
+                }
+
+                if config.audio_input != AudioInputType::None {
+                    cmd.arg("-c:a").arg("aac");
+                    cmd.arg("-b:a").arg("128k");
+                }
+
+                cmd.arg("-pix_fmt").arg("yuv420p");
+                cmd.arg("-movflags").arg(FRAGMENTED_MP4_FLAGS);
+                cmd.arg("-y");
+                cmd.arg(&output.next_segment());
+                cmd.stdout(Stdio::null());
+
+                let start = StreamStart::now();
+                let child = RecordingProcess::spawn(&mut cmd)
+                    .map_err(|e| RecordingError::RecordingFailed(format!("Failed to start screen recording: {}", e)))?;
+
+                (child, None, SegmentStarts { main: start, webcam: None })
+            }
+
+            RecordingMode::WebcamOnly => {
+                info!("Starting webcam-only recording");
+
+                let webcam_id = capture.webcam_device.as_deref()
+                    .ok_or(RecordingError::InvalidConfig("Webcam source required for webcam mode".into()))?;
+
+                let start = StreamStart::now();
+                let child = self.spawn_webcam_recording(webcam_id, config, &output.next_segment())?;
+
+                (child, None, SegmentStarts { main: start, webcam: None })
+            }
+
+            RecordingMode::ScreenAndWebcam => {
+                info!("Starting dual recording (screen + webcam)");
+
+                // Validate webcam configuration
+                let webcam_id = capture.webcam_device.as_deref()
+                    .ok_or(RecordingError::InvalidConfig("Webcam source required for dual mode".into()))?;
+
+                let webcam_output = capture.webcam_output.as_mut()
+                    .ok_or(RecordingError::InvalidConfig("Webcam output path required for dual mode".into()))?;
+
+                // Spawn screen recording process (with mic audio)
+                let screen_name = format!("Capture screen {}", source_id);
+                let mut screen_cmd = Command::new(&self.ffmpeg_path);
+                screen_cmd.arg("-f").arg("avfoundation");
+
+                if config.show_cursor {
+                    screen_cmd.arg("-capture_cursor").arg("1");
+                }
+
+                screen_cmd.arg("-framerate").arg(config.fps.to_string());
+
+                let device_input = match config.audio_input {
+                    AudioInputType::Microphone => {
+                        let audio_id = config.audio_device_id.as_deref().unwrap_or("0");
+                        format!("{}:{}", screen_name, audio_id)
+                    }
+                    _ => format!("{}:none", screen_name), // Default to no audio if not mic
+                };
+
+                screen_cmd.arg("-i").arg(&device_input);
+                screen_cmd.arg("-c:v").arg("libx264");
+                screen_cmd.arg("-preset").arg("ultrafast");
+
+                let crf = 51 - (config.quality * 5);
+                screen_cmd.arg("-crf").arg(crf.to_string());
+
+                if config.audio_input == AudioInputType::Microphone {
+                    screen_cmd.arg("-c:a").arg("aac");
+                    screen_cmd.arg("-b:a").arg("128k");
+                }
+
+                screen_cmd.arg("-pix_fmt").arg("yuv420p");
+                screen_cmd.arg("-movflags").arg(FRAGMENTED_MP4_FLAGS);
+                screen_cmd.arg("-y");
+                screen_cmd.arg(&output.next_segment());
+                screen_cmd.stdout(Stdio::null());
+
+                let screen_start = StreamStart::now();
+                let screen_child = RecordingProcess::spawn(&mut screen_cmd)
+                    .map_err(|e| RecordingError::RecordingFailed(format!("Failed to start screen: {}", e)))?;
+
+                // Spawn webcam recording process (NO audio)
+                let mut webcam_config = config.clone();
+                webcam_config.audio_input = AudioInputType::None; // Force no audio on webcam
+
+                let webcam_start = StreamStart::now();
+                let webcam_child = match self.spawn_webcam_recording(webcam_id, &webcam_config, &webcam_output.next_segment()) {
+                    Ok(child) => child,
+                    Err(e) => {
+                        stop_process(screen_child, "Screen").await;
+                        return Err(e);
+                    }
+                };
+
+                (screen_child, Some(webcam_child), SegmentStarts { main: screen_start, webcam: Some(webcam_start) })
+            }
+        };
+
+        capture.segment_started(process, webcam_process, starts).await
+    }
 }
 
+/// Audio devices in FFmpeg's avfoundation device list
+///
+/// ```text
+/// [AVFoundation indev @ 0x...] AVFoundation audio devices:
+/// [AVFoundation indev @ 0x...] [0] MacBook Pro Microphone
+/// [AVFoundation indev @ 0x...] [1] BlackHole 2ch
+/// ```
+///
+/// Loopback devices like BlackHole carry system audio.
+fn parse_audio_devices(stderr: &str) -> Vec<AudioDevice> {
+    stderr.lines()
+        .skip_while(|line| !line.contains("AVFoundation audio devices:"))
+        .skip(1)
+        .filter_map(|line| {
+            let start = line.rfind("] [")? + 2;
+            let end = start + line[start..].find(']')?;
+            let id = &line[start + 1..end];
+            id.parse::<u32>().ok()?;
+            let name = line[end + 1..].trim().to_string();
+            Some(AudioDevice {
+                id: id.to_string(),
+                is_monitor: name.contains("BlackHole") || name.contains("Loopback"),
+                name,
+            })
+        })
+        .collect()
+}
 
 #[async_trait::async_trait]
 impl ScreenRecorder for MacOSRecorder {
@@ -316,6 +501,10 @@ impl ScreenRecorder for MacOSRecorder {
         Ok(sources)
     }
 
+    async fn list_audio_devices(&self) -> Result<Vec<AudioDevice>, RecordingError> {
+        Self::get_audio_devices(&self.ffmpeg_path)
+    }
+
     async fn check_permissions(&self) -> Result<bool, RecordingError> {
         // On macOS 10.15+, screen recording requires permission
         // We can't programmatically check this without native code,
@@ -345,11 +534,7 @@ impl ScreenRecorder for MacOSRecorder {
         source: &RecordingSource,
         config: RecordingConfig,
     ) -> Result<(), RecordingError> {
-        let mut state = self.state.lock().unwrap();
-
-        if !state.state.can_start() {
-            return Err(RecordingError::AlreadyRecording);
-        }
+        self.captures.check_can_start()?;
 
         info!("Starting screen recording for source: {}", source.name());
 
@@ -359,345 +544,62 @@ impl ScreenRecorder for MacOSRecorder {
                 "Invalid output path".to_string(),
             ));
         }
+        create_parent_dir(&config.output_path)?;
 
-        // Create parent directory if it doesn't exist
-        if let Some(parent) = config.output_path.parent() {
-            std::fs::create_dir_all(parent).map_err(|e| {
-                RecordingError::SystemError(format!("Failed to create output directory: {}", e))
-            })?;
-        }
-
-        // Determine recording mode
-        match config.recording_mode {
-            RecordingMode::ScreenOnly => {
-                info!("Starting screen-only recording");
-
-                // Existing screen recording logic
-                let mut cmd = Command::new(&self.ffmpeg_path);
-                cmd.arg("-f").arg("avfoundation");
-
-                if config.show_cursor {
-                    cmd.arg("-capture_cursor").arg("1");
-                }
-
-                cmd.arg("-framerate").arg(config.fps.to_string());
-
-                let screen_name = format!("Capture screen {}", source.id());
-                let device_input = match config.audio_input {
-                    AudioInputType::None => format!("{}:none", screen_name),
-                    AudioInputType::Microphone => {
-                        let audio_id = config.audio_device_id.as_deref().unwrap_or("0");
-                        format!("{}:{}", screen_name, audio_id)
-                    }
-                    AudioInputType::SystemAudio => {
-                        warn!("System audio requires BlackHole setup");
-                        format!("{}:0", screen_name)
-                    }
-                    AudioInputType::Both => {
-                        warn!("Both audio sources require mixing setup");
-                        format!("{}:0", screen_name)
-                    }
-                };
-
-                cmd.arg("-i").arg(&device_input);
-                cmd.arg("-c:v").arg("libx264");
-                cmd.arg("-preset").arg("ultrafast");
-
-                let crf = 51 - (config.quality * 5);
-                cmd.arg("-crf").arg(crf.to_string());
-
-                if let Some(crop) = &config.crop_region {
-                    let crop_filter = format!("crop={}:{}:{}:{}", crop.width, crop.height, crop.x, crop.y);
-                    cmd.arg("-vf").arg(crop_filter);
-                }
-
-                if config.audio_input != AudioInputType::None {
-                    cmd.arg("-c:a").arg("aac");
-                    cmd.arg("-b:a").arg("128k");
-                }
-
-                cmd.arg("-pix_fmt").arg("yuv420p");
-                cmd.arg("-y");
-                cmd.arg(config.output_path.to_str().unwrap());
-                cmd.stderr(Stdio::piped());
-                cmd.stdout(Stdio::null());
-
-                let child = cmd.spawn()
-                    .map_err(|e| RecordingError::RecordingFailed(format!("Failed to start screen recording: {}", e)))?;
-
-                state.process = Some(child);
-                state.output_path = Some(config.output_path.clone());
+        let webcam_device = match config.recording_mode {
+            RecordingMode::ScreenOnly => None,
+            RecordingMode::WebcamOnly | RecordingMode::ScreenAndWebcam => {
+                config.webcam_source.as_ref().map(|webcam| webcam.id().to_string())
             }
+        };
+        let webcam_path = if config.recording_mode == RecordingMode::ScreenAndWebcam {
+            let webcam_path = config.webcam_output_path.clone()
+                .ok_or(RecordingError::InvalidConfig("Webcam output path required for dual mode".into()))?;
+            create_parent_dir(&webcam_path)?;
+            Some(webcam_path)
+        } else {
+            None
+        };
 
-            RecordingMode::WebcamOnly => {
-                info!("Starting webcam-only recording");
-
-                let webcam_source = config.webcam_source.as_ref()
-                    .ok_or(RecordingError::InvalidConfig("Webcam source required for webcam mode".into()))?;
-
-                let webcam_id = webcam_source.id();
-
-                let child = self.spawn_webcam_recording(webcam_id, &config)?;
-
-                state.process = Some(child);
-                state.output_path = Some(config.output_path.clone());
-            }
-
-            RecordingMode::ScreenAndWebcam => {
-                info!("Starting dual recording (screen + webcam)");
-
-                // Validate webcam configuration
-                let webcam_source = config.webcam_source.as_ref()
-                    .ok_or(RecordingError::InvalidConfig("Webcam source required for dual mode".into()))?;
-
-                let webcam_path = config.webcam_output_path.as_ref()
-                    .ok_or(RecordingError::InvalidConfig("Webcam output path required for dual mode".into()))?;
-
-                // Create parent directory for webcam file
-                if let Some(parent) = webcam_path.parent() {
-                    std::fs::create_dir_all(parent).map_err(|e| {
-                        RecordingError::SystemError(format!("Failed to create webcam output directory: {}", e))
-                    })?;
-                }
-
-                // Spawn screen recording process (with mic audio)
-                let screen_name = format!("Capture screen {}", source.id());
-                let mut screen_cmd = Command::new(&self.ffmpeg_path);
-                screen_cmd.arg("-f").arg("avfoundation");
-
-                if config.show_cursor {
-                    screen_cmd.arg("-capture_cursor").arg("1");
-                }
-
-                screen_cmd.arg("-framerate").arg(config.fps.to_string());
-
-                let device_input = match config.audio_input {
-                    AudioInputType::Microphone => {
-                        let audio_id = config.audio_device_id.as_deref().unwrap_or("0");
-                        format!("{}:{}", screen_name, audio_id)
-                    }
-                    _ => format!("{}:none", screen_name), // Default to no audio if not mic
-                };
-
-                screen_cmd.arg("-i").arg(&device_input);
-                screen_cmd.arg("-c:v").arg("libx264");
-                screen_cmd.arg("-preset").arg("ultrafast");
-
-                let crf = 51 - (config.quality * 5);
-                screen_cmd.arg("-crf").arg(crf.to_string());
-
-                if config.audio_input == AudioInputType::Microphone {
-                    screen_cmd.arg("-c:a").arg("aac");
-                    screen_cmd.arg("-b:a").arg("128k");
-                }
-
-                screen_cmd.arg("-pix_fmt").arg("yuv420p");
-                screen_cmd.arg("-y");
-                screen_cmd.arg(config.output_path.to_str().unwrap());
-                screen_cmd.stderr(Stdio::piped());
-                screen_cmd.stdout(Stdio::null());
-
-                let screen_child = screen_cmd.spawn()
-                    .map_err(|e| RecordingError::RecordingFailed(format!("Failed to start screen: {}", e)))?;
-
-                // Spawn webcam recording process (NO audio)
-                let mut webcam_config = config.clone();
-                webcam_config.output_path = webcam_path.clone();
-                webcam_config.audio_input = AudioInputType::None; // Force no audio on webcam
-
-                let webcam_child = self.spawn_webcam_recording(webcam_source.id(), &webcam_config)?;
-
-                state.process = Some(screen_child);
-                state.webcam_process = Some(webcam_child);
-                state.output_path = Some(config.output_path.clone());
-                state.webcam_output_path = Some(webcam_path.clone());
-            }
+        let mut capture = Capture::new(config, source.id().to_string(), webcam_device, webcam_path);
+        if let Err(e) = self.spawn_segment(&mut capture).await {
+            // Nothing was recorded, so there is nothing to keep a journal for
+            capture.discard();
+            return Err(e);
         }
-
-        state.start_time = Some(Instant::now());
-        state.state = RecordingState::Recording;
+        self.captures.started(capture);
 
         info!("Recording started successfully");
         Ok(())
     }
 
-    async fn stop_recording(&mut self) -> Result<PathBuf, RecordingError> {
-        let mut state = self.state.lock().unwrap();
-
-        if !state.state.can_stop() {
-            return Err(RecordingError::NotRecording);
-        }
-
-        info!("Stopping screen recording");
-        state.state = RecordingState::Finalizing;
-
-        // Get the process and output path
-        let mut process = state.process.take().ok_or_else(|| {
-            RecordingError::RecordingFailed("No recording process found".to_string())
-        })?;
-
-        let output_path = state.output_path.take().ok_or_else(|| {
-            RecordingError::RecordingFailed("No output path found".to_string())
-        })?;
-
-        // Get webcam process if it exists (for dual recording)
-        let webcam_process = state.webcam_process.take();
-        let webcam_output_path = state.webcam_output_path.take();
-
-        // Set state to Idle immediately after taking the process
-        // This allows new recordings to start while we finalize the current one
-        state.state = RecordingState::Idle;
-        state.start_time = None;
-
-        // Send SIGINT to FFmpeg for graceful shutdown FIRST
-        // This allows FFmpeg to finalize the video file properly
-        let pid = process.id();
-
-        drop(state); // Release lock before potentially blocking operation
-
-        #[cfg(unix)]
-        {
-            info!("Sending SIGINT to FFmpeg process {}", pid);
-            unsafe {
-                libc::kill(pid as i32, libc::SIGINT);
-            }
-        }
-
-        #[cfg(not(unix))]
-        {
-            // On non-Unix platforms, use kill() as fallback
-            let _ = process.kill();
-        }
-
-        // Now read stderr and wait for process to exit
-        let mut stderr_output = String::new();
-        if let Some(mut stderr) = process.stderr.take() {
-            use std::io::Read;
-            let _ = stderr.read_to_string(&mut stderr_output);
-        }
-
-        // Wait for process to exit
-        let wait_result = process.wait();
-
-        match wait_result {
-            Ok(status) => {
-                info!("Recording stopped, exit status: {}", status);
-                if !stderr_output.is_empty() {
-                    info!("FFmpeg stderr (last 500 chars): {}",
-                        &stderr_output.chars().rev().take(500).collect::<String>().chars().rev().collect::<String>());
-                }
-            }
-            Err(e) => {
-                warn!("Error waiting for recording process: {}", e);
-                if !stderr_output.is_empty() {
-                    warn!("FFmpeg stderr: {}", stderr_output);
-                }
-            }
-        }
-
-        // Stop webcam process if exists
-        if let Some(mut webcam_process) = webcam_process {
-            info!("Stopping webcam recording process");
-
-            #[cfg(unix)]
-            unsafe {
-                libc::kill(webcam_process.id() as i32, libc::SIGINT);
-            }
-
-            // Read stderr for debugging
-            if let Some(mut stderr) = webcam_process.stderr.take() {
-                use std::io::Read;
-                let mut stderr_output = String::new();
-                let _ = stderr.read_to_string(&mut stderr_output);
-                if !stderr_output.is_empty() {
-                    info!("Webcam FFmpeg stderr: {}", stderr_output);
-                }
-            }
-
-            // Wait for process to exit
-            match webcam_process.wait() {
-                Ok(status) => info!("Webcam process exited with status: {:?}", status),
-                Err(e) => warn!("Error waiting for webcam process: {}", e),
-            }
-        }
-
-        // Verify webcam file if it was being recorded
-        if let Some(webcam_path) = webcam_output_path {
-            for attempt in 0..5 {
-                if webcam_path.exists() && webcam_path.metadata().map(|m| m.len() > 0).unwrap_or(false) {
-                    info!("Webcam recording file verified: {:?}", webcam_path);
-                    break;
-                }
-
-                if attempt < 4 {
-                    warn!("Webcam file not ready, waiting... (attempt {})", attempt + 1);
-                    std::thread::sleep(std::time::Duration::from_millis(100));
-                }
-            }
-        }
-
-        // State was already set to Idle earlier, no need to update again
-
-        // Verify the output file was actually created by FFmpeg
-        // FFmpeg needs time to finalize the MP4 moov atom after receiving SIGINT
-        // Retry up to 5 times with 100ms delays (total 500ms max wait)
-        let mut file_exists = false;
-        for attempt in 0..5 {
-            if output_path.exists() {
-                file_exists = true;
-                break;
-            }
-            if attempt < 4 {
-                info!("Output file not found yet, waiting 100ms (attempt {}/5)", attempt + 1);
-                std::thread::sleep(std::time::Duration::from_millis(100));
-            }
-        }
-
-        if !file_exists {
-            error!("Recording file was not created by FFmpeg after 500ms: {}", output_path.display());
-            error!("FFmpeg may have failed silently. Check stderr output above.");
-            return Err(RecordingError::RecordingFailed(format!(
-                "Recording file not created: {}. FFmpeg may have failed during finalization.",
-                output_path.display()
-            )));
-        }
+    async fn pause_recording(&mut self) -> Result<(), RecordingError> {
+        // Each stretch between pauses is its own segment, joined on stop
+        self.captures.pause().await
+    }
 
-        // Verify file has non-zero size
-        match std::fs::metadata(&output_path) {
-            Ok(metadata) => {
-                let file_size = metadata.len();
-                if file_size == 0 {
-                    error!("Recording file is empty (0 bytes): {}", output_path.display());
-                    return Err(RecordingError::RecordingFailed(format!(
-                        "Recording file is empty. Recording may have been too short or FFmpeg failed."
-                    )));
-                }
-                info!("Recording saved successfully: {} ({} bytes)", output_path.display(), file_size);
-            }
-            Err(e) => {
-                error!("Cannot read recording file metadata: {}", e);
-                return Err(RecordingError::RecordingFailed(format!(
-                    "Cannot verify recording file: {}", e
-                )));
-            }
-        }
+    async fn resume_recording(&mut self) -> Result<(), RecordingError> {
+        let mut capture = self.captures.take_paused()?;
+        let result = self.spawn_segment(&mut capture).await;
+        self.captures.resumed(capture, result)
+    }
 
-        Ok(output_path)
+    async fn stop_recording(&mut self) -> Result<RecordingOutput, RecordingError> {
+        // SIGINT lets FFmpeg finalize the video file properly
+        let capture = self.captures.stop().await?;
+        self.captures.finalize(capture, self.ffmpeg_path.clone()).await
     }
 
     fn get_state(&self) -> RecordingState {
-        let state = self.state.lock().unwrap();
-        state.state
+        self.captures.state()
     }
 
     fn get_duration(&self) -> f64 {
-        let state = self.state.lock().unwrap();
+        self.captures.duration()
+    }
 
-        if let Some(start_time) = state.start_time {
-            start_time.elapsed().as_secs_f64()
-        } else {
-            0.0
-        }
+    fn get_output_size(&self) -> u64 {
+        self.captures.output_size()
     }
 }
 
@@ -729,4 +631,20 @@ mod tests {
         let line = "[AVFoundation indev @ 0x12345] [1] Capture screen 0";
         assert_eq!(extract_device_name(line), Some("Capture screen 0".to_string()));
     }
+
+    #[test]
+    fn test_parse_audio_devices() {
+        let stderr = "\
+[AVFoundation indev @ 0x12345] AVFoundation video devices:
+[AVFoundation indev @ 0x12345] [0] FaceTime HD Camera
+[AVFoundation indev @ 0x12345] AVFoundation audio devices:
+[AVFoundation indev @ 0x12345] [0] MacBook Pro Microphone
+[AVFoundation indev @ 0x12345] [1] BlackHole 2ch
+: Input/output error";
+        let devices = parse_audio_devices(stderr);
+        assert_eq!(devices, vec![
+            AudioDevice { id: "0".to_string(), name: "MacBook Pro Microphone".to_string(), is_monitor: false },
+            AudioDevice { id: "1".to_string(), name: "BlackHole 2ch".to_string(), is_monitor: true },
+        ]);
+    }
 }
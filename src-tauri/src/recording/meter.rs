@@ -0,0 +1,342 @@
+// Audio level metering for recording inputs
+//
+// A separate FFmpeg process listens to the input device and measures each
+// tenth of a second with the `astats` filter, so levels can be watched before
+// and during a recording without touching the capture itself.
+
+#[cfg(not(target_os = "linux"))]
+use super::AudioInputType;
+use super::{RecordingConfig, RecordingError};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use log::{info, warn};
+
+/// Levels below this are treated as silence (dBFS)
+pub const SILENCE_DB: f64 = -60.0;
+/// Peaks at or above this are treated as clipping (dBFS)
+pub const CLIPPING_DB: f64 = -0.1;
+/// Quietest level reported; digital silence measures as -inf
+const FLOOR_DB: f64 = -120.0;
+
+/// Samples measured per level update: 100ms at 48kHz
+const SAMPLES_PER_UPDATE: u32 = 4800;
+
+/// Peak and RMS level of one channel, in dBFS
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ChannelLevel {
+    pub peak: f64,
+    pub rms: f64,
+}
+
+/// Levels of every channel over one measurement window
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AudioLevels {
+    /// Seconds since metering started
+    pub time: f64,
+    pub channels: Vec<ChannelLevel>,
+}
+
+impl AudioLevels {
+    fn is_silent(&self) -> bool {
+        self.channels.iter().all(|c| c.peak < SILENCE_DB)
+    }
+
+    fn is_clipping(&self) -> bool {
+        self.channels.iter().any(|c| c.peak >= CLIPPING_DB)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LevelWarningKind {
+    Silent,
+    Clipping,
+}
+
+/// Input has been silent or clipping for too long, or has stopped being so
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LevelWarning {
+    pub kind: LevelWarningKind,
+    /// Whether the condition is still going on
+    pub active: bool,
+    /// How long it has lasted, in seconds
+    pub seconds: f64,
+}
+
+/// Reads the levels `ametadata` prints for each measurement window
+///
+/// ```text
+/// frame:3    pts:14400   pts_time:0.3
+/// lavfi.astats.1.Peak_level=-12.041200
+/// lavfi.astats.1.RMS_level=-24.310000
+/// lavfi.astats.2.Peak_level=-inf
+/// ```
+#[derive(Debug, Default)]
+pub struct LevelParser {
+    time: Option<f64>,
+    channels: BTreeMap<usize, ChannelLevel>,
+}
+
+impl LevelParser {
+    /// Feed one line; returns the previous window's levels when a new one starts
+    pub fn line(&mut self, line: &str) -> Option<AudioLevels> {
+        let line = line.trim();
+        if line.starts_with("frame:") {
+            let time = line.split_whitespace()
+                .find_map(|field| field.strip_prefix("pts_time:"))
+                .and_then(|t| t.parse().ok());
+            let finished = self.take();
+            self.time = time;
+            return finished;
+        }
+
+        let (key, value) = line.strip_prefix("lavfi.astats.")?.split_once('=')?;
+        let (channel, measure) = key.split_once('.')?;
+        let channel: usize = channel.parse().ok()?;
+        let value = parse_db(value);
+
+        let level = self.channels.entry(channel).or_insert(ChannelLevel { peak: FLOOR_DB, rms: FLOOR_DB });
+        match measure {
+            "Peak_level" => level.peak = value,
+            "RMS_level" => level.rms = value,
+            _ => {}
+        }
+        None
+    }
+
+    fn take(&mut self) -> Option<AudioLevels> {
+        let time = self.time.take()?;
+        if self.channels.is_empty() {
+            return None;
+        }
+        let channels = std::mem::take(&mut self.channels).into_values().collect();
+        Some(AudioLevels { time, channels })
+    }
+}
+
+fn parse_db(value: &str) -> f64 {
+    match value.trim().parse::<f64>() {
+        Ok(db) if db.is_finite() => db.max(FLOOR_DB),
+        _ => FLOOR_DB,
+    }
+}
+
+/// Tracks how long input has been silent or clipping
+///
+/// Warns once when a condition has lasted `warn_after` seconds, and again
+/// when it ends.
+#[derive(Debug)]
+pub struct LevelWatch {
+    warn_after: f64,
+    current: Option<(LevelWarningKind, f64)>,
+    warned: bool,
+}
+
+impl LevelWatch {
+    pub fn new(warn_after: f64) -> Self {
+        Self { warn_after, current: None, warned: false }
+    }
+
+    pub fn update(&mut self, levels: &AudioLevels) -> Option<LevelWarning> {
+        let kind = if levels.is_silent() {
+            Some(LevelWarningKind::Silent)
+        } else if levels.is_clipping() {
+            Some(LevelWarningKind::Clipping)
+        } else {
+            None
+        };
+
+        match (self.current, kind) {
+            (Some((current, since)), Some(kind)) if current == kind => {
+                let seconds = levels.time - since;
+                if !self.warned && seconds >= self.warn_after {
+                    self.warned = true;
+                    return Some(LevelWarning { kind, active: true, seconds });
+                }
+                None
+            }
+            (previous, kind) => {
+                let ended = previous
+                    .filter(|_| self.warned)
+                    .map(|(kind, since)| LevelWarning { kind, active: false, seconds: levels.time - since });
+                self.current = kind.map(|kind| (kind, levels.time));
+                self.warned = false;
+                ended
+            }
+        }
+    }
+}
+
+/// What a running meter reports
+#[derive(Debug, Clone, PartialEq)]
+pub enum MeterEvent {
+    Levels(AudioLevels),
+    Warning(LevelWarning),
+}
+
+/// Meter on an audio input, stopped when dropped
+pub struct AudioMeter {
+    process: Child,
+}
+
+impl AudioMeter {
+    /// Start metering `device`, reporting to `on_event` from a background thread
+    pub fn spawn<F>(ffmpeg_path: &Path, device: &str, warn_after: f64, mut on_event: F) -> Result<Self, RecordingError>
+    where
+        F: FnMut(MeterEvent) + Send + 'static,
+    {
+        info!("Starting audio meter on {}", device);
+        let mut process = Command::new(ffmpeg_path)
+            .args(["-hide_banner", "-nostats", "-loglevel", "error"])
+            .args(device_input(device))
+            .arg("-af")
+            .arg(meter_filter())
+            .args(["-f", "null", "-"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| RecordingError::RecordingFailed(format!("Failed to start audio meter: {}", e)))?;
+
+        let stdout = process.stdout.take()
+            .ok_or_else(|| RecordingError::SystemError("Audio meter has no output".to_string()))?;
+        let device_name = device.to_string();
+        std::thread::spawn(move || {
+            let mut parser = LevelParser::default();
+            let mut watch = LevelWatch::new(warn_after);
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if let Some(levels) = parser.line(&line) {
+                    if let Some(warning) = watch.update(&levels) {
+                        on_event(MeterEvent::Warning(warning));
+                    }
+                    on_event(MeterEvent::Levels(levels));
+                }
+            }
+            info!("Audio meter on {} ended", device_name);
+        });
+
+        Ok(Self { process })
+    }
+}
+
+impl Drop for AudioMeter {
+    fn drop(&mut self) {
+        if let Err(e) = self.process.kill() {
+            warn!("Failed to stop audio meter: {}", e);
+        }
+        let _ = self.process.wait();
+    }
+}
+
+/// `-af` chain measuring peak and RMS per channel every `SAMPLES_PER_UPDATE` samples
+pub fn meter_filter() -> String {
+    format!(
+        "aresample=48000,asetnsamples=n={}:p=0,\
+         astats=metadata=1:reset=1:measure_perchannel=Peak_level+RMS_level:measure_overall=none,\
+         ametadata=mode=print:file=-",
+        SAMPLES_PER_UPDATE
+    )
+}
+
+/// Device metered when none is chosen: the system's default input
+#[cfg(target_os = "linux")]
+pub const DEFAULT_DEVICE: &str = "default";
+
+#[cfg(not(target_os = "linux"))]
+pub const DEFAULT_DEVICE: &str = "0";
+
+/// FFmpeg input reading an audio device by the ID `list_audio_devices` gave it
+#[cfg(target_os = "linux")]
+pub fn device_input(device: &str) -> Vec<String> {
+    vec!["-f".to_string(), "pulse".to_string(), "-i".to_string(), device.to_string()]
+}
+
+#[cfg(target_os = "macos")]
+pub fn device_input(device: &str) -> Vec<String> {
+    vec!["-f".to_string(), "avfoundation".to_string(), "-i".to_string(), format!(":{}", device)]
+}
+
+#[cfg(target_os = "windows")]
+pub fn device_input(device: &str) -> Vec<String> {
+    vec!["-f".to_string(), "dshow".to_string(), "-i".to_string(), format!("audio={}", device)]
+}
+
+/// Device a recording captures audio from, to meter while it runs
+///
+/// With both microphone and system audio, the microphone is metered.
+#[cfg(target_os = "linux")]
+pub fn recording_device(config: &RecordingConfig) -> Option<String> {
+    super::linux::pulse_sources(&config.audio_input, config.audio_device_id.as_deref()).into_iter().next()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn recording_device(config: &RecordingConfig) -> Option<String> {
+    if config.audio_input == AudioInputType::None {
+        return None;
+    }
+    Some(config.audio_device_id.clone().unwrap_or_else(|| DEFAULT_DEVICE.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn levels(time: f64, peaks: &[f64]) -> AudioLevels {
+        AudioLevels {
+            time,
+            channels: peaks.iter().map(|&peak| ChannelLevel { peak, rms: peak - 10.0 }).collect(),
+        }
+    }
+
+    #[test]
+    fn test_parse_levels() {
+        let output = "\
+frame:0    pts:0       pts_time:0
+lavfi.astats.1.Peak_level=-12.041200
+lavfi.astats.1.RMS_level=-24.310000
+lavfi.astats.2.Peak_level=-inf
+lavfi.astats.2.RMS_level=-inf
+frame:1    pts:4800    pts_time:0.1
+lavfi.astats.1.Peak_level=0.000000
+";
+        let mut parser = LevelParser::default();
+        let finished: Vec<AudioLevels> = output.lines().filter_map(|line| parser.line(line)).collect();
+
+        assert_eq!(finished, vec![AudioLevels {
+            time: 0.0,
+            channels: vec![
+                ChannelLevel { peak: -12.0412, rms: -24.31 },
+                ChannelLevel { peak: -120.0, rms: -120.0 },
+            ],
+        }]);
+        assert_eq!(parser.take().unwrap().channels[0].peak, 0.0);
+    }
+
+    #[test]
+    fn test_warns_after_sustained_silence_or_clipping() {
+        let mut watch = LevelWatch::new(2.0);
+        assert_eq!(watch.update(&levels(0.0, &[-30.0, -30.0])), None);
+
+        // Silent on every channel from 1s; warned once at 3s
+        assert_eq!(watch.update(&levels(1.0, &[-80.0, -90.0])), None);
+        assert_eq!(watch.update(&levels(2.5, &[-80.0, -90.0])), None);
+        let warning = watch.update(&levels(3.0, &[-80.0, -90.0])).unwrap();
+        assert_eq!((warning.kind, warning.active, warning.seconds), (LevelWarningKind::Silent, true, 2.0));
+        assert_eq!(watch.update(&levels(4.0, &[-80.0, -90.0])), None);
+
+        // Sound again ends it
+        let ended = watch.update(&levels(4.5, &[-80.0, -20.0])).unwrap();
+        assert_eq!((ended.kind, ended.active), (LevelWarningKind::Silent, false));
+
+        // Clipping on one channel counts; a short burst is not reported
+        assert_eq!(watch.update(&levels(5.0, &[0.0, -20.0])), None);
+        assert_eq!(watch.update(&levels(6.0, &[-20.0, -20.0])), None);
+        assert_eq!(watch.update(&levels(7.0, &[0.0, -20.0])), None);
+        let warning = watch.update(&levels(9.0, &[-0.05, -20.0])).unwrap();
+        assert_eq!((warning.kind, warning.active), (LevelWarningKind::Clipping, true));
+    }
+}
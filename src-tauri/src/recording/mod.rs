@@ -2,12 +2,21 @@
 // Platform-independent recording API with platform-specific implementations
 
 use std::path::PathBuf;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 pub mod error;
 pub mod state;
+pub mod segments;
+pub mod journal;
+pub mod limits;
+pub mod meter;
+pub mod sync;
 pub mod integration;
 
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+pub mod capture;
+
 #[cfg(target_os = "macos")]
 pub mod macos;
 
@@ -17,6 +26,15 @@ pub mod windows;
 #[cfg(target_os = "linux")]
 pub mod linux;
 
+#[cfg(target_os = "linux")]
+pub mod x11;
+
+#[cfg(target_os = "linux")]
+pub mod wayland;
+
+#[cfg(target_os = "linux")]
+pub mod portal;
+
 // Re-export platform-specific recorder as default
 #[cfg(target_os = "macos")]
 pub use macos::MacOSRecorder as PlatformRecorder;
@@ -29,6 +47,7 @@ pub use linux::LinuxRecorder as PlatformRecorder;
 
 pub use error::RecordingError;
 pub use state::RecordingState;
+pub use sync::AlignedStream;
 
 /// Filter for listing recording sources
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -92,6 +111,16 @@ impl RecordingSource {
     }
 }
 
+/// Audio input device
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AudioDevice {
+    /// ID to use as `RecordingConfig::audio_device_id`
+    pub id: String,
+    pub name: String,
+    /// Captures what the system plays rather than a microphone
+    pub is_monitor: bool,
+}
+
 /// Audio input type for recording
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum AudioInputType {
@@ -112,7 +141,7 @@ impl Default for AudioInputType {
 pub enum RecordingMode {
     ScreenOnly,
     WebcamOnly,
-    ScreenAndWebcam,  // Records two separate files
+    ScreenAndWebcam,  // Records two separate files, aligned when stopped
 }
 
 impl Default for RecordingMode {
@@ -163,6 +192,30 @@ pub struct RecordingConfig {
     /// Webcam output path (for dual recording mode)
     #[serde(default)]
     pub webcam_output_path: Option<PathBuf>,
+
+    /// Stop after this many seconds of recording, not counting pauses
+    #[serde(default)]
+    pub max_duration: Option<f64>,
+
+    /// Stop once the recording's files reach this many bytes
+    #[serde(default)]
+    pub max_file_size: Option<u64>,
+
+    /// Stop at this time
+    #[serde(default)]
+    pub stop_at: Option<DateTime<Utc>>,
+
+    /// Seconds to count down before recording starts (default: 0)
+    #[serde(default)]
+    pub countdown: u32,
+
+    /// Start at this time instead of right away; the countdown ends then
+    #[serde(default)]
+    pub start_at: Option<DateTime<Utc>>,
+
+    /// Warn when the audio input stays silent or clipping this many seconds (default: 5)
+    #[serde(default = "default_audio_warning_seconds")]
+    pub audio_warning_seconds: f64,
 }
 
 /// Screen region for cropped recording
@@ -177,6 +230,7 @@ pub struct CropRegion {
 fn default_fps() -> u32 { 30 }
 fn default_quality() -> u8 { 7 }
 fn default_cursor() -> bool { true }
+fn default_audio_warning_seconds() -> f64 { 5.0 }
 
 impl Default for RecordingConfig {
     fn default() -> Self {
@@ -191,10 +245,29 @@ impl Default for RecordingConfig {
             recording_mode: RecordingMode::default(),
             webcam_source: None,
             webcam_output_path: None,
+            max_duration: None,
+            max_file_size: None,
+            stop_at: None,
+            countdown: 0,
+            start_at: None,
+            audio_warning_seconds: default_audio_warning_seconds(),
         }
     }
 }
 
+/// Files written by a finished recording
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingOutput {
+    /// The recording with its audio: the screen, or the webcam in webcam-only mode
+    pub path: PathBuf,
+    /// Webcam of a screen and webcam recording, aligned to `path`
+    pub webcam: Option<AlignedStream>,
+    /// System audio recorded apart from the microphone, starting with `path`
+    pub system_audio: Option<PathBuf>,
+    /// Frame rate it was recorded at
+    pub fps: u32,
+}
+
 /// Platform-independent screen recording trait
 ///
 /// Each platform (macOS, Windows, Linux) implements this trait
@@ -204,6 +277,9 @@ pub trait ScreenRecorder: Send + Sync {
     /// List available recording sources filtered by type
     async fn list_sources(&self, filter: SourceTypeFilter) -> Result<Vec<RecordingSource>, RecordingError>;
 
+    /// List audio input devices that can be recorded
+    async fn list_audio_devices(&self) -> Result<Vec<AudioDevice>, RecordingError>;
+
     /// Check if we have necessary recording permissions
     async fn check_permissions(&self) -> Result<bool, RecordingError>;
 
@@ -217,14 +293,25 @@ pub trait ScreenRecorder: Send + Sync {
         config: RecordingConfig,
     ) -> Result<(), RecordingError>;
 
-    /// Stop the current recording and finalize the file
-    async fn stop_recording(&mut self) -> Result<PathBuf, RecordingError>;
+    /// Pause the current recording, ending the segment being captured
+    async fn pause_recording(&mut self) -> Result<(), RecordingError>;
+
+    /// Resume a paused recording into a new segment
+    async fn resume_recording(&mut self) -> Result<(), RecordingError>;
+
+    /// Stop the current recording and finalize its files
+    ///
+    /// A recording made of several segments is joined into one file.
+    async fn stop_recording(&mut self) -> Result<RecordingOutput, RecordingError>;
 
     /// Get the current recording state
     fn get_state(&self) -> RecordingState;
 
-    /// Get the current recording duration in seconds
+    /// Get the current recording duration in seconds, not counting pauses
     fn get_duration(&self) -> f64;
+
+    /// Bytes written to the current recording's files so far
+    fn get_output_size(&self) -> u64;
 }
 
 #[cfg(test)]
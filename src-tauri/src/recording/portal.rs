@@ -0,0 +1,442 @@
+// xdg-desktop-portal ScreenCast client, used to record Wayland sessions
+
+use super::RecordingError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::os::fd::OwnedFd;
+use zbus::export::futures_util::StreamExt;
+use zbus::zvariant::{self, DeserializeDict, ObjectPath, OwnedObjectPath, Type, Value};
+use zbus::{Connection, MatchRule, MessageStream};
+use log::{info, warn};
+
+const PORTAL_BUS_NAME: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const SCREENCAST_INTERFACE: &str = "org.freedesktop.portal.ScreenCast";
+const REQUEST_INTERFACE: &str = "org.freedesktop.portal.Request";
+const SESSION_INTERFACE: &str = "org.freedesktop.portal.Session";
+
+/// What the user may pick in the portal dialog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum SourceKind {
+    Monitor = 1,
+    Window = 2,
+}
+
+/// How the cursor appears in the stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum CursorMode {
+    Hidden = 1,
+    Embedded = 2,
+}
+
+/// Options for a screen cast session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScreenCastRequest {
+    pub source_kind: SourceKind,
+    pub cursor_mode: CursorMode,
+    /// Token from an earlier session, letting the portal skip the dialog
+    pub restore_token: Option<String>,
+}
+
+/// A PipeWire stream shared through the portal
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub struct PortalStream {
+    pub node_id: u32,
+    pub properties: StreamProperties,
+}
+
+/// Properties the portal reports for a stream
+#[derive(Debug, Clone, Default, PartialEq, Eq, DeserializeDict, zvariant::SerializeDict, Type)]
+#[zvariant(signature = "dict")]
+pub struct StreamProperties {
+    pub position: Option<(i32, i32)>,  // Logical position of a monitor
+    pub size: Option<(i32, i32)>,      // Logical size of the source
+    pub source_type: Option<u32>,
+}
+
+/// A started screen cast
+#[derive(Debug)]
+pub struct ScreenCastSession {
+    pub handle: String,
+    pub streams: Vec<PortalStream>,
+    /// Connection to the PipeWire remote that carries the streams
+    pub pipewire_fd: OwnedFd,
+    pub restore_token: Option<String>,
+}
+
+/// The ScreenCast portal
+///
+/// The D-Bus implementation is [`DbusScreenCastPortal`]; tests substitute
+/// their own.
+#[async_trait::async_trait]
+pub trait ScreenCastPortal: Send + Sync {
+    /// Ask the user for a source and start streaming it
+    async fn start(&self, request: &ScreenCastRequest) -> Result<ScreenCastSession, RecordingError>;
+
+    /// Open another connection to a session's PipeWire remote
+    ///
+    /// Each process reading the streams needs its own connection.
+    async fn open_pipewire_remote(&self, session: &ScreenCastSession) -> Result<OwnedFd, RecordingError>;
+
+    /// End a session, which stops its streams
+    async fn close(&self, session: &ScreenCastSession) -> Result<(), RecordingError>;
+}
+
+#[derive(Debug, DeserializeDict, Type)]
+#[zvariant(signature = "dict")]
+struct CreateSessionResults {
+    session_handle: Option<String>,
+}
+
+#[derive(Debug, DeserializeDict, Type)]
+#[zvariant(signature = "dict")]
+struct EmptyResults {}
+
+#[derive(Debug, DeserializeDict, Type)]
+#[zvariant(signature = "dict")]
+struct StartResults {
+    streams: Option<Vec<PortalStream>>,
+    restore_token: Option<String>,
+}
+
+fn portal_error(context: &str, e: impl std::fmt::Display) -> RecordingError {
+    RecordingError::SystemError(format!("ScreenCast portal {} failed: {}", context, e))
+}
+
+/// ScreenCast portal reached over D-Bus
+pub struct DbusScreenCastPortal {
+    connection: Option<Connection>,
+}
+
+impl DbusScreenCastPortal {
+    /// Portal on the session bus, connected on first use
+    pub fn new() -> Self {
+        Self { connection: None }
+    }
+
+    /// Portal served on an existing connection
+    #[cfg(test)]
+    pub fn with_connection(connection: Connection) -> Self {
+        Self { connection: Some(connection) }
+    }
+
+    async fn connection(&self) -> Result<Connection, RecordingError> {
+        match &self.connection {
+            Some(connection) => Ok(connection.clone()),
+            None => Connection::session().await.map_err(|e| portal_error("connection", e)),
+        }
+    }
+
+    /// Call a portal method that answers through a Request object
+    ///
+    /// Responses are subscribed to before the call, so a portal that answers
+    /// immediately is not missed, and matched to the request handle it returns.
+    async fn request<B, R>(&self, connection: &Connection, method: &str, body: &B) -> Result<R, RecordingError>
+    where
+        B: Serialize + Type,
+        R: for<'de> Deserialize<'de> + Type,
+    {
+        let rule = MatchRule::builder()
+            .msg_type(zbus::message::Type::Signal)
+            .interface(REQUEST_INTERFACE)
+            .and_then(|b| b.member("Response"))
+            .map_err(|e| portal_error(method, e))?
+            .build();
+        let mut responses = MessageStream::for_match_rule(rule, connection, None)
+            .await
+            .map_err(|e| portal_error(method, e))?;
+
+        let reply = connection
+            .call_method(Some(PORTAL_BUS_NAME), PORTAL_PATH, Some(SCREENCAST_INTERFACE), method, body)
+            .await
+            .map_err(|e| portal_error(method, e))?;
+        let handle: OwnedObjectPath = reply.body().deserialize().map_err(|e| portal_error(method, e))?;
+
+        while let Some(message) = responses.next().await {
+            let message = message.map_err(|e| portal_error(method, e))?;
+            if message.header().path() != Some(&handle.as_ref()) {
+                continue;
+            }
+
+            let (code, results): (u32, R) = message.body().deserialize().map_err(|e| portal_error(method, e))?;
+            return match code {
+                0 => Ok(results),
+                1 => Err(RecordingError::PermissionDenied),
+                _ => Err(portal_error(method, "the request was ended")),
+            };
+        }
+
+        Err(portal_error(method, "the connection closed before a response"))
+    }
+}
+
+impl Default for DbusScreenCastPortal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+async fn open_pipewire_remote(connection: &Connection, session_path: &ObjectPath<'_>) -> Result<OwnedFd, RecordingError> {
+    let options: HashMap<&str, Value> = HashMap::new();
+    let reply = connection
+        .call_method(Some(PORTAL_BUS_NAME), PORTAL_PATH, Some(SCREENCAST_INTERFACE), "OpenPipeWireRemote", &(session_path, options))
+        .await
+        .map_err(|e| portal_error("OpenPipeWireRemote", e))?;
+    let fd: zvariant::OwnedFd = reply.body().deserialize().map_err(|e| portal_error("OpenPipeWireRemote", e))?;
+    Ok(fd.into())
+}
+
+fn token() -> String {
+    format!("clipforge_{}", uuid::Uuid::new_v4().simple())
+}
+
+#[async_trait::async_trait]
+impl ScreenCastPortal for DbusScreenCastPortal {
+    async fn start(&self, request: &ScreenCastRequest) -> Result<ScreenCastSession, RecordingError> {
+        let connection = self.connection().await?;
+
+        let session_token = token();
+        let request_token = token();
+        let options: HashMap<&str, Value> = HashMap::from([
+            ("handle_token", Value::from(request_token.as_str())),
+            ("session_handle_token", Value::from(session_token.as_str())),
+        ]);
+        let created: CreateSessionResults = self.request(&connection, "CreateSession", &(options,)).await?;
+        let handle = created.session_handle
+            .ok_or_else(|| portal_error("CreateSession", "no session handle"))?;
+        let session_path = ObjectPath::try_from(handle.as_str()).map_err(|e| portal_error("CreateSession", e))?;
+        info!("ScreenCast portal session created: {}", handle);
+
+        // The session stays open on the portal side until closed, also when
+        // the user cancels the dialog
+        match self.share_streams(&connection, &session_path, request).await {
+            Ok((streams, fd, restore_token)) => Ok(ScreenCastSession {
+                handle,
+                streams,
+                pipewire_fd: fd,
+                restore_token,
+            }),
+            Err(e) => {
+                if let Err(close_error) = close_session(&connection, &session_path).await {
+                    warn!("Failed to close ScreenCast session {}: {}", handle, close_error);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    async fn open_pipewire_remote(&self, session: &ScreenCastSession) -> Result<OwnedFd, RecordingError> {
+        let connection = self.connection().await?;
+        let path = ObjectPath::try_from(session.handle.as_str()).map_err(|e| portal_error("OpenPipeWireRemote", e))?;
+        open_pipewire_remote(&connection, &path).await
+    }
+
+    async fn close(&self, session: &ScreenCastSession) -> Result<(), RecordingError> {
+        let connection = self.connection().await?;
+        let path = ObjectPath::try_from(session.handle.as_str()).map_err(|e| portal_error("Close", e))?;
+
+        if let Err(e) = close_session(&connection, &path).await {
+            warn!("Failed to close ScreenCast session {}: {}", session.handle, e);
+            return Err(e);
+        }
+        info!("ScreenCast portal session closed: {}", session.handle);
+        Ok(())
+    }
+}
+
+impl DbusScreenCastPortal {
+    /// Let the user pick what to share in a created session and start it
+    async fn share_streams(
+        &self,
+        connection: &Connection,
+        session_path: &ObjectPath<'_>,
+        request: &ScreenCastRequest,
+    ) -> Result<(Vec<PortalStream>, OwnedFd, Option<String>), RecordingError> {
+        let request_token = token();
+        let mut options: HashMap<&str, Value> = HashMap::from([
+            ("handle_token", Value::from(request_token.as_str())),
+            ("types", Value::from(request.source_kind as u32)),
+            ("multiple", Value::from(false)),
+            ("cursor_mode", Value::from(request.cursor_mode as u32)),
+            ("persist_mode", Value::from(1u32)),  // Until the app exits
+        ]);
+        if let Some(token) = &request.restore_token {
+            options.insert("restore_token", Value::from(token.as_str()));
+        }
+        let _: EmptyResults = self.request(connection, "SelectSources", &(session_path, options)).await?;
+
+        let request_token = token();
+        let options: HashMap<&str, Value> = HashMap::from([
+            ("handle_token", Value::from(request_token.as_str())),
+        ]);
+        let started: StartResults = self.request(connection, "Start", &(session_path, "", options)).await?;
+        let streams = started.streams.unwrap_or_default();
+        if streams.is_empty() {
+            return Err(portal_error("Start", "no streams were shared"));
+        }
+
+        let fd = open_pipewire_remote(connection, session_path).await?;
+
+        info!("ScreenCast portal started {} stream(s), first node {}", streams.len(), streams[0].node_id);
+        Ok((streams, fd, started.restore_token))
+    }
+}
+
+async fn close_session(connection: &Connection, session_path: &ObjectPath<'_>) -> Result<(), RecordingError> {
+    connection.call_method(Some(PORTAL_BUS_NAME), session_path, Some(SESSION_INTERFACE), "Close", &()).await
+        .map_err(|e| portal_error("Close", e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use zbus::message::Header;
+    use zbus::{interface, ObjectServer};
+
+    /// Stand-in for xdg-desktop-portal, answering every request at once
+    struct MockScreenCast {
+        closed: Arc<AtomicBool>,
+        cancel_selection: bool,
+    }
+
+    struct MockSession {
+        closed: Arc<AtomicBool>,
+    }
+
+    #[derive(zvariant::SerializeDict, Type)]
+    #[zvariant(signature = "dict")]
+    struct MockStartResults {
+        streams: Vec<PortalStream>,
+        restore_token: String,
+    }
+
+    #[interface(name = "org.freedesktop.portal.Session")]
+    impl MockSession {
+        async fn close(&self) {
+            self.closed.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn request_path(options: &HashMap<String, zvariant::OwnedValue>) -> OwnedObjectPath {
+        let token = <&str>::try_from(&options["handle_token"]).unwrap().to_string();
+        OwnedObjectPath::try_from(format!("/org/freedesktop/portal/desktop/request/peer/{}", token)).unwrap()
+    }
+
+    async fn respond<R: Serialize + Type>(connection: &Connection, path: &OwnedObjectPath, code: u32, results: R) {
+        connection
+            .emit_signal(None::<&str>, path, REQUEST_INTERFACE, "Response", &(code, results))
+            .await
+            .unwrap();
+    }
+
+    #[interface(name = "org.freedesktop.portal.ScreenCast")]
+    impl MockScreenCast {
+        async fn create_session(
+            &self,
+            options: HashMap<String, zvariant::OwnedValue>,
+            #[zbus(connection)] connection: &Connection,
+            #[zbus(object_server)] server: &ObjectServer,
+        ) -> OwnedObjectPath {
+            let token = <&str>::try_from(&options["session_handle_token"]).unwrap().to_string();
+            let session = format!("/org/freedesktop/portal/desktop/session/peer/{}", token);
+            server.at(session.as_str(), MockSession { closed: self.closed.clone() }).await.unwrap();
+
+            let path = request_path(&options);
+            let results = HashMap::from([("session_handle", Value::from(session.as_str()))]);
+            respond(connection, &path, 0, results).await;
+            path
+        }
+
+        async fn select_sources(
+            &self,
+            _session_handle: OwnedObjectPath,
+            options: HashMap<String, zvariant::OwnedValue>,
+            #[zbus(connection)] connection: &Connection,
+        ) -> OwnedObjectPath {
+            assert_eq!(u32::try_from(&options["types"]).unwrap(), SourceKind::Monitor as u32);
+            assert_eq!(u32::try_from(&options["cursor_mode"]).unwrap(), CursorMode::Hidden as u32);
+
+            let path = request_path(&options);
+            let code = if self.cancel_selection { 1 } else { 0 };
+            respond(connection, &path, code, HashMap::<&str, Value>::new()).await;
+            path
+        }
+
+        async fn start(
+            &self,
+            _session_handle: OwnedObjectPath,
+            _parent_window: String,
+            options: HashMap<String, zvariant::OwnedValue>,
+            #[zbus(connection)] connection: &Connection,
+            #[zbus(header)] _header: Header<'_>,
+        ) -> OwnedObjectPath {
+            let path = request_path(&options);
+            let stream = PortalStream {
+                node_id: 57,
+                properties: StreamProperties { position: Some((0, 0)), size: Some((1920, 1080)), source_type: Some(1) },
+            };
+            let results = MockStartResults { streams: vec![stream], restore_token: "restore-me".to_string() };
+            respond(connection, &path, 0, results).await;
+            path
+        }
+
+        async fn open_pipe_wire_remote(
+            &self,
+            _session_handle: OwnedObjectPath,
+            _options: HashMap<String, zvariant::OwnedValue>,
+        ) -> zvariant::OwnedFd {
+            let file = std::fs::File::open("/dev/null").unwrap();
+            zvariant::OwnedFd::from(OwnedFd::from(file))
+        }
+    }
+
+    /// Portal client connected to a mock portal over a private D-Bus socket
+    async fn mock_portal(cancel_selection: bool) -> (DbusScreenCastPortal, Arc<AtomicBool>, Connection) {
+        let closed = Arc::new(AtomicBool::new(false));
+        let (client_socket, server_socket) = tokio::net::UnixStream::pair().unwrap();
+        let mock = MockScreenCast { closed: closed.clone(), cancel_selection };
+
+        let server = zbus::connection::Builder::unix_stream(server_socket)
+            .server(zbus::Guid::generate())
+            .unwrap()
+            .p2p()
+            .serve_at(PORTAL_PATH, mock)
+            .unwrap()
+            .build();
+        let client = zbus::connection::Builder::unix_stream(client_socket).p2p().build();
+        let (server, client) = tokio::try_join!(server, client).unwrap();
+
+        (DbusScreenCastPortal::with_connection(client), closed, server)
+    }
+
+    #[tokio::test]
+    async fn test_dbus_portal_session_against_mock_service() {
+        let (portal, closed, _server) = mock_portal(false).await;
+        let request = ScreenCastRequest {
+            source_kind: SourceKind::Monitor,
+            cursor_mode: CursorMode::Hidden,
+            restore_token: None,
+        };
+
+        let session = portal.start(&request).await.unwrap();
+        assert!(session.handle.starts_with("/org/freedesktop/portal/desktop/session/peer/clipforge_"));
+        assert_eq!(session.streams[0].node_id, 57);
+        assert_eq!(session.streams[0].properties.size, Some((1920, 1080)));
+        assert_eq!(session.restore_token.as_deref(), Some("restore-me"));
+        assert!(portal.open_pipewire_remote(&session).await.is_ok());
+
+        portal.close(&session).await.unwrap();
+        assert!(closed.load(Ordering::SeqCst));
+
+        // Cancelling the dialog is reported as a refused permission, and the
+        // session is closed
+        let (portal, closed, _server) = mock_portal(true).await;
+        assert!(matches!(portal.start(&request).await, Err(RecordingError::PermissionDenied)));
+        assert!(closed.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,284 @@
+// Segmented recording output: each stretch between pauses is its own file,
+// joined into the final recording when it stops
+
+use super::journal::{self, RecordingJournal};
+use super::RecordingError;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use log::{info, warn, error};
+
+/// FFmpeg `-movflags` writing MP4 as a series of self-contained fragments
+///
+/// A plain MP4 is unreadable until its index is written at the end; a
+/// fragmented one stays readable up to the last fragment if the recording
+/// process dies.
+pub const FRAGMENTED_MP4_FLAGS: &str = "+frag_keyframe+empty_moov+default_base_moof";
+
+/// Output of a recording that can be paused
+///
+/// Pausing ends the capture process, so every stretch of recording goes to
+/// a segment next to the final file. Segments share their encoding settings
+/// and are joined without re-encoding. A journaled output lists its segments
+/// in a recording journal until it is finished, so they can be recovered if
+/// the app dies first.
+#[derive(Debug, Clone)]
+pub struct SegmentedOutput {
+    final_path: PathBuf,
+    segments: Vec<PathBuf>,
+    journal: Option<(PathBuf, RecordingJournal)>,
+}
+
+impl SegmentedOutput {
+    pub fn new(final_path: PathBuf) -> Self {
+        Self {
+            final_path,
+            segments: Vec::new(),
+            journal: None,
+        }
+    }
+
+    /// Output kept in the app's recording journal
+    pub fn journaled(final_path: PathBuf) -> Self {
+        match journal::journal_dir() {
+            Some(dir) => Self::with_journal(final_path, dir),
+            None => {
+                warn!("No data directory for recording journals; {} can't be recovered after a crash", final_path.display());
+                Self::new(final_path)
+            }
+        }
+    }
+
+    /// Output kept in a recording journal in `journal_dir`
+    pub fn with_journal(final_path: PathBuf, journal_dir: PathBuf) -> Self {
+        let journal = RecordingJournal::new(final_path.clone());
+        Self {
+            final_path,
+            segments: Vec::new(),
+            journal: Some((journal_dir, journal)),
+        }
+    }
+
+    /// Output of a recording that never finished, from its journal
+    pub fn from_journal(journal_dir: PathBuf, journal: RecordingJournal) -> Self {
+        Self {
+            final_path: journal.output_path.clone(),
+            segments: journal.segments.clone(),
+            journal: Some((journal_dir, journal)),
+        }
+    }
+
+    pub fn final_path(&self) -> &Path {
+        &self.final_path
+    }
+
+    pub fn segments(&self) -> &[PathBuf] {
+        &self.segments
+    }
+
+    /// Path for the next segment to record
+    pub fn next_segment(&mut self) -> PathBuf {
+        let path = segment_path(&self.final_path, self.segments.len());
+        self.segments.push(path.clone());
+
+        if let Some((dir, journal)) = &mut self.journal {
+            journal.segments = self.segments.clone();
+            if let Err(e) = journal.save(dir) {
+                warn!("Failed to update recording journal for {}: {}", self.final_path.display(), e);
+            }
+        }
+        path
+    }
+
+    /// Bytes written to the segments so far
+    pub fn written_bytes(&self) -> u64 {
+        total_size(&self.segments)
+    }
+
+    fn recorded_segments(&self) -> Vec<&PathBuf> {
+        self.segments.iter()
+            .filter(|path| std::fs::metadata(path).map(|m| m.len() > 0).unwrap_or(false))
+            .collect()
+    }
+
+    /// Join the recorded segments into the final file
+    ///
+    /// Segments that were never written (a capture that failed to start) are
+    /// skipped. If joining fails the segments and journal are kept so nothing
+    /// recorded is lost.
+    pub fn finish(self, ffmpeg_path: &Path) -> Result<PathBuf, RecordingError> {
+        match self.recorded_segments().as_slice() {
+            [] => {
+                // Nothing to recover either
+                self.remove_segments();
+                return Err(RecordingError::RecordingFailed(format!(
+                    "Nothing was recorded to {}", self.final_path.display()
+                )));
+            }
+            [only] => {
+                std::fs::rename(only, &self.final_path)?;
+            }
+            recorded => self.join(ffmpeg_path, recorded)?,
+        }
+
+        self.remove_segments();
+        Ok(self.final_path)
+    }
+
+    /// Remux the segments of a recording that never finished into the final file
+    ///
+    /// Unlike `finish`, a single segment is remuxed too: the process writing
+    /// it was cut off, and may have left a partial fragment at its end.
+    pub fn recover(self, ffmpeg_path: &Path) -> Result<PathBuf, RecordingError> {
+        let recorded = self.recorded_segments();
+        if recorded.is_empty() {
+            return Err(RecordingError::RecordingFailed(format!(
+                "Nothing was recorded to {}", self.final_path.display()
+            )));
+        }
+
+        info!("Recovering recording {} from {} segment(s)", self.final_path.display(), recorded.len());
+        self.join(ffmpeg_path, &recorded)?;
+        self.remove_segments();
+        Ok(self.final_path)
+    }
+
+    /// Delete everything written for the recording
+    pub fn discard(self) {
+        info!("Discarding recording {}", self.final_path.display());
+        self.remove_segments();
+    }
+
+    fn join(&self, ffmpeg_path: &Path, recorded: &[&PathBuf]) -> Result<(), RecordingError> {
+        let list_path = self.final_path.with_extension("segments.txt");
+        std::fs::write(&list_path, concat_list(recorded))?;
+
+        info!("Joining {} recording segments into {}", recorded.len(), self.final_path.display());
+        let output = Command::new(ffmpeg_path)
+            .args(["-hide_banner", "-loglevel", "error", "-f", "concat", "-safe", "0", "-i"])
+            .arg(&list_path)
+            .args(["-c", "copy", "-movflags", "+faststart", "-y"])
+            .arg(&self.final_path)
+            .output();
+
+        let _ = std::fs::remove_file(&list_path);
+        let output = output.map_err(|e| {
+            RecordingError::RecordingFailed(format!("Failed to run FFmpeg to join segments: {}", e))
+        })?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            error!("Joining recording segments failed: {}", stderr);
+            return Err(RecordingError::RecordingFailed(format!(
+                "Failed to join recording segments (kept next to {}): {}",
+                self.final_path.display(),
+                stderr.lines().last().unwrap_or("FFmpeg exited with an error")
+            )));
+        }
+        Ok(())
+    }
+
+    /// Remove the segment files and the journal listing them
+    fn remove_segments(&self) {
+        for segment in &self.segments {
+            if segment.exists() {
+                if let Err(e) = std::fs::remove_file(segment) {
+                    warn!("Failed to remove recording segment {}: {}", segment.display(), e);
+                }
+            }
+        }
+        if let Some((dir, journal)) = &self.journal {
+            journal.remove(dir);
+        }
+    }
+}
+
+/// `recording.part1.mp4`, `recording.part2.mp4`, ... next to `recording.mp4`
+pub fn segment_path(final_path: &Path, index: usize) -> PathBuf {
+    let stem = final_path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let name = match final_path.extension() {
+        Some(ext) => format!("{}.part{}.{}", stem, index + 1, ext.to_string_lossy()),
+        None => format!("{}.part{}", stem, index + 1),
+    };
+    final_path.with_file_name(name)
+}
+
+/// Combined size of the files that exist
+pub fn total_size(paths: &[PathBuf]) -> u64 {
+    paths.iter()
+        .filter_map(|path| std::fs::metadata(path).ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Input list for FFmpeg's concat demuxer
+pub fn concat_list(segments: &[&PathBuf]) -> String {
+    segments.iter()
+        .map(|path| format!("file '{}'\n", path.to_string_lossy().replace('\'', "'\\''")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segment_paths_and_concat_list() {
+        let mut output = SegmentedOutput::new(PathBuf::from("/videos/it's here.mp4"));
+        assert_eq!(output.next_segment(), PathBuf::from("/videos/it's here.part1.mp4"));
+        assert_eq!(output.next_segment(), PathBuf::from("/videos/it's here.part2.mp4"));
+
+        let segments: Vec<&PathBuf> = output.segments().iter().collect();
+        assert_eq!(
+            concat_list(&segments),
+            "file '/videos/it'\\''s here.part1.mp4'\nfile '/videos/it'\\''s here.part2.mp4'\n"
+        );
+    }
+
+    #[test]
+    fn test_single_segment_becomes_the_recording() {
+        let dir = std::env::temp_dir().join(format!("clipforge-segments-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut output = SegmentedOutput::new(dir.join("screen.mp4"));
+        std::fs::write(output.next_segment(), b"video").unwrap();
+        output.next_segment();  // Capture never started
+
+        let path = output.finish(Path::new("ffmpeg")).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"video");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        assert!(SegmentedOutput::new(dir.join("empty.mp4")).finish(Path::new("ffmpeg")).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_journal_follows_segments() {
+        let dir = std::env::temp_dir().join(format!("clipforge-segments-{}", uuid::Uuid::new_v4()));
+        let journal_dir = dir.join("journal");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut output = SegmentedOutput::with_journal(dir.join("screen.mp4"), journal_dir.clone());
+        let first = output.next_segment();
+        let second = output.next_segment();
+        std::fs::write(&first, b"video").unwrap();
+
+        let id = output.journal.as_ref().unwrap().1.id.clone();
+        let journal = RecordingJournal::load(&journal_dir, &id).unwrap();
+        assert_eq!(journal.segments, vec![first.clone(), second]);
+        assert_eq!(journal.output_path, dir.join("screen.mp4"));
+
+        // Finishing removes the journal along with the segments
+        output.finish(Path::new("ffmpeg")).unwrap();
+        assert!(RecordingJournal::load(&journal_dir, &id).is_err());
+
+        // Discarding a recovered recording does the same
+        let mut journal = RecordingJournal::new(dir.join("crashed.mp4"));
+        journal.segments.push(first.clone());
+        journal.save(&journal_dir).unwrap();
+        std::fs::write(&first, b"video").unwrap();
+        SegmentedOutput::from_journal(journal_dir.clone(), journal.clone()).discard();
+        assert!(!first.exists());
+        assert!(RecordingJournal::load(&journal_dir, &journal.id).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,6 +1,7 @@
 // Recording state management
 
 use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
 
 /// Current state of the recording system
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -12,8 +13,7 @@ pub enum RecordingState {
     /// Recording is active
     Recording,
 
-    /// Recording is paused (future feature)
-    #[allow(dead_code)]
+    /// Recording is paused; capture resumes into a new segment
     Paused,
 
     /// Recording is being finalized
@@ -23,6 +23,17 @@ pub enum RecordingState {
     Error,
 }
 
+/// Something that moves a recording from one state to another
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordingTransition {
+    Start,
+    Pause,
+    Resume,
+    Stop,
+    Finish,
+    Fail,
+}
+
 impl Default for RecordingState {
     fn default() -> Self {
         RecordingState::Idle
@@ -30,8 +41,9 @@ impl Default for RecordingState {
 }
 
 impl RecordingState {
-    pub fn is_recording(&self) -> bool {
-        matches!(self, RecordingState::Recording)
+    /// Recording or paused: a recording exists that has not been stopped
+    pub fn is_active(&self) -> bool {
+        matches!(self, RecordingState::Recording | RecordingState::Paused)
     }
 
     pub fn is_idle(&self) -> bool {
@@ -39,11 +51,63 @@ impl RecordingState {
     }
 
     pub fn can_start(&self) -> bool {
-        matches!(self, RecordingState::Idle)
+        self.transition(RecordingTransition::Start).is_some()
+    }
+
+    pub fn can_resume(&self) -> bool {
+        self.transition(RecordingTransition::Resume).is_some()
     }
 
-    pub fn can_stop(&self) -> bool {
-        matches!(self, RecordingState::Recording)
+    /// State after a transition, or None when it isn't allowed from this state
+    pub fn transition(self, transition: RecordingTransition) -> Option<RecordingState> {
+        use RecordingState::*;
+        use RecordingTransition as T;
+
+        match (self, transition) {
+            // A failed recording doesn't keep the next one from starting
+            (Idle | Error, T::Start) => Some(Recording),
+            (Recording, T::Pause) => Some(Paused),
+            (Paused, T::Resume) => Some(Recording),
+            (Recording | Paused, T::Stop) => Some(Finalizing),
+            (Finalizing, T::Finish) => Some(Idle),
+            (Recording | Paused | Finalizing, T::Fail) => Some(Error),
+            _ => None,
+        }
+    }
+}
+
+/// Recorded time of a recording, not counting pauses
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RecordingClock {
+    recorded: Duration,              // Time recorded before the current stretch
+    running_since: Option<Instant>,  // Start of the current stretch, None while paused
+}
+
+impl RecordingClock {
+    /// A clock that starts running now
+    pub fn started() -> Self {
+        Self {
+            recorded: Duration::ZERO,
+            running_since: Some(Instant::now()),
+        }
+    }
+
+    pub fn pause(&mut self) {
+        if let Some(since) = self.running_since.take() {
+            self.recorded += since.elapsed();
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if self.running_since.is_none() {
+            self.running_since = Some(Instant::now());
+        }
+    }
+
+    /// Seconds recorded so far
+    pub fn elapsed(&self) -> f64 {
+        let running = self.running_since.map(|since| since.elapsed()).unwrap_or_default();
+        (self.recorded + running).as_secs_f64()
     }
 }
 
@@ -61,14 +125,61 @@ mod tests {
     fn test_recording_state_checks() {
         let idle = RecordingState::Idle;
         assert!(idle.is_idle());
-        assert!(!idle.is_recording());
+        assert!(!idle.is_active());
         assert!(idle.can_start());
-        assert!(!idle.can_stop());
+        assert_eq!(idle.transition(RecordingTransition::Stop), None);
 
         let recording = RecordingState::Recording;
         assert!(!recording.is_idle());
-        assert!(recording.is_recording());
+        assert!(recording.is_active());
         assert!(!recording.can_start());
-        assert!(recording.can_stop());
+
+        // Stopping finalizes, which finishes or fails
+        let finalizing = recording.transition(RecordingTransition::Stop).unwrap();
+        assert_eq!(finalizing, RecordingState::Finalizing);
+        assert!(!finalizing.is_active() && !finalizing.can_start());
+        assert_eq!(finalizing.transition(RecordingTransition::Fail), Some(RecordingState::Error));
+        assert!(RecordingState::Error.can_start());
+    }
+
+    #[test]
+    fn test_pause_and_resume_transitions() {
+        use RecordingTransition as T;
+
+        let paused = RecordingState::Recording.transition(T::Pause).unwrap();
+        assert_eq!(paused, RecordingState::Paused);
+        assert!(paused.is_active());
+        assert_eq!(paused.transition(T::Pause), None);
+        assert!(!paused.can_start());
+        assert!(paused.can_resume());
+
+        assert_eq!(paused.transition(T::Resume), Some(RecordingState::Recording));
+        assert_eq!(paused.transition(T::Stop), Some(RecordingState::Finalizing));
+        assert_eq!(RecordingState::Finalizing.transition(T::Finish), Some(RecordingState::Idle));
+
+        assert_eq!(RecordingState::Idle.transition(T::Pause), None);
+        assert_eq!(RecordingState::Idle.transition(T::Resume), None);
+        assert_eq!(RecordingState::Recording.transition(T::Resume), None);
+        assert_eq!(RecordingState::Finalizing.transition(T::Pause), None);
+        assert_eq!(RecordingState::Paused.transition(T::Fail), Some(RecordingState::Error));
+    }
+
+    #[test]
+    fn test_clock_excludes_paused_time() {
+        let pause = Duration::from_millis(30);
+        let mut clock = RecordingClock::started();
+        std::thread::sleep(pause);
+        clock.pause();
+        let at_pause = clock.elapsed();
+
+        std::thread::sleep(pause);
+        assert_eq!(clock.elapsed(), at_pause);
+
+        clock.resume();
+        clock.resume();  // Resuming twice doesn't restart the stretch
+        std::thread::sleep(pause);
+        let total = clock.elapsed();
+        assert!(total >= at_pause + pause.as_secs_f64());
+        assert!(total < at_pause + 3.0 * pause.as_secs_f64() + 0.5);
     }
 }
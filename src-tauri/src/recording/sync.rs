@@ -0,0 +1,220 @@
+// Aligning the files of a multi-source recording
+//
+// Screen and webcam are captured by separate processes that never start at
+// exactly the same moment. Each records when it started; the offsets between
+// them place the webcam against the screen recording afterwards.
+
+use crate::ffmpeg_utils;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::{info, warn};
+
+/// When a capture process started recording
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct StreamStart {
+    /// Unix time the process was spawned
+    pub spawned_at: f64,
+    /// Start time FFmpeg reported for its first input
+    ///
+    /// x11grab, PulseAudio and v4l2 (with `-timestamps mono2abs`) all report
+    /// wall clock time; avfoundation reports host time for every device.
+    pub reported: Option<f64>,
+}
+
+impl StreamStart {
+    /// A process being spawned now
+    pub fn now() -> Self {
+        let spawned_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or_default();
+        Self { spawned_at, reported: None }
+    }
+
+    /// Take the reported start from a finished FFmpeg process's stderr
+    pub fn report(&mut self, stderr: &str) {
+        self.reported = parse_input_start(stderr);
+    }
+}
+
+/// Start times of the processes recording one segment
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SegmentStarts {
+    pub main: StreamStart,
+    pub webcam: Option<StreamStart>,
+}
+
+/// How far `other` started after `main`, in seconds
+///
+/// Reported starts are only comparable with each other, so spawn times are
+/// used unless both processes reported one.
+pub fn stream_offset(main: &StreamStart, other: &StreamStart) -> f64 {
+    match (main.reported, other.reported) {
+        (Some(main), Some(other)) => other - main,
+        _ => other.spawned_at - main.spawned_at,
+    }
+}
+
+/// Start time of the first input in FFmpeg's input dump
+///
+/// ```text
+/// Input #0, x11grab, from ':0.0+0,0':
+///   Duration: N/A, start: 1718031206.543512, bitrate: 1990656 kb/s
+/// ```
+pub fn parse_input_start(stderr: &str) -> Option<f64> {
+    let mut in_first_input = false;
+    for line in stderr.lines() {
+        let line = line.trim();
+        if line.starts_with("Input #") {
+            in_first_input = line.starts_with("Input #0,");
+        } else if in_first_input && line.starts_with("Duration:") {
+            return line.split(',')
+                .find_map(|field| field.trim().strip_prefix("start:"))
+                .and_then(|start| start.trim().parse().ok());
+        }
+    }
+    None
+}
+
+/// Duration of a media file in seconds, from FFprobe
+pub fn probe_duration(path: &Path) -> Option<f64> {
+    let ffprobe_path = ffmpeg_utils::find_ffprobe_path().ok()?;
+    let output = Command::new(ffprobe_path)
+        .args(["-v", "error", "-show_entries", "format=duration", "-of", "default=noprint_wrappers=1:nokey=1"])
+        .arg(path)
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        warn!("FFprobe could not read the duration of {}", path.display());
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+/// A stretch of a recorded file, placed in the main recording's time
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StreamSpan {
+    pub position: f64,      // Seconds into the main recording
+    pub source_start: f64,  // Seconds into this file
+    pub duration: f64,
+}
+
+/// A file recorded alongside the main one
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AlignedStream {
+    pub path: PathBuf,
+    /// Where each recorded segment falls in the main recording
+    ///
+    /// Empty when segment durations couldn't be measured; `offset` is all
+    /// there is to go on then.
+    pub spans: Vec<StreamSpan>,
+    /// How far the first segment started after the main recording
+    pub offset: f64,
+}
+
+/// Place a stream's segments against the main recording's
+///
+/// Segments are joined back to back in both files, so segment `i` of the
+/// stream starts `offsets[i]` after segment `i` of the main file. A stream
+/// that started early is trimmed, and nothing runs past its main segment.
+pub fn align_segments(main_durations: &[f64], durations: &[f64], offsets: &[f64]) -> Vec<StreamSpan> {
+    let mut spans = Vec::new();
+    let (mut main_start, mut source_start) = (0.0, 0.0);
+
+    for ((&main_duration, &duration), &offset) in main_durations.iter().zip(durations).zip(offsets) {
+        let early = (-offset).max(0.0);
+        let position = main_start + offset.max(0.0);
+        let length = (duration - early).min(main_start + main_duration - position);
+        if length > 0.0 {
+            spans.push(StreamSpan {
+                position,
+                source_start: source_start + early,
+                duration: length,
+            });
+        }
+        main_start += main_duration;
+        source_start += duration;
+    }
+    spans
+}
+
+/// Align a stream recorded next to the main file, before their segments are joined
+///
+/// Segments that were never written count as empty.
+pub fn align_stream(
+    path: PathBuf,
+    main_segments: &[PathBuf],
+    segments: &[PathBuf],
+    starts: &[SegmentStarts],
+) -> AlignedStream {
+    let offsets: Vec<f64> = starts.iter()
+        .map(|s| s.webcam.map(|webcam| stream_offset(&s.main, &webcam)).unwrap_or(0.0))
+        .collect();
+    let durations = |paths: &[PathBuf]| -> Option<Vec<f64>> {
+        paths.iter()
+            .map(|path| match std::fs::metadata(path) {
+                Ok(metadata) if metadata.len() > 0 => probe_duration(path),
+                _ => Some(0.0),
+            })
+            .collect()
+    };
+
+    let spans = match (durations(main_segments), durations(segments)) {
+        (Some(main_durations), Some(durations)) => align_segments(&main_durations, &durations, &offsets),
+        _ => {
+            warn!("Could not measure the segments of {}; aligning by its first offset", path.display());
+            Vec::new()
+        }
+    };
+
+    let offset = offsets.first().copied().unwrap_or(0.0);
+    info!("Aligned {} in {} span(s), starting {:.3}s after the main recording", path.display(), spans.len(), offset);
+    AlignedStream { path, spans, offset }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_input_start() {
+        let stderr = "\
+[x11grab @ 0x55d0] Stream #0: not enough frames to estimate rate
+Input #0, x11grab, from ':0.0+0,0':
+  Duration: N/A, start: 1718031206.543512, bitrate: 1990656 kb/s
+  Stream #0:0: Video: rawvideo (BGR[0] / 0x524742), bgr0, 1920x1080, 30 fps
+Input #1, pulse, from 'default':
+  Duration: N/A, start: 1718031206.601000, bitrate: 1536 kb/s
+";
+        assert_eq!(parse_input_start(stderr), Some(1718031206.543512));
+        assert_eq!(parse_input_start("Input #0, v4l2, from '/dev/video0':\n  Duration: N/A, bitrate: N/A\n"), None);
+        assert_eq!(parse_input_start("/dev/video0: No such file or directory\n"), None);
+
+        let screen = StreamStart { spawned_at: 100.0, reported: Some(1718031206.5) };
+        let webcam = StreamStart { spawned_at: 100.1, reported: Some(1718031206.75) };
+        assert_eq!(stream_offset(&screen, &webcam), 0.25);
+
+        // Without both reports the spawn times are compared
+        let webcam = StreamStart { reported: None, ..webcam };
+        assert!((stream_offset(&screen, &webcam) - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_align_segments() {
+        // Webcam 0.25s late in the first segment, 0.5s early in the second
+        let spans = align_segments(&[10.0, 5.0], &[9.75, 6.0], &[0.25, -0.5]);
+        assert_eq!(spans, vec![
+            StreamSpan { position: 0.25, source_start: 0.0, duration: 9.75 },
+            StreamSpan { position: 10.0, source_start: 10.25, duration: 5.0 },
+        ]);
+
+        // A segment the webcam recorded nothing of is skipped
+        let spans = align_segments(&[4.0, 4.0], &[0.0, 4.0], &[0.0, 0.1]);
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].position, 4.1);
+        assert!((spans[0].duration - 3.9).abs() < 1e-9);
+    }
+}
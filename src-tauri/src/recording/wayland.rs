@@ -0,0 +1,605 @@
+// Wayland screen recording through the ScreenCast portal and GStreamer
+
+use super::capture::{create_parent_dir, stop_process, Capture, CaptureState, RecordingProcess};
+use super::linux::{crf, ffmpeg_args, pulse_audio_devices, pulse_sources, v4l2_input, webcam_device, webcam_devices};
+use super::portal::{CursorMode, ScreenCastPortal, ScreenCastRequest, ScreenCastSession, SourceKind};
+use super::sync::{SegmentStarts, StreamStart};
+use super::{AudioDevice, CropRegion, RecordingConfig, RecordingError, RecordingMode, RecordingOutput, RecordingSource, RecordingState, ScreenRecorder, SourceTypeFilter};
+use crate::ffmpeg_utils;
+use std::os::fd::{AsRawFd, RawFd};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use log::{info, warn};
+
+/// Source ids standing for "whatever the user picks in the portal dialog"
+const PORTAL_SCREEN_ID: &str = "portal-screen";
+const PORTAL_WINDOW_ID: &str = "portal-window";
+
+/// File descriptor the PipeWire remote is handed to GStreamer on
+const PIPEWIRE_FD: RawFd = 3;
+
+/// Screen recorder for Wayland sessions
+///
+/// Wayland compositors only share the screen through xdg-desktop-portal: the
+/// user picks a monitor or window, and the portal hands back a PipeWire
+/// stream, which `gst-launch-1.0` records. Audio and webcams are captured the
+/// same way as on X11.
+pub struct WaylandRecorder {
+    portal: Box<dyn ScreenCastPortal>,
+    /// Captures keep their portal session open while paused; None when only the webcam is recorded
+    captures: CaptureState<Option<ScreenCastSession>>,
+    restore_token: Option<String>,      // Lets the next session skip the portal dialog
+    ffmpeg_path: PathBuf,
+    gst_launch_path: PathBuf,
+}
+
+impl WaylandRecorder {
+    pub fn new(portal: Box<dyn ScreenCastPortal>) -> Self {
+        let ffmpeg_path = ffmpeg_utils::find_ffmpeg_path()
+            .unwrap_or_else(|e| {
+                warn!("Failed to find FFmpeg: {}. Webcam recording will be unavailable.", e);
+                PathBuf::from("ffmpeg")
+            });
+        let gst_launch_path = which::which("gst-launch-1.0")
+            .unwrap_or_else(|_| {
+                warn!("gst-launch-1.0 not found. Wayland screen recording will be unavailable.");
+                PathBuf::from("gst-launch-1.0")
+            });
+
+        info!("WaylandRecorder initialized with GStreamer at: {:?}", gst_launch_path);
+
+        Self {
+            portal,
+            captures: CaptureState::new(),
+            restore_token: None,
+            ffmpeg_path,
+            gst_launch_path,
+        }
+    }
+
+    fn spawn_ffmpeg(&self, args: &[String], label: &str) -> Result<RecordingProcess, RecordingError> {
+        info!("Spawning {} recording process", label);
+
+        RecordingProcess::spawn(Command::new(&self.ffmpeg_path)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null()))
+            .map_err(|e| RecordingError::RecordingFailed(format!("Failed to start {} recording: {}", label, e)))
+    }
+
+    /// Start GStreamer with the PipeWire remote as its descriptor 3
+    fn spawn_gstreamer(&self, args: &[String], pipewire_fd: RawFd) -> Result<RecordingProcess, RecordingError> {
+        info!("Spawning screen recording pipeline: {}", args.join(" "));
+
+        let mut cmd = Command::new(&self.gst_launch_path);
+        cmd.args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null());
+
+        // SAFETY: only async-signal-safe calls between fork and exec
+        unsafe {
+            cmd.pre_exec(move || {
+                let result = if pipewire_fd == PIPEWIRE_FD {
+                    // dup2 onto itself would keep close-on-exec set
+                    libc::fcntl(PIPEWIRE_FD, libc::F_SETFD, 0)
+                } else {
+                    libc::dup2(pipewire_fd, PIPEWIRE_FD)
+                };
+                if result == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+
+        RecordingProcess::spawn(&mut cmd)
+            .map_err(|e| RecordingError::RecordingFailed(format!("Failed to start GStreamer: {}", e)))
+    }
+
+    /// Start a portal session for a screen or window source
+    async fn start_session(
+        &self,
+        source: &RecordingSource,
+        config: &RecordingConfig,
+        restore_token: Option<String>,
+    ) -> Result<ScreenCastSession, RecordingError> {
+        let source_kind = match source {
+            RecordingSource::Screen { .. } => SourceKind::Monitor,
+            RecordingSource::Window { .. } => SourceKind::Window,
+            RecordingSource::Webcam { .. } => {
+                return Err(RecordingError::InvalidConfig(format!("{} is not a screen or window", source.name())));
+            }
+        };
+        let request = ScreenCastRequest {
+            source_kind,
+            cursor_mode: if config.show_cursor { CursorMode::Embedded } else { CursorMode::Hidden },
+            restore_token,
+        };
+
+        self.portal.start(&request).await
+    }
+
+    /// Start the processes recording the next segment of a capture
+    async fn spawn_segment(&self, capture: &mut Capture<Option<ScreenCastSession>>) -> Result<(), RecordingError> {
+        let config = &capture.config;
+        let system_audio = capture.system_audio_output.as_mut().map(|output| output.next_segment());
+        let mut starts = SegmentStarts { main: StreamStart::now(), webcam: None };
+        let process = match (&capture.source, &capture.webcam_device) {
+            (Some(session), _) => {
+                let stream = &session.streams[0];
+                let size = stream.properties.size
+                    .map(|(width, height)| (width.max(0) as u32, height.max(0) as u32));
+                let args = gst_pipeline_args(stream.node_id, size, config, &capture.output.next_segment(), system_audio.as_deref())?;
+                self.spawn_gstreamer(&args, session.pipewire_fd.as_raw_fd())?
+            }
+            (None, Some(device)) => {
+                let audio = pulse_sources(&config.audio_input, config.audio_device_id.as_deref());
+                let input = v4l2_input(device, config.fps);
+                let args = ffmpeg_args(&input, &audio, config.quality, &capture.output.next_segment(), system_audio.as_deref());
+                self.spawn_ffmpeg(&args, "webcam")?
+            }
+            (None, None) => return Err(RecordingError::InvalidConfig("Nothing to record".to_string())),
+        };
+
+        // Audio goes with the screen; the webcam file is video only
+        let mut webcam_process = None;
+        if let (Some(webcam_output), Some(_), Some(device)) = (capture.webcam_output.as_mut(), &capture.source, &capture.webcam_device) {
+            let input = v4l2_input(device, config.fps);
+            starts.webcam = Some(StreamStart::now());
+            match self.spawn_ffmpeg(&ffmpeg_args(&input, &[], config.quality, &webcam_output.next_segment(), None), "webcam") {
+                Ok(child) => webcam_process = Some(child),
+                Err(e) => {
+                    stop_process(process, "Screen").await;
+                    return Err(e);
+                }
+            }
+        }
+
+        capture.segment_started(process, webcam_process, starts).await
+    }
+
+    async fn close_session(&self, session: Option<ScreenCastSession>) {
+        if let Some(session) = session {
+            if let Err(e) = self.portal.close(&session).await {
+                warn!("Screen cast session was not closed: {}", e);
+            }
+        }
+    }
+}
+
+/// `gst-launch-1.0` arguments recording a PipeWire node to a file
+///
+/// `stream_size` is the size the portal reported, needed to crop. Portals
+/// report logical sizes, so crop regions are in logical pixels as well. As
+/// with [`ffmpeg_args`], system audio recorded with the microphone goes to
+/// `system_audio_path`.
+pub fn gst_pipeline_args(
+    node_id: u32,
+    stream_size: Option<(u32, u32)>,
+    config: &RecordingConfig,
+    output_path: &Path,
+    system_audio_path: Option<&Path>,
+) -> Result<Vec<String>, RecordingError> {
+    let mut pipeline: Vec<String> = vec![
+        "pipewiresrc".to_string(),
+        format!("fd={}", PIPEWIRE_FD),
+        format!("path={}", node_id),
+        "do-timestamp=true".to_string(),
+        "keepalive-time=1000".to_string(),
+        "!".to_string(),
+        "videoconvert".to_string(),
+        "!".to_string(),
+    ];
+
+    if let Some(crop) = &config.crop_region {
+        let (width, height) = stream_size.ok_or_else(|| RecordingError::InvalidConfig(
+            "The portal did not report the stream size needed to crop".to_string()
+        ))?;
+        pipeline.extend(videocrop(width, height, crop)?);
+        pipeline.push("!".to_string());
+    }
+
+    pipeline.extend([
+        "videorate".to_string(),
+        "!".to_string(),
+        format!("video/x-raw,format=I420,framerate={}/1", config.fps),
+        "!".to_string(),
+        "x264enc".to_string(),
+        "speed-preset=ultrafast".to_string(),
+        "pass=qual".to_string(),
+        format!("quantizer={}", crf(config.quality)),
+        "!".to_string(),
+        "h264parse".to_string(),
+        "!".to_string(),
+        "queue".to_string(),
+        "!".to_string(),
+        "mp4mux".to_string(),
+        "name=mux".to_string(),
+        // Fragmented so a crash leaves a readable file
+        "fragment-duration=1000".to_string(),
+        "!".to_string(),
+        "filesink".to_string(),
+        format!("location={}", output_path.to_string_lossy()),
+    ]);
+
+    let sources = pulse_sources(&config.audio_input, config.audio_device_id.as_deref());
+    let pulsesrc = |source: &str| -> Vec<String> {
+        // pulsesrc records the default source when no device is given
+        let mut element = vec!["pulsesrc".to_string()];
+        if source != "default" {
+            element.push(format!("device={}", source));
+        }
+        element.extend(["!", "audioconvert", "!", "audioresample"].iter().map(|s| s.to_string()));
+        element
+    };
+    let encoder = || -> Vec<String> {
+        ["!", "audioconvert", "!", "avenc_aac", "bitrate=128000", "!", "queue", "!"]
+            .iter().map(|s| s.to_string()).collect()
+    };
+
+    if let Some(source) = sources.first() {
+        pipeline.extend(pulsesrc(source));
+        pipeline.extend(encoder());
+        pipeline.push("mux.".to_string());
+    }
+
+    if let ([_, system, ..], Some(path)) = (sources.as_slice(), system_audio_path) {
+        pipeline.extend(pulsesrc(system));
+        pipeline.extend(encoder());
+        pipeline.extend([
+            "mp4mux".to_string(),
+            "fragment-duration=1000".to_string(),
+            "!".to_string(),
+            "filesink".to_string(),
+            format!("location={}", path.to_string_lossy()),
+        ]);
+    }
+
+    // -e finalizes the file on SIGINT
+    let mut args = vec!["-e".to_string(), "-q".to_string()];
+    args.extend(pipeline);
+    Ok(args)
+}
+
+/// `videocrop` element cutting a crop region out of a stream
+fn videocrop(width: u32, height: u32, crop: &CropRegion) -> Result<Vec<String>, RecordingError> {
+    if crop.width == 0 || crop.height == 0 || crop.x + crop.width > width || crop.y + crop.height > height {
+        return Err(RecordingError::InvalidConfig(format!(
+            "Crop region {}x{}+{}+{} is outside the {}x{} stream",
+            crop.width, crop.height, crop.x, crop.y, width, height
+        )));
+    }
+
+    // Keep the cropped size even, as I420 requires
+    let (crop_width, crop_height) = (crop.width & !1, crop.height & !1);
+    Ok(vec![
+        "videocrop".to_string(),
+        format!("left={}", crop.x),
+        format!("top={}", crop.y),
+        format!("right={}", width - crop.x - crop_width),
+        format!("bottom={}", height - crop.y - crop_height),
+    ])
+}
+
+#[async_trait::async_trait]
+impl ScreenRecorder for WaylandRecorder {
+    async fn list_sources(&self, filter: SourceTypeFilter) -> Result<Vec<RecordingSource>, RecordingError> {
+        info!("Listing Wayland recording sources (filter: {:?})", filter);
+
+        // Screens and windows can't be enumerated on Wayland; the portal asks when recording starts
+        let mut sources = Vec::new();
+
+        if matches!(filter, SourceTypeFilter::Screen | SourceTypeFilter::All) {
+            sources.push(RecordingSource::Screen {
+                id: PORTAL_SCREEN_ID.to_string(),
+                name: "Screen (chosen when recording starts)".to_string(),
+                width: 0,
+                height: 0,
+                preview_path: None,
+            });
+        }
+
+        if matches!(filter, SourceTypeFilter::Window | SourceTypeFilter::All) {
+            sources.push(RecordingSource::Window {
+                id: PORTAL_WINDOW_ID.to_string(),
+                name: "Window (chosen when recording starts)".to_string(),
+                app_name: String::new(),
+                preview_path: None,
+            });
+        }
+
+        if matches!(filter, SourceTypeFilter::Webcam | SourceTypeFilter::All) {
+            for (device, name) in webcam_devices() {
+                sources.push(RecordingSource::Webcam {
+                    id: device,
+                    name,
+                    preview_path: None,
+                });
+            }
+        }
+
+        info!("Found {} recording sources (filter: {:?})", sources.len(), filter);
+        Ok(sources)
+    }
+
+    async fn list_audio_devices(&self) -> Result<Vec<AudioDevice>, RecordingError> {
+        pulse_audio_devices()
+    }
+
+    async fn check_permissions(&self) -> Result<bool, RecordingError> {
+        // The portal asks the user for each session
+        Ok(true)
+    }
+
+    async fn request_permissions(&self) -> Result<bool, RecordingError> {
+        Ok(true)
+    }
+
+    async fn start_recording(
+        &mut self,
+        source: &RecordingSource,
+        config: RecordingConfig,
+    ) -> Result<(), RecordingError> {
+        self.captures.check_can_start()?;
+
+        info!("Starting recording for source: {}", source.name());
+
+        if config.output_path.to_str().is_none() {
+            return Err(RecordingError::InvalidConfig("Invalid output path".to_string()));
+        }
+        if config.fps == 0 {
+            return Err(RecordingError::InvalidConfig("Frame rate must be positive".to_string()));
+        }
+        create_parent_dir(&config.output_path)?;
+
+        // The portal dialog is awaited without holding the state lock
+        let (session, webcam_device, webcam_path) = match config.recording_mode {
+            RecordingMode::ScreenOnly => {
+                info!("Starting screen-only recording");
+                (Some(self.start_session(source, &config, self.restore_token.clone()).await?), None, None)
+            }
+
+            RecordingMode::WebcamOnly => {
+                info!("Starting webcam-only recording");
+                (None, Some(webcam_device(&config, "webcam mode")?), None)
+            }
+
+            RecordingMode::ScreenAndWebcam => {
+                info!("Starting dual recording (screen + webcam)");
+                let device = webcam_device(&config, "dual mode")?;
+                let webcam_path = config.webcam_output_path.clone()
+                    .ok_or(RecordingError::InvalidConfig("Webcam output path required for dual mode".into()))?;
+                create_parent_dir(&webcam_path)?;
+                let session = self.start_session(source, &config, self.restore_token.clone()).await?;
+                (Some(session), Some(device), Some(webcam_path))
+            }
+        };
+
+        let mut capture = Capture::new(config, session, webcam_device, webcam_path).with_separate_system_audio();
+        if let Err(e) = self.spawn_segment(&mut capture).await {
+            let session = capture.source.take();
+            capture.discard();
+            self.close_session(session).await;
+            return Err(e);
+        }
+
+        if let Some(token) = capture.source.as_ref().and_then(|s| s.restore_token.clone()) {
+            self.restore_token = Some(token);
+        }
+        self.captures.started(capture);
+
+        info!("Recording started successfully");
+        Ok(())
+    }
+
+    async fn pause_recording(&mut self) -> Result<(), RecordingError> {
+        // The portal session stays open so resuming doesn't ask again
+        self.captures.pause().await
+    }
+
+    async fn resume_recording(&mut self) -> Result<(), RecordingError> {
+        let mut capture = self.captures.take_paused()?;
+
+        let mut result = Ok(());
+        if let Some(session) = capture.source.as_mut() {
+            // The previous pipeline's PipeWire connection ended with it
+            match self.portal.open_pipewire_remote(session).await {
+                Ok(fd) => session.pipewire_fd = fd,
+                Err(e) => result = Err(e),
+            }
+        }
+        if result.is_ok() {
+            result = self.spawn_segment(&mut capture).await;
+        }
+        self.captures.resumed(capture, result)
+    }
+
+    async fn stop_recording(&mut self) -> Result<RecordingOutput, RecordingError> {
+        let mut capture = self.captures.stop().await?;
+        self.close_session(capture.source.take()).await;
+        self.captures.finalize(capture, self.ffmpeg_path.clone()).await
+    }
+
+    fn get_state(&self) -> RecordingState {
+        self.captures.state()
+    }
+
+    fn get_duration(&self) -> f64 {
+        self.captures.duration()
+    }
+
+    fn get_output_size(&self) -> u64 {
+        self.captures.output_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recording::portal::{PortalStream, StreamProperties};
+    use crate::recording::AudioInputType;
+    use std::os::fd::OwnedFd;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    /// Portal that shares one stream without asking anyone
+    struct MockPortal {
+        requests: Mutex<Vec<ScreenCastRequest>>,
+        reopened: AtomicUsize,
+        closed: AtomicUsize,
+    }
+
+    impl MockPortal {
+        fn new() -> Arc<Self> {
+            Arc::new(Self { requests: Mutex::new(Vec::new()), reopened: AtomicUsize::new(0), closed: AtomicUsize::new(0) })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ScreenCastPortal for Arc<MockPortal> {
+        async fn start(&self, request: &ScreenCastRequest) -> Result<ScreenCastSession, RecordingError> {
+            self.requests.lock().unwrap().push(request.clone());
+            Ok(ScreenCastSession {
+                handle: "/org/freedesktop/portal/desktop/session/1_1/test".to_string(),
+                streams: vec![PortalStream {
+                    node_id: 42,
+                    properties: StreamProperties { size: Some((1280, 720)), ..Default::default() },
+                }],
+                pipewire_fd: std::fs::File::open("/dev/null")?.into(),
+                restore_token: Some("token-1".to_string()),
+            })
+        }
+
+        async fn open_pipewire_remote(&self, _session: &ScreenCastSession) -> Result<OwnedFd, RecordingError> {
+            self.reopened.fetch_add(1, Ordering::SeqCst);
+            Ok(std::fs::File::open("/dev/null")?.into())
+        }
+
+        async fn close(&self, _session: &ScreenCastSession) -> Result<(), RecordingError> {
+            self.closed.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_gst_pipeline_args() {
+        let config = RecordingConfig {
+            output_path: PathBuf::from("/tmp/my recording.mp4"),
+            audio_input: AudioInputType::Both,
+            crop_region: Some(CropRegion { x: 100, y: 50, width: 641, height: 480 }),
+            ..Default::default()
+        };
+
+        let segment = PathBuf::from("/tmp/my recording.part1.mp4");
+        let system_audio = PathBuf::from("/tmp/my recording.system-audio.part1.m4a");
+        let args = gst_pipeline_args(42, Some((1280, 720)), &config, &segment, Some(&system_audio)).unwrap();
+        let pipeline = args.join(" ");
+        assert!(pipeline.starts_with("-e -q pipewiresrc fd=3 path=42 "));
+        assert!(pipeline.contains("videocrop left=100 top=50 right=540 bottom=190 !"));
+        assert!(pipeline.contains("framerate=30/1 ! x264enc speed-preset=ultrafast pass=qual quantizer=16"));
+        assert!(pipeline.contains("mp4mux name=mux fragment-duration=1000 ! filesink"));
+        assert!(args.contains(&"location=/tmp/my recording.part1.mp4".to_string()));
+        assert!(pipeline.contains("pulsesrc ! audioconvert ! audioresample ! audioconvert ! avenc_aac bitrate=128000 ! queue ! mux."));
+        assert!(!pipeline.contains("audiomixer"));
+
+        // System audio in a file of its own
+        assert!(pipeline.contains("pulsesrc device=@DEFAULT_MONITOR@ ! audioconvert ! audioresample ! audioconvert ! avenc_aac bitrate=128000 ! queue ! mp4mux fragment-duration=1000 ! filesink"));
+        assert_eq!(args.last().unwrap(), "location=/tmp/my recording.system-audio.part1.m4a");
+
+        // Cropping needs the stream size
+        assert!(gst_pipeline_args(42, None, &config, &segment, None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_portal_session_lifecycle() {
+        let portal = MockPortal::new();
+        let mut recorder = WaylandRecorder::new(Box::new(portal.clone()));
+        recorder.gst_launch_path = PathBuf::from("/nonexistent/gst-launch-1.0");
+
+        let sources = recorder.list_sources(SourceTypeFilter::Window).await.unwrap();
+        assert_eq!(sources[0].id(), PORTAL_WINDOW_ID);
+
+        let config = RecordingConfig {
+            output_path: std::env::temp_dir().join("clipforge-wayland-test.mp4"),
+            show_cursor: false,
+            ..Default::default()
+        };
+
+        // The pipeline can't start, so the session is closed again and the recorder stays idle
+        let result = recorder.start_recording(&sources[0], config).await;
+        assert!(matches!(result, Err(RecordingError::RecordingFailed(_))));
+        assert_eq!(recorder.get_state(), RecordingState::Idle);
+        assert_eq!(portal.closed.load(Ordering::SeqCst), 1);
+
+        let requests = portal.requests.lock().unwrap();
+        assert_eq!(requests[0].source_kind, SourceKind::Window);
+        assert_eq!(requests[0].cursor_mode, CursorMode::Hidden);
+    }
+
+    /// A stand-in for `gst-launch-1.0` that runs `script`, ignoring its arguments
+    fn fake_pipeline(script: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("clipforge-gst-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_exiting_at_once_fails_to_start() {
+        let portal = MockPortal::new();
+        let mut recorder = WaylandRecorder::new(Box::new(portal.clone()));
+        recorder.gst_launch_path = fake_pipeline("echo 'Failed to connect to PipeWire' >&2; exit 1");
+
+        let sources = recorder.list_sources(SourceTypeFilter::Screen).await.unwrap();
+        let config = RecordingConfig {
+            output_path: std::env::temp_dir().join(format!("clipforge-wayland-{}.mp4", uuid::Uuid::new_v4())),
+            ..Default::default()
+        };
+
+        let error = recorder.start_recording(&sources[0], config).await.unwrap_err();
+        assert!(error.to_string().contains("Failed to connect to PipeWire"), "{}", error);
+        assert_eq!(recorder.get_state(), RecordingState::Idle);
+        assert_eq!(portal.closed.load(Ordering::SeqCst), 1);
+
+        std::fs::remove_file(&recorder.gst_launch_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pause_keeps_the_portal_session() {
+        let portal = MockPortal::new();
+        let mut recorder = WaylandRecorder::new(Box::new(portal.clone()));
+        // A "pipeline" that runs without writing anything
+        recorder.gst_launch_path = fake_pipeline("exec sleep 30");
+
+        let sources = recorder.list_sources(SourceTypeFilter::Screen).await.unwrap();
+        let config = RecordingConfig {
+            output_path: std::env::temp_dir().join(format!("clipforge-wayland-{}.mp4", uuid::Uuid::new_v4())),
+            ..Default::default()
+        };
+
+        recorder.start_recording(&sources[0], config).await.unwrap();
+        assert!(matches!(recorder.resume_recording().await, Err(RecordingError::NotPaused)));
+
+        recorder.pause_recording().await.unwrap();
+        assert_eq!(recorder.get_state(), RecordingState::Paused);
+        let paused_at = recorder.get_duration();
+        assert_eq!(recorder.get_duration(), paused_at);
+        assert!(matches!(recorder.pause_recording().await, Err(RecordingError::NotRecording)));
+
+        recorder.resume_recording().await.unwrap();
+        assert_eq!(recorder.get_state(), RecordingState::Recording);
+        assert_eq!(portal.requests.lock().unwrap().len(), 1);
+        assert_eq!(portal.reopened.load(Ordering::SeqCst), 1);
+        assert_eq!(portal.closed.load(Ordering::SeqCst), 0);
+
+        // Nothing was written, so the recording fails, but the session is still closed
+        assert!(recorder.stop_recording().await.is_err());
+        assert_eq!(recorder.get_state(), RecordingState::Error);
+        assert_eq!(portal.closed.load(Ordering::SeqCst), 1);
+
+        std::fs::remove_file(&recorder.gst_launch_path).unwrap();
+    }
+}
@@ -1,7 +1,6 @@
 // Windows screen recording implementation (stub for future development)
 
-use super::{RecordingConfig, RecordingError, RecordingSource, RecordingState, ScreenRecorder, SourceTypeFilter};
-use std::path::PathBuf;
+use super::{AudioDevice, RecordingConfig, RecordingError, RecordingOutput, RecordingSource, RecordingState, ScreenRecorder, SourceTypeFilter};
 use log::warn;
 
 /// Windows screen recorder (not yet implemented)
@@ -28,6 +27,10 @@ impl ScreenRecorder for WindowsRecorder {
         Err(RecordingError::PlatformNotSupported)
     }
 
+    async fn list_audio_devices(&self) -> Result<Vec<AudioDevice>, RecordingError> {
+        Err(RecordingError::PlatformNotSupported)
+    }
+
     async fn check_permissions(&self) -> Result<bool, RecordingError> {
         Ok(true) // Windows doesn't require explicit permission for screen capture
     }
@@ -44,7 +47,15 @@ impl ScreenRecorder for WindowsRecorder {
         Err(RecordingError::PlatformNotSupported)
     }
 
-    async fn stop_recording(&mut self) -> Result<PathBuf, RecordingError> {
+    async fn pause_recording(&mut self) -> Result<(), RecordingError> {
+        Err(RecordingError::PlatformNotSupported)
+    }
+
+    async fn resume_recording(&mut self) -> Result<(), RecordingError> {
+        Err(RecordingError::PlatformNotSupported)
+    }
+
+    async fn stop_recording(&mut self) -> Result<RecordingOutput, RecordingError> {
         Err(RecordingError::PlatformNotSupported)
     }
 
@@ -55,6 +66,10 @@ impl ScreenRecorder for WindowsRecorder {
     fn get_duration(&self) -> f64 {
         0.0
     }
+
+    fn get_output_size(&self) -> u64 {
+        0
+    }
 }
 
 impl Default for WindowsRecorder {
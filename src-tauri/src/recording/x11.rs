@@ -0,0 +1,495 @@
+// X11 screen recording using FFmpeg's x11grab device
+
+use super::capture::{create_parent_dir, stop_process, Capture, CaptureState, RecordingProcess};
+use super::linux::{ffmpeg_args, pulse_audio_devices, pulse_sources, v4l2_input, webcam_device, webcam_devices};
+use super::sync::{SegmentStarts, StreamStart};
+use super::{AudioDevice, CropRegion, RecordingConfig, RecordingError, RecordingMode, RecordingOutput, RecordingSource, RecordingState, ScreenRecorder, SourceTypeFilter};
+use crate::ffmpeg_utils;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use log::{info, warn};
+
+/// Screen recorder for X11 sessions
+///
+/// Screens are captured with FFmpeg's x11grab device, audio through the
+/// PulseAudio API (also served by PipeWire) and webcams through v4l2.
+pub struct X11Recorder {
+    /// Captures grab a screen area, or None when only the webcam is recorded
+    captures: CaptureState<Option<CaptureArea>>,
+    ffmpeg_path: PathBuf,
+    display: String,
+}
+
+/// A monitor of the X screen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct X11Monitor {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub x: i32,
+    pub y: i32,
+    pub primary: bool,
+}
+
+/// Part of the X screen to grab, in screen coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaptureArea {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl X11Recorder {
+    pub fn new() -> Self {
+        let display = std::env::var("DISPLAY").unwrap_or_else(|_| ":0".to_string());
+        Self::with_display(&display)
+    }
+
+    /// Recorder for a specific X display, e.g. `:99` for an Xvfb server
+    pub fn with_display(display: &str) -> Self {
+        let ffmpeg_path = ffmpeg_utils::find_ffmpeg_path()
+            .unwrap_or_else(|e| {
+                warn!("Failed to find FFmpeg: {}. Recording features will be unavailable.", e);
+                PathBuf::from("ffmpeg") // Fallback to PATH lookup
+            });
+
+        info!("X11Recorder initialized for display {} with FFmpeg at: {:?}", display, ffmpeg_path);
+
+        Self {
+            captures: CaptureState::new(),
+            ffmpeg_path,
+            display: display.to_string(),
+        }
+    }
+
+    /// Enumerate the monitors of the X screen
+    ///
+    /// Uses `xrandr --listmonitors`, falling back to the whole screen from
+    /// `xdpyinfo` for servers without RandR.
+    fn get_monitors(display: &str) -> Result<Vec<X11Monitor>, RecordingError> {
+        match Command::new("xrandr").env("DISPLAY", display).arg("--listmonitors").output() {
+            Ok(output) if output.status.success() => {
+                let monitors = parse_xrandr_monitors(&String::from_utf8_lossy(&output.stdout));
+                if !monitors.is_empty() {
+                    info!("xrandr found {} monitor(s) on {}", monitors.len(), display);
+                    return Ok(monitors);
+                }
+                warn!("xrandr listed no monitors on {}", display);
+            }
+            Ok(output) => warn!("xrandr failed on {}: {}", display, String::from_utf8_lossy(&output.stderr).trim()),
+            Err(e) => warn!("Failed to run xrandr: {}", e),
+        }
+
+        let output = Command::new("xdpyinfo")
+            .env("DISPLAY", display)
+            .output()
+            .map_err(|e| RecordingError::SystemError(format!("Failed to query X display {}: {}", display, e)))?;
+
+        if !output.status.success() {
+            return Err(RecordingError::SystemError(format!(
+                "Cannot open X display {}: {}", display, String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        parse_xdpyinfo_dimensions(&String::from_utf8_lossy(&output.stdout))
+            .map(|(width, height)| vec![X11Monitor {
+                name: "screen".to_string(),
+                width,
+                height,
+                x: 0,
+                y: 0,
+                primary: true,
+            }])
+            .ok_or_else(|| RecordingError::SystemError(format!("Cannot read the size of X display {}", display)))
+    }
+
+    /// Look up the monitor a screen source refers to
+    fn find_monitor(&self, source: &RecordingSource) -> Result<X11Monitor, RecordingError> {
+        Self::get_monitors(&self.display)?
+            .into_iter()
+            .find(|m| m.name == source.id())
+            .ok_or_else(|| RecordingError::SourceNotFound(source.id().to_string()))
+    }
+
+    /// Area to grab for a screen source
+    fn screen_area(&self, source: &RecordingSource, config: &RecordingConfig) -> Result<CaptureArea, RecordingError> {
+        if !matches!(source, RecordingSource::Screen { .. }) {
+            return Err(RecordingError::InvalidConfig(format!("{} is not a screen", source.name())));
+        }
+        let area = capture_area(&self.find_monitor(source)?, config.crop_region.as_ref())?;
+        info!("Grabbing {}x{} at +{},{} on {}", area.width, area.height, area.x, area.y, self.display);
+        Ok(area)
+    }
+
+    /// Start the processes recording the next segment of a capture
+    async fn spawn_segment(&self, capture: &mut Capture<Option<CaptureArea>>) -> Result<(), RecordingError> {
+        let config = &capture.config;
+        let audio = pulse_sources(&config.audio_input, config.audio_device_id.as_deref());
+        let (input, label) = match (&capture.source, &capture.webcam_device) {
+            (Some(area), _) => (x11grab_input(&self.display, area, config.fps, config.show_cursor), "screen"),
+            (None, Some(device)) => (v4l2_input(device, config.fps), "webcam"),
+            (None, None) => return Err(RecordingError::InvalidConfig("Nothing to record".to_string())),
+        };
+        let system_audio = capture.system_audio_output.as_mut().map(|output| output.next_segment());
+        let args = ffmpeg_args(&input, &audio, config.quality, &capture.output.next_segment(), system_audio.as_deref());
+        let mut starts = SegmentStarts { main: StreamStart::now(), webcam: None };
+        let process = self.spawn_ffmpeg(&args, label)?;
+
+        // Audio goes with the screen; the webcam file is video only
+        let mut webcam_process = None;
+        if let (Some(webcam_output), Some(device)) = (capture.webcam_output.as_mut(), &capture.webcam_device) {
+            let input = v4l2_input(device, config.fps);
+            starts.webcam = Some(StreamStart::now());
+            match self.spawn_ffmpeg(&ffmpeg_args(&input, &[], config.quality, &webcam_output.next_segment(), None), "webcam") {
+                Ok(child) => webcam_process = Some(child),
+                Err(e) => {
+                    stop_process(process, "Screen").await;
+                    return Err(e);
+                }
+            }
+        }
+
+        capture.segment_started(process, webcam_process, starts).await
+    }
+
+    fn spawn_ffmpeg(&self, args: &[String], label: &str) -> Result<RecordingProcess, RecordingError> {
+        info!("Spawning {} recording process", label);
+
+        RecordingProcess::spawn(Command::new(&self.ffmpeg_path)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null()))
+            .map_err(|e| RecordingError::RecordingFailed(format!("Failed to start {} recording: {}", label, e)))
+    }
+}
+
+/// Parse `xrandr --listmonitors` output
+///
+/// ```text
+/// Monitors: 2
+///  0: +*eDP-1 1920/344x1080/194+0+0  eDP-1
+///  1: +HDMI-1 2560/597x1440/336+1920+0  HDMI-1
+/// ```
+pub fn parse_xrandr_monitors(stdout: &str) -> Vec<X11Monitor> {
+    stdout.lines()
+        .filter_map(|line| {
+            let (_, rest) = line.split_once(':')?;
+            let mut fields = rest.split_whitespace();
+            let flagged_name = fields.next()?;
+            let geometry = fields.next()?;
+
+            // WIDTH/MMxHEIGHT/MM+X+Y
+            let (width, rest) = geometry.split_once('x')?;
+            let (height, offsets) = rest.split_once('/')?;
+            let (_, offsets) = offsets.split_once('+')?;
+            let (x, y) = offsets.split_once('+')?;
+
+            Some(X11Monitor {
+                name: flagged_name.trim_start_matches(['+', '*']).to_string(),
+                width: width.split('/').next()?.parse().ok()?,
+                height: height.parse().ok()?,
+                x: x.parse().ok()?,
+                y: y.parse().ok()?,
+                primary: flagged_name.contains('*'),
+            })
+        })
+        .collect()
+}
+
+/// Parse the screen size from `xdpyinfo` output (`dimensions:    1280x1024 pixels ...`)
+pub fn parse_xdpyinfo_dimensions(stdout: &str) -> Option<(u32, u32)> {
+    let line = stdout.lines().find_map(|l| l.trim().strip_prefix("dimensions:"))?;
+    let (width, height) = line.split_whitespace().next()?.split_once('x')?;
+    Some((width.parse().ok()?, height.parse().ok()?))
+}
+
+/// Area of the X screen to grab for a monitor and optional crop region
+///
+/// The crop region is relative to the monitor. Sizes are rounded down to
+/// even numbers, which yuv420p encoding requires.
+pub fn capture_area(monitor: &X11Monitor, crop: Option<&CropRegion>) -> Result<CaptureArea, RecordingError> {
+    let (x, y, width, height) = match crop {
+        Some(crop) => {
+            if crop.width == 0 || crop.height == 0 {
+                return Err(RecordingError::InvalidConfig("Crop region is empty".to_string()));
+            }
+            if crop.x + crop.width > monitor.width || crop.y + crop.height > monitor.height {
+                return Err(RecordingError::InvalidConfig(format!(
+                    "Crop region {}x{}+{}+{} is outside monitor {} ({}x{})",
+                    crop.width, crop.height, crop.x, crop.y, monitor.name, monitor.width, monitor.height
+                )));
+            }
+            (monitor.x + crop.x as i32, monitor.y + crop.y as i32, crop.width, crop.height)
+        }
+        None => (monitor.x, monitor.y, monitor.width, monitor.height),
+    };
+
+    let (width, height) = (width & !1, height & !1);
+    if width == 0 || height == 0 {
+        return Err(RecordingError::InvalidConfig(format!("Capture area is too small: {}x{}", width, height)));
+    }
+    Ok(CaptureArea { x, y, width, height })
+}
+
+/// FFmpeg input options for grabbing an area of an X display
+pub fn x11grab_input(display: &str, area: &CaptureArea, fps: u32, show_cursor: bool) -> Vec<String> {
+    vec![
+        "-f".to_string(), "x11grab".to_string(),
+        "-draw_mouse".to_string(), if show_cursor { "1" } else { "0" }.to_string(),
+        "-framerate".to_string(), fps.to_string(),
+        "-video_size".to_string(), format!("{}x{}", area.width, area.height),
+        "-i".to_string(), format!("{}+{},{}", display, area.x, area.y),
+    ]
+}
+
+#[async_trait::async_trait]
+impl ScreenRecorder for X11Recorder {
+    async fn list_sources(&self, filter: SourceTypeFilter) -> Result<Vec<RecordingSource>, RecordingError> {
+        info!("Listing Linux recording sources on {} (filter: {:?})", self.display, filter);
+
+        let preview_generator = match crate::screen_preview::ScreenPreviewGenerator::new() {
+            Ok(gen) => Some(gen),
+            Err(e) => {
+                warn!("Failed to initialize screen preview generator: {:?}", e);
+                None
+            }
+        };
+
+        let mut sources = Vec::new();
+
+        if matches!(filter, SourceTypeFilter::Screen | SourceTypeFilter::All) {
+            for monitor in Self::get_monitors(&self.display)? {
+                let preview_path = match (&preview_generator, capture_area(&monitor, None)) {
+                    (Some(generator), Ok(area)) => {
+                        let input = x11grab_input(&self.display, &area, 1, false);
+                        match generator.capture_input_preview(&monitor.name, &input).await {
+                            Ok(path) => path.to_str().map(|s| s.to_string()),
+                            Err(e) => {
+                                warn!("Failed to generate preview for monitor {}: {:?}", monitor.name, e);
+                                None
+                            }
+                        }
+                    }
+                    _ => None,
+                };
+
+                let name = if monitor.primary {
+                    format!("{} (primary)", monitor.name)
+                } else {
+                    monitor.name.clone()
+                };
+
+                sources.push(RecordingSource::Screen {
+                    id: monitor.name,
+                    name,
+                    width: monitor.width,
+                    height: monitor.height,
+                    preview_path,
+                });
+            }
+        }
+
+        if matches!(filter, SourceTypeFilter::Webcam | SourceTypeFilter::All) {
+            for (device, name) in webcam_devices() {
+                let preview_path = if let Some(ref generator) = preview_generator {
+                    let input = ["-f".to_string(), "v4l2".to_string(), "-i".to_string(), device.clone()];
+                    match generator.capture_input_preview(&device, &input).await {
+                        Ok(path) => path.to_str().map(|s| s.to_string()),
+                        Err(e) => {
+                            warn!("Failed to generate preview for webcam {}: {:?}", device, e);
+                            None
+                        }
+                    }
+                } else {
+                    None
+                };
+
+                sources.push(RecordingSource::Webcam {
+                    id: device,
+                    name,
+                    preview_path,
+                });
+            }
+        }
+
+        if matches!(filter, SourceTypeFilter::Window) {
+            info!("Window capture is not supported on X11; record a screen region instead");
+        }
+
+        info!("Found {} recording sources (filter: {:?})", sources.len(), filter);
+        Ok(sources)
+    }
+
+    async fn list_audio_devices(&self) -> Result<Vec<AudioDevice>, RecordingError> {
+        pulse_audio_devices()
+    }
+
+    async fn check_permissions(&self) -> Result<bool, RecordingError> {
+        // X11 has no capture permission; what matters is that the display can be opened
+        match Self::get_monitors(&self.display) {
+            Ok(_) => Ok(true),
+            Err(e) => {
+                warn!("X display {} is not available: {}", self.display, e);
+                Ok(false)
+            }
+        }
+    }
+
+    async fn request_permissions(&self) -> Result<bool, RecordingError> {
+        Ok(true)
+    }
+
+    async fn start_recording(
+        &mut self,
+        source: &RecordingSource,
+        config: RecordingConfig,
+    ) -> Result<(), RecordingError> {
+        self.captures.check_can_start()?;
+
+        info!("Starting recording for source: {}", source.name());
+
+        if config.output_path.to_str().is_none() {
+            return Err(RecordingError::InvalidConfig("Invalid output path".to_string()));
+        }
+        if config.fps == 0 {
+            return Err(RecordingError::InvalidConfig("Frame rate must be positive".to_string()));
+        }
+        create_parent_dir(&config.output_path)?;
+
+        let (screen_area, webcam_device, webcam_path) = match config.recording_mode {
+            RecordingMode::ScreenOnly => {
+                info!("Starting screen-only recording");
+                (Some(self.screen_area(source, &config)?), None, None)
+            }
+
+            RecordingMode::WebcamOnly => {
+                info!("Starting webcam-only recording");
+                (None, Some(webcam_device(&config, "webcam mode")?), None)
+            }
+
+            RecordingMode::ScreenAndWebcam => {
+                info!("Starting dual recording (screen + webcam)");
+                let device = webcam_device(&config, "dual mode")?;
+                let webcam_path = config.webcam_output_path.clone()
+                    .ok_or(RecordingError::InvalidConfig("Webcam output path required for dual mode".into()))?;
+                create_parent_dir(&webcam_path)?;
+                (Some(self.screen_area(source, &config)?), Some(device), Some(webcam_path))
+            }
+        };
+
+        let mut capture = Capture::new(config, screen_area, webcam_device, webcam_path).with_separate_system_audio();
+        if let Err(e) = self.spawn_segment(&mut capture).await {
+            capture.discard();
+            return Err(e);
+        }
+        self.captures.started(capture);
+
+        info!("Recording started successfully");
+        Ok(())
+    }
+
+    async fn pause_recording(&mut self) -> Result<(), RecordingError> {
+        self.captures.pause().await
+    }
+
+    async fn resume_recording(&mut self) -> Result<(), RecordingError> {
+        let mut capture = self.captures.take_paused()?;
+        let result = self.spawn_segment(&mut capture).await;
+        self.captures.resumed(capture, result)
+    }
+
+    async fn stop_recording(&mut self) -> Result<RecordingOutput, RecordingError> {
+        let capture = self.captures.stop().await?;
+        self.captures.finalize(capture, self.ffmpeg_path.clone()).await
+    }
+
+    fn get_state(&self) -> RecordingState {
+        self.captures.state()
+    }
+
+    fn get_duration(&self) -> f64 {
+        self.captures.duration()
+    }
+
+    fn get_output_size(&self) -> u64 {
+        self.captures.output_size()
+    }
+}
+
+impl Default for X11Recorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_monitors_and_capture_area() {
+        let stdout = "Monitors: 2\n 0: +*eDP-1 1920/344x1080/194+0+0  eDP-1\n 1: +HDMI-1 2560/597x1440/336+1920+0  HDMI-1\n";
+        let monitors = parse_xrandr_monitors(stdout);
+        assert_eq!(monitors.len(), 2);
+        assert!(monitors[0].primary);
+        assert_eq!(monitors[1], X11Monitor {
+            name: "HDMI-1".to_string(),
+            width: 2560,
+            height: 1440,
+            x: 1920,
+            y: 0,
+            primary: false,
+        });
+        assert_eq!(parse_xdpyinfo_dimensions("  dimensions:    1280x1024 pixels (338x270 millimeters)"), Some((1280, 1024)));
+
+        // The crop region is relative to the monitor, with odd sizes rounded down
+        let crop = CropRegion { x: 100, y: 50, width: 801, height: 601 };
+        let area = capture_area(&monitors[1], Some(&crop)).unwrap();
+        assert_eq!(area, CaptureArea { x: 2020, y: 50, width: 800, height: 600 });
+        assert_eq!(
+            x11grab_input(":1", &area, 30, false),
+            vec!["-f", "x11grab", "-draw_mouse", "0", "-framerate", "30", "-video_size", "800x600", "-i", ":1+2020,50"]
+        );
+
+        let outside = CropRegion { x: 2000, y: 0, width: 800, height: 600 };
+        assert!(capture_area(&monitors[1], Some(&outside)).is_err());
+    }
+
+    /// Records a cropped region of a headless display
+    ///
+    /// Run with `cargo test -- --ignored` on a machine with Xvfb, xrandr and FFmpeg.
+    #[tokio::test]
+    #[ignore = "needs Xvfb, xrandr and ffmpeg"]
+    async fn test_record_xvfb_display() {
+        let display = ":97";
+        let mut xvfb = Command::new("Xvfb")
+            .args([display, "-screen", "0", "640x480x24", "-nolisten", "tcp"])
+            .spawn()
+            .expect("Xvfb should start");
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+        let mut recorder = X11Recorder::with_display(display);
+        let sources = recorder.list_sources(SourceTypeFilter::Screen).await.unwrap();
+        assert!(matches!(sources[0], RecordingSource::Screen { width: 640, height: 480, .. }));
+
+        let output_path = std::env::temp_dir().join(format!("clipforge-xvfb-{}.mp4", uuid::Uuid::new_v4()));
+        let config = RecordingConfig {
+            output_path: output_path.clone(),
+            crop_region: Some(CropRegion { x: 10, y: 10, width: 320, height: 240 }),
+            ..Default::default()
+        };
+        recorder.start_recording(&sources[0], config).await.unwrap();
+        assert_eq!(recorder.get_state(), RecordingState::Recording);
+
+        tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+        let result = recorder.stop_recording().await;
+        let _ = xvfb.kill();
+        let _ = xvfb.wait();
+
+        assert_eq!(result.unwrap().path, output_path);
+        assert_eq!(recorder.get_state(), RecordingState::Idle);
+        assert!(std::fs::metadata(&output_path).unwrap().len() > 0);
+        std::fs::remove_file(&output_path).unwrap();
+    }
+}
@@ -0,0 +1,165 @@
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::ffmpeg_utils;
+use crate::models::FileError;
+
+/// Settings for finding shot boundaries in a video
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SceneDetectionOptions {
+    pub threshold: f64,          // FFmpeg scene score (0-1) above which a frame starts a new shot
+    pub min_shot_duration: f64,  // Cuts closer than this to the previous one are ignored (seconds)
+}
+
+impl Default for SceneDetectionOptions {
+    fn default() -> Self {
+        Self {
+            threshold: 0.3,
+            min_shot_duration: 1.0,
+        }
+    }
+}
+
+impl SceneDetectionOptions {
+    pub fn validate(&self) -> Result<(), FileError> {
+        if !(self.threshold > 0.0 && self.threshold < 1.0) {
+            return Err(FileError::InvalidSetting(
+                format!("Scene threshold must be between 0 and 1: {}", self.threshold)
+            ));
+        }
+        if !self.min_shot_duration.is_finite() || self.min_shot_duration < 0.0 {
+            return Err(FileError::InvalidSetting(
+                format!("Minimum shot duration cannot be negative: {}", self.min_shot_duration)
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A detected shot boundary
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SceneCut {
+    pub time: f64,   // seconds
+    pub score: f64,  // 0-1
+}
+
+/// Find shot boundaries in a video with FFmpeg's scene change score
+pub async fn detect_scene_cuts(
+    video_path: &Path,
+    duration: f64,
+    options: &SceneDetectionOptions,
+) -> Result<Vec<SceneCut>, FileError> {
+    options.validate()?;
+
+    let ffmpeg_path = ffmpeg_utils::find_ffmpeg_path()
+        .map_err(FileError::SceneDetectionError)?;
+
+    // Frames are scored at low resolution; scores barely change and decoding dominates
+    let filter = format!(
+        "scale=320:-2,select='gt(scene,{})',metadata=print:file=-",
+        options.threshold
+    );
+
+    log::debug!("Detecting scene cuts in {:?} (threshold {})", video_path, options.threshold);
+
+    let output = tokio::process::Command::new(&ffmpeg_path)
+        .arg("-hide_banner")
+        .arg("-nostats")
+        .arg("-i")
+        .arg(video_path)
+        .args(["-map", "0:v:0", "-an", "-vf", &filter, "-f", "null", "-"])
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        log::error!("FFmpeg scene detection failed for {:?}: {}", video_path, stderr);
+        return Err(FileError::SceneDetectionError(
+            stderr.lines().last().unwrap_or("FFmpeg exited with an error").to_string()
+        ));
+    }
+
+    let cuts = parse_scene_scores(&String::from_utf8_lossy(&output.stdout));
+    let cuts = filter_cuts(&cuts, duration, options.min_shot_duration);
+
+    log::info!("Detected {} scene cuts in {:?}", cuts.len(), video_path);
+    Ok(cuts)
+}
+
+/// Parse frame times and scene scores printed by FFmpeg's `metadata=print` filter
+pub fn parse_scene_scores(stdout: &str) -> Vec<SceneCut> {
+    let mut cuts = Vec::new();
+    let mut frame_time: Option<f64> = None;
+
+    for line in stdout.lines() {
+        if let Some(pos) = line.find("pts_time:") {
+            frame_time = line[pos + 9..].split_whitespace().next().and_then(|v| v.parse().ok());
+        } else if let Some(score) = line.trim().strip_prefix("lavfi.scene_score=") {
+            if let (Some(time), Ok(score)) = (frame_time.take(), score.parse::<f64>()) {
+                cuts.push(SceneCut { time, score });
+            }
+        }
+    }
+
+    cuts
+}
+
+/// Drop cuts that would leave a shot shorter than `min_shot` seconds
+///
+/// Within a run of close cuts the first is kept, as it marks where the change began.
+pub fn filter_cuts(cuts: &[SceneCut], duration: f64, min_shot: f64) -> Vec<SceneCut> {
+    let mut kept: Vec<SceneCut> = Vec::new();
+    let mut shot_start = 0.0;
+
+    for cut in cuts {
+        if cut.time - shot_start < min_shot || duration - cut.time < min_shot {
+            continue;
+        }
+        kept.push(*cut);
+        shot_start = cut.time;
+    }
+
+    kept
+}
+
+/// Time of the representative frame of each shot, half way through it
+pub fn shot_thumbnail_times(cuts: &[SceneCut], duration: f64) -> Vec<f64> {
+    cuts.iter()
+        .enumerate()
+        .map(|(i, cut)| {
+            let end = cuts.get(i + 1).map(|next| next.time).unwrap_or(duration);
+            (cut.time + end) / 2.0
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_filter_scene_cuts() {
+        let stdout = "frame:0    pts:61440   pts_time:4.8\n\
+                      lavfi.scene_score=0.612000\n\
+                      frame:1    pts:64000   pts_time:5\n\
+                      lavfi.scene_score=0.410000\n\
+                      frame:2    pts:153600  pts_time:12\n\
+                      lavfi.scene_score=0.950000\n\
+                      frame:3    pts:252160  pts_time:19.7\n\
+                      lavfi.scene_score=0.330000\n";
+
+        let cuts = parse_scene_scores(stdout);
+        assert_eq!(cuts.len(), 4);
+        assert_eq!(cuts[0], SceneCut { time: 4.8, score: 0.612 });
+
+        // 5.0 is too close to 4.8, and 19.7 leaves too short a final shot
+        let kept = filter_cuts(&cuts, 20.0, 1.0);
+        let times: Vec<f64> = kept.iter().map(|c| c.time).collect();
+        assert_eq!(times, vec![4.8, 12.0]);
+
+        let thumbnail_times = shot_thumbnail_times(&kept, 20.0);
+        assert!((thumbnail_times[0] - 8.4).abs() < 1e-9);
+        assert!((thumbnail_times[1] - 16.0).abs() < 1e-9);
+        assert!(SceneDetectionOptions { threshold: 1.5, ..Default::default() }.validate().is_err());
+    }
+}
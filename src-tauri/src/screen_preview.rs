@@ -101,6 +101,44 @@ impl ScreenPreviewGenerator {
         Ok(output_path)
     }
 
+    /// Generate a preview thumbnail from an FFmpeg capture input
+    ///
+    /// `input_args` are the FFmpeg input options ending in `-i <input>`, e.g.
+    /// `-f v4l2 -i /dev/video0`.
+    #[cfg(target_os = "linux")]
+    pub async fn capture_input_preview(
+        &self,
+        source_id: &str,
+        input_args: &[String],
+    ) -> Result<PathBuf, ThumbnailError> {
+        let safe_id: String = source_id.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+            .collect();
+        let output_path = self.cache_dir.join(format!("source-{}.jpg", safe_id));
+
+        log::debug!("Capturing preview for source {}", source_id);
+
+        let output = Command::new(&self.ffmpeg_path)
+            .arg("-hide_banner")
+            .args(input_args)
+            .arg("-vframes").arg("1")
+            .arg("-vf").arg("scale=320:-1")
+            .arg("-q:v").arg("2")
+            .arg("-y")
+            .arg(&output_path)
+            .output()
+            .map_err(ThumbnailError::IoError)?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            log::error!("FFmpeg preview failed for source {}: {}", source_id, stderr);
+            return Err(ThumbnailError::GenerationFailed);
+        }
+
+        log::info!("Source preview generated: {:?}", output_path);
+        Ok(output_path)
+    }
+
     /// Generate a window preview thumbnail (placeholder for future implementation)
     #[cfg(target_os = "macos")]
     pub async fn capture_window_preview(
@@ -1,32 +1,33 @@
-use crate::models::{MediaFile, SubtitleSegment, SubtitleSource, SubtitleTrack, SubtitleError};
+use crate::audio_chunks::{self, AudioChunk};
+use crate::models::{
+    Clip, MediaFile, SubtitleSegment, SubtitleSource, SubtitleTrack, SubtitleError, SubtitleWord, Timeline,
+    TrackType,
+};
+use crate::subtitle_edit;
+use crate::transcription::TranscriptionProvider;
 use log::{info, warn, error};
-use reqwest::multipart;
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 use sha2::{Sha256, Digest};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
 use tauri::{Window, Emitter};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 
-/// OpenAI Whisper API response format
-#[derive(Debug, Deserialize)]
-struct WhisperResponse {
-    text: String,
-}
+/// Shortest remainder of a segment kept when a cut removes part of it (seconds)
+const MIN_REMAPPED_SEGMENT: f64 = 0.2;
 
-/// OpenAI Whisper verbose JSON response with segments
-#[derive(Debug, Deserialize)]
-struct WhisperVerboseResponse {
-    text: String,
-    segments: Vec<WhisperSegment>,
-}
+/// Chunks transcribed at the same time
+const CHUNK_PARALLELISM: usize = 3;
 
-#[derive(Debug, Deserialize)]
-struct WhisperSegment {
-    id: usize,
-    start: f64,
-    end: f64,
-    text: String,
-}
+/// Attempts per chunk before the transcription fails
+const CHUNK_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry, doubled for each further attempt
+const CHUNK_RETRY_DELAY: Duration = Duration::from_secs(2);
 
 /// Progress event payload
 #[derive(Debug, Clone, Serialize)]
@@ -35,93 +36,118 @@ struct SubtitleProgress {
     progress: f64,  // 0.0 to 1.0
 }
 
+/// Emits `subtitle:progress` for a share of the overall job
+#[derive(Clone)]
+struct ProgressReporter {
+    window: Option<Window>,
+    start: f64,
+    span: f64,
+}
+
+impl ProgressReporter {
+    fn new(window: Option<Window>) -> Self {
+        Self { window, start: 0.0, span: 1.0 }
+    }
+
+    /// Reporter for the part of this job from `start` to `start + span`
+    fn slice(&self, start: f64, span: f64) -> Self {
+        Self {
+            window: self.window.clone(),
+            start: self.start + self.span * start,
+            span: self.span * span,
+        }
+    }
+
+    fn emit(&self, stage: impl Into<String>, fraction: f64) {
+        if let Some(ref win) = self.window {
+            let _ = win.emit("subtitle:progress", SubtitleProgress {
+                stage: stage.into(),
+                progress: self.start + self.span * fraction.clamp(0.0, 1.0),
+            });
+        }
+    }
+}
+
 /// Subtitle service for AI transcription and SRT handling
 pub struct SubtitleService {
-    api_key: String,
+    provider: Arc<dyn TranscriptionProvider>,
     cache_dir: PathBuf,
-    client: reqwest::Client,
 }
 
 impl SubtitleService {
-    /// Create a new subtitle service
-    pub fn new(api_key: String) -> Result<Self, SubtitleError> {
+    /// Create a new subtitle service using the given transcription backend
+    pub fn new(provider: Box<dyn TranscriptionProvider>) -> Result<Self, SubtitleError> {
         let cache_dir = dirs::cache_dir()
             .ok_or_else(|| SubtitleError::CacheError("Could not find cache directory".into()))?
             .join("clipforge")
             .join("subtitle_cache");
 
-        fs::create_dir_all(&cache_dir)?;
-
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(300))  // 5 minute timeout
-            .build()?;
+        fs::create_dir_all(cache_dir.join("chunks"))?;
 
         Ok(Self {
-            api_key,
+            provider: Arc::from(provider),
             cache_dir,
-            client,
         })
     }
 
-    /// Transcribe a media file using OpenAI Whisper API
-    pub async fn transcribe_media_file(
+    /// Name of the transcription backend in use
+    pub fn provider_name(&self) -> String {
+        self.provider.name()
+    }
+
+    /// Transcribe a media file with the configured backend
+    ///
+    /// Long audio is split in pauses and the chunks are transcribed in
+    /// parallel. Finished chunks are cached until the whole file is done, so
+    /// a failed run picks up where it stopped.
+    async fn transcribe_media_file(
         &self,
         media_file: &MediaFile,
         language: Option<String>,
-        window: Option<Window>,
+        progress: &ProgressReporter,
     ) -> Result<SubtitleTrack, SubtitleError> {
         info!("Starting transcription for media file: {:?}", media_file.path);
+        progress.emit("Preparing audio", 0.0);
 
-        // Emit progress: Starting
-        if let Some(ref win) = window {
-            let _ = win.emit("subtitle:progress", SubtitleProgress {
-                stage: "Preparing audio".to_string(),
-                progress: 0.1,
-            });
-        }
-
-        // Check cache first
-        let file_hash = self.compute_file_hash(&media_file.path)?;
+        // Check cache first (results differ between backends)
+        let provider = self.provider.name();
+        let file_hash = self.compute_cache_key(&media_file.path, &provider)?;
         let lang = language.as_deref().unwrap_or("en");
 
         if let Some(cached_track) = self.get_cached_transcription(&file_hash, lang)? {
             info!("Using cached transcription for file hash: {}", file_hash);
-            if let Some(ref win) = window {
-                let _ = win.emit("subtitle:progress", SubtitleProgress {
-                    stage: "Loaded from cache".to_string(),
-                    progress: 1.0,
-                });
-            }
+            progress.emit("Loaded from cache", 1.0);
             return Ok(cached_track);
         }
 
-        // Extract audio if needed (for video files)
-        let audio_path = if media_file.codec.video.is_some() {
-            if let Some(ref win) = window {
-                let _ = win.emit("subtitle:progress", SubtitleProgress {
-                    stage: "Extracting audio".to_string(),
-                    progress: 0.2,
-                });
-            }
-            self.extract_audio(&media_file.path).await?
+        // Extract audio if needed (for video files or formats the backend can't read)
+        let audio_extension = self.provider.audio_extension();
+        let readable = media_file.path.extension()
+            .map(|ext| ext.eq_ignore_ascii_case(audio_extension))
+            .unwrap_or(false);
+        let audio_path = if media_file.codec.video.is_some() || !readable {
+            progress.emit("Extracting audio", 0.02);
+            self.extract_audio(&media_file.path, audio_extension).await?
         } else {
             media_file.path.clone()
         };
 
-        // Call OpenAI Whisper API
-        if let Some(ref win) = window {
-            let _ = win.emit("subtitle:progress", SubtitleProgress {
-                stage: "Transcribing with OpenAI Whisper".to_string(),
-                progress: 0.4,
-            });
-        }
-
-        let segments = self.call_whisper_api(&audio_path, language.clone()).await?;
+        progress.emit("Detecting pauses", 0.08);
+        let segments = match audio_chunks::plan_audio_chunks(&audio_path).await {
+            Ok(chunks) => {
+                let chunk_key = format!("{}_{}", file_hash, lang);
+                self.transcribe_chunks(&audio_path, &chunks, &chunk_key, language.as_deref(), &progress.slice(0.1, 0.9))
+                    .await
+                    .map(|segments| (segments, chunks))
+            }
+            Err(e) => Err(e),
+        };
 
         // Clean up extracted audio if temporary
         if audio_path != media_file.path {
             let _ = fs::remove_file(&audio_path);
         }
+        let (segments, chunks) = segments?;
 
         // Create subtitle track
         let track = SubtitleTrack {
@@ -129,32 +155,207 @@ impl SubtitleService {
             language: lang.to_string(),
             source: SubtitleSource::Transcribed {
                 media_file_id: media_file.id.clone(),
-                provider: "openai-whisper".to_string(),
+                provider,
             },
+            ass_header: None,
         };
 
-        // Cache the result
+        // Cache the result, which supersedes the per-chunk results
         self.cache_transcription(&file_hash, lang, &track)?;
-
-        if let Some(ref win) = window {
-            let _ = win.emit("subtitle:progress", SubtitleProgress {
-                stage: "Complete".to_string(),
-                progress: 1.0,
-            });
+        for chunk in &chunks {
+            let _ = fs::remove_file(self.chunk_cache_path(&format!("{}_{}", file_hash, lang), chunk));
         }
 
         info!("Transcription complete: {} segments", track.segments.len());
         Ok(track)
     }
 
-    /// Extract audio from video file using FFmpeg
-    async fn extract_audio(&self, video_path: &Path) -> Result<PathBuf, SubtitleError> {
+    /// Transcribe the chunks of an audio file and stitch the results together
+    async fn transcribe_chunks(
+        &self,
+        audio_path: &Path,
+        chunks: &[AudioChunk],
+        chunk_key: &str,
+        language: Option<&str>,
+        progress: &ProgressReporter,
+    ) -> Result<Vec<SubtitleSegment>, SubtitleError> {
+        let total = chunks.len();
+        let mut results: Vec<Option<Vec<SubtitleSegment>>> = vec![None; total];
+
+        for chunk in chunks {
+            results[chunk.index] = self.get_cached_chunk(chunk_key, chunk)?;
+        }
+
+        let mut done = results.iter().filter(|r| r.is_some()).count();
+        if done > 0 {
+            info!("Resuming transcription with {} of {} chunks cached", done, total);
+            progress.emit(format!("Resuming ({} of {} chunks done)", done, total), done as f64 / total as f64);
+        } else {
+            progress.emit(format!("Transcribing with {}", self.provider.name()), 0.0);
+        }
+
+        // A single chunk covering the whole file is uploaded as is
+        let whole_file = total == 1;
+        let extension = self.provider.audio_extension();
+        let semaphore = Arc::new(Semaphore::new(CHUNK_PARALLELISM));
+        let mut tasks = JoinSet::new();
+
+        for chunk in chunks.iter().filter(|c| results[c.index].is_none()) {
+            let provider = self.provider.clone();
+            let semaphore = semaphore.clone();
+            let audio_path = audio_path.to_path_buf();
+            let chunk = *chunk;
+            let language = language.map(str::to_string);
+
+            tasks.spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                let result = transcribe_chunk(provider, &audio_path, &chunk, whole_file, extension, language.as_deref()).await;
+                (chunk.index, result)
+            });
+        }
+
+        // Returning early drops the set, which cancels the remaining chunks
+        while let Some(joined) = tasks.join_next().await {
+            let (index, result) = joined
+                .map_err(|e| SubtitleError::ProviderError(format!("Chunk task failed: {}", e)))?;
+            let segments = result?;
+
+            if !whole_file {
+                self.cache_chunk(chunk_key, &chunks[index], &segments)?;
+            }
+            results[index] = Some(segments);
+            done += 1;
+            progress.emit(format!("Transcribed chunk {} of {}", done, total), done as f64 / total as f64);
+        }
+
+        Ok(stitch_chunks(chunks, results.into_iter().flatten().collect()))
+    }
+
+    /// Transcribe the audio tracks of a timeline
+    ///
+    /// Each source file is transcribed once, then its segments are remapped
+    /// through every clip that uses it, so the result follows trims, moves
+    /// and speed changes. Speech in cut regions is dropped.
+    pub async fn transcribe_timeline(
+        &self,
+        timeline: &Timeline,
+        media_files: &[MediaFile],
+        language: Option<String>,
+        window: Option<Window>,
+    ) -> Result<SubtitleTrack, SubtitleError> {
+        let clips: Vec<&Clip> = timeline.tracks.iter()
+            .filter(|t| t.track_type == TrackType::Audio && !t.muted)
+            .flat_map(|t| t.clips.iter())
+            .filter(|c| !c.is_generated())
+            .collect();
+
+        if clips.is_empty() {
+            return Err(SubtitleError::NoAudioTrack);
+        }
+
+        // Transcribe each source file once, in timeline order
+        let mut source_ids: Vec<&str> = Vec::new();
+        for clip in &clips {
+            if !source_ids.contains(&clip.media_file_id.as_str()) {
+                source_ids.push(&clip.media_file_id);
+            }
+        }
+
+        let progress = ProgressReporter::new(window);
+        let share = 1.0 / source_ids.len() as f64;
+        let mut source_segments: HashMap<&str, Vec<SubtitleSegment>> = HashMap::new();
+        for (index, media_file_id) in source_ids.iter().enumerate() {
+            let media_file = media_files.iter()
+                .find(|m| m.id == *media_file_id)
+                .ok_or_else(|| SubtitleError::MediaNotFound(media_file_id.to_string()))?;
+
+            progress.emit(
+                format!("Transcribing {} ({} of {})", media_file.filename, index + 1, source_ids.len()),
+                index as f64 * share,
+            );
+
+            let source_progress = progress.slice(index as f64 * share, share);
+            let track = self.transcribe_media_file(media_file, language.clone(), &source_progress).await?;
+            source_segments.insert(media_file_id, track.segments);
+        }
+
+        let mut segments: Vec<SubtitleSegment> = clips.iter()
+            .flat_map(|clip| Self::map_segments_to_timeline(&source_segments[clip.media_file_id.as_str()], clip))
+            .collect();
+        segments.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+        for (index, segment) in segments.iter_mut().enumerate() {
+            segment.id = index + 1;
+        }
+
+        progress.emit("Complete", 1.0);
+
+        info!("Timeline transcription complete: {} segments from {} clips", segments.len(), clips.len());
+
+        Ok(SubtitleTrack {
+            segments,
+            language: language.as_deref().unwrap_or("en").to_string(),
+            source: SubtitleSource::TranscribedTimeline {
+                timeline_id: timeline.id.clone(),
+                provider: self.provider.name(),
+            },
+            ass_header: None,
+        })
+    }
+
+    /// Map segments timed against a source file onto the timeline through a clip
+    ///
+    /// Segments outside the part of the source the clip plays are dropped, and
+    /// segments crossing a cut are shortened to the visible part. With word
+    /// timing, their text is cut down to the words left as well.
+    pub fn map_segments_to_timeline(segments: &[SubtitleSegment], clip: &Clip) -> Vec<SubtitleSegment> {
+        let speed = if clip.speed > 0.0 { clip.speed as f64 } else { 1.0 };
+        let source_start = clip.trim_start;
+        let source_end = clip.trim_start + clip.duration * speed;
+
+        segments.iter()
+            .filter_map(|segment| {
+                let start = segment.start_time.max(source_start);
+                let end = segment.end_time.min(source_end);
+                let was_cut = start > segment.start_time || end < segment.end_time;
+                if end <= start || (was_cut && end - start < MIN_REMAPPED_SEGMENT) {
+                    return None;
+                }
+
+                let words: Vec<SubtitleWord> = segment.words.iter()
+                    .filter(|w| w.start_time >= source_start && w.end_time <= source_end)
+                    .map(|w| w.remapped(source_start, clip.track_position, speed))
+                    .collect();
+                let text = if was_cut && !segment.words.is_empty() {
+                    if words.is_empty() {
+                        return None;
+                    }
+                    subtitle_edit::join_words(&words)
+                } else {
+                    segment.text.clone()
+                };
+
+                Some(SubtitleSegment {
+                    id: segment.id,
+                    start_time: clip.track_position + (start - source_start) / speed,
+                    end_time: clip.track_position + (end - source_start) / speed,
+                    text,
+                    style: segment.style.clone(),
+                    cue_settings: segment.cue_settings.clone(),
+                    style_override: segment.style_override.clone(),
+                    words,
+                })
+            })
+            .collect()
+    }
+
+    /// Extract audio from a media file using FFmpeg, as MP3 or 16-bit WAV
+    async fn extract_audio(&self, video_path: &Path, extension: &str) -> Result<PathBuf, SubtitleError> {
         let temp_dir = std::env::temp_dir();
-        let audio_path = temp_dir.join(format!("clipforge_audio_{}.mp3", uuid::Uuid::new_v4()));
+        let audio_path = temp_dir.join(format!("clipforge_audio_{}.{}", uuid::Uuid::new_v4(), extension));
 
         info!("Extracting audio to: {:?}", audio_path);
 
-        let output = tokio::process::Command::new("ffmpeg")
+        let output = tokio::process::Command::new(audio_chunks::ffmpeg_path()?)
             .arg("-y")
             .arg("-i")
             .arg(video_path)
@@ -163,8 +364,7 @@ impl SubtitleService {
             .arg("16000")  // 16kHz sample rate (Whisper recommended)
             .arg("-ac")
             .arg("1")  // Mono
-            .arg("-b:a")
-            .arg("64k")  // Low bitrate for smaller upload
+            .args(audio_chunks::audio_codec_args(extension))
             .arg(&audio_path)
             .output()
             .await
@@ -178,70 +378,12 @@ impl SubtitleService {
         Ok(audio_path)
     }
 
-    /// Call OpenAI Whisper API
-    async fn call_whisper_api(
-        &self,
-        audio_path: &Path,
-        language: Option<String>,
-    ) -> Result<Vec<SubtitleSegment>, SubtitleError> {
-        info!("Calling OpenAI Whisper API");
-
-        // Read audio file
-        let audio_bytes = fs::read(audio_path)?;
-        let file_name = audio_path.file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("audio.mp3");
-
-        // Build multipart form
-        let file_part = multipart::Part::bytes(audio_bytes)
-            .file_name(file_name.to_string())
-            .mime_str("audio/mpeg")?;
-
-        let mut form = multipart::Form::new()
-            .part("file", file_part)
-            .text("model", "whisper-1")
-            .text("response_format", "verbose_json")  // Get segments with timing
-            .text("timestamp_granularities[]", "segment");
-
-        if let Some(lang) = language {
-            form = form.text("language", lang);
-        }
-
-        // Make API request
-        let response = self.client
-            .post("https://api.openai.com/v1/audio/transcriptions")
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .multipart(form)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            error!("OpenAI API error: {}", error_text);
-            return Err(SubtitleError::ApiError(error_text));
-        }
-
-        let verbose_response: WhisperVerboseResponse = response.json().await?;
-
-        // Convert to SubtitleSegment format
-        let segments = verbose_response.segments
-            .into_iter()
-            .map(|seg| SubtitleSegment {
-                id: seg.id,
-                start_time: seg.start,
-                end_time: seg.end,
-                text: seg.text.trim().to_string(),
-            })
-            .collect();
-
-        Ok(segments)
-    }
-
-    /// Compute SHA256 hash of file for caching
-    fn compute_file_hash(&self, path: &Path) -> Result<String, SubtitleError> {
+    /// Compute SHA256 hash of file and backend for caching
+    fn compute_cache_key(&self, path: &Path, provider: &str) -> Result<String, SubtitleError> {
         let bytes = fs::read(path)?;
         let mut hasher = Sha256::new();
         hasher.update(&bytes);
+        hasher.update(provider.as_bytes());
         Ok(format!("{:x}", hasher.finalize()))
     }
 
@@ -279,95 +421,97 @@ impl SubtitleService {
         Ok(())
     }
 
-    /// Parse SRT format string into subtitle segments
-    pub fn parse_srt(srt_content: &str) -> Result<Vec<SubtitleSegment>, SubtitleError> {
-        let mut segments = Vec::new();
-        let blocks: Vec<&str> = srt_content.split("\n\n").collect();
-
-        for block in blocks {
-            let lines: Vec<&str> = block.lines().collect();
-            if lines.len() < 3 {
-                continue;
-            }
-
-            // Parse ID
-            let id = lines[0].trim().parse::<usize>()
-                .map_err(|_| SubtitleError::InvalidSRT("Invalid segment ID".into()))?;
-
-            // Parse timestamps
-            let times: Vec<&str> = lines[1].split(" --> ").collect();
-            if times.len() != 2 {
-                return Err(SubtitleError::InvalidSRT("Invalid timestamp format".into()));
-            }
-
-            let start_time = Self::parse_srt_timestamp(times[0].trim())?;
-            let end_time = Self::parse_srt_timestamp(times[1].trim())?;
+    /// Cache file for one chunk of a transcription in progress
+    fn chunk_cache_path(&self, chunk_key: &str, chunk: &AudioChunk) -> PathBuf {
+        let start_ms = (chunk.start * 1000.0).round() as u64;
+        let end_ms = (chunk.end * 1000.0).round() as u64;
+        self.cache_dir.join("chunks").join(format!("{}_{}-{}.json", chunk_key, start_ms, end_ms))
+    }
 
-            // Parse text (may span multiple lines)
-            let text = lines[2..].join("\n");
+    /// Get the cached segments of a chunk, relative to the chunk start
+    fn get_cached_chunk(
+        &self,
+        chunk_key: &str,
+        chunk: &AudioChunk,
+    ) -> Result<Option<Vec<SubtitleSegment>>, SubtitleError> {
+        let cache_path = self.chunk_cache_path(chunk_key, chunk);
 
-            segments.push(SubtitleSegment {
-                id,
-                start_time,
-                end_time,
-                text,
-            });
+        if cache_path.exists() {
+            let json = fs::read_to_string(cache_path)?;
+            Ok(Some(serde_json::from_str(&json)?))
+        } else {
+            Ok(None)
         }
+    }
 
-        Ok(segments)
+    /// Cache the segments of a finished chunk
+    fn cache_chunk(
+        &self,
+        chunk_key: &str,
+        chunk: &AudioChunk,
+        segments: &[SubtitleSegment],
+    ) -> Result<(), SubtitleError> {
+        let json = serde_json::to_string(segments)?;
+        fs::write(self.chunk_cache_path(chunk_key, chunk), json)?;
+        Ok(())
     }
+}
 
-    /// Parse SRT timestamp (00:00:05,000)
-    fn parse_srt_timestamp(timestamp: &str) -> Result<f64, SubtitleError> {
-        let parts: Vec<&str> = timestamp.split(&[':', ',']).collect();
-        if parts.len() != 4 {
-            return Err(SubtitleError::InvalidSRT("Invalid timestamp format".into()));
+/// Transcribe one chunk, retrying with backoff on failure
+async fn transcribe_chunk(
+    provider: Arc<dyn TranscriptionProvider>,
+    audio_path: &Path,
+    chunk: &AudioChunk,
+    whole_file: bool,
+    extension: &str,
+    language: Option<&str>,
+) -> Result<Vec<SubtitleSegment>, SubtitleError> {
+    let chunk_path = if whole_file {
+        audio_path.to_path_buf()
+    } else {
+        audio_chunks::extract_chunk(audio_path, chunk, extension).await?
+    };
+
+    let mut attempt = 1;
+    let result = loop {
+        match provider.transcribe(&chunk_path, language).await {
+            Ok(segments) => break Ok(segments),
+            Err(e) if attempt < CHUNK_ATTEMPTS => {
+                let delay = CHUNK_RETRY_DELAY * 2u32.pow(attempt - 1);
+                warn!("Chunk {} failed (attempt {} of {}), retrying in {:?}: {}",
+                    chunk.index + 1, attempt, CHUNK_ATTEMPTS, delay, e);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => break Err(e),
         }
+    };
 
-        let hours: f64 = parts[0].parse()
-            .map_err(|_| SubtitleError::InvalidSRT("Invalid hours".into()))?;
-        let minutes: f64 = parts[1].parse()
-            .map_err(|_| SubtitleError::InvalidSRT("Invalid minutes".into()))?;
-        let seconds: f64 = parts[2].parse()
-            .map_err(|_| SubtitleError::InvalidSRT("Invalid seconds".into()))?;
-        let millis: f64 = parts[3].parse()
-            .map_err(|_| SubtitleError::InvalidSRT("Invalid milliseconds".into()))?;
-
-        Ok(hours * 3600.0 + minutes * 60.0 + seconds + millis / 1000.0)
+    if !whole_file {
+        let _ = fs::remove_file(&chunk_path);
     }
+    result
+}
 
-    /// Generate SRT format string from subtitle segments
-    pub fn generate_srt(segments: &[SubtitleSegment]) -> String {
-        segments.iter()
-            .map(|seg| {
-                let start = Self::format_srt_timestamp(seg.start_time);
-                let end = Self::format_srt_timestamp(seg.end_time);
-                format!("{}\n{} --> {}\n{}\n", seg.id, start, end, seg.text)
+/// Join per-chunk segments into one list timed against the whole file
+fn stitch_chunks(chunks: &[AudioChunk], results: Vec<Vec<SubtitleSegment>>) -> Vec<SubtitleSegment> {
+    let mut segments: Vec<SubtitleSegment> = chunks.iter()
+        .zip(results)
+        .flat_map(|(chunk, segments)| {
+            segments.into_iter().map(move |segment| SubtitleSegment {
+                start_time: chunk.start + segment.start_time,
+                end_time: (chunk.start + segment.end_time).min(chunk.end),
+                words: segment.words.iter().map(|w| w.remapped(0.0, chunk.start, 1.0)).collect(),
+                ..segment
             })
-            .collect::<Vec<_>>()
-            .join("\n")
-    }
-
-    /// Format timestamp for SRT (00:00:05,000)
-    fn format_srt_timestamp(seconds: f64) -> String {
-        let hours = (seconds / 3600.0).floor() as u32;
-        let minutes = ((seconds % 3600.0) / 60.0).floor() as u32;
-        let secs = (seconds % 60.0).floor() as u32;
-        let millis = ((seconds % 1.0) * 1000.0).round() as u32;
-
-        format!("{:02}:{:02}:{:02},{:03}", hours, minutes, secs, millis)
-    }
+        })
+        .filter(|segment| segment.end_time > segment.start_time)
+        .collect();
 
-    /// Export subtitle track to SRT file
-    pub fn export_srt(
-        track: &SubtitleTrack,
-        output_path: &Path,
-    ) -> Result<(), SubtitleError> {
-        let srt_content = Self::generate_srt(&track.segments);
-        fs::write(output_path, srt_content)?;
-        info!("Exported SRT file to: {:?}", output_path);
-        Ok(())
+    for (index, segment) in segments.iter_mut().enumerate() {
+        segment.id = index + 1;
     }
+    segments
 }
 
 #[cfg(test)]
@@ -375,69 +519,87 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_parse_srt_timestamp() {
-        assert_eq!(
-            SubtitleService::parse_srt_timestamp("00:00:05,000").unwrap(),
-            5.0
-        );
-        assert_eq!(
-            SubtitleService::parse_srt_timestamp("00:01:30,500").unwrap(),
-            90.5
-        );
-        assert_eq!(
-            SubtitleService::parse_srt_timestamp("01:00:00,000").unwrap(),
-            3600.0
-        );
-    }
+    fn test_map_segments_to_timeline() {
+        let segment = |id, start_time, end_time| SubtitleSegment {
+            id,
+            start_time,
+            end_time,
+            text: format!("segment {}", id),
+            words: Vec::new(),
+            style: None,
+            cue_settings: None,
+            style_override: None,
+        };
+        let source = vec![
+            segment(1, 0.0, 2.0),    // Before the trimmed region
+            segment(2, 3.0, 6.0),    // Crosses the trim start
+            segment(3, 7.0, 9.0),    // Fully inside
+            segment(4, 13.9, 16.0),  // Only 0.1s left before the clip ends
+            segment(5, 20.0, 22.0),  // After the clip ends
+        ];
+        let clip = Clip {
+            id: "clip".to_string(),
+            media_file_id: "media".to_string(),
+            name: None,
+            track_position: 10.0,
+            duration: 5.0,
+            trim_start: 4.0,
+            trim_end: 14.0,
+            effects: Vec::new(),
+            volume: 1.0,
+            speed: 2.0,
+            generator: None,
+            ken_burns: None,
+            animation_span: None,
+        };
 
-    #[test]
-    fn test_format_srt_timestamp() {
-        assert_eq!(
-            SubtitleService::format_srt_timestamp(5.0),
-            "00:00:05,000"
-        );
-        assert_eq!(
-            SubtitleService::format_srt_timestamp(90.5),
-            "00:01:30,500"
-        );
-        assert_eq!(
-            SubtitleService::format_srt_timestamp(3600.0),
-            "01:00:00,000"
-        );
-    }
+        let mapped = SubtitleService::map_segments_to_timeline(&source, &clip);
 
-    #[test]
-    fn test_parse_srt() {
-        let srt = "1\n00:00:05,000 --> 00:00:08,000\nHello world\n\n2\n00:00:08,000 --> 00:00:12,000\nThis is a test\n";
-        let segments = SubtitleService::parse_srt(srt).unwrap();
-
-        assert_eq!(segments.len(), 2);
-        assert_eq!(segments[0].id, 1);
-        assert_eq!(segments[0].text, "Hello world");
-        assert_eq!(segments[1].id, 2);
-        assert_eq!(segments[1].text, "This is a test");
+        assert_eq!(mapped.len(), 2);
+        assert_eq!(mapped[0].id, 2);
+        assert_eq!((mapped[0].start_time, mapped[0].end_time), (10.0, 11.0));
+        assert_eq!(mapped[1].id, 3);
+        assert_eq!((mapped[1].start_time, mapped[1].end_time), (11.5, 12.5));
     }
 
     #[test]
-    fn test_generate_srt() {
-        let segments = vec![
-            SubtitleSegment {
-                id: 1,
-                start_time: 5.0,
-                end_time: 8.0,
-                text: "Hello world".to_string(),
-            },
-            SubtitleSegment {
-                id: 2,
-                start_time: 8.0,
-                end_time: 12.0,
-                text: "This is a test".to_string(),
-            },
-        ];
+    fn test_segment_cut_by_clip_keeps_its_words() {
+        let word = |text: &str, start_time, end_time| SubtitleWord { text: text.to_string(), start_time, end_time };
+        let source = vec![SubtitleSegment {
+            id: 1,
+            start_time: 0.0,
+            end_time: 4.0,
+            text: "one two three four".to_string(),
+            words: vec![word("one", 0.0, 0.8), word("two", 1.0, 1.8), word("three", 2.2, 3.0), word("four", 3.2, 4.0)],
+            style: None,
+            cue_settings: None,
+            style_override: None,
+        }];
+        let clip = |trim_start: f64, duration: f64| Clip {
+            id: "clip".to_string(),
+            media_file_id: "media".to_string(),
+            name: None,
+            track_position: 0.0,
+            duration,
+            trim_start,
+            trim_end: trim_start + duration,
+            effects: Vec::new(),
+            volume: 1.0,
+            speed: 1.0,
+            generator: None,
+            ken_burns: None,
+            animation_span: None,
+        };
+
+        // Each side of a cut at 2s shows only its own words
+        let first = SubtitleService::map_segments_to_timeline(&source, &clip(0.0, 2.0));
+        assert_eq!(first[0].text, "one two");
+        let second = SubtitleService::map_segments_to_timeline(&source, &clip(2.0, 2.0));
+        assert_eq!(second[0].text, "three four");
+        assert!((second[0].words[0].start_time - 0.2).abs() < 1e-9);
 
-        let srt = SubtitleService::generate_srt(&segments);
-        assert!(srt.contains("00:00:05,000 --> 00:00:08,000"));
-        assert!(srt.contains("Hello world"));
-        assert!(srt.contains("This is a test"));
+        // Uncut segments keep their text as written
+        let whole = SubtitleService::map_segments_to_timeline(&source, &clip(0.0, 4.0));
+        assert_eq!(whole[0].text, "one two three four");
     }
 }
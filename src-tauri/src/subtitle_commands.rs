@@ -1,6 +1,16 @@
+use crate::credential_commands::{self, CredentialState};
+use crate::credentials::OPENAI_PROVIDER;
 use crate::models::{MediaFile, SubtitleSegment, SubtitleTrack, Timeline};
 use crate::subtitle::SubtitleService;
+use crate::subtitle_edit;
+use crate::subtitle_format::{self, SubtitleFormat};
+use crate::subtitle_segmentation::{self, ReadingSpeedWarning, SegmentationOptions};
+use crate::transcription::TranscriptionConfig;
+use crate::translation::{self, TranslationConfig, TranslationProvider};
+use crate::timeline::TimelineError;
+use crate::timeline_commands::TimelineServiceState;
 use log::{info, error};
+use serde::Serialize;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tauri::{State, Window};
@@ -9,23 +19,39 @@ use tokio::sync::Mutex;
 /// Shared subtitle service state
 pub struct SubtitleServiceState {
     pub service: Arc<Mutex<Option<SubtitleService>>>,
+    pub translator: Arc<Mutex<Option<Arc<dyn TranslationProvider>>>>,
 }
 
-/// Set OpenAI API key
+/// Set OpenAI API key and save it for later launches
 #[tauri::command]
 pub async fn set_openai_api_key(
     api_key: String,
     state: State<'_, SubtitleServiceState>,
+    credential_state: State<'_, CredentialState>,
 ) -> Result<(), String> {
     info!("Setting OpenAI API key");
 
-    let service = SubtitleService::new(api_key)
+    credential_commands::save_provider_credential(&credential_state, &state, OPENAI_PROVIDER, api_key, None).await
+}
+
+/// Choose the transcription backend, returning its name
+#[tauri::command]
+pub async fn set_transcription_provider(
+    config: TranscriptionConfig,
+    state: State<'_, SubtitleServiceState>,
+) -> Result<String, String> {
+    let provider = config.build()
+        .map_err(|e| format!("Invalid transcription provider: {}", e))?;
+
+    let service = SubtitleService::new(provider)
         .map_err(|e| format!("Failed to initialize subtitle service: {}", e))?;
+    let name = service.provider_name();
+    info!("Using transcription provider: {}", name);
 
     let mut service_lock = state.service.lock().await;
     *service_lock = Some(service);
 
-    Ok(())
+    Ok(name)
 }
 
 /// Check if subtitle service is available
@@ -45,89 +71,258 @@ pub async fn transcribe_timeline_audio(
     language: Option<String>,
     window: Window,
     state: State<'_, SubtitleServiceState>,
+    timeline_state: State<'_, TimelineServiceState>,
 ) -> Result<SubtitleTrack, String> {
     info!("Transcribing timeline audio: {}", timeline_id);
 
+    let timeline = {
+        let timeline_service = timeline_state.service.lock().await;
+        timeline_service.get_timeline()
+            .map_err(|e| e.to_string())?
+            .clone()
+    };
+
+    if timeline.id != timeline_id {
+        return Err(format!("Timeline {} is not the current timeline", timeline_id));
+    }
+
     let service_lock = state.service.lock().await;
     let service = service_lock.as_ref()
         .ok_or_else(|| "Subtitle service not initialized. Please set API key first.".to_string())?;
 
-    // For now, transcribe the first media file with audio
-    // TODO: In future, merge all audio tracks
-    let media_file = media_files.iter()
-        .find(|f| f.codec.audio.is_some())
-        .ok_or_else(|| "No audio track found in timeline".to_string())?;
-
-    let track = service.transcribe_media_file(media_file, language, Some(window))
+    let track = service.transcribe_timeline(&timeline, &media_files, language, Some(window))
         .await
         .map_err(|e| format!("Transcription failed: {}", e))?;
 
     Ok(track)
 }
 
-/// Update a subtitle segment
+/// Re-segmented subtitles, with cues that remain too fast to read
+#[derive(Debug, Serialize)]
+pub struct ResegmentResult {
+    pub track: SubtitleTrack,
+    pub warnings: Vec<ReadingSpeedWarning>,
+}
+
+/// Split subtitles into readable cues by line length, duration and reading speed
+#[tauri::command]
+pub async fn resegment_subtitles(
+    track: SubtitleTrack,
+    options: Option<SegmentationOptions>,
+) -> Result<ResegmentResult, String> {
+    let options = options.unwrap_or_default();
+    if options.max_chars_per_line == 0 || options.max_lines == 0 {
+        return Err("Line length and line count must be at least 1".to_string());
+    }
+    if options.max_duration <= 0.0 || options.max_cps <= 0.0 || options.min_gap < 0.0 {
+        return Err("Duration and reading speed limits must be positive".to_string());
+    }
+
+    let segments = subtitle_segmentation::resegment(&track.segments, &options);
+    let warnings = subtitle_segmentation::check_reading_speed(&segments, &options);
+    info!("Re-segmented {} subtitles into {} cues ({} too fast)",
+        track.segments.len(), segments.len(), warnings.len());
+
+    Ok(ResegmentResult {
+        track: SubtitleTrack { segments, ..track },
+        warnings,
+    })
+}
+
+/// Choose the translation backend, returning its name
+#[tauri::command]
+pub async fn set_translation_provider(
+    config: TranslationConfig,
+    state: State<'_, SubtitleServiceState>,
+) -> Result<String, String> {
+    let provider = config.build()
+        .map_err(|e| format!("Invalid translation provider: {}", e))?;
+    let name = provider.name();
+    info!("Using translation provider: {}", name);
+
+    let mut translator = state.translator.lock().await;
+    *translator = Some(Arc::from(provider));
+
+    Ok(name)
+}
+
+/// Translate a subtitle track into another language, keeping its timing
+#[tauri::command]
+pub async fn translate_subtitles(
+    track: SubtitleTrack,
+    target_language: String,
+    state: State<'_, SubtitleServiceState>,
+) -> Result<SubtitleTrack, String> {
+    info!("Translating {} subtitles to {}", track.language, target_language);
+
+    // Release the lock so the provider can be changed during a long translation
+    let provider = state.translator.lock().await.clone()
+        .ok_or_else(|| "Translation provider not configured".to_string())?;
+
+    translation::translate_track(provider.as_ref(), &track, &target_language)
+        .await
+        .map_err(|e| {
+            error!("Translation failed: {}", e);
+            format!("Translation failed: {}", e)
+        })
+}
+
+/// Apply an edit to a subtitle track of the current timeline, returning the edited track
+async fn edit_subtitles<T>(
+    timeline_state: &State<'_, TimelineServiceState>,
+    timeline_id: &str,
+    language: &str,
+    edit: impl FnOnce(&mut SubtitleTrack) -> Result<T, TimelineError>,
+) -> Result<SubtitleTrack, String> {
+    let mut service = timeline_state.service.lock().await;
+
+    let current_id = &service.get_timeline().map_err(|e| e.to_string())?.id;
+    if current_id != timeline_id {
+        return Err(format!("Timeline {} is not the current timeline", timeline_id));
+    }
+
+    let (_, track) = service.edit_subtitles(language, edit)
+        .map_err(|e| e.to_string())?;
+    Ok(track)
+}
+
+/// Update a subtitle segment's text and/or times
 #[tauri::command]
 pub async fn update_subtitle_segment(
-    _timeline_id: String,
-    _segment_id: usize,
-    _new_text: String,
-    _new_start: Option<f64>,
-    _new_end: Option<f64>,
-) -> Result<(), String> {
-    // Note: This command would need access to timeline service to persist changes
-    // For now, we'll rely on the frontend to update the timeline and call save_timeline
-    // This is consistent with the optimistic update pattern
+    timeline_id: String,
+    language: String,
+    segment_id: usize,
+    new_text: Option<String>,
+    new_start: Option<f64>,
+    new_end: Option<f64>,
+    timeline_state: State<'_, TimelineServiceState>,
+) -> Result<SubtitleTrack, String> {
+    edit_subtitles(&timeline_state, &timeline_id, &language, |track| {
+        subtitle_edit::update_segment(track, segment_id, new_text, new_start, new_end)
+    }).await
+}
+
+/// Split a subtitle segment in two at a timeline time
+#[tauri::command]
+pub async fn split_subtitle_segment(
+    timeline_id: String,
+    language: String,
+    segment_id: usize,
+    time: f64,
+    timeline_state: State<'_, TimelineServiceState>,
+) -> Result<SubtitleTrack, String> {
+    edit_subtitles(&timeline_state, &timeline_id, &language, |track| {
+        subtitle_edit::split_segment(track, segment_id, time)
+    }).await
+}
 
-    Ok(())
+/// Merge a subtitle segment with the one after it
+#[tauri::command]
+pub async fn merge_subtitle_segments(
+    timeline_id: String,
+    language: String,
+    segment_id: usize,
+    timeline_state: State<'_, TimelineServiceState>,
+) -> Result<SubtitleTrack, String> {
+    edit_subtitles(&timeline_state, &timeline_id, &language, |track| {
+        subtitle_edit::merge_with_next(track, segment_id)
+    }).await
+}
+
+/// Add a subtitle segment
+#[tauri::command]
+pub async fn insert_subtitle_segment(
+    timeline_id: String,
+    language: String,
+    start_time: f64,
+    end_time: f64,
+    text: String,
+    timeline_state: State<'_, TimelineServiceState>,
+) -> Result<SubtitleTrack, String> {
+    edit_subtitles(&timeline_state, &timeline_id, &language, |track| {
+        subtitle_edit::insert_segment(track, start_time, end_time, text)
+    }).await
+}
+
+/// Remove a subtitle segment
+#[tauri::command]
+pub async fn delete_subtitle_segment(
+    timeline_id: String,
+    language: String,
+    segment_id: usize,
+    timeline_state: State<'_, TimelineServiceState>,
+) -> Result<SubtitleTrack, String> {
+    edit_subtitles(&timeline_state, &timeline_id, &language, |track| {
+        subtitle_edit::delete_segment(track, segment_id)
+    }).await
+}
+
+/// Move every segment of a subtitle track by an offset in seconds
+#[tauri::command]
+pub async fn shift_subtitles(
+    timeline_id: String,
+    language: String,
+    offset: f64,
+    timeline_state: State<'_, TimelineServiceState>,
+) -> Result<SubtitleTrack, String> {
+    info!("Shifting {} subtitles by {}s", language, offset);
+    edit_subtitles(&timeline_state, &timeline_id, &language, |track| {
+        subtitle_edit::shift_all(track, offset)
+    }).await
 }
 
 /// Toggle subtitles on/off for timeline
 #[tauri::command]
 pub async fn toggle_subtitles(
-    _timeline_id: String,
-    _enabled: bool,
+    timeline_id: String,
+    enabled: bool,
+    timeline_state: State<'_, TimelineServiceState>,
 ) -> Result<(), String> {
-    // Note: This would be handled by timeline service
-    // Frontend will update timeline.subtitle_enabled and persist
+    let mut service = timeline_state.service.lock().await;
 
-    Ok(())
+    let current_id = &service.get_timeline().map_err(|e| e.to_string())?.id;
+    if *current_id != timeline_id {
+        return Err(format!("Timeline {} is not the current timeline", timeline_id));
+    }
+
+    service.set_subtitles_enabled(enabled)
+        .map_err(|e| e.to_string())
 }
 
-/// Export subtitles to SRT file
+/// Export subtitles, choosing the format from the file extension unless given
 #[tauri::command]
-pub async fn export_subtitles_srt(
+pub async fn export_subtitles(
     track: SubtitleTrack,
     output_path: String,
-) -> Result<(), String> {
-    info!("Exporting subtitles to SRT: {}", output_path);
+    format: Option<SubtitleFormat>,
+) -> Result<SubtitleFormat, String> {
+    info!("Exporting subtitles to: {}", output_path);
 
     let path = PathBuf::from(output_path);
-    SubtitleService::export_srt(&track, &path)
-        .map_err(|e| format!("Failed to export SRT: {}", e))?;
-
-    Ok(())
+    subtitle_format::write_file(&track, &path, format)
+        .map_err(|e| format!("Failed to export subtitles: {}", e))
 }
 
-/// Import subtitles from SRT file
+/// Import subtitles, detecting the format from the file content
 #[tauri::command]
-pub async fn import_subtitles_srt(
+pub async fn import_subtitles(
     file_path: String,
     language: Option<String>,
+    format: Option<SubtitleFormat>,
 ) -> Result<SubtitleTrack, String> {
-    info!("Importing subtitles from SRT: {}", file_path);
+    info!("Importing subtitles from: {}", file_path);
 
     let path = PathBuf::from(&file_path);
-    let content = std::fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read SRT file: {}", e))?;
-
-    let segments = SubtitleService::parse_srt(&content)
-        .map_err(|e| format!("Failed to parse SRT: {}", e))?;
+    let parsed = subtitle_format::read_file(&path, format)
+        .map_err(|e| format!("Failed to import subtitles: {}", e))?;
+    info!("Imported {} segments as {:?}", parsed.segments.len(), parsed.format);
 
     Ok(SubtitleTrack {
-        segments,
+        segments: parsed.segments,
         language: language.unwrap_or_else(|| "en".to_string()),
         source: crate::models::SubtitleSource::Imported {
             file_path: path,
         },
+        ass_header: parsed.ass_header,
     })
 }
@@ -0,0 +1,422 @@
+use crate::models::{SubtitleSegment, SubtitleTrack, SubtitleWord};
+use crate::timeline::TimelineError;
+
+/// Shortest segment an edit may leave behind (seconds)
+pub const MIN_SEGMENT_DURATION: f64 = 0.1;
+
+/// Tolerance for touching segments, so floating point rounding is not an overlap
+const TIME_EPSILON: f64 = 1e-6;
+
+fn invalid(message: String) -> TimelineError {
+    TimelineError::InvalidOperation(message)
+}
+
+fn segment_index(track: &SubtitleTrack, segment_id: usize) -> Result<usize, TimelineError> {
+    track.segments.iter()
+        .position(|s| s.id == segment_id)
+        .ok_or_else(|| invalid(format!("Subtitle segment not found: {}", segment_id)))
+}
+
+fn next_id(track: &SubtitleTrack) -> usize {
+    track.segments.iter().map(|s| s.id).max().unwrap_or(0) + 1
+}
+
+/// Something wrong with a segment or a pair of segments
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SegmentIssue {
+    StartsBeforeZero(usize),
+    TooShort(usize),
+    /// Ids of the two segments, lower first
+    Overlap(usize, usize),
+}
+
+/// Segments starting before zero, shorter than `MIN_SEGMENT_DURATION` or
+/// overlapping, each with a message describing it
+///
+/// Segments must already be sorted by start time.
+fn segment_issues(segments: &[SubtitleSegment]) -> Vec<(SegmentIssue, String)> {
+    let mut issues = Vec::new();
+    for segment in segments {
+        if segment.start_time < 0.0 {
+            issues.push((SegmentIssue::StartsBeforeZero(segment.id), format!(
+                "Subtitle segment {} starts before the timeline ({:.3}s)", segment.id, segment.start_time
+            )));
+        }
+        if segment.end_time - segment.start_time < MIN_SEGMENT_DURATION - TIME_EPSILON {
+            issues.push((SegmentIssue::TooShort(segment.id), format!(
+                "Subtitle segment {} must end at least {}s after it starts ({:.3}s - {:.3}s)",
+                segment.id, MIN_SEGMENT_DURATION, segment.start_time, segment.end_time
+            )));
+        }
+    }
+
+    for (i, first) in segments.iter().enumerate() {
+        for second in segments[i + 1..].iter().take_while(|s| s.start_time < first.end_time - TIME_EPSILON) {
+            let ids = (first.id.min(second.id), first.id.max(second.id));
+            issues.push((SegmentIssue::Overlap(ids.0, ids.1), format!(
+                "Subtitle segments {} and {} overlap ({:.3}s > {:.3}s)",
+                first.id, second.id, first.end_time, second.start_time
+            )));
+        }
+    }
+
+    issues
+}
+
+fn sorted(mut segments: Vec<SubtitleSegment>) -> Vec<SubtitleSegment> {
+    segments.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+    segments
+}
+
+/// Apply an edit to a copy of the segments and keep it only if it adds no problems
+///
+/// Imported subtitles often have overlapping or very short cues already;
+/// those don't stand in the way of editing the track.
+fn edit<T>(
+    track: &mut SubtitleTrack,
+    change: impl FnOnce(&mut Vec<SubtitleSegment>) -> Result<T, TimelineError>,
+) -> Result<T, TimelineError> {
+    let mut segments = track.segments.clone();
+    let result = change(&mut segments)?;
+    let segments = sorted(segments);
+
+    let existing: Vec<SegmentIssue> = segment_issues(&sorted(track.segments.clone()))
+        .into_iter()
+        .map(|(issue, _)| issue)
+        .collect();
+    if let Some((_, message)) = segment_issues(&segments).into_iter().find(|(issue, _)| !existing.contains(issue)) {
+        return Err(invalid(message));
+    }
+
+    track.segments = segments;
+    Ok(result)
+}
+
+/// Change a segment's text and/or times
+///
+/// Word timing is dropped when the text changes, and trimmed to the new
+/// times otherwise.
+pub fn update_segment(
+    track: &mut SubtitleTrack,
+    segment_id: usize,
+    text: Option<String>,
+    start_time: Option<f64>,
+    end_time: Option<f64>,
+) -> Result<(), TimelineError> {
+    let index = segment_index(track, segment_id)?;
+
+    edit(track, |segments| {
+        let segment = &mut segments[index];
+        if let Some(text) = text {
+            if text.trim().is_empty() {
+                return Err(invalid("Subtitle text cannot be empty".to_string()));
+            }
+            if text != segment.text {
+                segment.text = text;
+                segment.words.clear();
+            }
+        }
+        segment.start_time = start_time.unwrap_or(segment.start_time);
+        segment.end_time = end_time.unwrap_or(segment.end_time);
+
+        let (start, end) = (segment.start_time, segment.end_time);
+        segment.words.retain(|w| w.start_time >= start - TIME_EPSILON && w.end_time <= end + TIME_EPSILON);
+        Ok(())
+    })
+}
+
+/// Split a segment in two at `time`, returning the new second segment's id
+///
+/// Words go to the side their midpoint falls on. Without word timing the
+/// text is divided at the word nearest the same fraction of the segment.
+pub fn split_segment(track: &mut SubtitleTrack, segment_id: usize, time: f64) -> Result<usize, TimelineError> {
+    let index = segment_index(track, segment_id)?;
+    let new_id = next_id(track);
+
+    edit(track, |segments| {
+        let segment = &segments[index];
+        if time <= segment.start_time || time >= segment.end_time {
+            return Err(invalid(format!(
+                "Split time {:.3}s is outside subtitle segment {}", time, segment_id
+            )));
+        }
+
+        let (first_text, second_text, first_words, second_words) = if segment.words.is_empty() {
+            let words: Vec<&str> = segment.text.split_whitespace().collect();
+            let fraction = (time - segment.start_time) / (segment.end_time - segment.start_time);
+            let at = ((words.len() as f64 * fraction).round() as usize).clamp(1, words.len().max(2) - 1);
+            let (first, second) = words.split_at(at.min(words.len()));
+            (first.join(" "), second.join(" "), Vec::new(), Vec::new())
+        } else {
+            let (first, second): (Vec<SubtitleWord>, Vec<SubtitleWord>) = segment.words.iter()
+                .cloned()
+                .partition(|w| (w.start_time + w.end_time) / 2.0 < time);
+            (join_words(&first), join_words(&second), first, second)
+        };
+
+        if first_text.is_empty() || second_text.is_empty() {
+            return Err(invalid(format!(
+                "Splitting subtitle segment {} at {:.3}s would leave one side without text", segment_id, time
+            )));
+        }
+
+        let mut second = segment.clone();
+        second.id = new_id;
+        second.start_time = time;
+        second.text = second_text;
+        second.words = second_words;
+
+        let first = &mut segments[index];
+        first.end_time = time;
+        first.text = first_text;
+        first.words = first_words;
+
+        segments.insert(index + 1, second);
+        Ok(new_id)
+    })
+}
+
+/// Merge a segment with the one that follows it
+pub fn merge_with_next(track: &mut SubtitleTrack, segment_id: usize) -> Result<(), TimelineError> {
+    let index = segment_index(track, segment_id)?;
+    if index + 1 >= track.segments.len() {
+        return Err(invalid(format!("Subtitle segment {} is the last segment", segment_id)));
+    }
+
+    edit(track, |segments| {
+        let next = segments.remove(index + 1);
+        let segment = &mut segments[index];
+        segment.end_time = next.end_time;
+        segment.text = format!("{}\n{}", segment.text, next.text);
+        segment.words.extend(next.words);
+        Ok(())
+    })
+}
+
+/// Add a segment, returning its id
+pub fn insert_segment(
+    track: &mut SubtitleTrack,
+    start_time: f64,
+    end_time: f64,
+    text: String,
+) -> Result<usize, TimelineError> {
+    if text.trim().is_empty() {
+        return Err(invalid("Subtitle text cannot be empty".to_string()));
+    }
+    let id = next_id(track);
+
+    edit(track, |segments| {
+        segments.push(SubtitleSegment {
+            id,
+            start_time,
+            end_time,
+            text,
+            words: Vec::new(),
+            style: None,
+            cue_settings: None,
+            style_override: None,
+        });
+        Ok(id)
+    })
+}
+
+/// Remove a segment
+pub fn delete_segment(track: &mut SubtitleTrack, segment_id: usize) -> Result<(), TimelineError> {
+    let index = segment_index(track, segment_id)?;
+    track.segments.remove(index);
+    Ok(())
+}
+
+/// Move every segment by `offset` seconds (negative is earlier)
+pub fn shift_all(track: &mut SubtitleTrack, offset: f64) -> Result<(), TimelineError> {
+    if !offset.is_finite() {
+        return Err(invalid(format!("Invalid subtitle offset: {}", offset)));
+    }
+
+    edit(track, |segments| {
+        for segment in segments.iter_mut() {
+            segment.start_time += offset;
+            segment.end_time += offset;
+            for word in &mut segment.words {
+                word.start_time += offset;
+                word.end_time += offset;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Remove a time range from a track and move later segments back to close the gap
+///
+/// Segments inside the range are dropped, segments crossing it lose the
+/// words inside it. Unlike the other edits this never fails, since ripple
+/// deletes must keep subtitles in step with the clips.
+pub fn cut_range(track: &mut SubtitleTrack, start: f64, end: f64) {
+    let removed = end - start;
+    let map_time = |t: f64| {
+        if t <= start {
+            t
+        } else if t >= end {
+            t - removed
+        } else {
+            start
+        }
+    };
+
+    track.segments.retain_mut(|segment| {
+        let had_words = !segment.words.is_empty();
+        segment.words.retain(|w| (w.start_time + w.end_time) / 2.0 < start || (w.start_time + w.end_time) / 2.0 > end);
+        for word in &mut segment.words {
+            word.start_time = map_time(word.start_time);
+            word.end_time = map_time(word.end_time);
+        }
+
+        segment.start_time = map_time(segment.start_time);
+        segment.end_time = map_time(segment.end_time);
+
+        if had_words {
+            if segment.words.is_empty() {
+                return false;
+            }
+            segment.text = join_words(&segment.words);
+        }
+        segment.end_time - segment.start_time >= MIN_SEGMENT_DURATION
+    });
+}
+
+/// Segment text made of its words, for segments that lost some of them
+pub(crate) fn join_words(words: &[SubtitleWord]) -> String {
+    words.iter().map(|w| w.text.as_str()).collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SubtitleSource;
+
+    fn segment(id: usize, start_time: f64, end_time: f64, text: &str) -> SubtitleSegment {
+        SubtitleSegment {
+            id,
+            start_time,
+            end_time,
+            text: text.to_string(),
+            words: Vec::new(),
+            style: None,
+            cue_settings: None,
+            style_override: None,
+        }
+    }
+
+    fn track(segments: Vec<SubtitleSegment>) -> SubtitleTrack {
+        SubtitleTrack {
+            segments,
+            language: "en".to_string(),
+            source: SubtitleSource::Manual,
+            ass_header: None,
+        }
+    }
+
+    #[test]
+    fn test_update_rejects_overlap_and_inverted_times() {
+        let mut track = track(vec![segment(1, 0.0, 2.0, "One"), segment(2, 3.0, 5.0, "Two")]);
+
+        assert!(update_segment(&mut track, 1, None, None, Some(3.5)).is_err());
+        assert!(update_segment(&mut track, 2, None, Some(6.0), None).is_err());
+        assert!(update_segment(&mut track, 2, Some("  ".to_string()), None, None).is_err());
+        assert!(update_segment(&mut track, 9, None, None, None).is_err());
+        // Failed edits leave the track untouched
+        assert_eq!(track.segments[0].end_time, 2.0);
+
+        update_segment(&mut track, 1, Some("Uno".to_string()), None, Some(3.0)).unwrap();
+        assert_eq!((track.segments[0].text.as_str(), track.segments[0].end_time), ("Uno", 3.0));
+    }
+
+    #[test]
+    fn test_edits_allowed_around_existing_overlaps() {
+        // As imported: 1 and 2 overlap, 3 is very short
+        let mut track = track(vec![
+            segment(1, 0.0, 2.5, "One"),
+            segment(2, 2.0, 4.0, "Two"),
+            segment(3, 5.0, 5.05, "Three"),
+            segment(4, 6.0, 7.0, "Four"),
+        ]);
+        assert_eq!(segment_issues(&track.segments).len(), 2);
+
+        update_segment(&mut track, 4, Some("Vier".to_string()), None, None).unwrap();
+        update_segment(&mut track, 1, Some("Eins".to_string()), None, Some(2.2)).unwrap();
+        update_segment(&mut track, 3, Some("Drei".to_string()), None, None).unwrap();
+        insert_segment(&mut track, 8.0, 9.0, "Five".to_string()).unwrap();
+        assert_eq!(track.segments[0].text, "Eins");
+
+        // A new overlap or a newly short segment is still refused
+        assert!(update_segment(&mut track, 4, None, Some(4.5), None).is_err());
+        assert!(update_segment(&mut track, 4, None, None, Some(6.05)).is_err());
+        assert_eq!((track.segments[3].start_time, track.segments[3].end_time), (6.0, 7.0));
+    }
+
+    #[test]
+    fn test_split_and_merge() {
+        let mut track = track(vec![segment(1, 0.0, 4.0, "one two three four")]);
+
+        let second = split_segment(&mut track, 1, 2.0).unwrap();
+        assert_eq!(second, 2);
+        assert_eq!(track.segments[0].text, "one two");
+        assert_eq!((track.segments[1].start_time, track.segments[1].text.as_str()), (2.0, "three four"));
+        assert!(split_segment(&mut track, 1, 3.0).is_err());
+
+        merge_with_next(&mut track, 1).unwrap();
+        assert_eq!(track.segments.len(), 1);
+        assert_eq!((track.segments[0].end_time, track.segments[0].text.as_str()), (4.0, "one two\nthree four"));
+        assert!(merge_with_next(&mut track, 1).is_err());
+    }
+
+    #[test]
+    fn test_split_uses_word_timing() {
+        let word = |text: &str, start_time, end_time| SubtitleWord { text: text.to_string(), start_time, end_time };
+        let mut track = track(vec![SubtitleSegment {
+            words: vec![word("Hello", 0.0, 0.4), word("big", 0.5, 0.7), word("world", 2.0, 2.5)],
+            ..segment(1, 0.0, 3.0, "Hello big world")
+        }]);
+
+        split_segment(&mut track, 1, 1.5).unwrap();
+        assert_eq!(track.segments[0].text, "Hello big");
+        assert_eq!(track.segments[1].words, vec![word("world", 2.0, 2.5)]);
+    }
+
+    #[test]
+    fn test_cut_range() {
+        let word = |text: &str, start_time, end_time| SubtitleWord { text: text.to_string(), start_time, end_time };
+        let mut track = track(vec![
+            SubtitleSegment {
+                words: vec![word("So", 0.0, 0.5), word("um", 1.0, 1.4), word("yes", 2.0, 2.5)],
+                ..segment(1, 0.0, 2.5, "So um yes")
+            },
+            segment(2, 1.8, 2.9, "Gone"),
+            segment(3, 4.0, 5.0, "Later"),
+        ]);
+
+        cut_range(&mut track, 0.75, 3.0);
+
+        assert_eq!(track.segments.len(), 2);
+        assert_eq!(track.segments[0].text, "So");
+        assert_eq!(track.segments[0].end_time, 0.75);
+        assert_eq!((track.segments[1].start_time, track.segments[1].end_time), (1.75, 2.75));
+    }
+
+    #[test]
+    fn test_insert_delete_and_shift() {
+        let mut track = track(vec![segment(1, 1.0, 2.0, "One"), segment(2, 5.0, 6.0, "Two")]);
+
+        assert!(insert_segment(&mut track, 1.5, 3.0, "Overlap".to_string()).is_err());
+        let id = insert_segment(&mut track, 3.0, 4.0, "Middle".to_string()).unwrap();
+        assert_eq!(id, 3);
+        assert_eq!(track.segments.iter().map(|s| s.id).collect::<Vec<_>>(), vec![1, 3, 2]);
+
+        delete_segment(&mut track, 3).unwrap();
+        assert!(delete_segment(&mut track, 3).is_err());
+
+        assert!(shift_all(&mut track, -1.5).is_err());
+        shift_all(&mut track, -0.5).unwrap();
+        assert_eq!((track.segments[0].start_time, track.segments[1].end_time), (0.5, 5.5));
+    }
+}
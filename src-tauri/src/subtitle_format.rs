@@ -0,0 +1,618 @@
+use crate::models::{SubtitleError, SubtitleSegment, SubtitleTrack};
+use log::info;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Subtitle file formats that can be imported and exported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubtitleFormat {
+    Srt,
+    Vtt,  // WebVTT
+    Ass,  // Advanced SubStation Alpha (v4.00+)
+    Ssa,  // SubStation Alpha (v4.00)
+    Sbv,  // YouTube SubViewer
+}
+
+/// Segments read from a subtitle file
+#[derive(Debug, Clone)]
+pub struct ParsedSubtitles {
+    pub format: SubtitleFormat,
+    pub segments: Vec<SubtitleSegment>,
+    pub ass_header: Option<String>,  // ASS/SSA sections before [Events]
+}
+
+const ASS_EVENT_FORMAT: &str = "Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text";
+const SSA_EVENT_FORMAT: &str = "Marked, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text";
+
+const DEFAULT_ASS_HEADER: &str = "[Script Info]
+ScriptType: v4.00+
+PlayResX: 1920
+PlayResY: 1080
+WrapStyle: 0
+ScaledBorderAndShadow: yes
+
+[V4+ Styles]
+Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding
+Style: Default,Arial,48,&H00FFFFFF,&H000000FF,&H00000000,&H80000000,0,0,0,0,100,100,0,0,1,2,1,2,20,20,40,1";
+
+const DEFAULT_SSA_HEADER: &str = "[Script Info]
+ScriptType: v4.00
+PlayResX: 1920
+PlayResY: 1080
+
+[V4 Styles]
+Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, TertiaryColour, BackColour, Bold, Italic, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, AlphaLevel, Encoding
+Style: Default,Arial,48,16777215,255,0,0,0,0,1,2,1,2,20,20,40,0,1";
+
+impl SubtitleFormat {
+    /// Format implied by a file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        match ext.as_str() {
+            "srt" => Some(Self::Srt),
+            "vtt" => Some(Self::Vtt),
+            "ass" => Some(Self::Ass),
+            "ssa" => Some(Self::Ssa),
+            "sbv" => Some(Self::Sbv),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Srt => "srt",
+            Self::Vtt => "vtt",
+            Self::Ass => "ass",
+            Self::Ssa => "ssa",
+            Self::Sbv => "sbv",
+        }
+    }
+
+    /// Recognise a format from file content
+    pub fn detect(content: &str) -> Option<Self> {
+        let content = normalize(content);
+        let first_line = content.lines().find(|l| !l.trim().is_empty())?.trim();
+
+        if first_line.starts_with("WEBVTT") {
+            Some(Self::Vtt)
+        } else if content.contains("[Script Info]") || content.contains("[Events]") {
+            if content.contains("[V4+ Styles]") || content.contains("v4.00+") {
+                Some(Self::Ass)
+            } else {
+                Some(Self::Ssa)
+            }
+        } else if content.contains("-->") {
+            Some(Self::Srt)
+        } else if parse_sbv_timing(first_line).is_some() {
+            Some(Self::Sbv)
+        } else {
+            None
+        }
+    }
+}
+
+/// ISO 639-2 code for an ISO 639-1 language, as MP4 and Matroska expect
+///
+/// Codes that are already three letters, or not in the table, pass through.
+pub fn iso639_2(language: &str) -> String {
+    let code = language.split(['-', '_']).next().unwrap_or_default().to_lowercase();
+    let mapped = match code.as_str() {
+        "ar" => "ara", "bn" => "ben", "cs" => "cze", "da" => "dan", "de" => "ger",
+        "el" => "gre", "en" => "eng", "es" => "spa", "fa" => "per", "fi" => "fin",
+        "fr" => "fre", "he" => "heb", "hi" => "hin", "hu" => "hun", "id" => "ind",
+        "it" => "ita", "ja" => "jpn", "ko" => "kor", "nl" => "dut", "no" => "nor",
+        "pl" => "pol", "pt" => "por", "ro" => "rum", "ru" => "rus", "sv" => "swe",
+        "th" => "tha", "tr" => "tur", "uk" => "ukr", "vi" => "vie", "zh" => "chi",
+        _ => return if code.is_empty() { "und".to_string() } else { code },
+    };
+    mapped.to_string()
+}
+
+/// Read a subtitle file, detecting the format from its content or extension
+pub fn read_file(path: &Path, format: Option<SubtitleFormat>) -> Result<ParsedSubtitles, SubtitleError> {
+    let bytes = fs::read(path)?;
+    let content = String::from_utf8_lossy(&bytes);
+
+    let format = format
+        .or_else(|| SubtitleFormat::detect(&content))
+        .or_else(|| SubtitleFormat::from_path(path))
+        .ok_or_else(|| SubtitleError::InvalidFormat("Unrecognised subtitle format".into()))?;
+
+    let parsed = parse(&content, format)?;
+    info!("Read {} {:?} subtitles from {:?}", parsed.segments.len(), format, path);
+    Ok(parsed)
+}
+
+/// Write a subtitle track, choosing the format from the extension when not given
+pub fn write_file(
+    track: &SubtitleTrack,
+    path: &Path,
+    format: Option<SubtitleFormat>,
+) -> Result<SubtitleFormat, SubtitleError> {
+    let format = format
+        .or_else(|| SubtitleFormat::from_path(path))
+        .unwrap_or(SubtitleFormat::Srt);
+
+    fs::write(path, generate(track, format))?;
+    info!("Exported {:?} subtitles to {:?}", format, path);
+    Ok(format)
+}
+
+/// Parse subtitle file content in the given format
+pub fn parse(content: &str, format: SubtitleFormat) -> Result<ParsedSubtitles, SubtitleError> {
+    let content = normalize(content);
+    let (segments, ass_header) = match format {
+        SubtitleFormat::Srt => (parse_srt(&content)?, None),
+        SubtitleFormat::Vtt => (parse_vtt(&content)?, None),
+        SubtitleFormat::Ass | SubtitleFormat::Ssa => {
+            let (segments, header) = parse_ass(&content)?;
+            (segments, Some(header).filter(|h| !h.is_empty()))
+        }
+        SubtitleFormat::Sbv => (parse_sbv(&content)?, None),
+    };
+
+    Ok(ParsedSubtitles { format, segments, ass_header })
+}
+
+/// Generate subtitle file content in the given format
+pub fn generate(track: &SubtitleTrack, format: SubtitleFormat) -> String {
+    match format {
+        SubtitleFormat::Srt => generate_srt(&track.segments),
+        SubtitleFormat::Vtt => generate_vtt(&track.segments),
+        SubtitleFormat::Ass | SubtitleFormat::Ssa => generate_ass(track, format),
+        SubtitleFormat::Sbv => generate_sbv(&track.segments),
+    }
+}
+
+/// Strip a byte order mark and convert CRLF/CR line endings
+fn normalize(content: &str) -> String {
+    content.trim_start_matches('\u{feff}').replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// Split content into blocks of non-blank lines
+fn blocks(content: &str) -> Vec<Vec<&str>> {
+    let mut blocks = Vec::new();
+    let mut current = Vec::new();
+
+    for line in content.lines() {
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                blocks.push(std::mem::take(&mut current));
+            }
+        } else {
+            current.push(line);
+        }
+    }
+    if !current.is_empty() {
+        blocks.push(current);
+    }
+
+    blocks
+}
+
+/// Parse "HH:MM:SS,mmm", "HH:MM:SS.mmm", "MM:SS.mmm" or "H:MM:SS.cc"
+pub fn parse_timestamp(timestamp: &str) -> Option<f64> {
+    let parts: Vec<&str> = timestamp.trim().split(':').collect();
+    let (hours, minutes, seconds) = match parts.as_slice() {
+        [h, m, s] => (h.parse::<f64>().ok()?, m.parse::<f64>().ok()?, *s),
+        [m, s] => (0.0, m.parse::<f64>().ok()?, *s),
+        _ => return None,
+    };
+    let seconds: f64 = seconds.replace(',', ".").parse().ok()?;
+
+    if minutes < 0.0 || seconds < 0.0 || hours < 0.0 {
+        return None;
+    }
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
+/// Format a timestamp with `fraction_digits` (2 or 3) after `separator`
+fn format_timestamp(seconds: f64, separator: char, fraction_digits: u32, pad_hours: bool) -> String {
+    let scale = 10u64.pow(fraction_digits);
+    let total = (seconds.max(0.0) * scale as f64).round() as u64;
+    let fraction = total % scale;
+    let total_secs = total / scale;
+    let (hours, minutes, secs) = (total_secs / 3600, (total_secs % 3600) / 60, total_secs % 60);
+
+    if pad_hours {
+        format!("{:02}:{:02}:{:02}{}{:0width$}", hours, minutes, secs, separator, fraction, width = fraction_digits as usize)
+    } else {
+        format!("{}:{:02}:{:02}{}{:0width$}", hours, minutes, secs, separator, fraction, width = fraction_digits as usize)
+    }
+}
+
+/// Format timestamp for SRT (00:00:05,000)
+pub fn format_srt_timestamp(seconds: f64) -> String {
+    format_timestamp(seconds, ',', 3, true)
+}
+
+fn segment(id: usize, start_time: f64, end_time: f64, text: String) -> SubtitleSegment {
+    SubtitleSegment {
+        id,
+        start_time,
+        end_time,
+        text,
+        words: Vec::new(),
+        style: None,
+        cue_settings: None,
+        style_override: None,
+    }
+}
+
+// ============================================================================
+// SRT
+// ============================================================================
+
+/// Parse SRT; blank-line tolerant, with or without cue numbers
+pub fn parse_srt(content: &str) -> Result<Vec<SubtitleSegment>, SubtitleError> {
+    let mut segments = Vec::new();
+
+    for block in blocks(content) {
+        let timing_index = block.iter()
+            .position(|l| l.contains("-->"))
+            .ok_or_else(|| SubtitleError::InvalidSRT(format!("Missing timestamps in block: {}", block[0])))?;
+
+        let (start_time, end_time, _) = parse_arrow_timing(block[timing_index])
+            .ok_or_else(|| SubtitleError::InvalidSRT(format!("Invalid timestamp line: {}", block[timing_index])))?;
+
+        let id = block[..timing_index].first()
+            .and_then(|l| l.trim().parse::<usize>().ok())
+            .unwrap_or(segments.len() + 1);
+
+        segments.push(segment(id, start_time, end_time, block[timing_index + 1..].join("\n")));
+    }
+
+    Ok(segments)
+}
+
+/// Parse "start --> end [settings]", returning the settings after the end time
+fn parse_arrow_timing(line: &str) -> Option<(f64, f64, Option<String>)> {
+    let (start, rest) = line.split_once("-->")?;
+    let rest = rest.trim();
+    let (end, settings) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+
+    let settings = settings.trim();
+    Some((
+        parse_timestamp(start)?,
+        parse_timestamp(end)?,
+        (!settings.is_empty()).then(|| settings.to_string()),
+    ))
+}
+
+/// Generate SRT, numbering cues from 1
+pub fn generate_srt(segments: &[SubtitleSegment]) -> String {
+    segments.iter()
+        .enumerate()
+        .map(|(index, seg)| {
+            format!(
+                "{}\n{} --> {}\n{}\n",
+                index + 1,
+                format_srt_timestamp(seg.start_time),
+                format_srt_timestamp(seg.end_time),
+                seg.text,
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// ============================================================================
+// WebVTT
+// ============================================================================
+
+fn vtt_tag_regex() -> &'static Regex {
+    static TAGS: OnceLock<Regex> = OnceLock::new();
+    TAGS.get_or_init(|| Regex::new(r"</?[^>]*>").unwrap())
+}
+
+/// Parse WebVTT, keeping cue settings and dropping inline markup
+pub fn parse_vtt(content: &str) -> Result<Vec<SubtitleSegment>, SubtitleError> {
+    let mut blocks = blocks(content).into_iter();
+
+    match blocks.next() {
+        Some(header) if header[0].trim_start().starts_with("WEBVTT") => {}
+        _ => return Err(SubtitleError::InvalidFormat("WebVTT file must start with WEBVTT".into())),
+    }
+
+    let mut segments = Vec::new();
+    for block in blocks {
+        let first = block[0].trim_start();
+        if first.starts_with("NOTE") || first.starts_with("STYLE") || first.starts_with("REGION") {
+            continue;
+        }
+
+        let Some(timing_index) = block.iter().take(2).position(|l| l.contains("-->")) else {
+            return Err(SubtitleError::InvalidFormat(format!("Missing cue timing: {}", block[0])));
+        };
+        let (start_time, end_time, cue_settings) = parse_arrow_timing(block[timing_index])
+            .ok_or_else(|| SubtitleError::InvalidFormat(format!("Invalid cue timing: {}", block[timing_index])))?;
+
+        let text = block[timing_index + 1..].iter()
+            .map(|line| unescape_vtt(&vtt_tag_regex().replace_all(line, "")))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let mut seg = segment(segments.len() + 1, start_time, end_time, text);
+        seg.cue_settings = cue_settings;
+        segments.push(seg);
+    }
+
+    Ok(segments)
+}
+
+fn unescape_vtt(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", "\u{a0}")
+        .replace("&amp;", "&")
+}
+
+fn escape_vtt(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Generate WebVTT with cue settings
+pub fn generate_vtt(segments: &[SubtitleSegment]) -> String {
+    let mut vtt = String::from("WEBVTT\n");
+
+    for seg in segments {
+        let settings = seg.cue_settings.as_deref()
+            .map(|s| format!(" {}", s))
+            .unwrap_or_default();
+        vtt.push_str(&format!(
+            "\n{} --> {}{}\n{}\n",
+            format_timestamp(seg.start_time, '.', 3, true),
+            format_timestamp(seg.end_time, '.', 3, true),
+            settings,
+            escape_vtt(&seg.text),
+        ));
+    }
+
+    vtt
+}
+
+// ============================================================================
+// ASS / SSA
+// ============================================================================
+
+fn ass_override_regex() -> &'static Regex {
+    static OVERRIDES: OnceLock<Regex> = OnceLock::new();
+    OVERRIDES.get_or_init(|| Regex::new(r"\{[^}]*\}").unwrap())
+}
+
+/// Parse ASS/SSA events, returning the sections before [Events] verbatim
+fn parse_ass(content: &str) -> Result<(Vec<SubtitleSegment>, String), SubtitleError> {
+    let events_start = content.find("[Events]")
+        .ok_or_else(|| SubtitleError::InvalidFormat("Missing [Events] section".into()))?;
+    let header = content[..events_start].trim().to_string();
+
+    let mut fields: Vec<String> = ASS_EVENT_FORMAT.split(',').map(|f| f.trim().to_string()).collect();
+    let mut segments = Vec::new();
+
+    for line in content[events_start..].lines().skip(1) {
+        let line = line.trim();
+        if line.starts_with('[') {
+            break;  // Next section, e.g. [Fonts]
+        }
+        if let Some(format) = line.strip_prefix("Format:") {
+            fields = format.split(',').map(|f| f.trim().to_string()).collect();
+            continue;
+        }
+        let Some(dialogue) = line.strip_prefix("Dialogue:") else {
+            continue;  // Comments and other event types
+        };
+
+        // Text is last and may itself contain commas
+        let values: Vec<&str> = dialogue.trim_start().splitn(fields.len(), ',').collect();
+        let field = |name: &str| fields.iter().position(|f| f == name).and_then(|i| values.get(i).copied());
+
+        let timing = field("Start").and_then(parse_timestamp).zip(field("End").and_then(parse_timestamp));
+        let Some((start_time, end_time)) = timing else {
+            return Err(SubtitleError::InvalidFormat(format!("Invalid dialogue line: {}", line)));
+        };
+        let text = ass_override_regex()
+            .replace_all(field("Text").unwrap_or_default(), "")
+            .replace("\\N", "\n")
+            .replace("\\n", "\n")
+            .replace("\\h", "\u{a0}");
+
+        let mut seg = segment(segments.len() + 1, start_time, end_time, text.trim().to_string());
+        seg.style = field("Style").map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        segments.push(seg);
+    }
+
+    Ok((segments, header))
+}
+
+/// Generate ASS or SSA, reusing the imported header when it matches the flavour
+fn generate_ass(track: &SubtitleTrack, format: SubtitleFormat) -> String {
+    let (styles_section, default_header, event_format, first_field) = match format {
+        SubtitleFormat::Ssa => ("[V4 Styles]", DEFAULT_SSA_HEADER, SSA_EVENT_FORMAT, "Marked=0"),
+        _ => ("[V4+ Styles]", DEFAULT_ASS_HEADER, ASS_EVENT_FORMAT, "0"),
+    };
+    let header = track.ass_header.as_deref()
+        .filter(|h| h.contains(styles_section))
+        .unwrap_or(default_header);
+
+    let mut ass = format!("{}\n\n[Events]\nFormat: {}\n", header.trim(), event_format);
+    for seg in &track.segments {
+        ass.push_str(&format!(
+            "Dialogue: {},{},{},{},,0,0,0,,{}\n",
+            first_field,
+            format_timestamp(seg.start_time, '.', 2, false),
+            format_timestamp(seg.end_time, '.', 2, false),
+            seg.style.as_deref().unwrap_or("Default"),
+            seg.text.replace('\n', "\\N"),
+        ));
+    }
+
+    ass
+}
+
+// ============================================================================
+// SBV
+// ============================================================================
+
+/// Parse "0:00:01.000,0:00:03.500"
+fn parse_sbv_timing(line: &str) -> Option<(f64, f64)> {
+    let (start, end) = line.trim().split_once(',')?;
+    Some((parse_timestamp(start)?, parse_timestamp(end)?))
+}
+
+/// Parse YouTube SBV
+pub fn parse_sbv(content: &str) -> Result<Vec<SubtitleSegment>, SubtitleError> {
+    blocks(content).into_iter()
+        .enumerate()
+        .map(|(index, block)| {
+            let (start_time, end_time) = parse_sbv_timing(block[0])
+                .ok_or_else(|| SubtitleError::InvalidFormat(format!("Invalid SBV timing: {}", block[0])))?;
+            Ok(segment(index + 1, start_time, end_time, block[1..].join("\n")))
+        })
+        .collect()
+}
+
+/// Generate YouTube SBV
+pub fn generate_sbv(segments: &[SubtitleSegment]) -> String {
+    segments.iter()
+        .map(|seg| {
+            format!(
+                "{},{}\n{}\n",
+                format_timestamp(seg.start_time, '.', 3, false),
+                format_timestamp(seg.end_time, '.', 3, false),
+                seg.text,
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SubtitleSource;
+
+    fn track(segments: Vec<SubtitleSegment>) -> SubtitleTrack {
+        SubtitleTrack {
+            segments,
+            language: "en".to_string(),
+            source: SubtitleSource::Manual,
+            ass_header: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("00:00:05,000"), Some(5.0));
+        assert_eq!(parse_timestamp("00:01:30,500"), Some(90.5));
+        assert_eq!(parse_timestamp("01:00:00.000"), Some(3600.0));
+        assert_eq!(parse_timestamp("01:30.250"), Some(90.25));
+        assert_eq!(parse_timestamp("0:00:05.20"), Some(5.2));
+        assert_eq!(parse_timestamp("later"), None);
+    }
+
+    #[test]
+    fn test_format_srt_timestamp() {
+        assert_eq!(format_srt_timestamp(5.0), "00:00:05,000");
+        assert_eq!(format_srt_timestamp(90.5), "00:01:30,500");
+        assert_eq!(format_srt_timestamp(3600.0), "01:00:00,000");
+        assert_eq!(format_srt_timestamp(59.9996), "00:01:00,000");
+    }
+
+    #[test]
+    fn test_parse_srt() {
+        let srt = "1\n00:00:05,000 --> 00:00:08,000\nHello world\n\n2\n00:00:08,000 --> 00:00:12,000\nThis is a test\n";
+        let segments = parse_srt(srt).unwrap();
+
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].id, 1);
+        assert_eq!(segments[0].text, "Hello world");
+        assert_eq!(segments[1].id, 2);
+        assert_eq!(segments[1].text, "This is a test");
+    }
+
+    #[test]
+    fn test_parse_srt_crlf_bom_and_blank_lines() {
+        let srt = "\u{feff}1\r\n00:00:01,000 --> 00:00:02,500\r\nFirst line\r\nSecond line\r\n\r\n\r\n\r\n2\r\n00:00:03,000 --> 00:00:04,000 X1:10 X2:20\r\nNext\r\n";
+        let parsed = parse(srt, SubtitleFormat::detect(srt).unwrap()).unwrap();
+
+        assert_eq!(parsed.format, SubtitleFormat::Srt);
+        assert_eq!(parsed.segments.len(), 2);
+        assert_eq!(parsed.segments[0].text, "First line\nSecond line");
+        assert_eq!((parsed.segments[1].start_time, parsed.segments[1].end_time), (3.0, 4.0));
+    }
+
+    #[test]
+    fn test_generate_srt() {
+        let segments = vec![
+            segment(1, 5.0, 8.0, "Hello world".to_string()),
+            segment(2, 8.0, 12.0, "This is a test".to_string()),
+        ];
+
+        let srt = generate_srt(&segments);
+        assert!(srt.contains("00:00:05,000 --> 00:00:08,000"));
+        assert!(srt.contains("Hello world"));
+        assert!(srt.contains("This is a test"));
+        assert_eq!(parse_srt(&srt).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_vtt_round_trip_with_cue_settings() {
+        let vtt = "WEBVTT - Demo\n\nNOTE written by hand\n\nintro\n00:01.000 --> 00:03.000 align:start line:10%\n<v Ana>Fish &amp; chips</v>\n\n00:00:04.000 --> 00:00:05.000\nBye\n";
+        let parsed = parse(vtt, SubtitleFormat::detect(vtt).unwrap()).unwrap();
+
+        assert_eq!(parsed.format, SubtitleFormat::Vtt);
+        assert_eq!(parsed.segments.len(), 2);
+        assert_eq!(parsed.segments[0].text, "Fish & chips");
+        assert_eq!(parsed.segments[0].cue_settings.as_deref(), Some("align:start line:10%"));
+
+        let output = generate(&track(parsed.segments), SubtitleFormat::Vtt);
+        assert!(output.starts_with("WEBVTT\n"));
+        assert!(output.contains("00:00:01.000 --> 00:00:03.000 align:start line:10%\nFish &amp; chips\n"));
+    }
+
+    #[test]
+    fn test_ass_styles_preserved() {
+        let ass = "[Script Info]\nScriptType: v4.00+\n\n[V4+ Styles]\nFormat: Name, Fontname, Fontsize\nStyle: Sign,Impact,60\n\n[Events]\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\nComment: 0,0:00:00.00,0:00:01.00,Sign,,0,0,0,,ignored\nDialogue: 0,0:00:01.50,0:00:04.00,Sign,,0,0,0,,{\\b1}Wait,{\\b0} what?\\NReally\n";
+        let parsed = parse(ass, SubtitleFormat::detect(ass).unwrap()).unwrap();
+
+        assert_eq!(parsed.format, SubtitleFormat::Ass);
+        assert_eq!(parsed.segments.len(), 1);
+        assert_eq!(parsed.segments[0].text, "Wait, what?\nReally");
+        assert_eq!(parsed.segments[0].style.as_deref(), Some("Sign"));
+        assert_eq!((parsed.segments[0].start_time, parsed.segments[0].end_time), (1.5, 4.0));
+
+        let mut imported = track(parsed.segments);
+        imported.ass_header = parsed.ass_header;
+        let output = generate(&imported, SubtitleFormat::Ass);
+        assert!(output.contains("Style: Sign,Impact,60"));
+        assert!(output.contains("Dialogue: 0,0:00:01.50,0:00:04.00,Sign,,0,0,0,,Wait, what?\\NReally"));
+
+        // The ASS header does not fit SSA, which gets its default styles
+        let ssa = generate(&imported, SubtitleFormat::Ssa);
+        assert!(ssa.contains("[V4 Styles]"));
+        assert!(ssa.contains("Dialogue: Marked=0,0:00:01.50"));
+        assert_eq!(SubtitleFormat::detect(&ssa), Some(SubtitleFormat::Ssa));
+    }
+
+    #[test]
+    fn test_iso639_2() {
+        assert_eq!(iso639_2("en"), "eng");
+        assert_eq!(iso639_2("pt-BR"), "por");
+        assert_eq!(iso639_2("deu"), "deu");
+        assert_eq!(iso639_2(""), "und");
+    }
+
+    #[test]
+    fn test_sbv_round_trip() {
+        let sbv = "0:00:00.500,0:00:02.000\nHello\n\n0:00:02.500,0:00:04.000\nTwo\nlines\n";
+        let parsed = parse(sbv, SubtitleFormat::detect(sbv).unwrap()).unwrap();
+
+        assert_eq!(parsed.format, SubtitleFormat::Sbv);
+        assert_eq!(parsed.segments[1].text, "Two\nlines");
+        assert_eq!(generate(&track(parsed.segments), SubtitleFormat::Sbv), sbv);
+    }
+}
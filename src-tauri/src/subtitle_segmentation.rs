@@ -0,0 +1,274 @@
+use crate::models::{SubtitleSegment, SubtitleWord};
+use serde::{Deserialize, Serialize};
+
+/// Pause between words that always starts a new cue (seconds)
+const PAUSE_BREAK_SECONDS: f64 = 0.7;
+
+/// Limits for re-segmenting subtitles into readable cues
+///
+/// Defaults follow common broadcast guidelines: two lines of 42 characters,
+/// at most 7 seconds on screen and 17 characters per second.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SegmentationOptions {
+    pub max_chars_per_line: usize,
+    pub max_lines: usize,
+    pub max_duration: f64,    // seconds
+    pub min_duration: f64,    // seconds, cues are extended up to this when room allows
+    pub min_gap: f64,         // seconds between consecutive cues
+    pub max_cps: f64,         // reading speed, characters per second
+}
+
+impl Default for SegmentationOptions {
+    fn default() -> Self {
+        Self {
+            max_chars_per_line: 42,
+            max_lines: 2,
+            max_duration: 7.0,
+            min_duration: 1.0,
+            min_gap: 0.083,  // Two frames at 24 fps
+            max_cps: 17.0,
+        }
+    }
+}
+
+/// A cue that is still too fast to read after re-segmentation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReadingSpeedWarning {
+    pub segment_id: usize,
+    pub chars_per_second: f64,
+}
+
+/// Characters per second needed to read a cue, ignoring line breaks
+pub fn reading_speed(segment: &SubtitleSegment) -> f64 {
+    let chars = segment.text.chars().filter(|c| *c != '\n').count() as f64;
+    let duration = segment.end_time - segment.start_time;
+    if duration <= 0.0 {
+        f64::INFINITY
+    } else {
+        chars / duration
+    }
+}
+
+/// Cues that exceed the reading speed limit
+pub fn check_reading_speed(segments: &[SubtitleSegment], options: &SegmentationOptions) -> Vec<ReadingSpeedWarning> {
+    segments.iter()
+        .map(|segment| (segment.id, reading_speed(segment)))
+        .filter(|(_, cps)| *cps > options.max_cps)
+        .map(|(segment_id, chars_per_second)| ReadingSpeedWarning { segment_id, chars_per_second })
+        .collect()
+}
+
+/// Split subtitles into cues that fit the line, duration and gap limits
+///
+/// Word timing is used where the backend provided it; otherwise word times
+/// are estimated from each word's share of the segment's characters. Cues
+/// break at long pauses and sentence ends, and short or fast cues are
+/// extended into the following gap.
+pub fn resegment(segments: &[SubtitleSegment], options: &SegmentationOptions) -> Vec<SubtitleSegment> {
+    let words: Vec<SubtitleWord> = segments.iter().flat_map(segment_words).collect();
+
+    let mut cues: Vec<Vec<SubtitleWord>> = Vec::new();
+    let mut current: Vec<SubtitleWord> = Vec::new();
+
+    for word in words {
+        if let Some(last) = current.last() {
+            let pause = word.start_time - last.end_time > PAUSE_BREAK_SECONDS;
+            let sentence_end = ends_sentence(&last.text)
+                && joined_len(&current) * 2 >= options.max_chars_per_line;
+
+            let mut candidate = current.clone();
+            candidate.push(word.clone());
+            let too_long = word.end_time - current[0].start_time > options.max_duration;
+            let too_wide = wrap_words(&candidate, options.max_chars_per_line).len() > options.max_lines.max(1);
+
+            if pause || sentence_end || too_long || too_wide {
+                cues.push(std::mem::take(&mut current));
+            }
+        }
+        current.push(word);
+    }
+    if !current.is_empty() {
+        cues.push(current);
+    }
+
+    let mut result: Vec<SubtitleSegment> = cues.iter()
+        .enumerate()
+        .map(|(index, words)| SubtitleSegment {
+            id: index + 1,
+            start_time: words[0].start_time,
+            end_time: words[words.len() - 1].end_time,
+            text: wrap_words(words, options.max_chars_per_line).join("\n"),
+            words: words.clone(),
+            style: None,
+            cue_settings: None,
+            style_override: None,
+        })
+        .collect();
+
+    apply_timing_limits(&mut result, options);
+    result
+}
+
+/// Extend short or fast cues into the following gap and enforce the minimum gap
+fn apply_timing_limits(segments: &mut [SubtitleSegment], options: &SegmentationOptions) {
+    for i in 0..segments.len() {
+        let next_start = segments.get(i + 1).map(|s| s.start_time).unwrap_or(f64::INFINITY);
+        let segment = &mut segments[i];
+
+        let chars = segment.text.chars().filter(|c| *c != '\n').count() as f64;
+        let wanted = (chars / options.max_cps).max(options.min_duration).min(options.max_duration);
+        let latest_end = next_start - options.min_gap;
+
+        if segment.end_time - segment.start_time < wanted {
+            segment.end_time = (segment.start_time + wanted).min(latest_end).max(segment.end_time);
+        }
+        if segment.end_time > latest_end && latest_end > segment.start_time {
+            segment.end_time = latest_end;
+        }
+    }
+}
+
+/// Words of a segment, with estimated timing when the backend gave none
+fn segment_words(segment: &SubtitleSegment) -> Vec<SubtitleWord> {
+    if !segment.words.is_empty() {
+        return segment.words.clone();
+    }
+
+    let texts: Vec<&str> = segment.text.split_whitespace().collect();
+    let total_chars: usize = texts.iter().map(|t| t.chars().count()).sum();
+    let duration = segment.end_time - segment.start_time;
+
+    let mut time = segment.start_time;
+    texts.iter()
+        .map(|text| {
+            let share = text.chars().count() as f64 / total_chars.max(1) as f64;
+            let word = SubtitleWord {
+                text: text.to_string(),
+                start_time: time,
+                end_time: time + duration * share,
+            };
+            time = word.end_time;
+            word
+        })
+        .collect()
+}
+
+/// Greedily wrap words into lines of at most `max_chars` (longer words get a line of their own)
+fn wrap_words(words: &[SubtitleWord], max_chars: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+
+    for word in words {
+        if !line.is_empty() && line.chars().count() + 1 + word.text.chars().count() > max_chars {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&word.text);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Re-wrap text into lines of at most `max_chars`, ignoring its current line breaks
+pub fn wrap_text(text: &str, max_chars: usize) -> String {
+    let words: Vec<SubtitleWord> = text.split_whitespace()
+        .map(|w| SubtitleWord { text: w.to_string(), start_time: 0.0, end_time: 0.0 })
+        .collect();
+    wrap_words(&words, max_chars).join("\n")
+}
+
+fn joined_len(words: &[SubtitleWord]) -> usize {
+    words.iter().map(|w| w.text.chars().count() + 1).sum::<usize>().saturating_sub(1)
+}
+
+fn ends_sentence(text: &str) -> bool {
+    text.ends_with(['.', '?', '!'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(text: &str, start_time: f64, end_time: f64) -> SubtitleWord {
+        SubtitleWord { text: text.to_string(), start_time, end_time }
+    }
+
+    #[test]
+    fn test_long_segment_split_into_lines_and_cues() {
+        let text = "This is a long paragraph of speech that goes on and on without any \
+                    break at all so it has to be split into several readable captions";
+        let segment = SubtitleSegment {
+            id: 1,
+            start_time: 0.0,
+            end_time: 12.0,
+            text: text.to_string(),
+            words: Vec::new(),
+            style: None,
+            cue_settings: None,
+            style_override: None,
+        };
+        let options = SegmentationOptions::default();
+
+        let cues = resegment(&[segment], &options);
+
+        assert!(cues.len() > 1);
+        for cue in &cues {
+            let lines: Vec<&str> = cue.text.lines().collect();
+            assert!(lines.len() <= options.max_lines);
+            assert!(lines.iter().all(|l| l.chars().count() <= options.max_chars_per_line));
+            assert!(cue.end_time - cue.start_time <= options.max_duration);
+        }
+        let rejoined: Vec<String> = cues.iter().map(|c| c.text.replace('\n', " ")).collect();
+        assert_eq!(rejoined.join(" "), text.split_whitespace().collect::<Vec<_>>().join(" "));
+        assert_eq!(cues.iter().map(|c| c.id).collect::<Vec<_>>(), (1..=cues.len()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_breaks_at_pauses_and_keeps_min_gap() {
+        let segment = SubtitleSegment {
+            id: 1,
+            start_time: 0.0,
+            end_time: 3.0,
+            text: "Hi there okay".to_string(),
+            words: vec![word("Hi", 0.0, 0.3), word("there", 0.3, 0.6), word("okay", 2.0, 2.4)],
+            style: None,
+            cue_settings: None,
+            style_override: None,
+        };
+        let options = SegmentationOptions::default();
+
+        let cues = resegment(&[segment], &options);
+
+        assert_eq!(cues.len(), 2);
+        assert_eq!(cues[0].text, "Hi there");
+        // Extended to the minimum duration, which still leaves the gap
+        assert_eq!(cues[0].end_time, 1.0);
+        assert_eq!(cues[1].start_time, 2.0);
+        assert_eq!(cues[1].words, vec![word("okay", 2.0, 2.4)]);
+    }
+
+    #[test]
+    fn test_reading_speed_warnings() {
+        let fast = SubtitleSegment {
+            id: 7,
+            start_time: 0.0,
+            end_time: 1.0,
+            text: "Far too many characters\nfor one second".to_string(),
+            words: Vec::new(),
+            style: None,
+            cue_settings: None,
+            style_override: None,
+        };
+
+        let warnings = check_reading_speed(&[fast], &SegmentationOptions::default());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].segment_id, 7);
+        assert_eq!(warnings[0].chars_per_second, 37.0);
+    }
+}
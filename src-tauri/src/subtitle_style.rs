@@ -0,0 +1,194 @@
+use crate::export::escape_filter_path;
+use crate::generator::rgba_components;
+use crate::models::{
+    ExportError, Resolution, SubtitlePosition, SubtitleStyle, SubtitleTrack, TextAlignment,
+};
+use crate::subtitle_format::{self, SubtitleFormat};
+use std::path::Path;
+
+const ASS_STYLE_FORMAT: &str = "Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding";
+
+/// ASS colour (&HAABBGGRR, where alpha 00 is opaque)
+fn ass_color(color: &str) -> Result<String, ExportError> {
+    let [r, g, b, a] = rgba_components(color).ok_or_else(|| ExportError::ValidationError(
+        format!("Invalid subtitle color: {}", color)
+    ))?;
+    Ok(format!("&H{:02X}{:02X}{:02X}{:02X}", 255 - a, b, g, r))
+}
+
+/// ASS numpad alignment: 1-3 bottom, 4-6 middle, 7-9 top
+fn ass_alignment(position: SubtitlePosition, alignment: TextAlignment) -> u8 {
+    let row = match position {
+        SubtitlePosition::Bottom => 0,
+        SubtitlePosition::Middle => 3,
+        SubtitlePosition::Top => 6,
+    };
+    let column = match alignment {
+        TextAlignment::Left => 1,
+        TextAlignment::Center => 2,
+        TextAlignment::Right => 3,
+    };
+    row + column
+}
+
+/// Check that a style can be rendered
+pub fn validate_style(style: &SubtitleStyle) -> Result<(), ExportError> {
+    if style.font_family.trim().is_empty() {
+        return Err(ExportError::ValidationError("Subtitle font family is required".to_string()));
+    }
+    if !(style.font_size > 0.0 && style.font_size <= 0.5) {
+        return Err(ExportError::ValidationError(
+            format!("Subtitle font size must be between 0 and 0.5 of the frame height, got {}", style.font_size)
+        ));
+    }
+    for (name, value) in [
+        ("outline width", style.outline_width),
+        ("shadow offset", style.shadow_offset),
+        ("horizontal margin", style.margin_horizontal),
+        ("vertical margin", style.margin_vertical),
+    ] {
+        if !(0.0..0.5).contains(&value) {
+            return Err(ExportError::ValidationError(
+                format!("Subtitle {} must be between 0 and 0.5, got {}", name, value)
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// "Style:" line for a style at the given resolution
+fn style_line(name: &str, style: &SubtitleStyle, resolution: &Resolution) -> Result<String, ExportError> {
+    validate_style(style)?;
+    let (width, height) = (resolution.width as f64, resolution.height as f64);
+
+    // With a box, libass draws it in the outline colour and pads by the outline width
+    let (border_style, outline_color) = match &style.background {
+        Some(background) => (3, ass_color(background)?),
+        None => (1, ass_color(&style.outline_color)?),
+    };
+
+    Ok(format!(
+        "Style: {},{},{},{},&H000000FF,{},{},{},{},0,0,100,100,0,0,{},{:.1},{:.1},{},{},{},{},1",
+        name,
+        style.font_family.replace(',', " "),
+        (style.font_size * height).round(),
+        ass_color(&style.primary_color)?,
+        outline_color,
+        ass_color(&style.shadow_color)?,
+        if style.bold { -1 } else { 0 },
+        if style.italic { -1 } else { 0 },
+        border_style,
+        style.outline_width * height,
+        style.shadow_offset * height,
+        ass_alignment(style.position, style.alignment),
+        (style.margin_horizontal * width).round(),
+        (style.margin_horizontal * width).round(),
+        (style.margin_vertical * height).round(),
+    ))
+}
+
+/// ASS script rendering a subtitle track with a timeline style
+///
+/// The script's resolution is the timeline's, and libass scales it to the
+/// frame it draws on, so preview frames and exports look the same. Segments
+/// with overrides get a style of their own.
+pub fn build_burn_in_script(
+    track: &SubtitleTrack,
+    style: &SubtitleStyle,
+    resolution: &Resolution,
+) -> Result<String, ExportError> {
+    let mut styles = vec![style_line("Default", style, resolution)?];
+    let mut styled = track.clone();
+
+    for (index, segment) in styled.segments.iter_mut().enumerate() {
+        segment.style = match &segment.style_override {
+            Some(style_override) => {
+                let name = format!("Segment{}", index + 1);
+                styles.push(style_line(&name, &style.with_override(style_override), resolution)?);
+                Some(name)
+            }
+            None => None,
+        };
+    }
+
+    styled.ass_header = Some(format!(
+        "[Script Info]\nScriptType: v4.00+\nPlayResX: {}\nPlayResY: {}\nWrapStyle: 0\nScaledBorderAndShadow: yes\n\n[V4+ Styles]\nFormat: {}\n{}",
+        resolution.width,
+        resolution.height,
+        ASS_STYLE_FORMAT,
+        styles.join("\n"),
+    ));
+
+    Ok(subtitle_format::generate(&styled, SubtitleFormat::Ass))
+}
+
+/// `subtitles` filter drawing a script written from `build_burn_in_script`
+pub fn burn_in_filter(script_path: &Path, style: &SubtitleStyle) -> String {
+    let fonts_dir = style.font_file.as_deref().and_then(Path::parent);
+    match fonts_dir {
+        Some(dir) => format!(
+            "subtitles={}:fontsdir={}",
+            escape_filter_path(script_path),
+            escape_filter_path(dir)
+        ),
+        None => format!("subtitles={}", escape_filter_path(script_path)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{SubtitleSegment, SubtitleSource, SubtitleStyleOverride};
+
+    #[test]
+    fn test_burn_in_script_with_overrides() {
+        let segment = |id: usize, style_override| SubtitleSegment {
+            id,
+            start_time: id as f64,
+            end_time: id as f64 + 1.0,
+            text: format!("Line {}", id),
+            words: Vec::new(),
+            style: None,
+            cue_settings: None,
+            style_override,
+        };
+        let track = SubtitleTrack {
+            segments: vec![
+                segment(1, None),
+                segment(2, Some(SubtitleStyleOverride {
+                    primary_color: Some("#FFFF00".to_string()),
+                    position: Some(SubtitlePosition::Top),
+                    background: Some(None),
+                    ..Default::default()
+                })),
+            ],
+            language: "en".to_string(),
+            source: SubtitleSource::Manual,
+            ass_header: None,
+        };
+
+        let script = build_burn_in_script(
+            &track,
+            &SubtitleStyle::default(),
+            &Resolution { width: 1920, height: 1080 },
+        ).unwrap();
+
+        assert!(script.contains("PlayResY: 1080"));
+        assert!(script.contains(
+            "Style: Default,Arial,90,&H00FFFFFF,&H000000FF,&H00000000,&H7F000000,0,0,0,0,100,100,0,0,3,7.6,3.8,2,50,50,76,1"
+        ));
+        // Yellow, at the top and outlined rather than boxed
+        assert!(script.contains("Style: Segment2,Arial,90,&H0000FFFF,&H000000FF,&H00000000,&H7F000000,0,0,0,0,100,100,0,0,1,7.6,3.8,8,"));
+        assert!(script.contains("Dialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,Line 1"));
+        assert!(script.contains("Dialogue: 0,0:00:02.00,0:00:03.00,Segment2,,0,0,0,,Line 2"));
+    }
+
+    #[test]
+    fn test_invalid_style_rejected() {
+        let style = SubtitleStyle { font_size: 0.0, ..Default::default() };
+        assert!(style_line("Default", &style, &Resolution { width: 1280, height: 720 }).is_err());
+
+        let style = SubtitleStyle { primary_color: "white".to_string(), ..Default::default() };
+        assert!(style_line("Default", &style, &Resolution { width: 1280, height: 720 }).is_err());
+    }
+}
@@ -1,7 +1,7 @@
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use uuid::Uuid;
-use crate::models::ThumbnailError;
+use crate::models::{is_still_image_path, ThumbnailError};
 use crate::ffmpeg_utils;
 
 /// Service for generating video thumbnails
@@ -63,6 +63,9 @@ impl ThumbnailGenerator {
                 )
             ))?;
 
+        // Still images only have a single frame to seek to
+        let timestamp = if is_still_image_path(video_path) { 0.0 } else { timestamp };
+
         log::debug!("Generating thumbnail for {:?} at {}s", video_path, timestamp);
 
         let status = Command::new(&self.ffmpeg_path)
@@ -94,6 +97,12 @@ impl ThumbnailGenerator {
         duration: f64,
         count: usize
     ) -> Result<Vec<PathBuf>, ThumbnailError> {
+        // Every frame of a still image looks the same
+        if is_still_image_path(video_path) {
+            let thumb = self.generate(video_path, 0.0).await?;
+            return Ok(vec![thumb; count]);
+        }
+
         let mut thumbnails = Vec::new();
         let interval = duration / count as f64;
 
@@ -1,10 +1,16 @@
-use crate::models::{Timeline, Track, Clip, TrackType, Resolution};
+use crate::models::{Timeline, Track, Clip, TrackType, Resolution, SubtitleStyle, SubtitleTrack, TimelineMarker};
+use crate::markers;
+use crate::subtitle_edit;
+use crate::subtitle_style;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::fs;
 use uuid::Uuid;
 use log::{info, warn, error};
 
+/// Clips closer than this to a ripple boundary are treated as touching it (seconds)
+const RIPPLE_EPSILON: f64 = 0.001;
+
 /// Timeline service for managing non-destructive video editing
 pub struct TimelineService {
     current_timeline: Option<Timeline>,
@@ -85,8 +91,11 @@ impl TimelineService {
                 },
             ],
             duration: 0.0,
-            subtitle_track: None,
+            subtitle_tracks: Vec::new(),
+            burn_in_language: None,
             subtitle_enabled: false,
+            subtitle_style: SubtitleStyle::default(),
+            markers: Vec::new(),
         };
 
         self.current_timeline = Some(timeline.clone());
@@ -95,6 +104,14 @@ impl TimelineService {
         Ok(timeline)
     }
 
+    /// Service editing a copy of a timeline, for previewing edits
+    pub fn from_timeline(timeline: Timeline) -> Self {
+        Self {
+            current_timeline: Some(timeline),
+            project_path: None,
+        }
+    }
+
     /// Get current timeline
     pub fn get_timeline(&self) -> Result<&Timeline, TimelineError> {
         self.current_timeline.as_ref()
@@ -161,6 +178,10 @@ impl TimelineService {
             ));
         }
 
+        if let Some(reason) = Self::check_clip_source(track, &clip) {
+            return Err(TimelineError::InvalidOperation(reason));
+        }
+
         // Check for overlaps
         if let Some(overlap) = Self::check_overlap(track, &clip) {
             warn!("Overlap detected when adding clip");
@@ -236,6 +257,10 @@ impl TimelineService {
             ));
         }
 
+        if let Some(reason) = Self::check_clip_source(new_track, &clip) {
+            return Err(TimelineError::InvalidOperation(reason));
+        }
+
         // Check for overlaps in new track
         if let Some(overlap) = Self::check_overlap(new_track, &clip) {
             warn!("Overlap detected when moving clip");
@@ -332,6 +357,9 @@ impl TimelineService {
                     effects: original_clip.effects.clone(),
                     volume: original_clip.volume,
                     speed: original_clip.speed,
+                    generator: original_clip.generator.clone(),
+                    ken_burns: original_clip.ken_burns.clone(),
+                    animation_span: Some(original_clip.animation_range()),
                 };
 
                 // Create second part (after split)
@@ -346,6 +374,9 @@ impl TimelineService {
                     effects: original_clip.effects.clone(),
                     volume: original_clip.volume,
                     speed: original_clip.speed,
+                    generator: original_clip.generator.clone(),
+                    ken_burns: original_clip.ken_burns.clone(),
+                    animation_span: Some(original_clip.animation_range()),
                 };
 
                 let first_id = first_clip.id.clone();
@@ -364,6 +395,44 @@ impl TimelineService {
         Err(TimelineError::ClipNotFound(clip_id.to_string()))
     }
 
+    /// Split a clip at each of the given source times that fall inside it
+    ///
+    /// Returns the ids of the resulting pieces in timeline order.
+    pub fn split_clip_at_source_times(
+        &mut self,
+        clip_id: &str,
+        source_times: &[f64],
+    ) -> Result<Vec<String>, TimelineError> {
+        let clip = self.get_timeline()?
+            .tracks
+            .iter()
+            .flat_map(|t| &t.clips)
+            .find(|c| c.id == clip_id)
+            .ok_or_else(|| TimelineError::ClipNotFound(clip_id.to_string()))?;
+
+        let clip_start = clip.track_position;
+        let clip_end = clip_start + clip.duration;
+        let mut split_times: Vec<f64> = source_times.iter()
+            .map(|t| clip_start + (t - clip.trim_start) / clip.speed as f64)
+            .filter(|t| *t > clip_start + RIPPLE_EPSILON && *t < clip_end - RIPPLE_EPSILON)
+            .collect();
+        split_times.sort_by(|a, b| b.total_cmp(a));
+        split_times.dedup_by(|a, b| (*a - *b).abs() < RIPPLE_EPSILON);
+
+        // Splitting from the end keeps the remaining first piece covering every earlier time
+        let mut pieces = Vec::with_capacity(split_times.len() + 1);
+        let mut remaining = clip_id.to_string();
+        for time in split_times {
+            let (first, second) = self.split_clip(&remaining, time)?;
+            pieces.push(second);
+            remaining = first;
+        }
+        pieces.push(remaining);
+        pieces.reverse();
+
+        Ok(pieces)
+    }
+
     /// Get clips at a specific time (playhead position)
     pub fn get_clips_at_time(&self, time: f64) -> Result<Vec<Clip>, TimelineError> {
         let timeline = self.get_timeline()?;
@@ -388,6 +457,185 @@ impl TimelineService {
         Ok(clips_at_time)
     }
 
+    /// Split every clip that spans `time`, on all tracks
+    fn split_clips_at(&mut self, time: f64) -> Result<(), TimelineError> {
+        let spanning: Vec<String> = self.get_timeline()?.tracks.iter()
+            .flat_map(|t| &t.clips)
+            .filter(|c| {
+                time > c.track_position + RIPPLE_EPSILON
+                    && time < c.track_position + c.duration - RIPPLE_EPSILON
+            })
+            .map(|c| c.id.clone())
+            .collect();
+
+        for clip_id in spanning {
+            self.split_clip(&clip_id, time)?;
+        }
+        Ok(())
+    }
+
+    /// Remove a time range from every track and subtitle track, closing the gap
+    ///
+    /// Clips spanning the range boundaries are split first, so only the
+    /// material inside the range is removed. Fails without changing anything
+    /// when a locked track has clips at or after the range.
+    pub fn ripple_delete(&mut self, start: f64, end: f64) -> Result<(), TimelineError> {
+        if !(start >= 0.0 && end > start) {
+            return Err(TimelineError::InvalidOperation(
+                format!("Invalid range to remove: {} - {}", start, end)
+            ));
+        }
+
+        let timeline = self.get_timeline()?;
+        if let Some(track) = timeline.tracks.iter()
+            .find(|t| t.locked && t.clips.iter().any(|c| c.track_position + c.duration > start))
+        {
+            return Err(TimelineError::InvalidOperation(
+                format!("Track {} is locked", track.id)
+            ));
+        }
+
+        self.split_clips_at(start)?;
+        self.split_clips_at(end)?;
+
+        let timeline = self.get_timeline_mut()?;
+        let removed = end - start;
+
+        for track in &mut timeline.tracks {
+            track.clips.retain(|c| {
+                c.track_position < start - RIPPLE_EPSILON
+                    || c.track_position + c.duration > end + RIPPLE_EPSILON
+            });
+            for clip in &mut track.clips {
+                if clip.track_position >= end - RIPPLE_EPSILON {
+                    clip.track_position = (clip.track_position - removed).max(0.0);
+                }
+            }
+        }
+
+        for subtitles in &mut timeline.subtitle_tracks {
+            subtitle_edit::cut_range(subtitles, start, end);
+        }
+        markers::cut_range(&mut timeline.markers, start, end);
+
+        timeline.duration = Self::calculate_duration(&timeline.tracks);
+
+        info!("Ripple deleted {:.3}s - {:.3}s", start, end);
+        Ok(())
+    }
+
+    /// Set the look of burned-in subtitles
+    pub fn set_subtitle_style(&mut self, style: SubtitleStyle) -> Result<(), TimelineError> {
+        subtitle_style::validate_style(&style)
+            .map_err(|e| TimelineError::InvalidOperation(e.to_string()))?;
+
+        let timeline = self.get_timeline_mut()?;
+        timeline.subtitle_style = style;
+
+        info!("Updated subtitle style for timeline: {}", timeline.id);
+        Ok(())
+    }
+
+    /// Add a subtitle track, replacing the track with the same language
+    pub fn set_subtitle_track(&mut self, track: SubtitleTrack) -> Result<(), TimelineError> {
+        if track.language.trim().is_empty() {
+            return Err(TimelineError::InvalidOperation("Subtitle track has no language".to_string()));
+        }
+
+        let timeline = self.get_timeline_mut()?;
+        let language = track.language.clone();
+        let segment_count = track.segments.len();
+
+        match timeline.subtitle_track_mut(&language) {
+            Some(existing) => *existing = track,
+            None => timeline.subtitle_tracks.push(track),
+        }
+
+        info!("Set {} subtitle track with {} segments", language, segment_count);
+        Ok(())
+    }
+
+    /// Remove the subtitle track for a language
+    pub fn remove_subtitle_track(&mut self, language: &str) -> Result<(), TimelineError> {
+        let timeline = self.get_timeline_mut()?;
+
+        let before = timeline.subtitle_tracks.len();
+        timeline.subtitle_tracks.retain(|t| t.language != language);
+        if timeline.subtitle_tracks.len() == before {
+            return Err(TimelineError::InvalidOperation(format!("No subtitle track for language: {}", language)));
+        }
+
+        if timeline.burn_in_language.as_deref() == Some(language) {
+            timeline.burn_in_language = None;
+        }
+
+        info!("Removed {} subtitle track", language);
+        Ok(())
+    }
+
+    /// Choose the subtitle track burned into the video (`None` for the first track)
+    pub fn set_burn_in_subtitles(&mut self, language: Option<String>) -> Result<(), TimelineError> {
+        let timeline = self.get_timeline_mut()?;
+
+        if let Some(language) = &language {
+            if timeline.subtitle_track(language).is_none() {
+                return Err(TimelineError::InvalidOperation(format!("No subtitle track for language: {}", language)));
+            }
+        }
+
+        info!("Burn-in subtitle language: {:?}", language);
+        timeline.burn_in_language = language;
+        Ok(())
+    }
+
+    /// Edit the subtitle track for a language, returning the edited track
+    pub fn edit_subtitles<T>(
+        &mut self,
+        language: &str,
+        edit: impl FnOnce(&mut SubtitleTrack) -> Result<T, TimelineError>,
+    ) -> Result<(T, SubtitleTrack), TimelineError> {
+        let timeline = self.get_timeline_mut()?;
+        let track = timeline.subtitle_track_mut(language)
+            .ok_or_else(|| TimelineError::InvalidOperation(format!("No subtitle track for language: {}", language)))?;
+
+        let result = edit(track)?;
+        Ok((result, track.clone()))
+    }
+
+    /// Turn subtitles on or off for preview and export
+    pub fn set_subtitles_enabled(&mut self, enabled: bool) -> Result<(), TimelineError> {
+        let timeline = self.get_timeline_mut()?;
+        timeline.subtitle_enabled = enabled;
+
+        info!("Subtitles {} for timeline: {}", if enabled { "enabled" } else { "disabled" }, timeline.id);
+        Ok(())
+    }
+
+    /// Add a marker, chapter or region, returning its id
+    pub fn add_marker(&mut self, marker: TimelineMarker) -> Result<String, TimelineError> {
+        let timeline = self.get_timeline_mut()?;
+        let name = marker.name.clone();
+        let id = markers::add_marker(&mut timeline.markers, marker)?;
+
+        info!("Added marker {} ({})", name, id);
+        Ok(id)
+    }
+
+    /// Replace the marker with the same id
+    pub fn update_marker(&mut self, marker: TimelineMarker) -> Result<(), TimelineError> {
+        let timeline = self.get_timeline_mut()?;
+        markers::update_marker(&mut timeline.markers, marker)
+    }
+
+    /// Remove a marker by id
+    pub fn remove_marker(&mut self, marker_id: &str) -> Result<(), TimelineError> {
+        let timeline = self.get_timeline_mut()?;
+        markers::remove_marker(&mut timeline.markers, marker_id)?;
+
+        info!("Removed marker {}", marker_id);
+        Ok(())
+    }
+
     /// Save timeline to project file
     pub fn save_project(&mut self, path: PathBuf) -> Result<(), TimelineError> {
         let timeline = self.get_timeline()?.clone();
@@ -420,6 +668,18 @@ impl TimelineService {
         Ok(project.timeline)
     }
 
+    /// Check that a clip has a source the track can render
+    fn check_clip_source(track: &Track, clip: &Clip) -> Option<String> {
+        if clip.is_generated() {
+            if track.track_type == TrackType::Audio {
+                return Some(format!("Generated clip {} cannot be placed on an audio track", clip.id));
+            }
+        } else if clip.media_file_id.is_empty() {
+            return Some(format!("Clip {} has no media file or generator", clip.id));
+        }
+        None
+    }
+
     /// Check if a clip overlaps with existing clips in the track
     fn check_overlap(track: &Track, new_clip: &Clip) -> Option<String> {
         let new_start = new_clip.track_position;
@@ -466,6 +726,7 @@ impl TimelineService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::ClipGenerator;
 
     fn create_test_clip(position: f64, duration: f64) -> Clip {
         Clip {
@@ -479,6 +740,9 @@ mod tests {
             effects: Vec::new(),
             volume: 1.0,
             speed: 1.0,
+            generator: None,
+            ken_burns: None,
+            animation_span: None,
         }
     }
 
@@ -545,6 +809,33 @@ mod tests {
         assert_eq!(clips[0].duration, 5.0);
         assert_eq!(clips[1].duration, 5.0);
         assert_eq!(clips[1].track_position, 5.0);
+        // Both halves animate across the original clip
+        assert_eq!(clips[0].animation_range(), (0.0, 10.0));
+        assert_eq!(clips[1].animation_range(), (0.0, 10.0));
+    }
+
+    #[test]
+    fn test_split_clip_at_source_times() {
+        let mut service = TimelineService::new();
+        service.create_timeline(
+            "Test".to_string(),
+            30.0,
+            Resolution { width: 1920, height: 1080 },
+        ).unwrap();
+
+        let track_id = service.get_timeline().unwrap().tracks[0].id.clone();
+        let clip = create_test_clip(2.0, 10.0);
+        let clip_id = clip.id.clone();
+        service.add_clip(&track_id, clip).unwrap();
+
+        // Cuts at the clip's edges or outside it are ignored
+        let pieces = service.split_clip_at_source_times(&clip_id, &[7.5, 0.0, 3.0, 12.0]).unwrap();
+
+        let clips = &service.get_timeline().unwrap().tracks[0].clips;
+        let ids: Vec<String> = clips.iter().map(|c| c.id.clone()).collect();
+        assert_eq!(pieces, ids);
+        let spans: Vec<(f64, f64)> = clips.iter().map(|c| (c.track_position, c.trim_start)).collect();
+        assert_eq!(spans, vec![(2.0, 0.0), (5.0, 3.0), (9.5, 7.5)]);
     }
 
     #[test]
@@ -568,4 +859,136 @@ mod tests {
 
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_generated_clip_placement() {
+        let mut service = TimelineService::new();
+        service.create_timeline(
+            "Test".to_string(),
+            30.0,
+            Resolution { width: 1920, height: 1080 },
+        ).unwrap();
+
+        let audio_track_id = service.get_timeline().unwrap().tracks[1].id.clone();
+        let overlay_track_id = service.add_track(TrackType::Overlay).unwrap();
+
+        let mut clip = create_test_clip(0.0, 5.0);
+        clip.media_file_id = String::new();
+        clip.generator = Some(ClipGenerator::SolidColor { color: "#000000".to_string() });
+
+        // Generated clips have no audio to play
+        assert!(service.add_clip(&audio_track_id, clip.clone()).is_err());
+        service.add_clip(&overlay_track_id, clip).unwrap();
+
+        // Clips need either a media file or a generator
+        let mut empty = create_test_clip(5.0, 5.0);
+        empty.media_file_id = String::new();
+        assert!(service.add_clip(&overlay_track_id, empty).is_err());
+    }
+
+    fn subtitle_track(language: &str, text: &str) -> SubtitleTrack {
+        SubtitleTrack {
+            segments: vec![crate::models::SubtitleSegment {
+                id: 1,
+                start_time: 0.0,
+                end_time: 2.0,
+                text: text.to_string(),
+                words: Vec::new(),
+                style: None,
+                cue_settings: None,
+                style_override: None,
+            }],
+            language: language.to_string(),
+            source: crate::models::SubtitleSource::Manual,
+            ass_header: None,
+        }
+    }
+
+    #[test]
+    fn test_subtitle_tracks_by_language() {
+        let mut service = TimelineService::new();
+        service.create_timeline(
+            "Test".to_string(),
+            30.0,
+            Resolution { width: 1920, height: 1080 },
+        ).unwrap();
+
+        service.set_subtitle_track(subtitle_track("en", "Hello")).unwrap();
+        service.set_subtitle_track(subtitle_track("de", "Hallo")).unwrap();
+        service.set_subtitle_track(subtitle_track("en", "Hi")).unwrap();
+
+        let timeline = service.get_timeline().unwrap();
+        assert_eq!(timeline.subtitle_tracks.len(), 2);
+        assert_eq!(timeline.subtitle_track("en").unwrap().segments[0].text, "Hi");
+        // Nothing is drawn until subtitles are switched on
+        assert!(timeline.burn_in_subtitle_track().is_none());
+
+        assert!(service.set_burn_in_subtitles(Some("fr".to_string())).is_err());
+        service.set_burn_in_subtitles(Some("de".to_string())).unwrap();
+        let mut timeline = service.get_timeline().unwrap().clone();
+        timeline.subtitle_enabled = true;
+        assert_eq!(timeline.burn_in_subtitle_track().unwrap().language, "de");
+        assert_eq!(timeline.export_subtitle_tracks().len(), 2);
+
+        // Removing the burn-in track falls back to the first track
+        service.remove_subtitle_track("de").unwrap();
+        let timeline = service.get_timeline().unwrap();
+        assert_eq!(timeline.burn_in_language, None);
+        assert!(service.remove_subtitle_track("de").is_err());
+    }
+
+    #[test]
+    fn test_markers_round_trip_through_project() {
+        let mut service = TimelineService::new();
+        service.create_timeline(
+            "Test".to_string(),
+            30.0,
+            Resolution { width: 1920, height: 1080 },
+        ).unwrap();
+        service.add_marker(TimelineMarker {
+            id: String::new(),
+            name: "Intro".to_string(),
+            time: 1.5,
+            end_time: Some(4.0),
+            kind: crate::models::MarkerKind::Chapter,
+            color: "#48BB78".to_string(),
+            notes: "Tighten the opening".to_string(),
+        }).unwrap();
+
+        let path = std::env::temp_dir().join(format!("clipforge_markers_{}.json", Uuid::new_v4()));
+        service.save_project(path.clone()).unwrap();
+        let saved = service.get_timeline().unwrap().markers.clone();
+        let loaded = TimelineService::new().load_project(path.clone()).unwrap();
+        fs::remove_file(&path).ok();
+
+        assert_eq!(loaded.markers, saved);
+        assert_eq!(loaded.markers[0].notes, "Tighten the opening");
+    }
+
+    #[test]
+    fn test_load_project_with_single_subtitle_track() {
+        let mut service = TimelineService::new();
+        let timeline = service.create_timeline(
+            "Test".to_string(),
+            30.0,
+            Resolution { width: 1920, height: 1080 },
+        ).unwrap();
+
+        // Projects saved before multiple tracks stored one `subtitle_track`
+        let mut json = serde_json::to_value(&Project {
+            version: "1.0.0".to_string(),
+            timeline,
+            created_at: String::new(),
+            modified_at: String::new(),
+        }).unwrap();
+        json["timeline"]["subtitle_track"] = serde_json::to_value(subtitle_track("es", "Hola")).unwrap();
+
+        let path = std::env::temp_dir().join(format!("clipforge_legacy_{}.json", Uuid::new_v4()));
+        fs::write(&path, json.to_string()).unwrap();
+        let loaded = service.load_project(path.clone()).unwrap();
+        fs::remove_file(&path).ok();
+
+        assert_eq!(loaded.subtitle_tracks.len(), 1);
+        assert_eq!(loaded.subtitle_tracks[0].language, "es");
+    }
 }
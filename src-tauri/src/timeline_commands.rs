@@ -1,5 +1,10 @@
-use crate::models::{Timeline, Clip, TrackType, Resolution};
+use crate::models::{Timeline, Clip, TrackType, Resolution, SubtitleStyle, SubtitleTrack, MediaFile, MediaMarkerKind, TimelineMarker};
+use crate::markers;
+use crate::file_service::FileService;
+use crate::cut_suggestions::{self, CutAnalysisOptions, CutSuggestion};
+use crate::transcript_edit::{self, CutResult, TimeRange, TranscriptRemoval};
 use crate::timeline::TimelineService;
+use log::info;
 use tauri::State;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -128,6 +133,38 @@ pub async fn split_clip_at_time(
         .map_err(|e| e.to_string())
 }
 
+/// Split a clip at every scene cut detected in its source media
+///
+/// Returns the ids of the resulting pieces in timeline order.
+#[tauri::command]
+pub async fn split_clip_at_scene_cuts(
+    clip_id: String,
+    state: State<'_, TimelineServiceState>,
+    file_service: State<'_, FileService>,
+) -> Result<Vec<String>, String> {
+    let mut service = state.service.lock().await;
+    let media_file_id = service.get_timeline()
+        .map_err(|e| e.to_string())?
+        .tracks.iter()
+        .flat_map(|t| &t.clips)
+        .find(|c| c.id == clip_id)
+        .map(|c| c.media_file_id.clone())
+        .ok_or_else(|| format!("Clip not found: {}", clip_id))?;
+
+    let cuts: Vec<f64> = file_service.get_markers(&media_file_id)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|m| m.kind == MediaMarkerKind::SceneCut)
+        .map(|m| m.time)
+        .collect();
+    if cuts.is_empty() {
+        return Err("No scene cuts detected for this clip's media".to_string());
+    }
+
+    service.split_clip_at_source_times(&clip_id, &cuts)
+        .map_err(|e| e.to_string())
+}
+
 /// Get clips at a specific time (playhead position)
 #[tauri::command]
 pub async fn get_clips_at_playhead(
@@ -164,3 +201,232 @@ pub async fn load_timeline_project(
     service.load_project(path)
         .map_err(|e| e.to_string())
 }
+
+/// Add a marker, chapter or region to the current timeline
+#[tauri::command]
+pub async fn add_timeline_marker(
+    marker: TimelineMarker,
+    state: State<'_, TimelineServiceState>,
+) -> Result<Timeline, String> {
+    let mut service = state.service.lock().await;
+    service.add_marker(marker)
+        .map_err(|e| e.to_string())?;
+
+    service.get_timeline()
+        .cloned()
+        .map_err(|e| e.to_string())
+}
+
+/// Replace a marker's name, times, kind, color or notes
+#[tauri::command]
+pub async fn update_timeline_marker(
+    marker: TimelineMarker,
+    state: State<'_, TimelineServiceState>,
+) -> Result<Timeline, String> {
+    let mut service = state.service.lock().await;
+    service.update_marker(marker)
+        .map_err(|e| e.to_string())?;
+
+    service.get_timeline()
+        .cloned()
+        .map_err(|e| e.to_string())
+}
+
+/// Remove a marker from the current timeline
+#[tauri::command]
+pub async fn remove_timeline_marker(
+    marker_id: String,
+    state: State<'_, TimelineServiceState>,
+) -> Result<Timeline, String> {
+    let mut service = state.service.lock().await;
+    service.remove_marker(&marker_id)
+        .map_err(|e| e.to_string())?;
+
+    service.get_timeline()
+        .cloned()
+        .map_err(|e| e.to_string())
+}
+
+/// First marker after a time, for jumping the playhead forward
+#[tauri::command]
+pub async fn get_next_marker(
+    time: f64,
+    state: State<'_, TimelineServiceState>,
+) -> Result<Option<TimelineMarker>, String> {
+    let service = state.service.lock().await;
+    let timeline = service.get_timeline()
+        .map_err(|e| e.to_string())?;
+
+    Ok(markers::next_marker(&timeline.markers, time).cloned())
+}
+
+/// Last marker before a time, for jumping the playhead back
+#[tauri::command]
+pub async fn get_previous_marker(
+    time: f64,
+    state: State<'_, TimelineServiceState>,
+) -> Result<Option<TimelineMarker>, String> {
+    let service = state.service.lock().await;
+    let timeline = service.get_timeline()
+        .map_err(|e| e.to_string())?;
+
+    Ok(markers::previous_marker(&timeline.markers, time).cloned())
+}
+
+/// YouTube-style chapter list of the timeline's chapter markers, optionally saved to a text file
+#[tauri::command]
+pub async fn export_youtube_chapters(
+    path: Option<String>,
+    state: State<'_, TimelineServiceState>,
+) -> Result<String, String> {
+    let service = state.service.lock().await;
+    let timeline = service.get_timeline()
+        .map_err(|e| e.to_string())?;
+
+    let chapters = markers::chapters(timeline);
+    if chapters.is_empty() {
+        return Err("The timeline has no chapter markers".to_string());
+    }
+
+    let text = markers::youtube_chapters(&chapters);
+    if let Some(path) = path {
+        std::fs::write(&path, format!("{}\n", text))
+            .map_err(|e| format!("Failed to write chapters: {}", e))?;
+        info!("Exported {} chapters to {}", chapters.len(), path);
+    }
+
+    Ok(text)
+}
+
+/// Set the look of burned-in subtitles for the current timeline
+#[tauri::command]
+pub async fn set_subtitle_style(
+    style: SubtitleStyle,
+    state: State<'_, TimelineServiceState>,
+) -> Result<Timeline, String> {
+    let mut service = state.service.lock().await;
+    service.set_subtitle_style(style)
+        .map_err(|e| e.to_string())?;
+
+    service.get_timeline()
+        .cloned()
+        .map_err(|e| e.to_string())
+}
+
+/// Add a subtitle track, replacing the current track for its language
+#[tauri::command]
+pub async fn set_subtitle_track(
+    track: SubtitleTrack,
+    state: State<'_, TimelineServiceState>,
+) -> Result<Timeline, String> {
+    let mut service = state.service.lock().await;
+    service.set_subtitle_track(track)
+        .map_err(|e| e.to_string())?;
+
+    service.get_timeline()
+        .cloned()
+        .map_err(|e| e.to_string())
+}
+
+/// Remove the subtitle track for a language
+#[tauri::command]
+pub async fn remove_subtitle_track(
+    language: String,
+    state: State<'_, TimelineServiceState>,
+) -> Result<Timeline, String> {
+    let mut service = state.service.lock().await;
+    service.remove_subtitle_track(&language)
+        .map_err(|e| e.to_string())?;
+
+    service.get_timeline()
+        .cloned()
+        .map_err(|e| e.to_string())
+}
+
+/// Choose the subtitle language burned into the video
+#[tauri::command]
+pub async fn set_burn_in_subtitles(
+    language: Option<String>,
+    state: State<'_, TimelineServiceState>,
+) -> Result<Timeline, String> {
+    let mut service = state.service.lock().await;
+    service.set_burn_in_subtitles(language)
+        .map_err(|e| e.to_string())?;
+
+    service.get_timeline()
+        .cloned()
+        .map_err(|e| e.to_string())
+}
+
+/// Cut transcript words or segments out of the timeline with splits and ripple deletes
+///
+/// With `preview` set the current timeline is left alone and the result
+/// shows what the edit would produce.
+#[tauri::command]
+pub async fn cut_transcript(
+    language: String,
+    removals: Vec<TranscriptRemoval>,
+    preview: bool,
+    state: State<'_, TimelineServiceState>,
+) -> Result<CutResult, String> {
+    let mut service = state.service.lock().await;
+    let timeline = service.get_timeline()
+        .map_err(|e| e.to_string())?;
+
+    let track = timeline.subtitle_track(&language)
+        .ok_or_else(|| format!("No subtitle track for language: {}", language))?;
+    let removed = transcript_edit::removal_ranges(track, &removals)
+        .map_err(|e| e.to_string())?;
+
+    transcript_edit::cut(&mut service, removed, preview)
+        .map_err(|e| e.to_string())
+}
+
+/// Cut time ranges, such as accepted cut suggestions, out of the timeline
+#[tauri::command]
+pub async fn apply_cuts(
+    ranges: Vec<TimeRange>,
+    preview: bool,
+    state: State<'_, TimelineServiceState>,
+) -> Result<CutResult, String> {
+    let mut service = state.service.lock().await;
+
+    transcript_edit::cut(&mut service, transcript_edit::merge_ranges(ranges), preview)
+        .map_err(|e| e.to_string())
+}
+
+/// Propose cuts for a clip's silences and, with a transcript language, its filler words
+///
+/// Nothing is changed; accepted ranges are applied with `apply_cuts`.
+#[tauri::command]
+pub async fn detect_cut_suggestions(
+    clip_id: String,
+    media_file: MediaFile,
+    language: Option<String>,
+    options: Option<CutAnalysisOptions>,
+    state: State<'_, TimelineServiceState>,
+) -> Result<Vec<CutSuggestion>, String> {
+    // Copy what the analysis needs so the timeline isn't locked while FFmpeg runs
+    let (clip, transcript) = {
+        let service = state.service.lock().await;
+        let timeline = service.get_timeline()
+            .map_err(|e| e.to_string())?;
+
+        let clip = timeline.tracks.iter()
+            .flat_map(|t| &t.clips)
+            .find(|c| c.id == clip_id)
+            .cloned()
+            .ok_or_else(|| format!("Clip not found: {}", clip_id))?;
+        let transcript = match &language {
+            Some(language) => Some(timeline.subtitle_track(language)
+                .cloned()
+                .ok_or_else(|| format!("No subtitle track for language: {}", language))?),
+            None => None,
+        };
+        (clip, transcript)
+    };
+
+    cut_suggestions::analyze_clip(&clip, &media_file, transcript.as_ref(), &options.unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())
+}
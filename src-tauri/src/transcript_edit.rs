@@ -0,0 +1,272 @@
+use crate::models::{SubtitleTrack, Timeline, TrackType};
+use crate::timeline::{TimelineError, TimelineService};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+/// Ranges closer than this are removed as one (seconds)
+const MERGE_GAP: f64 = 0.01;
+
+/// Part of a transcript to cut out of the timeline
+///
+/// Without word indices the whole segment is removed. Word indices are
+/// inclusive and need word timing on the segment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptRemoval {
+    pub segment_id: usize,
+    pub first_word: Option<usize>,
+    pub last_word: Option<usize>,
+}
+
+/// A span of timeline time (seconds)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TimeRange {
+    pub start: f64,
+    pub end: f64,
+}
+
+/// One event of an edit decision list: a piece of source placed on the timeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EdlEvent {
+    pub index: usize,
+    pub track_id: String,
+    pub track_type: TrackType,
+    pub clip_id: String,
+    pub media_file_id: String,
+    pub source_in: f64,
+    pub source_out: f64,
+    pub record_in: f64,
+    pub record_out: f64,
+}
+
+/// Outcome of cutting ranges out of the timeline, applied or previewed
+#[derive(Debug, Clone, Serialize)]
+pub struct CutResult {
+    pub timeline: Timeline,
+    pub removed: Vec<TimeRange>,  // In the original timeline's time
+    pub removed_duration: f64,
+    pub edl: Vec<EdlEvent>,
+}
+
+/// Timeline ranges covered by transcript removals, sorted and merged
+pub fn removal_ranges(
+    track: &SubtitleTrack,
+    removals: &[TranscriptRemoval],
+) -> Result<Vec<TimeRange>, TimelineError> {
+    let mut ranges = Vec::with_capacity(removals.len());
+
+    for removal in removals {
+        let segment = track.segments.iter()
+            .find(|s| s.id == removal.segment_id)
+            .ok_or_else(|| TimelineError::InvalidOperation(
+                format!("Subtitle segment not found: {}", removal.segment_id)
+            ))?;
+
+        let range = match (removal.first_word, removal.last_word) {
+            (None, None) => TimeRange { start: segment.start_time, end: segment.end_time },
+            (first, last) => {
+                if segment.words.is_empty() {
+                    return Err(TimelineError::InvalidOperation(format!(
+                        "Subtitle segment {} has no word timing; remove the whole segment instead",
+                        segment.id
+                    )));
+                }
+                let first = first.unwrap_or(0);
+                let last = last.unwrap_or(segment.words.len() - 1);
+                if first > last || last >= segment.words.len() {
+                    return Err(TimelineError::InvalidOperation(format!(
+                        "Invalid word range {}-{} in subtitle segment {}", first, last, segment.id
+                    )));
+                }
+                TimeRange { start: segment.words[first].start_time, end: segment.words[last].end_time }
+            }
+        };
+
+        ranges.push(range);
+    }
+
+    Ok(merge_ranges(ranges))
+}
+
+/// Sort ranges and join those that overlap or nearly touch, dropping empty ones
+pub fn merge_ranges(mut ranges: Vec<TimeRange>) -> Vec<TimeRange> {
+    ranges.retain(|r| r.end > r.start && r.start >= 0.0);
+    ranges.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+    let mut merged: Vec<TimeRange> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end + MERGE_GAP => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Ripple delete the ranges, latest first so earlier ranges keep their times
+pub fn apply_ranges(service: &mut TimelineService, ranges: &[TimeRange]) -> Result<(), TimelineError> {
+    for range in ranges.iter().rev() {
+        service.ripple_delete(range.start, range.end)?;
+    }
+    Ok(())
+}
+
+/// Cut merged ranges out of the current timeline, or preview the cut on a copy
+pub fn cut(
+    service: &mut TimelineService,
+    removed: Vec<TimeRange>,
+    preview: bool,
+) -> Result<CutResult, TimelineError> {
+    // Edit a copy first, so a failed ripple never leaves the timeline half cut
+    let mut scratch = TimelineService::from_timeline(service.get_timeline()?.clone());
+    apply_ranges(&mut scratch, &removed)?;
+
+    // Splits create new clip ids, so an applied edit reports the real timeline
+    let edited = if preview {
+        scratch.get_timeline()?.clone()
+    } else {
+        apply_ranges(service, &removed)?;
+        info!("Cut {} ranges from the timeline", removed.len());
+        service.get_timeline()?.clone()
+    };
+
+    Ok(CutResult {
+        edl: edit_decision_list(&edited),
+        removed_duration: removed.iter().map(|r| r.end - r.start).sum(),
+        removed,
+        timeline: edited,
+    })
+}
+
+/// Edit decision list of a timeline's media clips, by track then position
+///
+/// Generated clips have no source and are left out.
+pub fn edit_decision_list(timeline: &Timeline) -> Vec<EdlEvent> {
+    let mut events = Vec::new();
+
+    for track in &timeline.tracks {
+        let mut clips: Vec<_> = track.clips.iter().filter(|c| !c.is_generated()).collect();
+        clips.sort_by(|a, b| a.track_position.total_cmp(&b.track_position));
+
+        for clip in clips {
+            events.push(EdlEvent {
+                index: events.len() + 1,
+                track_id: track.id.clone(),
+                track_type: track.track_type,
+                clip_id: clip.id.clone(),
+                media_file_id: clip.media_file_id.clone(),
+                source_in: clip.trim_start,
+                source_out: clip.trim_end,
+                record_in: clip.track_position,
+                record_out: clip.track_position + clip.duration,
+            });
+        }
+    }
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Clip, Resolution, SubtitleSegment, SubtitleSource, SubtitleWord};
+
+    fn word(text: &str, start_time: f64, end_time: f64) -> SubtitleWord {
+        SubtitleWord { text: text.to_string(), start_time, end_time }
+    }
+
+    fn transcript() -> SubtitleTrack {
+        SubtitleTrack {
+            segments: vec![
+                SubtitleSegment {
+                    id: 1,
+                    start_time: 1.0,
+                    end_time: 4.0,
+                    text: "Hello um well everyone".to_string(),
+                    words: vec![word("Hello", 1.0, 1.5), word("um", 1.6, 2.0), word("well", 2.0, 2.4), word("everyone", 3.0, 4.0)],
+                    style: None,
+                    cue_settings: None,
+                    style_override: None,
+                },
+                SubtitleSegment {
+                    id: 2,
+                    start_time: 5.0,
+                    end_time: 6.0,
+                    text: "Scratch that".to_string(),
+                    words: Vec::new(),
+                    style: None,
+                    cue_settings: None,
+                    style_override: None,
+                },
+            ],
+            language: "en".to_string(),
+            source: SubtitleSource::Manual,
+            ass_header: None,
+        }
+    }
+
+    #[test]
+    fn test_removal_ranges_merge_and_validate() {
+        let track = transcript();
+        let removals = [
+            TranscriptRemoval { segment_id: 2, first_word: None, last_word: None },
+            TranscriptRemoval { segment_id: 1, first_word: Some(1), last_word: Some(1) },
+            TranscriptRemoval { segment_id: 1, first_word: Some(2), last_word: Some(2) },
+        ];
+
+        let ranges = removal_ranges(&track, &removals).unwrap();
+        assert_eq!(ranges, vec![TimeRange { start: 1.6, end: 2.4 }, TimeRange { start: 5.0, end: 6.0 }]);
+
+        let bad = [TranscriptRemoval { segment_id: 2, first_word: Some(0), last_word: None }];
+        assert!(removal_ranges(&track, &bad).is_err());
+        let bad = [TranscriptRemoval { segment_id: 1, first_word: Some(2), last_word: Some(9) }];
+        assert!(removal_ranges(&track, &bad).is_err());
+    }
+
+    #[test]
+    fn test_apply_ranges_ripples_clips_and_subtitles() {
+        let mut service = TimelineService::new();
+        service.create_timeline("Test".to_string(), 30.0, Resolution { width: 1920, height: 1080 }).unwrap();
+        let track_ids: Vec<String> = service.get_timeline().unwrap().tracks.iter().map(|t| t.id.clone()).collect();
+        for track_id in &track_ids {
+            service.add_clip(track_id, Clip {
+                id: uuid::Uuid::new_v4().to_string(),
+                media_file_id: "talk".to_string(),
+                name: None,
+                track_position: 0.0,
+                duration: 10.0,
+                trim_start: 0.0,
+                trim_end: 10.0,
+                effects: Vec::new(),
+                volume: 1.0,
+                speed: 1.0,
+                generator: None,
+                ken_burns: None,
+                animation_span: None,
+            }).unwrap();
+        }
+        service.set_subtitle_track(transcript()).unwrap();
+
+        let ranges = [TimeRange { start: 1.6, end: 2.4 }, TimeRange { start: 5.0, end: 6.0 }];
+        apply_ranges(&mut service, &ranges).unwrap();
+
+        let timeline = service.get_timeline().unwrap();
+        assert!((timeline.duration - 8.2).abs() < 1e-9);
+
+        let edl = edit_decision_list(timeline);
+        let video: Vec<_> = edl.iter().filter(|e| e.track_type == TrackType::Video).collect();
+        let spans: Vec<(f64, f64, f64, f64)> = video.iter()
+            .map(|e| (e.source_in, e.source_out, e.record_in, e.record_out))
+            .collect();
+        let expected = [(0.0, 1.6, 0.0, 1.6), (2.4, 5.0, 1.6, 4.2), (6.0, 10.0, 4.2, 8.2)];
+        assert_eq!(spans.len(), expected.len());
+        for (actual, expected) in spans.iter().zip(expected) {
+            for (a, b) in [(actual.0, expected.0), (actual.1, expected.1), (actual.2, expected.2), (actual.3, expected.3)] {
+                assert!((a - b).abs() < 1e-9, "{:?} != {:?}", actual, expected);
+            }
+        }
+
+        let subtitles = timeline.subtitle_track("en").unwrap();
+        assert_eq!(subtitles.segments.len(), 1);
+        assert_eq!(subtitles.segments[0].text, "Hello everyone");
+    }
+}
@@ -0,0 +1,534 @@
+use crate::models::{SubtitleError, SubtitleSegment, SubtitleWord};
+use log::{error, info};
+use reqwest::multipart;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::fs;
+
+/// Base URL of the hosted OpenAI API
+pub const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// Model used by the hosted OpenAI API
+pub const OPENAI_WHISPER_MODEL: &str = "whisper-1";
+
+/// Backend that turns an audio file into timed subtitle segments
+#[async_trait::async_trait]
+pub trait TranscriptionProvider: Send + Sync {
+    /// Identifier recorded in `SubtitleSource` and used to key the cache
+    fn name(&self) -> String;
+
+    /// File extension of the audio format the backend reads
+    fn audio_extension(&self) -> &'static str {
+        "mp3"
+    }
+
+    /// Transcribe an audio file, with times relative to its start
+    async fn transcribe(
+        &self,
+        audio_path: &Path,
+        language: Option<&str>,
+    ) -> Result<Vec<SubtitleSegment>, SubtitleError>;
+}
+
+/// How requests to an HTTP backend are authenticated
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum TranscriptionAuth {
+    None,
+    Bearer { token: String },
+    Header { name: String, value: String },
+}
+
+impl TranscriptionAuth {
+    /// Add the credentials to a request
+    pub fn apply(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match self {
+            TranscriptionAuth::None => request,
+            TranscriptionAuth::Bearer { token } => request.bearer_auth(token),
+            TranscriptionAuth::Header { name, value } => request.header(name.as_str(), value.as_str()),
+        }
+    }
+}
+
+/// Output format of a command-line whisper binary
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WhisperCliFlavor {
+    WhisperCpp,  // whisper.cpp `whisper-cli`
+    OpenAiWhisper,  // Python `whisper` package
+}
+
+/// Serializable description of a transcription backend
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum TranscriptionConfig {
+    /// Any server implementing `POST {base_url}/audio/transcriptions`
+    OpenAiCompatible {
+        base_url: String,
+        model: String,
+        auth: TranscriptionAuth,
+    },
+    /// A whisper binary run on this machine
+    WhisperCli {
+        binary_path: PathBuf,
+        model: String,  // Model file for whisper.cpp, model name for Python whisper
+        flavor: WhisperCliFlavor,
+    },
+}
+
+impl TranscriptionConfig {
+    /// Configuration for the hosted OpenAI API
+    pub fn openai(api_key: String) -> Self {
+        TranscriptionConfig::OpenAiCompatible {
+            base_url: OPENAI_BASE_URL.to_string(),
+            model: OPENAI_WHISPER_MODEL.to_string(),
+            auth: TranscriptionAuth::Bearer { token: api_key },
+        }
+    }
+
+    /// Create the provider described by this configuration
+    pub fn build(&self) -> Result<Box<dyn TranscriptionProvider>, SubtitleError> {
+        match self {
+            TranscriptionConfig::OpenAiCompatible { base_url, model, auth } => {
+                Ok(Box::new(OpenAiCompatibleProvider::new(base_url, model, auth.clone())?))
+            }
+            TranscriptionConfig::WhisperCli { binary_path, model, flavor } => {
+                Ok(Box::new(WhisperCliProvider::new(binary_path.clone(), model.clone(), *flavor)?))
+            }
+        }
+    }
+}
+
+/// OpenAI-compatible verbose JSON response with segments
+#[derive(Debug, Deserialize)]
+struct VerboseResponse {
+    segments: Vec<VerboseSegment>,
+    #[serde(default)]
+    words: Vec<VerboseWord>,  // Present when word timestamps were requested
+}
+
+#[derive(Debug, Deserialize)]
+struct VerboseSegment {
+    id: usize,
+    start: f64,
+    end: f64,
+    text: String,
+    #[serde(default)]
+    words: Vec<VerboseWord>,  // Written per segment by the whisper CLI
+}
+
+#[derive(Debug, Deserialize)]
+struct VerboseWord {
+    word: String,
+    start: f64,
+    end: f64,
+}
+
+impl From<VerboseWord> for SubtitleWord {
+    fn from(word: VerboseWord) -> Self {
+        SubtitleWord {
+            text: word.word.trim().to_string(),
+            start_time: word.start,
+            end_time: word.end,
+        }
+    }
+}
+
+impl From<VerboseSegment> for SubtitleSegment {
+    fn from(seg: VerboseSegment) -> Self {
+        SubtitleSegment {
+            id: seg.id,
+            start_time: seg.start,
+            end_time: seg.end,
+            text: seg.text.trim().to_string(),
+            words: seg.words.into_iter()
+                .map(SubtitleWord::from)
+                .filter(|w| !w.text.is_empty())
+                .collect(),
+            style: None,
+            cue_settings: None,
+            style_override: None,
+        }
+    }
+}
+
+impl VerboseResponse {
+    /// Segments with the response-level words assigned to them by midpoint
+    fn into_segments(self) -> Vec<SubtitleSegment> {
+        let mut segments: Vec<SubtitleSegment> = self.segments.into_iter().map(SubtitleSegment::from).collect();
+
+        for word in self.words.into_iter().map(SubtitleWord::from).filter(|w| !w.text.is_empty()) {
+            let mid = (word.start_time + word.end_time) / 2.0;
+            let index = segments.iter()
+                .position(|s| mid < s.end_time)
+                .unwrap_or(segments.len().saturating_sub(1));
+            if let Some(segment) = segments.get_mut(index) {
+                segment.words.push(word);
+            }
+        }
+
+        segments
+    }
+}
+
+/// Transcription over the OpenAI audio API, hosted or self-hosted
+pub struct OpenAiCompatibleProvider {
+    endpoint: String,
+    model: String,
+    auth: TranscriptionAuth,
+    client: reqwest::Client,
+}
+
+impl OpenAiCompatibleProvider {
+    pub fn new(base_url: &str, model: &str, auth: TranscriptionAuth) -> Result<Self, SubtitleError> {
+        let base_url = base_url.trim().trim_end_matches('/');
+        if !(base_url.starts_with("http://") || base_url.starts_with("https://")) {
+            return Err(SubtitleError::ProviderError(
+                format!("Base URL must start with http:// or https://: {}", base_url)
+            ));
+        }
+        if model.trim().is_empty() {
+            return Err(SubtitleError::ProviderError("Model name is required".to_string()));
+        }
+
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(300))  // 5 minute timeout
+            .build()?;
+
+        Ok(Self {
+            endpoint: format!("{}/audio/transcriptions", base_url),
+            model: model.trim().to_string(),
+            auth,
+            client,
+        })
+    }
+
+    fn is_hosted_openai(&self) -> bool {
+        self.endpoint.starts_with(OPENAI_BASE_URL)
+    }
+}
+
+#[async_trait::async_trait]
+impl TranscriptionProvider for OpenAiCompatibleProvider {
+    fn name(&self) -> String {
+        if self.is_hosted_openai() && self.model == OPENAI_WHISPER_MODEL {
+            "openai-whisper".to_string()
+        } else {
+            let host = self.endpoint
+                .split("://")
+                .nth(1)
+                .and_then(|rest| rest.split('/').next())
+                .unwrap_or_default();
+            format!("openai-compatible:{}@{}", self.model, host)
+        }
+    }
+
+    async fn transcribe(
+        &self,
+        audio_path: &Path,
+        language: Option<&str>,
+    ) -> Result<Vec<SubtitleSegment>, SubtitleError> {
+        info!("Calling transcription endpoint {} with model {}", self.endpoint, self.model);
+
+        // Read audio file
+        let audio_bytes = fs::read(audio_path)?;
+        let file_name = audio_path.file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("audio.mp3");
+
+        // Build multipart form
+        let file_part = multipart::Part::bytes(audio_bytes)
+            .file_name(file_name.to_string())
+            .mime_str("audio/mpeg")?;
+
+        let mut form = multipart::Form::new()
+            .part("file", file_part)
+            .text("model", self.model.clone())
+            .text("response_format", "verbose_json")  // Get segments with timing
+            .text("timestamp_granularities[]", "segment")
+            .text("timestamp_granularities[]", "word");
+
+        if let Some(lang) = language {
+            form = form.text("language", lang.to_string());
+        }
+
+        let request = self.auth.apply(self.client.post(&self.endpoint).multipart(form));
+
+        let response = request.send().await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            error!("Transcription API error: {}", error_text);
+            return Err(SubtitleError::ApiError(error_text));
+        }
+
+        let verbose_response: VerboseResponse = response.json().await?;
+        Ok(verbose_response.into_segments())
+    }
+}
+
+/// whisper.cpp JSON output (`-oj`)
+#[derive(Debug, Deserialize)]
+struct WhisperCppOutput {
+    transcription: Vec<WhisperCppSegment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WhisperCppSegment {
+    offsets: WhisperCppOffsets,
+    text: String,
+    #[serde(default)]
+    tokens: Vec<WhisperCppToken>,  // Only in full output (`-ojf`)
+}
+
+#[derive(Debug, Deserialize)]
+struct WhisperCppToken {
+    text: String,
+    offsets: WhisperCppOffsets,
+}
+
+impl WhisperCppSegment {
+    /// Join sub-word tokens into words; a leading space starts a new word
+    fn words(&self) -> Vec<SubtitleWord> {
+        let mut words: Vec<SubtitleWord> = Vec::new();
+
+        for token in &self.tokens {
+            if token.text.starts_with("[_") || token.text.trim().is_empty() {
+                continue;  // Special tokens such as [_BEG_]
+            }
+            let start_time = token.offsets.from as f64 / 1000.0;
+            let end_time = token.offsets.to as f64 / 1000.0;
+
+            match words.last_mut() {
+                Some(word) if !token.text.starts_with(' ') => {
+                    word.text.push_str(&token.text);
+                    word.end_time = end_time;
+                }
+                _ => words.push(SubtitleWord {
+                    text: token.text.trim().to_string(),
+                    start_time,
+                    end_time,
+                }),
+            }
+        }
+
+        words
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct WhisperCppOffsets {
+    from: u64,  // milliseconds
+    to: u64,
+}
+
+/// Transcription with a local whisper binary, keeping audio on this machine
+pub struct WhisperCliProvider {
+    binary_path: PathBuf,
+    model: String,
+    flavor: WhisperCliFlavor,
+}
+
+impl WhisperCliProvider {
+    pub fn new(binary_path: PathBuf, model: String, flavor: WhisperCliFlavor) -> Result<Self, SubtitleError> {
+        // Accept bare command names found on PATH
+        let binary_path = if binary_path.components().count() == 1 {
+            which::which(&binary_path).unwrap_or(binary_path)
+        } else {
+            binary_path
+        };
+
+        if !binary_path.exists() {
+            return Err(SubtitleError::ProviderError(
+                format!("Whisper binary not found: {}", binary_path.display())
+            ));
+        }
+        if model.trim().is_empty() {
+            return Err(SubtitleError::ProviderError("Model is required".to_string()));
+        }
+        if flavor == WhisperCliFlavor::WhisperCpp && !Path::new(&model).exists() {
+            return Err(SubtitleError::ProviderError(
+                format!("Whisper model file not found: {}", model)
+            ));
+        }
+
+        Ok(Self { binary_path, model, flavor })
+    }
+
+    /// Parse the JSON file written by the binary
+    fn parse_output(flavor: WhisperCliFlavor, json: &str) -> Result<Vec<SubtitleSegment>, SubtitleError> {
+        match flavor {
+            WhisperCliFlavor::WhisperCpp => {
+                let output: WhisperCppOutput = serde_json::from_str(json)?;
+                Ok(output.transcription.into_iter()
+                    .filter(|seg| !seg.text.trim().is_empty())
+                    .enumerate()
+                    .map(|(i, seg)| SubtitleSegment {
+                        id: i + 1,
+                        start_time: seg.offsets.from as f64 / 1000.0,
+                        end_time: seg.offsets.to as f64 / 1000.0,
+                        text: seg.text.trim().to_string(),
+                        words: seg.words(),
+                        style: None,
+                        cue_settings: None,
+                        style_override: None,
+                    })
+                    .collect())
+            }
+            WhisperCliFlavor::OpenAiWhisper => {
+                let output: VerboseResponse = serde_json::from_str(json)?;
+                Ok(output.into_segments())
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl TranscriptionProvider for WhisperCliProvider {
+    fn name(&self) -> String {
+        let model = Path::new(&self.model)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.model.clone());
+        match self.flavor {
+            WhisperCliFlavor::WhisperCpp => format!("whisper.cpp:{}", model),
+            WhisperCliFlavor::OpenAiWhisper => format!("whisper-cli:{}", model),
+        }
+    }
+
+    fn audio_extension(&self) -> &'static str {
+        // whisper.cpp only reads 16 kHz WAV
+        match self.flavor {
+            WhisperCliFlavor::WhisperCpp => "wav",
+            WhisperCliFlavor::OpenAiWhisper => "mp3",
+        }
+    }
+
+    async fn transcribe(
+        &self,
+        audio_path: &Path,
+        language: Option<&str>,
+    ) -> Result<Vec<SubtitleSegment>, SubtitleError> {
+        let output_dir = std::env::temp_dir().join(format!("clipforge_whisper_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&output_dir)?;
+
+        let mut command = tokio::process::Command::new(&self.binary_path);
+        let output_file = match self.flavor {
+            WhisperCliFlavor::WhisperCpp => {
+                let output_base = output_dir.join("transcript");
+                command
+                    .arg("-m").arg(&self.model)
+                    .arg("-f").arg(audio_path)
+                    .arg("-ojf")  // Full JSON, with token timing
+                    .arg("-of").arg(&output_base)
+                    .arg("-np");
+                if let Some(lang) = language {
+                    command.arg("-l").arg(lang);
+                }
+                output_base.with_extension("json")
+            }
+            WhisperCliFlavor::OpenAiWhisper => {
+                command
+                    .arg(audio_path)
+                    .arg("--model").arg(&self.model)
+                    .arg("--output_format").arg("json")
+                    .arg("--word_timestamps").arg("True")
+                    .arg("--output_dir").arg(&output_dir);
+                if let Some(lang) = language {
+                    command.arg("--language").arg(lang);
+                }
+                let stem = audio_path.file_stem().unwrap_or_default();
+                output_dir.join(stem).with_extension("json")
+            }
+        };
+
+        info!("Running local whisper: {:?}", self.binary_path);
+        let output = command.output().await?;
+
+        let result = if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            error!("Whisper binary failed: {}", stderr);
+            Err(SubtitleError::ProviderError(stderr))
+        } else {
+            fs::read_to_string(&output_file)
+                .map_err(SubtitleError::from)
+                .and_then(|json| Self::parse_output(self.flavor, &json))
+        };
+
+        let _ = fs::remove_dir_all(&output_dir);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openai_compatible_names() {
+        let hosted = OpenAiCompatibleProvider::new(
+            OPENAI_BASE_URL, OPENAI_WHISPER_MODEL, TranscriptionAuth::None,
+        ).unwrap();
+        assert_eq!(hosted.name(), "openai-whisper");
+
+        let local = OpenAiCompatibleProvider::new(
+            "http://localhost:8000/v1/", "Systran/faster-whisper-small", TranscriptionAuth::None,
+        ).unwrap();
+        assert_eq!(local.endpoint, "http://localhost:8000/v1/audio/transcriptions");
+        assert_eq!(local.name(), "openai-compatible:Systran/faster-whisper-small@localhost:8000");
+
+        assert!(OpenAiCompatibleProvider::new("localhost:8000", "model", TranscriptionAuth::None).is_err());
+    }
+
+    #[test]
+    fn test_parse_whisper_cpp_output() {
+        let json = r#"{"transcription": [
+            {"timestamps": {"from": "00:00:00,000", "to": "00:00:02,500"},
+             "offsets": {"from": 0, "to": 2500}, "text": " Hello there.",
+             "tokens": [
+                {"text": "[_BEG_]", "offsets": {"from": 0, "to": 0}},
+                {"text": " Hello", "offsets": {"from": 0, "to": 800}},
+                {"text": " there", "offsets": {"from": 800, "to": 2300}},
+                {"text": ".", "offsets": {"from": 2300, "to": 2500}}
+             ]},
+            {"timestamps": {"from": "00:00:02,500", "to": "00:00:03,000"},
+             "offsets": {"from": 2500, "to": 3000}, "text": " "}
+        ]}"#;
+
+        let segments = WhisperCliProvider::parse_output(WhisperCliFlavor::WhisperCpp, json).unwrap();
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].id, 1);
+        assert_eq!(segments[0].end_time, 2.5);
+        assert_eq!(segments[0].text, "Hello there.");
+        assert_eq!(segments[0].words, vec![
+            SubtitleWord { text: "Hello".to_string(), start_time: 0.0, end_time: 0.8 },
+            SubtitleWord { text: "there.".to_string(), start_time: 0.8, end_time: 2.5 },
+        ]);
+    }
+
+    #[test]
+    fn test_response_words_assigned_to_segments() {
+        let json = r#"{"segments": [
+            {"id": 0, "start": 0.0, "end": 1.0, "text": " One two"},
+            {"id": 1, "start": 1.0, "end": 2.0, "text": " three"}
+        ], "words": [
+            {"word": "One", "start": 0.0, "end": 0.4},
+            {"word": "two", "start": 0.5, "end": 1.1},
+            {"word": "three", "start": 1.2, "end": 1.8}
+        ]}"#;
+
+        let response: VerboseResponse = serde_json::from_str(json).unwrap();
+        let segments = response.into_segments();
+        assert_eq!(segments[0].words.len(), 2);
+        assert_eq!(segments[1].words[0].text, "three");
+    }
+
+    #[test]
+    fn test_parse_openai_whisper_output() {
+        let json = r#"{"text": "Hi", "segments": [{"id": 0, "start": 0.0, "end": 1.2, "text": " Hi"}]}"#;
+
+        let segments = WhisperCliProvider::parse_output(WhisperCliFlavor::OpenAiWhisper, json).unwrap();
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].start_time, 0.0);
+        assert_eq!(segments[0].text, "Hi");
+    }
+}
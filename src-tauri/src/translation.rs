@@ -0,0 +1,377 @@
+use crate::models::{SubtitleError, SubtitleSource, SubtitleTrack};
+use crate::subtitle_segmentation::{self, SegmentationOptions};
+use crate::transcription::TranscriptionAuth;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+
+/// Backend that translates subtitle text between languages
+#[async_trait::async_trait]
+pub trait TranslationProvider: Send + Sync {
+    /// Identifier recorded in `SubtitleSource::Translated`
+    fn name(&self) -> String;
+
+    /// Most texts sent in one request
+    fn batch_size(&self) -> usize {
+        50
+    }
+
+    /// Translate texts, returning one translation per input in the same order
+    async fn translate(
+        &self,
+        texts: &[String],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<String>, SubtitleError>;
+}
+
+/// Serializable description of a translation backend
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum TranslationConfig {
+    /// Any server implementing `POST {base_url}/chat/completions` (OpenAI, Ollama, llama.cpp, vLLM)
+    OpenAiCompatible {
+        base_url: String,
+        model: String,
+        auth: TranscriptionAuth,
+    },
+    /// A LibreTranslate server, which can run on this machine
+    LibreTranslate {
+        base_url: String,
+        api_key: Option<String>,
+    },
+}
+
+impl TranslationConfig {
+    /// Create the provider described by this configuration
+    pub fn build(&self) -> Result<Box<dyn TranslationProvider>, SubtitleError> {
+        match self {
+            TranslationConfig::OpenAiCompatible { base_url, model, auth } => {
+                Ok(Box::new(ChatTranslationProvider::new(base_url, model, auth.clone())?))
+            }
+            TranslationConfig::LibreTranslate { base_url, api_key } => {
+                Ok(Box::new(LibreTranslateProvider::new(base_url, api_key.clone())?))
+            }
+        }
+    }
+}
+
+/// Translate a subtitle track, keeping every cue's timing
+///
+/// Line breaks are redone for the translated text, and word timing is
+/// dropped since it belongs to the original language.
+pub async fn translate_track(
+    provider: &dyn TranslationProvider,
+    track: &SubtitleTrack,
+    target_language: &str,
+) -> Result<SubtitleTrack, SubtitleError> {
+    if target_language.trim().is_empty() {
+        return Err(SubtitleError::ProviderError("Target language is required".to_string()));
+    }
+    if target_language == track.language {
+        return Err(SubtitleError::ProviderError(
+            format!("Subtitles are already in {}", target_language)
+        ));
+    }
+
+    let texts: Vec<String> = track.segments.iter()
+        .map(|s| s.text.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect();
+
+    let mut translated = Vec::with_capacity(texts.len());
+    for batch in texts.chunks(provider.batch_size().max(1)) {
+        let result = provider.translate(batch, &track.language, target_language).await?;
+        if result.len() != batch.len() {
+            return Err(SubtitleError::ApiError(format!(
+                "Expected {} translations, got {}", batch.len(), result.len()
+            )));
+        }
+        translated.extend(result);
+    }
+
+    let max_chars = SegmentationOptions::default().max_chars_per_line;
+    let segments = track.segments.iter()
+        .zip(translated)
+        .map(|(segment, text)| {
+            let mut segment = segment.clone();
+            segment.text = subtitle_segmentation::wrap_text(&text, max_chars);
+            segment.words.clear();
+            segment
+        })
+        .collect();
+
+    info!("Translated {} subtitles from {} to {} with {}",
+        track.segments.len(), track.language, target_language, provider.name());
+
+    Ok(SubtitleTrack {
+        segments,
+        language: target_language.to_string(),
+        source: SubtitleSource::Translated {
+            from_language: track.language.clone(),
+            provider: provider.name(),
+        },
+        ass_header: track.ass_header.clone(),
+    })
+}
+
+/// Check an HTTP base URL and strip its trailing slash
+fn normalize_base_url(base_url: &str) -> Result<String, SubtitleError> {
+    let base_url = base_url.trim().trim_end_matches('/');
+    if !(base_url.starts_with("http://") || base_url.starts_with("https://")) {
+        return Err(SubtitleError::ProviderError(
+            format!("Base URL must start with http:// or https://: {}", base_url)
+        ));
+    }
+    Ok(base_url.to_string())
+}
+
+fn host(base_url: &str) -> &str {
+    base_url.split("://")
+        .nth(1)
+        .and_then(|rest| rest.split('/').next())
+        .unwrap_or_default()
+}
+
+fn http_client() -> Result<reqwest::Client, SubtitleError> {
+    Ok(reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(120))
+        .build()?)
+}
+
+/// Translation by prompting a chat model
+pub struct ChatTranslationProvider {
+    base_url: String,
+    model: String,
+    auth: TranscriptionAuth,
+    client: reqwest::Client,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChoice {
+    message: ChatMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatMessage {
+    content: String,
+}
+
+impl ChatTranslationProvider {
+    pub fn new(base_url: &str, model: &str, auth: TranscriptionAuth) -> Result<Self, SubtitleError> {
+        if model.trim().is_empty() {
+            return Err(SubtitleError::ProviderError("Model name is required".to_string()));
+        }
+
+        Ok(Self {
+            base_url: normalize_base_url(base_url)?,
+            model: model.trim().to_string(),
+            auth,
+            client: http_client()?,
+        })
+    }
+}
+
+/// Parse the JSON array of strings a chat model was asked to reply with
+fn parse_translation_list(content: &str) -> Result<Vec<String>, SubtitleError> {
+    // Models often wrap JSON in a Markdown code fence
+    let start = content.find('[');
+    let end = content.rfind(']');
+    let json = match (start, end) {
+        (Some(start), Some(end)) if start < end => &content[start..=end],
+        _ => return Err(SubtitleError::ApiError(format!("Translation reply is not a JSON array: {}", content))),
+    };
+
+    serde_json::from_str(json)
+        .map_err(|e| SubtitleError::ApiError(format!("Invalid translation reply: {}", e)))
+}
+
+#[async_trait::async_trait]
+impl TranslationProvider for ChatTranslationProvider {
+    fn name(&self) -> String {
+        format!("openai-compatible:{}@{}", self.model, host(&self.base_url))
+    }
+
+    fn batch_size(&self) -> usize {
+        40  // Keeps replies well inside common output token limits
+    }
+
+    async fn translate(
+        &self,
+        texts: &[String],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<String>, SubtitleError> {
+        let instructions = format!(
+            "You translate video subtitles from {} to {}. The user sends a JSON array of \
+             subtitle cues. Reply with only a JSON array of the translated cues, with exactly \
+             one entry per input entry and in the same order.",
+            source_language, target_language
+        );
+        let body = serde_json::json!({
+            "model": self.model,
+            "temperature": 0,
+            "messages": [
+                { "role": "system", "content": instructions },
+                { "role": "user", "content": serde_json::to_string(texts)? },
+            ],
+        });
+
+        let endpoint = format!("{}/chat/completions", self.base_url);
+        let response = self.auth.apply(self.client.post(&endpoint).json(&body)).send().await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            error!("Translation API error: {}", error_text);
+            return Err(SubtitleError::ApiError(error_text));
+        }
+
+        let chat: ChatResponse = response.json().await?;
+        let content = chat.choices.into_iter().next()
+            .map(|c| c.message.content)
+            .ok_or_else(|| SubtitleError::ApiError("Translation reply has no choices".to_string()))?;
+
+        parse_translation_list(&content)
+    }
+}
+
+/// Translation through a LibreTranslate server
+pub struct LibreTranslateProvider {
+    base_url: String,
+    api_key: Option<String>,
+    client: reqwest::Client,
+}
+
+#[derive(Debug, Deserialize)]
+struct LibreTranslateResponse {
+    #[serde(rename = "translatedText")]
+    translated_text: Vec<String>,
+}
+
+impl LibreTranslateProvider {
+    pub fn new(base_url: &str, api_key: Option<String>) -> Result<Self, SubtitleError> {
+        Ok(Self {
+            base_url: normalize_base_url(base_url)?,
+            api_key: api_key.filter(|k| !k.trim().is_empty()),
+            client: http_client()?,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl TranslationProvider for LibreTranslateProvider {
+    fn name(&self) -> String {
+        format!("libretranslate@{}", host(&self.base_url))
+    }
+
+    fn batch_size(&self) -> usize {
+        100
+    }
+
+    async fn translate(
+        &self,
+        texts: &[String],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<String>, SubtitleError> {
+        let mut body = serde_json::json!({
+            "q": texts,
+            "source": source_language,
+            "target": target_language,
+            "format": "text",
+        });
+        if let Some(api_key) = &self.api_key {
+            body["api_key"] = serde_json::Value::String(api_key.clone());
+        }
+
+        let endpoint = format!("{}/translate", self.base_url);
+        let response = self.client.post(&endpoint).json(&body).send().await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            error!("LibreTranslate error: {}", error_text);
+            return Err(SubtitleError::ApiError(error_text));
+        }
+
+        let result: LibreTranslateResponse = response.json().await?;
+        Ok(result.translated_text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{SubtitleSegment, SubtitleWord};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Stand-in provider that upper-cases text
+    struct ShoutingProvider {
+        requests: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl TranslationProvider for ShoutingProvider {
+        fn name(&self) -> String {
+            "shouting".to_string()
+        }
+
+        fn batch_size(&self) -> usize {
+            2
+        }
+
+        async fn translate(&self, texts: &[String], _: &str, _: &str) -> Result<Vec<String>, SubtitleError> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            Ok(texts.iter().map(|t| t.to_uppercase()).collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_translate_track_keeps_timing() {
+        let segments = (1..=3)
+            .map(|id| SubtitleSegment {
+                id,
+                start_time: id as f64,
+                end_time: id as f64 + 0.5,
+                text: format!("line\n{}", id),
+                words: vec![SubtitleWord { text: "line".to_string(), start_time: 0.0, end_time: 0.1 }],
+                style: None,
+                cue_settings: Some("line:0".to_string()),
+                style_override: None,
+            })
+            .collect();
+        let track = SubtitleTrack {
+            segments,
+            language: "en".to_string(),
+            source: SubtitleSource::Manual,
+            ass_header: None,
+        };
+        let provider = ShoutingProvider { requests: AtomicUsize::new(0) };
+
+        let translated = translate_track(&provider, &track, "de").await.unwrap();
+
+        assert_eq!(provider.requests.load(Ordering::SeqCst), 2);
+        assert_eq!(translated.language, "de");
+        assert!(matches!(
+            translated.source,
+            SubtitleSource::Translated { ref from_language, ref provider } if from_language == "en" && provider == "shouting"
+        ));
+        for (original, translated) in track.segments.iter().zip(&translated.segments) {
+            assert_eq!((original.start_time, original.end_time), (translated.start_time, translated.end_time));
+            assert_eq!(translated.text, format!("LINE {}", original.id));
+            assert_eq!(translated.cue_settings, original.cue_settings);
+            assert!(translated.words.is_empty());
+        }
+
+        assert!(translate_track(&provider, &track, "en").await.is_err());
+    }
+
+    #[test]
+    fn test_parse_translation_list() {
+        let reply = "```json\n[\"Hallo\", \"Welt \\\"zitiert\\\"\"]\n```";
+        assert_eq!(parse_translation_list(reply).unwrap(), vec!["Hallo", "Welt \"zitiert\""]);
+        assert!(parse_translation_list("Sorry, I can't do that").is_err());
+    }
+}
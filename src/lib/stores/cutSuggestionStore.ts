@@ -0,0 +1,74 @@
+import { writable } from 'svelte/store';
+import { invoke } from '@tauri-apps/api/core';
+import { applyCutResult, type CutResult } from './subtitleStore';
+import type { MediaFile } from './mediaLibraryStore';
+
+// Types matching Rust backend
+export interface CutAnalysisOptions {
+    noise_db: number;
+    min_silence: number;
+    padding: number;
+    detect_fillers: boolean;
+    filler_words: string[];
+}
+
+export type CutReason =
+    | { type: 'silence' }
+    | { type: 'filler_word'; word: string };
+
+export interface CutSuggestion {
+    range: { start: number; end: number };
+    reason: CutReason;
+}
+
+// Suggestions for the last analyzed clip
+export const cutSuggestions = writable<CutSuggestion[]>([]);
+
+/**
+ * Find silences in a clip's audio, and filler words when a transcript language is given
+ */
+export async function detectCutSuggestions(
+    clipId: string,
+    mediaFile: MediaFile,
+    language?: string,
+    options?: Partial<CutAnalysisOptions>
+): Promise<CutSuggestion[]> {
+    try {
+        const suggestions = await invoke<CutSuggestion[]>('detect_cut_suggestions', {
+            clipId,
+            mediaFile,
+            language: language ?? null,
+            options: options ?? null,
+        });
+        cutSuggestions.set(suggestions);
+        return suggestions;
+    } catch (error) {
+        console.error('Failed to detect cut suggestions:', error);
+        throw error;
+    }
+}
+
+/**
+ * Ripple delete the suggested ranges, or preview the result without changing the timeline
+ */
+export async function applyCutSuggestions(
+    suggestions: CutSuggestion[],
+    preview: boolean
+): Promise<CutResult> {
+    try {
+        const result = await invoke<CutResult>('apply_cuts', {
+            ranges: suggestions.map(s => s.range),
+            preview,
+        });
+
+        if (!preview) {
+            applyCutResult(result);
+            cutSuggestions.set([]);
+        }
+
+        return result;
+    } catch (error) {
+        console.error('Failed to apply cuts:', error);
+        throw error;
+    }
+}
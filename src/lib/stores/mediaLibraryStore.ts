@@ -41,9 +41,28 @@ export interface FileMetadata {
     has_video: boolean;
 }
 
+export interface MediaMarker {
+    media_file_id: string;
+    kind: 'scene_cut';
+    time: number;           // Source time in seconds
+    score?: number;
+    thumbnail_path?: string;
+}
+
+export interface SceneDetectionOptions {
+    threshold: number;          // Scene score (0-1) that starts a new shot
+    min_shot_duration: number;  // seconds
+}
+
 // Media library state
 export const mediaLibraryStore = writable<MediaFile[]>([]);
 
+// Detected markers by media file ID, loaded on demand
+export const mediaMarkers = writable<Record<string, MediaMarker[]>>({});
+
+// Whether imported videos are analyzed for scene cuts
+export const detectScenesOnImport = writable<boolean>(false);
+
 // Loading state
 export const isLoadingLibrary = writable<boolean>(false);
 
@@ -75,13 +94,24 @@ export async function loadMediaLibrary() {
 
 /**
  * Import a media file into the library
+ * With scene detection options, videos are also analyzed for shot boundaries
  */
-export async function importMediaFile(path: string): Promise<MediaFile | null> {
+export async function importMediaFile(
+    path: string,
+    sceneDetection?: Partial<SceneDetectionOptions>
+): Promise<MediaFile | null> {
     isLoadingLibrary.set(true);
     mediaLibraryError.set(null);
 
     try {
-        const mediaFile = await invoke<MediaFile>('import_media_file', { path });
+        const mediaFile = await invoke<MediaFile>('import_media_file', {
+            path,
+            sceneDetection: sceneDetection ?? null,
+        });
+
+        if (sceneDetection && mediaFile.media_type === 'video') {
+            await loadMediaMarkers(mediaFile.id);
+        }
 
         // Add to store (optimistic update)
         mediaLibraryStore.update(files => {
@@ -103,6 +133,40 @@ export async function importMediaFile(path: string): Promise<MediaFile | null> {
     }
 }
 
+/**
+ * Load the stored markers of a media file
+ */
+export async function loadMediaMarkers(id: string): Promise<MediaMarker[]> {
+    try {
+        const markers = await invoke<MediaMarker[]>('get_media_markers', { id });
+        mediaMarkers.update(all => ({ ...all, [id]: markers }));
+        return markers;
+    } catch (error) {
+        console.error('Failed to load media markers:', error);
+        return [];
+    }
+}
+
+/**
+ * Detect shot boundaries in a video, replacing previously detected ones
+ */
+export async function detectSceneCuts(
+    id: string,
+    options?: Partial<SceneDetectionOptions>
+): Promise<MediaMarker[]> {
+    mediaLibraryError.set(null);
+
+    try {
+        const markers = await invoke<MediaMarker[]>('detect_scene_cuts', { id, options: options ?? null });
+        mediaMarkers.update(all => ({ ...all, [id]: markers }));
+        return markers;
+    } catch (error) {
+        console.error('Failed to detect scene cuts:', error);
+        mediaLibraryError.set(error as string);
+        return [];
+    }
+}
+
 /**
  * Delete a media file from the library
  */
@@ -114,6 +178,7 @@ export async function deleteMediaFile(id: string): Promise<boolean> {
 
         // Remove from store
         mediaLibraryStore.update(files => files.filter(f => f.id !== id));
+        mediaMarkers.update(({ [id]: _, ...rest }) => rest);
 
         // Clear selection if deleted file was selected
         selectedMediaFile.update(selected => selected === id ? null : selected);
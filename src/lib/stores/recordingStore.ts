@@ -1,7 +1,9 @@
-import { writable, derived } from 'svelte/store';
+import { writable, derived, get } from 'svelte/store';
 import { invoke } from '@tauri-apps/api/core';
 import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import { videoDir } from '@tauri-apps/api/path';
+import { timelineStore, type Timeline } from './timelineStore';
+import { loadMediaLibrary, importMediaFile, type MediaFile } from './mediaLibraryStore';
 
 // Types matching backend data structures
 export type RecordingStateType = 'Idle' | 'Recording' | 'Paused' | 'Finalizing' | 'Error';
@@ -19,6 +21,24 @@ export interface RecordingSource {
 export interface AudioDevice {
     id: string;
     name: string;
+    is_monitor: boolean;  // Carries system audio rather than a microphone
+}
+
+// Peak and RMS level of one channel, in dBFS
+export interface ChannelLevel {
+    peak: number;
+    rms: number;
+}
+
+export interface AudioLevels {
+    time: number;  // Seconds since metering started
+    channels: ChannelLevel[];
+}
+
+export interface AudioWarning {
+    kind: 'silent' | 'clipping';
+    active: boolean;  // False once the input is back to normal
+    seconds: number;
 }
 
 export type AudioInputType = 'None' | 'SystemAudio' | 'Microphone' | 'Both';
@@ -42,19 +62,41 @@ export interface RecordingConfig {
     recording_mode?: RecordingMode;
     webcam_source?: RecordingSource;
     webcam_output_path?: string;
+    max_duration?: number;     // Seconds, not counting pauses
+    max_file_size?: number;    // Bytes
+    stop_at?: string;          // ISO 8601 time
+    countdown?: number;        // Seconds before the recording starts
+    start_at?: string;         // ISO 8601 time to start at instead of now
+    audio_warning_seconds?: number; // Warn when input is silent or clipping this long
 }
 
+export type AutoStopReason = 'max_duration' | 'max_file_size' | 'stop_time';
+
 export interface RecordingState {
     state: RecordingStateType;
     duration: number;
+    file_size: number;  // Bytes written so far
+    bitrate: number;    // Average bits per second
     output_path: string | null;
     error_message: string | null;
 }
 
+// A recording an earlier run of the app never finished, e.g. after a crash
+export interface UnfinishedRecording {
+    id: string;
+    output_path: string;
+    segments: string[];
+    started_at: string;
+    updated_at: string;
+    recorded_bytes: number;
+}
+
 // Store state
 export const recordingState = writable<RecordingState>({
     state: 'Idle',
     duration: 0,
+    file_size: 0,
+    bitrate: 0,
     output_path: null,
     error_message: null,
 });
@@ -70,6 +112,14 @@ export const recordingConfig = writable<Partial<RecordingConfig>>({
 });
 
 export const isLoadingSources = writable<boolean>(false);
+export const unfinishedRecordings = writable<UnfinishedRecording[]>([]);
+
+// Scheduled start and countdown of a recording that hasn't started yet
+export const scheduledStart = writable<string | null>(null);
+export const countdownRemaining = writable<number | null>(null);
+export const autoStopReason = writable<AutoStopReason | null>(null);
+export const audioLevels = writable<AudioLevels | null>(null);
+export const audioWarning = writable<AudioWarning | null>(null);
 export const hasPermissions = writable<boolean | null>(null);
 export const recordingError = writable<string | null>(null);
 
@@ -82,6 +132,8 @@ export const isRecording = derived(recordingState, $state => $state.state === 'R
 export const isPaused = derived(recordingState, $state => $state.state === 'Paused');
 export const isIdle = derived(recordingState, $state => $state.state === 'Idle');
 export const isFinalizing = derived(recordingState, $state => $state.state === 'Finalizing');
+// Recording or paused: there is a recording that hasn't been stopped
+export const isActive = derived(recordingState, $state => $state.state === 'Recording' || $state.state === 'Paused');
 
 export const isDualMode = derived(recordingMode, $mode => $mode === 'ScreenAndWebcam');
 export const isWebcamMode = derived(recordingMode, $mode => $mode === 'WebcamOnly' || $mode === 'ScreenAndWebcam');
@@ -98,6 +150,16 @@ export const screenSources = derived(availableSources, $sources =>
 let unlistenStarted: UnlistenFn | null = null;
 let unlistenDuration: UnlistenFn | null = null;
 let unlistenStopped: UnlistenFn | null = null;
+let unlistenPaused: UnlistenFn | null = null;
+let unlistenResumed: UnlistenFn | null = null;
+let unlistenAssembled: UnlistenFn | null = null;
+let unlistenScheduled: UnlistenFn | null = null;
+let unlistenCountdown: UnlistenFn | null = null;
+let unlistenCancelled: UnlistenFn | null = null;
+let unlistenAutoStop: UnlistenFn | null = null;
+let unlistenError: UnlistenFn | null = null;
+let unlistenAudioLevel: UnlistenFn | null = null;
+let unlistenAudioWarning: UnlistenFn | null = null;
 
 /**
  * Initialize event listeners for recording updates
@@ -106,30 +168,103 @@ export async function initializeRecordingListeners() {
     // Listen for recording started event
     unlistenStarted = await listen<void>('recording:started', () => {
         console.log('Recording started');
+        scheduledStart.set(null);
+        countdownRemaining.set(null);
+        autoStopReason.set(null);
         recordingState.update(state => ({
             ...state,
             state: 'Recording',
             duration: 0,
+            file_size: 0,
+            bitrate: 0,
             error_message: null,
         }));
     });
 
     // Listen for duration updates (emitted every 500ms)
-    unlistenDuration = await listen<{ duration: number }>('recording:duration', (event) => {
+    unlistenDuration = await listen<{ duration: number; file_size: number; bitrate: number }>('recording:duration', (event) => {
+        recordingState.update(state => ({
+            ...state,
+            duration: event.payload.duration,
+            file_size: event.payload.file_size,
+            bitrate: event.payload.bitrate,
+        }));
+    });
+
+    // A countdown or scheduled start delays the recording
+    unlistenScheduled = await listen<{ start_at: string }>('recording:scheduled', (event) => {
+        scheduledStart.set(event.payload.start_at);
+    });
+
+    unlistenCountdown = await listen<{ remaining: number }>('recording:countdown', (event) => {
+        countdownRemaining.set(event.payload.remaining);
+    });
+
+    unlistenCancelled = await listen<void>('recording:cancelled', () => {
+        scheduledStart.set(null);
+        countdownRemaining.set(null);
+    });
+
+    // A limit was reached; recording:stopped follows
+    unlistenAutoStop = await listen<{ reason: AutoStopReason }>('recording:autostop', (event) => {
+        console.log('Recording stopped automatically:', event.payload.reason);
+        autoStopReason.set(event.payload.reason);
+    });
+
+    // Failures of a delayed start or an automatic stop
+    unlistenError = await listen<{ message: string }>('recording:error', (event) => {
+        scheduledStart.set(null);
+        countdownRemaining.set(null);
+        recordingError.set(event.payload.message);
+    });
+
+    // Audio input levels while metering, before or during a recording
+    unlistenAudioLevel = await listen<AudioLevels>('recording:audio_level', (event) => {
+        audioLevels.set(event.payload);
+    });
+
+    unlistenAudioWarning = await listen<AudioWarning>('recording:audio_warning', (event) => {
+        audioWarning.set(event.payload.active ? event.payload : null);
+    });
+
+    // Listen for pause/resume; the duration excludes paused time
+    unlistenPaused = await listen<{ duration: number }>('recording:paused', (event) => {
+        recordingState.update(state => ({
+            ...state,
+            state: 'Paused',
+            duration: event.payload.duration,
+        }));
+    });
+
+    unlistenResumed = await listen<{ duration: number }>('recording:resumed', (event) => {
         recordingState.update(state => ({
             ...state,
+            state: 'Recording',
             duration: event.payload.duration,
         }));
     });
 
     // Listen for recording stopped event
-    unlistenStopped = await listen<{ file_path: string }>('recording:stopped', (event) => {
+    unlistenStopped = await listen<{ file_path: string }>('recording:stopped', async (event) => {
         console.log('Recording stopped:', event.payload.file_path);
         recordingState.update(state => ({
             ...state,
             state: 'Idle',
             output_path: event.payload.file_path,
         }));
+
+        // Nobody pressed stop, so import the recording here
+        if (get(autoStopReason)) {
+            await importMediaFile(event.payload.file_path);
+        }
+    });
+
+    // Recordings of several files are added to the end of the current
+    // timeline, which comes back with everything already on it
+    unlistenAssembled = await listen<Timeline>('recording:assembled', async (event) => {
+        console.log('Recording added to timeline:', event.payload.name);
+        timelineStore.set(event.payload);
+        await loadMediaLibrary();
     });
 }
 
@@ -149,6 +284,46 @@ export function cleanupRecordingListeners() {
         unlistenStopped();
         unlistenStopped = null;
     }
+    if (unlistenPaused) {
+        unlistenPaused();
+        unlistenPaused = null;
+    }
+    if (unlistenResumed) {
+        unlistenResumed();
+        unlistenResumed = null;
+    }
+    if (unlistenAssembled) {
+        unlistenAssembled();
+        unlistenAssembled = null;
+    }
+    if (unlistenScheduled) {
+        unlistenScheduled();
+        unlistenScheduled = null;
+    }
+    if (unlistenCountdown) {
+        unlistenCountdown();
+        unlistenCountdown = null;
+    }
+    if (unlistenCancelled) {
+        unlistenCancelled();
+        unlistenCancelled = null;
+    }
+    if (unlistenAutoStop) {
+        unlistenAutoStop();
+        unlistenAutoStop = null;
+    }
+    if (unlistenError) {
+        unlistenError();
+        unlistenError = null;
+    }
+    if (unlistenAudioLevel) {
+        unlistenAudioLevel();
+        unlistenAudioLevel = null;
+    }
+    if (unlistenAudioWarning) {
+        unlistenAudioWarning();
+        unlistenAudioWarning = null;
+    }
 }
 
 /**
@@ -319,6 +494,12 @@ export async function startRecording(): Promise<boolean> {
             recording_mode: mode,
             webcam_source: webcamSource,
             webcam_output_path: (mode === 'ScreenAndWebcam') ? webcamPath : undefined,
+            max_duration: config.max_duration,
+            max_file_size: config.max_file_size,
+            stop_at: config.stop_at,
+            countdown: config.countdown || 0,
+            start_at: config.start_at,
+            audio_warning_seconds: config.audio_warning_seconds,
         };
 
         await invoke('start_recording', {
@@ -326,13 +507,20 @@ export async function startRecording(): Promise<boolean> {
             config: fullConfig,
         });
 
-        recordingState.update(state => ({
-            ...state,
-            state: 'Recording',
-            duration: 0,
-            output_path: null,
-            error_message: null,
-        }));
+        // A delayed recording starts later, announced by recording:started
+        const delayed = fullConfig.countdown! > 0
+            || (fullConfig.start_at !== undefined && new Date(fullConfig.start_at) > new Date());
+        if (!delayed) {
+            recordingState.update(state => ({
+                ...state,
+                state: 'Recording',
+                duration: 0,
+                file_size: 0,
+                bitrate: 0,
+                output_path: null,
+                error_message: null,
+            }));
+        }
 
         return true;
     } catch (error) {
@@ -347,6 +535,46 @@ export async function startRecording(): Promise<boolean> {
     }
 }
 
+/**
+ * Pause the current recording
+ */
+export async function pauseRecording(): Promise<boolean> {
+    recordingError.set(null);
+
+    try {
+        await invoke('pause_recording');
+        recordingState.update(state => ({
+            ...state,
+            state: 'Paused',
+        }));
+        return true;
+    } catch (error) {
+        console.error('Failed to pause recording:', error);
+        recordingError.set(error as string);
+        return false;
+    }
+}
+
+/**
+ * Resume a paused recording
+ */
+export async function resumeRecording(): Promise<boolean> {
+    recordingError.set(null);
+
+    try {
+        await invoke('resume_recording');
+        recordingState.update(state => ({
+            ...state,
+            state: 'Recording',
+        }));
+        return true;
+    } catch (error) {
+        console.error('Failed to resume recording:', error);
+        recordingError.set(error as string);
+        return false;
+    }
+}
+
 /**
  * Stop the current recording
  */
@@ -422,8 +650,111 @@ export function resetRecordingState(): void {
     recordingState.set({
         state: 'Idle',
         duration: 0,
+        file_size: 0,
+        bitrate: 0,
         output_path: null,
         error_message: null,
     });
     recordingError.set(null);
 }
+
+/**
+ * Look for recordings an earlier run of the app never finished
+ */
+export async function loadUnfinishedRecordings(): Promise<void> {
+    try {
+        const recordings = await invoke<UnfinishedRecording[]>('list_unfinished_recordings');
+        unfinishedRecordings.set(recordings);
+    } catch (error) {
+        console.error('Failed to list unfinished recordings:', error);
+        unfinishedRecordings.set([]);
+    }
+}
+
+/**
+ * Recover an unfinished recording into its file and import it
+ */
+export async function recoverRecording(id: string): Promise<MediaFile> {
+    const mediaFile = await invoke<MediaFile>('recover_recording', { id });
+    unfinishedRecordings.update(recordings => recordings.filter(r => r.id !== id));
+    await loadMediaLibrary();
+    return mediaFile;
+}
+
+/**
+ * Delete an unfinished recording instead of recovering it
+ */
+export async function discardUnfinishedRecording(id: string): Promise<void> {
+    await invoke('discard_unfinished_recording', { id });
+    unfinishedRecordings.update(recordings => recordings.filter(r => r.id !== id));
+}
+
+/**
+ * Cancel a recording waiting for its countdown or scheduled start
+ */
+export async function cancelScheduledRecording(): Promise<boolean> {
+    try {
+        return await invoke<boolean>('cancel_scheduled_recording');
+    } catch (error) {
+        console.error('Failed to cancel scheduled recording:', error);
+        return false;
+    }
+}
+
+/**
+ * Load the audio input devices recordings can capture
+ */
+export async function loadAudioDevices(): Promise<void> {
+    try {
+        const devices = await invoke<AudioDevice[]>('list_audio_devices');
+        availableAudioDevices.set(devices);
+    } catch (error) {
+        console.error('Failed to list audio devices:', error);
+        availableAudioDevices.set([]);
+    }
+}
+
+/**
+ * Meter an audio input; the system default when no device is given
+ */
+export async function startAudioMeter(deviceId?: string, warningSeconds?: number): Promise<boolean> {
+    try {
+        audioLevels.set(null);
+        audioWarning.set(null);
+        await invoke('start_audio_meter', { deviceId, warningSeconds });
+        return true;
+    } catch (error) {
+        console.error('Failed to start audio meter:', error);
+        return false;
+    }
+}
+
+/**
+ * Stop metering audio input
+ */
+export async function stopAudioMeter(): Promise<void> {
+    try {
+        await invoke('stop_audio_meter');
+    } catch (error) {
+        console.error('Failed to stop audio meter:', error);
+    }
+    audioLevels.set(null);
+    audioWarning.set(null);
+}
+
+/**
+ * Format a byte count for display
+ */
+export function formatFileSize(bytes: number): string {
+    if (bytes >= 1024 * 1024 * 1024) return `${(bytes / (1024 * 1024 * 1024)).toFixed(2)} GB`;
+    if (bytes >= 1024 * 1024) return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+    return `${Math.round(bytes / 1024)} KB`;
+}
+
+/**
+ * Format a bitrate in bits per second for display
+ */
+export function formatBitrate(bitsPerSecond: number): string {
+    if (bitsPerSecond >= 1_000_000) return `${(bitsPerSecond / 1_000_000).toFixed(1)} Mbps`;
+    return `${Math.round(bitsPerSecond / 1000)} kbps`;
+}
@@ -0,0 +1,68 @@
+import { writable } from 'svelte/store';
+import { invoke } from '@tauri-apps/api/core';
+
+// Types matching Rust backend (keys are never sent to the frontend)
+export interface ProviderCredentialInfo {
+    provider: string;
+    base_url?: string;
+    updated_at: string;
+}
+
+// Providers with saved credentials
+export const providerCredentials = writable<ProviderCredentialInfo[]>([]);
+
+/**
+ * Refresh the list of providers with saved credentials
+ */
+export async function loadProviderCredentials(): Promise<ProviderCredentialInfo[]> {
+    try {
+        const list = await invoke<ProviderCredentialInfo[]>('list_provider_credentials');
+        providerCredentials.set(list);
+        return list;
+    } catch (error) {
+        console.error('Failed to list provider credentials:', error);
+        throw error;
+    }
+}
+
+/**
+ * Save a provider's API key, encrypted on disk
+ */
+export async function setProviderCredential(
+    provider: string,
+    apiKey: string,
+    baseUrl?: string
+): Promise<void> {
+    try {
+        await invoke('set_provider_credential', {
+            provider,
+            apiKey,
+            baseUrl: baseUrl || null,
+        });
+        await loadProviderCredentials();
+    } catch (error) {
+        console.error('Failed to save provider credential:', error);
+        throw error;
+    }
+}
+
+/**
+ * Check a saved key against its provider; rejects with the reason on failure
+ */
+export async function testProviderCredential(provider: string): Promise<void> {
+    await invoke('test_provider_credential', { provider });
+}
+
+/**
+ * Delete a provider's saved key
+ */
+export async function clearProviderCredential(provider: string): Promise<boolean> {
+    try {
+        const removed = await invoke<boolean>('clear_provider_credential', { provider });
+        await loadProviderCredentials();
+        return removed;
+    } catch (error) {
+        console.error('Failed to clear provider credential:', error);
+        throw error;
+    }
+}
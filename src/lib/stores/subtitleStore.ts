@@ -1,7 +1,8 @@
 import { writable, derived, get } from 'svelte/store';
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
-import { playheadTime } from './timelineStore';
+import { playheadTime, timelineStore } from './timelineStore';
+import type { SubtitleStyle, Timeline } from './timelineStore';
 
 // Types matching Rust backend
 export interface SubtitleSegment {
@@ -9,21 +10,91 @@ export interface SubtitleSegment {
     start_time: number;
     end_time: number;
     text: string;
+    words?: SubtitleWord[];
+    style?: string;         // ASS/SSA style name
+    cue_settings?: string;  // WebVTT cue settings
+    style_override?: SubtitleStyleOverride;
+}
+
+// Per-segment changes to the timeline's subtitle style
+export type SubtitleStyleOverride = Partial<SubtitleStyle>;
+
+export interface TranscriptRemoval {
+    segment_id: number;
+    first_word?: number;  // Inclusive word indices; whole segment when omitted
+    last_word?: number;
+}
+
+export interface EdlEvent {
+    index: number;
+    track_id: string;
+    track_type: 'Video' | 'Audio' | 'Overlay';
+    clip_id: string;
+    media_file_id: string;
+    source_in: number;
+    source_out: number;
+    record_in: number;
+    record_out: number;
+}
+
+export interface CutResult {
+    timeline: Timeline;
+    removed: { start: number; end: number }[];
+    removed_duration: number;
+    edl: EdlEvent[];
+}
+
+export type SubtitleFormat = 'srt' | 'vtt' | 'ass' | 'ssa' | 'sbv';
+
+export interface SubtitleWord {
+    text: string;
+    start_time: number;
+    end_time: number;
+}
+
+export interface SegmentationOptions {
+    max_chars_per_line: number;
+    max_lines: number;
+    max_duration: number;
+    min_duration: number;
+    min_gap: number;
+    max_cps: number;
+}
+
+export interface ReadingSpeedWarning {
+    segment_id: number;
+    chars_per_second: number;
 }
 
 export interface SubtitleSource {
-    type: 'Transcribed' | 'Imported' | 'Manual';
+    type: 'Transcribed' | 'TranscribedTimeline' | 'Imported' | 'Translated' | 'Manual';
     media_file_id?: string;
+    timeline_id?: string;
     provider?: string;
     file_path?: string;
+    from_language?: string;
 }
 
 export interface SubtitleTrack {
     segments: SubtitleSegment[];
     language: string;
     source: SubtitleSource;
+    ass_header?: string;
 }
 
+export type TranscriptionAuth =
+    | { type: 'None' }
+    | { type: 'Bearer'; token: string }
+    | { type: 'Header'; name: string; value: string };
+
+export type TranscriptionConfig =
+    | { type: 'OpenAiCompatible'; base_url: string; model: string; auth: TranscriptionAuth }
+    | { type: 'WhisperCli'; binary_path: string; model: string; flavor: 'WhisperCpp' | 'OpenAiWhisper' };
+
+export type TranslationConfig =
+    | { type: 'OpenAiCompatible'; base_url: string; model: string; auth: TranscriptionAuth }
+    | { type: 'LibreTranslate'; base_url: string; api_key?: string };
+
 export interface SubtitleProgress {
     stage: string;
     progress: number;  // 0.0 to 1.0
@@ -31,7 +102,9 @@ export interface SubtitleProgress {
 
 // Store state
 export interface SubtitleState {
-    currentTrack: SubtitleTrack | null;
+    tracks: SubtitleTrack[];             // One per language
+    currentTrack: SubtitleTrack | null;  // Track being edited and previewed
+    burnInLanguage: string | null;       // Track burned into exports, the first when null
     isTranscribing: boolean;
     transcriptionProgress: SubtitleProgress | null;
     enabled: boolean;
@@ -41,7 +114,9 @@ export interface SubtitleState {
 
 // Main subtitle store
 export const subtitleStore = writable<SubtitleState>({
+    tracks: [],
     currentTrack: null,
+    burnInLanguage: null,
     isTranscribing: false,
     transcriptionProgress: null,
     enabled: false,
@@ -63,6 +138,54 @@ export const currentSubtitle = derived(
     }
 );
 
+/**
+ * All tracks with the edited track's latest segments
+ */
+function mergedTracks(state: SubtitleState): SubtitleTrack[] {
+    const current = state.currentTrack;
+    if (!current) return state.tracks;
+    return state.tracks.some(t => t.language === current.language)
+        ? state.tracks.map(t => (t.language === current.language ? current : t))
+        : [...state.tracks, current];
+}
+
+/**
+ * Replace the edited track with the backend's copy after an edit
+ */
+function applyEditedTrack(track: SubtitleTrack): void {
+    subtitleStore.update(state => {
+        const merged = { ...state, currentTrack: track };
+        return { ...merged, tracks: mergedTracks(merged) };
+    });
+}
+
+/**
+ * Add or replace the track for a language, make it the edited track and save it with the timeline
+ */
+async function storeTrack(track: SubtitleTrack): Promise<void> {
+    applyEditedTrack(track);
+
+    const timeline = await invoke<Timeline>('set_subtitle_track', { track });
+    timelineStore.set(timeline);
+}
+
+// Pick up the tracks saved with a project when another timeline is loaded
+let loadedTimelineId: string | null = null;
+timelineStore.subscribe(timeline => {
+    if (timeline.id === loadedTimelineId) return;
+    loadedTimelineId = timeline.id;
+
+    const tracks = timeline.subtitle_tracks ?? [];
+    subtitleStore.update(state => ({
+        ...state,
+        tracks,
+        currentTrack: tracks[0] ?? null,
+        burnInLanguage: timeline.burn_in_language ?? null,
+        enabled: timeline.subtitle_enabled ?? false,
+        editingSegmentId: null,
+    }));
+});
+
 // Setup event listeners for transcription progress
 let progressUnlisten: (() => void) | null = null;
 
@@ -101,6 +224,24 @@ export async function setOpenAIApiKey(apiKey: string): Promise<void> {
     }
 }
 
+/**
+ * Choose the transcription backend (hosted, self-hosted server or local binary)
+ * Returns the provider name recorded on transcribed tracks
+ */
+export async function setTranscriptionProvider(config: TranscriptionConfig): Promise<string> {
+    try {
+        const name = await invoke<string>('set_transcription_provider', { config });
+        subtitleStore.update(state => ({
+            ...state,
+            apiKeyConfigured: true,
+        }));
+        return name;
+    } catch (error) {
+        console.error('Failed to set transcription provider:', error);
+        throw error;
+    }
+}
+
 /**
  * Check if subtitle service is available
  */
@@ -148,11 +289,11 @@ export async function transcribeTimelineAudio(
         // Update with transcribed track
         subtitleStore.update(state => ({
             ...state,
-            currentTrack: track,
             isTranscribing: false,
             transcriptionProgress: null,
             enabled: true,  // Auto-enable after transcription
         }));
+        await storeTrack(track);
 
     } catch (error) {
         console.error('Transcription failed:', error);
@@ -166,59 +307,82 @@ export async function transcribeTimelineAudio(
     }
 }
 
+/**
+ * Run a segment edit command on the edited track
+ */
+async function editSegments(
+    command: string,
+    timelineId: string,
+    args: Record<string, unknown>
+): Promise<void> {
+    const state = get(subtitleStore);
+
+    if (!state.currentTrack) {
+        throw new Error('No subtitle track to edit');
+    }
+
+    try {
+        const track = await invoke<SubtitleTrack>(command, {
+            timelineId,
+            language: state.currentTrack.language,
+            ...args,
+        });
+        applyEditedTrack(track);
+    } catch (error) {
+        console.error(`Subtitle edit ${command} failed:`, error);
+        throw error;
+    }
+}
+
 /**
  * Update a subtitle segment
  */
 export async function updateSubtitleSegment(
     timelineId: string,
     segmentId: number,
-    newText: string,
+    newText?: string,
     newStart?: number,
     newEnd?: number
 ): Promise<void> {
-    const previousState = get(subtitleStore);
-
-    try {
-        // Optimistically update UI
-        subtitleStore.update(state => {
-            if (!state.currentTrack) return state;
-
-            const segments = state.currentTrack.segments.map(seg => {
-                if (seg.id === segmentId) {
-                    return {
-                        ...seg,
-                        text: newText,
-                        start_time: newStart !== undefined ? newStart : seg.start_time,
-                        end_time: newEnd !== undefined ? newEnd : seg.end_time,
-                    };
-                }
-                return seg;
-            });
+    await editSegments('update_subtitle_segment', timelineId, {
+        segmentId,
+        newText: newText ?? null,
+        newStart: newStart ?? null,
+        newEnd: newEnd ?? null,
+    });
+}
 
-            return {
-                ...state,
-                currentTrack: {
-                    ...state.currentTrack,
-                    segments,
-                },
-            };
-        });
+/**
+ * Split a subtitle segment in two at a timeline time
+ */
+export async function splitSubtitleSegment(
+    timelineId: string,
+    segmentId: number,
+    time: number
+): Promise<void> {
+    await editSegments('split_subtitle_segment', timelineId, { segmentId, time });
+}
 
-        // Sync backend
-        await invoke('update_subtitle_segment', {
-            timelineId,
-            segmentId,
-            newText,
-            newStart: newStart || null,
-            newEnd: newEnd || null,
-        });
+/**
+ * Merge a subtitle segment with the one after it
+ */
+export async function mergeSubtitleSegments(
+    timelineId: string,
+    segmentId: number
+): Promise<void> {
+    await editSegments('merge_subtitle_segments', timelineId, { segmentId });
+}
 
-    } catch (error) {
-        console.error('Failed to update subtitle segment:', error);
-        // Rollback on error
-        subtitleStore.set(previousState);
-        throw error;
-    }
+/**
+ * Add a subtitle segment
+ */
+export async function insertSubtitleSegment(
+    timelineId: string,
+    startTime: number,
+    endTime: number,
+    text: string
+): Promise<void> {
+    await editSegments('insert_subtitle_segment', timelineId, { startTime, endTime, text });
 }
 
 /**
@@ -228,31 +392,59 @@ export async function deleteSubtitleSegment(
     timelineId: string,
     segmentId: number
 ): Promise<void> {
-    const previousState = get(subtitleStore);
+    await editSegments('delete_subtitle_segment', timelineId, { segmentId });
+}
 
-    try {
-        // Optimistically update UI
-        subtitleStore.update(state => {
-            if (!state.currentTrack) return state;
+/**
+ * Move every segment of the edited track by an offset in seconds
+ */
+export async function shiftSubtitles(timelineId: string, offset: number): Promise<void> {
+    await editSegments('shift_subtitles', timelineId, { offset });
+}
+
+/**
+ * Take over the timeline from an applied cut
+ * Every language track was rippled along with the clips
+ */
+export function applyCutResult(result: CutResult): void {
+    const tracks = result.timeline.subtitle_tracks ?? [];
+    subtitleStore.update(s => ({
+        ...s,
+        tracks,
+        currentTrack: tracks.find(t => t.language === s.currentTrack?.language) ?? null,
+        editingSegmentId: null,
+    }));
+    timelineStore.set(result.timeline);
+}
 
-            const segments = state.currentTrack.segments.filter(seg => seg.id !== segmentId);
+/**
+ * Cut transcript words or segments out of the timeline
+ * With preview set nothing changes; the result shows the resulting edit decision list
+ */
+export async function cutTranscript(
+    removals: TranscriptRemoval[],
+    preview: boolean
+): Promise<CutResult> {
+    const state = get(subtitleStore);
 
-            return {
-                ...state,
-                currentTrack: {
-                    ...state.currentTrack,
-                    segments,
-                },
-            };
+    if (!state.currentTrack) {
+        throw new Error('No transcript to cut');
+    }
+
+    try {
+        const result = await invoke<CutResult>('cut_transcript', {
+            language: state.currentTrack.language,
+            removals,
+            preview,
         });
 
-        // Note: Backend doesn't have delete command yet, would use update_subtitle_segment
-        // For now, this is frontend-only until saved to timeline
+        if (!preview) {
+            applyCutResult(result);
+        }
 
+        return result;
     } catch (error) {
-        console.error('Failed to delete subtitle segment:', error);
-        // Rollback on error
-        subtitleStore.set(previousState);
+        console.error('Failed to cut transcript:', error);
         throw error;
     }
 }
@@ -288,9 +480,9 @@ export async function toggleSubtitles(
 }
 
 /**
- * Export subtitles to SRT file
+ * Export subtitles; the format follows the file extension unless given
  */
-export async function exportSubtitlesSRT(outputPath: string): Promise<void> {
+export async function exportSubtitles(outputPath: string, format?: SubtitleFormat): Promise<void> {
     const state = get(subtitleStore);
 
     if (!state.currentTrack) {
@@ -298,44 +490,188 @@ export async function exportSubtitlesSRT(outputPath: string): Promise<void> {
     }
 
     try {
-        await invoke('export_subtitles_srt', {
+        await invoke('export_subtitles', {
             track: state.currentTrack,
             outputPath,
+            format: format || null,
         });
     } catch (error) {
-        console.error('Failed to export SRT:', error);
+        console.error('Failed to export subtitles:', error);
         throw error;
     }
 }
 
 /**
- * Import subtitles from SRT file
+ * Import subtitles from SRT, WebVTT, ASS/SSA or SBV (detected from content)
  */
-export async function importSubtitlesSRT(
+export async function importSubtitles(
     filePath: string,
-    language?: string
+    language?: string,
+    format?: SubtitleFormat
 ): Promise<void> {
     const previousState = get(subtitleStore);
 
     try {
-        const track = await invoke<SubtitleTrack>('import_subtitles_srt', {
+        const track = await invoke<SubtitleTrack>('import_subtitles', {
             filePath,
             language: language || null,
+            format: format || null,
         });
 
         subtitleStore.update(state => ({
             ...state,
-            currentTrack: track,
             enabled: true,
         }));
+        await storeTrack(track);
 
     } catch (error) {
-        console.error('Failed to import SRT:', error);
+        console.error('Failed to import subtitles:', error);
         subtitleStore.set(previousState);
         throw error;
     }
 }
 
+/**
+ * Re-split the current track into readable cues, returning cues still too fast to read
+ */
+export async function resegmentSubtitles(
+    options?: Partial<SegmentationOptions>
+): Promise<ReadingSpeedWarning[]> {
+    const state = get(subtitleStore);
+
+    if (!state.currentTrack) {
+        throw new Error('No subtitle track to re-segment');
+    }
+
+    try {
+        const result = await invoke<{ track: SubtitleTrack; warnings: ReadingSpeedWarning[] }>(
+            'resegment_subtitles',
+            {
+                track: state.currentTrack,
+                options: options || null,
+            }
+        );
+
+        await storeTrack(result.track);
+
+        return result.warnings;
+    } catch (error) {
+        console.error('Failed to re-segment subtitles:', error);
+        throw error;
+    }
+}
+
+/**
+ * Choose the translation backend (hosted, self-hosted server or LibreTranslate)
+ * Returns the provider name recorded on translated tracks
+ */
+export async function setTranslationProvider(config: TranslationConfig): Promise<string> {
+    try {
+        return await invoke<string>('set_translation_provider', { config });
+    } catch (error) {
+        console.error('Failed to set translation provider:', error);
+        throw error;
+    }
+}
+
+/**
+ * Translate the edited track into another language, keeping its timing
+ */
+export async function translateSubtitles(targetLanguage: string): Promise<void> {
+    const state = get(subtitleStore);
+
+    if (!state.currentTrack) {
+        throw new Error('No subtitle track to translate');
+    }
+
+    try {
+        const track = await invoke<SubtitleTrack>('translate_subtitles', {
+            track: state.currentTrack,
+            targetLanguage,
+        });
+
+        // Keep edits to the source track before switching to the translation
+        subtitleStore.update(s => ({ ...s, tracks: mergedTracks(s) }));
+        await storeTrack(track);
+    } catch (error) {
+        console.error('Failed to translate subtitles:', error);
+        throw error;
+    }
+}
+
+/**
+ * Switch the edited and previewed track to another language
+ */
+export function selectSubtitleTrack(language: string): void {
+    subtitleStore.update(state => {
+        const tracks = mergedTracks(state);
+        return {
+            ...state,
+            tracks,
+            currentTrack: tracks.find(t => t.language === language) ?? state.currentTrack,
+            editingSegmentId: null,
+        };
+    });
+}
+
+/**
+ * Choose the language burned into exports (null for the first track)
+ */
+export async function setBurnInLanguage(language: string | null): Promise<void> {
+    try {
+        const timeline = await invoke<Timeline>('set_burn_in_subtitles', { language });
+        timelineStore.set(timeline);
+        subtitleStore.update(state => ({ ...state, burnInLanguage: language }));
+    } catch (error) {
+        console.error('Failed to set burn-in subtitles:', error);
+        throw error;
+    }
+}
+
+/**
+ * Remove the track for a language
+ */
+export async function removeSubtitleTrack(language: string): Promise<void> {
+    try {
+        const timeline = await invoke<Timeline>('remove_subtitle_track', { language });
+        timelineStore.set(timeline);
+        subtitleStore.update(state => {
+            const tracks = mergedTracks(state).filter(t => t.language !== language);
+            return {
+                ...state,
+                tracks,
+                currentTrack: state.currentTrack?.language === language
+                    ? tracks[0] ?? null
+                    : state.currentTrack,
+                burnInLanguage: state.burnInLanguage === language ? null : state.burnInLanguage,
+            };
+        });
+    } catch (error) {
+        console.error('Failed to remove subtitle track:', error);
+        throw error;
+    }
+}
+
+/**
+ * Timeline with the subtitle tracks attached, as export and preview rendering expect
+ *
+ * Preview frames show the edited track, exports burn in the chosen one.
+ */
+export function withSubtitles(timeline: Timeline, forExport = false): Timeline & {
+    subtitle_tracks: SubtitleTrack[];
+    burn_in_language: string | null;
+    subtitle_enabled: boolean;
+} {
+    const state = get(subtitleStore);
+    const tracks = mergedTracks(state);
+    return {
+        ...timeline,
+        subtitle_tracks: tracks,
+        burn_in_language: forExport ? state.burnInLanguage : state.currentTrack?.language ?? null,
+        subtitle_enabled: state.enabled && tracks.length > 0,
+    };
+}
+
 /**
  * Set which segment is being edited
  */
@@ -352,7 +688,9 @@ export function setEditingSegment(segmentId: number | null): void {
 export function clearSubtitles(): void {
     subtitleStore.update(state => ({
         ...state,
+        tracks: [],
         currentTrack: null,
+        burnInLanguage: null,
         enabled: false,
     }));
 }
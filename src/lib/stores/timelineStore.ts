@@ -1,5 +1,6 @@
-import { writable, derived } from 'svelte/store';
+import { writable, derived, get } from 'svelte/store';
 import { invoke } from '@tauri-apps/api/core';
+import type { SubtitleTrack } from './subtitleStore';
 
 // Types matching backend data structures
 export interface Timeline {
@@ -12,6 +13,23 @@ export interface Timeline {
     };
     tracks: Track[];
     duration: number;
+    subtitle_tracks?: SubtitleTrack[];
+    burn_in_language?: string | null;
+    subtitle_enabled?: boolean;
+    subtitle_style?: SubtitleStyle;
+    markers?: TimelineMarker[];  // Sorted by time
+}
+
+export type MarkerKind = 'Marker' | 'Chapter';
+
+export interface TimelineMarker {
+    id: string;           // Empty when adding; the backend assigns one
+    name: string;
+    time: number;         // seconds
+    end_time?: number;    // Set for regions
+    kind: MarkerKind;
+    color: string;        // Hex color
+    notes?: string;
 }
 
 export interface Track {
@@ -33,6 +51,67 @@ export interface Clip {
     effects: Effect[];
     volume: number;
     speed: number;
+    generator?: ClipGenerator;  // Generated content instead of a media file
+    ken_burns?: KenBurns;       // Pan and zoom, typically for still images
+    animation_span?: [number, number];  // Source range of the clip this was split from
+}
+
+// Pan and zoom matching backend models.rs
+export interface KenBurns {
+    start_zoom: number;  // 1.0 = whole frame
+    end_zoom: number;
+    start_x: number;     // Centre of the view, 0 to 1
+    start_y: number;
+    end_x: number;
+    end_y: number;
+}
+
+// Generated clip content matching backend models.rs
+export type ClipGenerator =
+    | ({ type: 'Text' } & TextOverlay)
+    | { type: 'SolidColor'; color: string }
+    | { type: 'Gradient'; start_color: string; end_color: string; angle?: number };
+
+export type TextAlignment = 'Left' | 'Center' | 'Right';
+export type TextAnimation = 'None' | 'Fade' | 'SlideUp' | 'SlideLeft';
+
+export interface TextOverlay {
+    text: string;
+    font_family?: string;
+    font_file?: string;
+    font_size: number;         // Pixels at timeline resolution
+    color: string;             // "#RRGGBB" or "#RRGGBBAA"
+    outline_color?: string;
+    outline_width?: number;
+    background?: { color: string; padding?: number };
+    alignment?: TextAlignment;
+    x: number;                 // Anchor position, 0 (left) to 1 (right)
+    y: number;                 // Anchor position, 0 (top) to 1 (bottom)
+    animate_in?: TextAnimation;
+    animate_out?: TextAnimation;
+    animation_duration?: number;
+}
+
+export type SubtitlePosition = 'Top' | 'Middle' | 'Bottom';
+
+// Burned-in subtitle look; sizes are fractions of the frame height,
+// horizontal margin a fraction of the width
+export interface SubtitleStyle {
+    font_family: string;
+    font_file?: string;
+    font_size: number;
+    bold: boolean;
+    italic: boolean;
+    primary_color: string;     // "#RRGGBB" or "#RRGGBBAA"
+    outline_color: string;
+    outline_width: number;
+    shadow_color: string;
+    shadow_offset: number;
+    background?: string | null;  // Box behind the text; null draws an outline instead
+    position: SubtitlePosition;
+    alignment: TextAlignment;
+    margin_horizontal: number;
+    margin_vertical: number;
 }
 
 // Effect structure matching backend models.rs
@@ -49,9 +128,34 @@ export type EffectType =
     | { type: 'Saturation'; value: number }
     | { type: 'Blur'; radius: number }
     | { type: 'Sharpen'; amount: number }
+    | { type: 'ColorTemperature'; kelvin: number }
+    | { type: 'Tint'; value: number }
+    | { type: 'Curves'; master?: string; red?: string; green?: string; blue?: string }
+    | { type: 'Lut'; path: string }
+    | { type: 'HueShift'; degrees: number }
+    | { type: 'Vignette'; intensity: number }
+    | { type: 'ChromaKey'; color: string; similarity: number; blend: number }
+    | { type: 'Crop'; x: number; y: number; width: number; height: number }
+    | { type: 'Flip'; horizontal: boolean; vertical: boolean }
+    | { type: 'Rotate'; degrees: number }
+    | { type: 'Stabilize'; strength: number }
+    | { type: 'Denoise'; strength: number }
     | { type: 'Normalize' }
     | { type: 'FadeIn'; duration: number }
-    | { type: 'FadeOut'; duration: number };
+    | { type: 'FadeOut'; duration: number }
+    | { type: 'Equalizer'; bands: EqBand[] }
+    | { type: 'Compressor'; threshold_db: number; ratio: number; attack_ms: number; release_ms: number; makeup_db: number }
+    | { type: 'NoiseGate'; threshold_db: number; ratio: number; attack_ms: number; release_ms: number }
+    | { type: 'HighPass'; frequency: number }
+    | { type: 'NoiseReduction'; amount_db: number }
+    | { type: 'Pitch'; semitones: number };
+
+// Single equalizer band matching backend EqBand
+export interface EqBand {
+    frequency: number;  // Hz
+    gain_db: number;
+    q: number;
+}
 
 // Timeline state
 export const timelineStore = writable<Timeline>({
@@ -322,6 +426,87 @@ export async function splitClipAtPlayhead(): Promise<{ firstClipId: string; seco
     }
 }
 
+/**
+ * Split a clip at every scene cut detected in its media
+ * Returns the IDs of the resulting clips in timeline order
+ */
+export async function splitClipAtSceneCuts(clipId: string): Promise<string[]> {
+    try {
+        const pieces = await invoke<string[]>('split_clip_at_scene_cuts', { clipId });
+
+        // Refresh from backend to get accurate state
+        await initializeTimeline();
+        selectedClipId.set(pieces[0] ?? null);
+
+        return pieces;
+    } catch (error) {
+        console.error('Failed to split clip at scene cuts:', error);
+        throw error;
+    }
+}
+
+/**
+ * Add a marker, chapter or region to the timeline
+ */
+export async function addMarker(marker: Omit<TimelineMarker, 'id'>): Promise<void> {
+    try {
+        const timeline = await invoke<Timeline>('add_timeline_marker', { marker: { ...marker, id: '' } });
+        timelineStore.set(timeline);
+    } catch (error) {
+        console.error('Failed to add marker:', error);
+        throw error;
+    }
+}
+
+/**
+ * Replace a marker's name, times, kind, color or notes
+ */
+export async function updateMarker(marker: TimelineMarker): Promise<void> {
+    try {
+        const timeline = await invoke<Timeline>('update_timeline_marker', { marker });
+        timelineStore.set(timeline);
+    } catch (error) {
+        console.error('Failed to update marker:', error);
+        throw error;
+    }
+}
+
+/**
+ * Remove a marker from the timeline
+ */
+export async function removeMarker(markerId: string): Promise<void> {
+    try {
+        const timeline = await invoke<Timeline>('remove_timeline_marker', { markerId });
+        timelineStore.set(timeline);
+    } catch (error) {
+        console.error('Failed to remove marker:', error);
+        throw error;
+    }
+}
+
+/**
+ * Move the playhead to the next or previous marker
+ * Returns the marker, or null when there is none in that direction
+ */
+export async function jumpToMarker(direction: 'next' | 'previous'): Promise<TimelineMarker | null> {
+    const time = get(playheadTime);
+    const marker = await invoke<TimelineMarker | null>(
+        direction === 'next' ? 'get_next_marker' : 'get_previous_marker',
+        { time }
+    );
+    if (marker) {
+        playheadTime.set(marker.time);
+    }
+    return marker;
+}
+
+/**
+ * YouTube-style chapter list of the chapter markers, also saved when a path is given
+ */
+export async function exportYoutubeChapters(path?: string): Promise<string> {
+    return invoke<string>('export_youtube_chapters', { path: path ?? null });
+}
+
 export function setPlayheadTime(time: number) {
     playheadTime.set(Math.max(0, time));
 }
@@ -458,3 +643,16 @@ export async function loadTimelineProject(filePath: string): Promise<void> {
         throw error;
     }
 }
+
+/**
+ * Set the look of burned-in subtitles (saved with the project)
+ */
+export async function setSubtitleStyle(style: SubtitleStyle): Promise<void> {
+    try {
+        const timeline = await invoke<Timeline>('set_subtitle_style', { style });
+        timelineStore.set(timeline);
+    } catch (error) {
+        console.error('Failed to set subtitle style:', error);
+        throw error;
+    }
+}